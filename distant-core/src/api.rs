@@ -1,7 +1,7 @@
 use crate::{
     data::{
-        Capabilities, ChangeKind, DirEntry, Environment, Error, Metadata, ProcessId, PtySize,
-        SearchId, SearchQuery, SystemInfo,
+        Capabilities, ChangeKind, DirEntry, Environment, Error, FindFilesQuery, Metadata,
+        ProcessId, PtySize, SearchId, SearchQuery, SystemInfo, TailId, TailOptions, WatchOptions,
     },
     DistantMsg, DistantRequestData, DistantResponseData,
 };
@@ -12,7 +12,7 @@ use log::*;
 use std::{io, path::PathBuf, sync::Arc};
 
 mod local;
-pub use local::LocalDistantApi;
+pub use local::{LocalDistantApi, WatcherConfig};
 
 mod reply;
 use reply::DistantSingleReply;
@@ -48,6 +48,14 @@ impl DistantApiServerHandler<LocalDistantApi, <LocalDistantApi as DistantApi>::L
             api: LocalDistantApi::initialize()?,
         })
     }
+
+    /// Creates a new server using the [`LocalDistantApi`] implementation, detecting filesystem
+    /// changes using the given `config`
+    pub fn local_with_watcher_config(config: WatcherConfig) -> io::Result<Self> {
+        Ok(Self {
+            api: LocalDistantApi::initialize_with_watcher_config(config)?,
+        })
+    }
 }
 
 #[inline]
@@ -171,6 +179,34 @@ pub trait DistantApi {
         unsupported("append_file_text")
     }
 
+    /// Reads data from the end of a file, optionally following the file to send data as it is
+    /// appended until the tail is cancelled using [`DistantApi::cancel_tail`]. Returns the id
+    /// of the tail.
+    ///
+    /// * `path` - the path to the file
+    /// * `options` - where to start reading and whether to follow the file
+    ///
+    /// *Override this, otherwise it will return "unsupported" as an error.*
+    #[allow(unused_variables)]
+    async fn file_tail(
+        &self,
+        ctx: DistantCtx<Self::LocalData>,
+        path: PathBuf,
+        options: TailOptions,
+    ) -> io::Result<TailId> {
+        unsupported("file_tail")
+    }
+
+    /// Cancels an active tail of a file.
+    ///
+    /// * `id` - the id of the tail to cancel
+    ///
+    /// *Override this, otherwise it will return "unsupported" as an error.*
+    #[allow(unused_variables)]
+    async fn cancel_tail(&self, ctx: DistantCtx<Self::LocalData>, id: TailId) -> io::Result<()> {
+        unsupported("cancel_tail")
+    }
+
     /// Reads entries from a directory.
     ///
     /// * `path` - the path to the directory
@@ -263,6 +299,7 @@ pub trait DistantApi {
     /// * `recursive` - if true, will watch for changes within subdirectories and beyond
     /// * `only` - if non-empty, will limit reported changes to those included in this list
     /// * `except` - if non-empty, will limit reported changes to those not included in this list
+    /// * `options` - additional options that customize how changes are reported
     ///
     /// *Override this, otherwise it will return "unsupported" as an error.*
     #[allow(unused_variables)]
@@ -273,10 +310,28 @@ pub trait DistantApi {
         recursive: bool,
         only: Vec<ChangeKind>,
         except: Vec<ChangeKind>,
+        options: WatchOptions,
     ) -> io::Result<()> {
         unsupported("watch")
     }
 
+    /// Resumes watching a path, replaying changes reported after a sequence number, or reporting
+    /// that the changes can no longer be replayed.
+    ///
+    /// * `path` - the path to the file or directory being watched
+    /// * `since_seq` - the sequence number of the last change received
+    ///
+    /// *Override this, otherwise it will return "unsupported" as an error.*
+    #[allow(unused_variables)]
+    async fn watch_resume(
+        &self,
+        ctx: DistantCtx<Self::LocalData>,
+        path: PathBuf,
+        since_seq: u64,
+    ) -> io::Result<()> {
+        unsupported("watch_resume")
+    }
+
     /// Removes a file or directory from being watched.
     ///
     /// * `path` - the path to the file or directory
@@ -343,6 +398,51 @@ pub trait DistantApi {
         unsupported("cancel_search")
     }
 
+    /// Builds an index of the contents of files beneath a directory, keeping it up to date as
+    /// files change so future content searches of the directory can skip files that cannot match.
+    ///
+    /// * `path` - the path to the directory to index
+    ///
+    /// *Override this, otherwise it will return "unsupported" as an error.*
+    #[allow(unused_variables)]
+    async fn search_index(
+        &self,
+        ctx: DistantCtx<Self::LocalData>,
+        path: PathBuf,
+    ) -> io::Result<()> {
+        unsupported("search_index")
+    }
+
+    /// Removes the index of a directory built by [`DistantApi::search_index`], no longer keeping
+    /// it up to date as files change.
+    ///
+    /// * `path` - the path to the indexed directory
+    ///
+    /// *Override this, otherwise it will return "unsupported" as an error.*
+    #[allow(unused_variables)]
+    async fn search_unindex(
+        &self,
+        ctx: DistantCtx<Self::LocalData>,
+        path: PathBuf,
+    ) -> io::Result<()> {
+        unsupported("search_unindex")
+    }
+
+    /// Finds paths beneath a directory that fuzzy match a pattern, returning the id of the
+    /// search, which can be cancelled using [`DistantApi::cancel_search`].
+    ///
+    /// * `query` - the specific query to perform
+    ///
+    /// *Override this, otherwise it will return "unsupported" as an error.*
+    #[allow(unused_variables)]
+    async fn find_files(
+        &self,
+        ctx: DistantCtx<Self::LocalData>,
+        query: FindFilesQuery,
+    ) -> io::Result<SearchId> {
+        unsupported("find_files")
+    }
+
     /// Spawns a new process, returning its id.
     ///
     /// * `cmd` - the full command to run as a new process (including arguments)
@@ -559,6 +659,18 @@ where
             .await
             .map(|_| DistantResponseData::Ok)
             .unwrap_or_else(DistantResponseData::from),
+        DistantRequestData::FileTail { path, options } => server
+            .api
+            .file_tail(ctx, path, options)
+            .await
+            .map(|id| DistantResponseData::TailStarted { id })
+            .unwrap_or_else(DistantResponseData::from),
+        DistantRequestData::CancelTail { id } => server
+            .api
+            .cancel_tail(ctx, id)
+            .await
+            .map(|_| DistantResponseData::Ok)
+            .unwrap_or_else(DistantResponseData::from),
         DistantRequestData::DirRead {
             path,
             depth,
@@ -603,9 +715,16 @@ where
             recursive,
             only,
             except,
+            options,
         } => server
             .api
-            .watch(ctx, path, recursive, only, except)
+            .watch(ctx, path, recursive, only, except, options)
+            .await
+            .map(|_| DistantResponseData::Ok)
+            .unwrap_or_else(DistantResponseData::from),
+        DistantRequestData::WatchResume { path, since_seq } => server
+            .api
+            .watch_resume(ctx, path, since_seq)
             .await
             .map(|_| DistantResponseData::Ok)
             .unwrap_or_else(DistantResponseData::from),
@@ -643,6 +762,24 @@ where
             .await
             .map(|_| DistantResponseData::Ok)
             .unwrap_or_else(DistantResponseData::from),
+        DistantRequestData::SearchIndex { path } => server
+            .api
+            .search_index(ctx, path)
+            .await
+            .map(|_| DistantResponseData::Ok)
+            .unwrap_or_else(DistantResponseData::from),
+        DistantRequestData::SearchUnindex { path } => server
+            .api
+            .search_unindex(ctx, path)
+            .await
+            .map(|_| DistantResponseData::Ok)
+            .unwrap_or_else(DistantResponseData::from),
+        DistantRequestData::FindFiles { query } => server
+            .api
+            .find_files(ctx, query)
+            .await
+            .map(|id| DistantResponseData::SearchStarted { id })
+            .unwrap_or_else(DistantResponseData::from),
         DistantRequestData::ProcSpawn {
             cmd,
             environment,
@@ -1,7 +1,8 @@
 use crate::{
     data::{
-        Capabilities, ChangeKind, ChangeKindSet, DirEntry, Environment, FileType, Metadata,
-        ProcessId, PtySize, SearchId, SearchQuery, SystemInfo,
+        Capabilities, ChangeKind, ChangeKindSet, DirEntry, DistantResponseData, Environment,
+        FileType, FindFilesQuery, Metadata, ProcessId, PtySize, SearchId, SearchQuery, SystemInfo,
+        TailId, TailOptions, WatchOptions,
     },
     DistantApi, DistantCtx,
 };
@@ -17,6 +18,7 @@ use walkdir::WalkDir;
 mod process;
 
 mod state;
+pub use state::WatcherConfig;
 use state::*;
 
 /// Represents an implementation of [`DistantApi`] that works with the local machine
@@ -30,8 +32,13 @@ pub struct LocalDistantApi {
 impl LocalDistantApi {
     /// Initialize the api instance
     pub fn initialize() -> io::Result<Self> {
+        Self::initialize_with_watcher_config(WatcherConfig::default())
+    }
+
+    /// Initialize the api instance, detecting filesystem changes using the given `config`
+    pub fn initialize_with_watcher_config(config: WatcherConfig) -> io::Result<Self> {
         Ok(Self {
-            state: GlobalState::initialize()?,
+            state: GlobalState::initialize(config)?,
         })
     }
 }
@@ -42,7 +49,6 @@ impl DistantApi for LocalDistantApi {
 
     async fn capabilities(&self, ctx: DistantCtx<Self::LocalData>) -> io::Result<Capabilities> {
         debug!("[Conn {}] Querying capabilities", ctx.connection_id);
-
         Ok(Capabilities::all())
     }
 
@@ -138,6 +144,24 @@ impl DistantApi for LocalDistantApi {
         file.write_all(data.as_ref()).await
     }
 
+    async fn file_tail(
+        &self,
+        ctx: DistantCtx<Self::LocalData>,
+        path: PathBuf,
+        options: TailOptions,
+    ) -> io::Result<TailId> {
+        debug!(
+            "[Conn {}] Tailing file {:?} {{options: {:?}}}",
+            ctx.connection_id, path, options
+        );
+        self.state.tail.start(path, options, ctx.reply).await
+    }
+
+    async fn cancel_tail(&self, ctx: DistantCtx<Self::LocalData>, id: TailId) -> io::Result<()> {
+        debug!("[Conn {}] Cancelling tail {}", ctx.connection_id, id);
+        self.state.tail.cancel(id).await
+    }
+
     async fn read_dir(
         &self,
         ctx: DistantCtx<Self::LocalData>,
@@ -354,12 +378,13 @@ impl DistantApi for LocalDistantApi {
         recursive: bool,
         only: Vec<ChangeKind>,
         except: Vec<ChangeKind>,
+        options: WatchOptions,
     ) -> io::Result<()> {
         let only = only.into_iter().collect::<ChangeKindSet>();
         let except = except.into_iter().collect::<ChangeKindSet>();
         debug!(
-            "[Conn {}] Watching {:?} {{recursive: {}, only: {}, except: {}}}",
-            ctx.connection_id, path, recursive, only, except
+            "[Conn {}] Watching {:?} {{recursive: {}, only: {}, except: {}, options: {:?}}}",
+            ctx.connection_id, path, recursive, only, except, options
         );
 
         let path = RegisteredPath::register(
@@ -368,6 +393,7 @@ impl DistantApi for LocalDistantApi {
             recursive,
             only,
             except,
+            options,
             ctx.reply,
         )
         .await?;
@@ -377,6 +403,44 @@ impl DistantApi for LocalDistantApi {
         Ok(())
     }
 
+    async fn watch_resume(
+        &self,
+        ctx: DistantCtx<Self::LocalData>,
+        path: PathBuf,
+        since_seq: u64,
+    ) -> io::Result<()> {
+        debug!(
+            "[Conn {}] Resuming watch of {:?} since {}",
+            ctx.connection_id, path, since_seq
+        );
+
+        match self
+            .state
+            .watcher
+            .resume(ctx.connection_id, path.as_path(), since_seq)
+            .await?
+        {
+            Replay::Changes(changes) if changes.is_empty() => (),
+            Replay::Changes(mut changes) if changes.len() == 1 => {
+                ctx.reply
+                    .send(DistantResponseData::Changed(changes.remove(0)))
+                    .await?;
+            }
+            Replay::Changes(changes) => {
+                ctx.reply
+                    .send(DistantResponseData::ChangedBatch { changes })
+                    .await?;
+            }
+            Replay::Overflow { seq } => {
+                ctx.reply
+                    .send(DistantResponseData::WatchOverflow { path, seq })
+                    .await?;
+            }
+        }
+
+        Ok(())
+    }
+
     async fn unwatch(&self, ctx: DistantCtx<Self::LocalData>, path: PathBuf) -> io::Result<()> {
         debug!("[Conn {}] Unwatching {:?}", ctx.connection_id, path);
 
@@ -423,6 +487,10 @@ impl DistantApi for LocalDistantApi {
             ctx.connection_id,
         );
 
+        // Apply changes to indexed directories that have been observed but not yet indexed so
+        // the search does not skip files based on their old contents
+        self.state.watcher.flush_index().await?;
+
         self.state.search.start(query, ctx.reply).await
     }
 
@@ -436,6 +504,55 @@ impl DistantApi for LocalDistantApi {
         self.state.search.cancel(id).await
     }
 
+    async fn search_index(
+        &self,
+        ctx: DistantCtx<Self::LocalData>,
+        path: PathBuf,
+    ) -> io::Result<()> {
+        debug!(
+            "[Conn {}] Indexing {:?} for searches",
+            ctx.connection_id, path
+        );
+        let root = tokio::fs::canonicalize(path).await?;
+        let index = self.state.search.index().clone();
+
+        // Start watching before building so there is no gap between finishing the build and
+        // receiving changes, although changes to a file made after the build has read it but
+        // before the build has finished can still be missed
+        self.state
+            .watcher
+            .index(root.clone(), index.clone())
+            .await?;
+
+        tokio::task::spawn_blocking(move || index.build(root))
+            .await
+            .map_err(|x| io::Error::new(io::ErrorKind::Other, x))?
+    }
+
+    async fn search_unindex(
+        &self,
+        ctx: DistantCtx<Self::LocalData>,
+        path: PathBuf,
+    ) -> io::Result<()> {
+        debug!(
+            "[Conn {}] Removing search index of {:?}",
+            ctx.connection_id, path
+        );
+        let root = tokio::fs::canonicalize(&path).await.unwrap_or(path);
+        let index = self.state.search.index().clone();
+        self.state.watcher.unindex(root, index).await
+    }
+
+    async fn find_files(
+        &self,
+        ctx: DistantCtx<Self::LocalData>,
+        query: FindFilesQuery,
+    ) -> io::Result<SearchId> {
+        debug!("[Conn {}] Finding files via {query:?}", ctx.connection_id);
+
+        self.state.search.find_files(query, ctx.reply).await
+    }
+
     async fn proc_spawn(
         &self,
         ctx: DistantCtx<Self::LocalData>,
@@ -487,7 +604,10 @@ impl DistantApi for LocalDistantApi {
 
     async fn system_info(&self, ctx: DistantCtx<Self::LocalData>) -> io::Result<SystemInfo> {
         debug!("[Conn {}] Reading system information", ctx.connection_id);
-        Ok(SystemInfo::default())
+        Ok(SystemInfo {
+            watch_backend: Some(self.state.watcher.backend().to_string()),
+            ..Default::default()
+        })
     }
 }
 
@@ -495,7 +615,7 @@ impl DistantApi for LocalDistantApi {
 mod tests {
     use super::*;
     use crate::api::ConnectionCtx;
-    use crate::data::DistantResponseData;
+    use crate::data::{DistantResponseData, SearchQuery, SearchQueryCondition, SearchQueryTarget};
     use assert_fs::prelude::*;
     use distant_net::server::Reply;
     use once_cell::sync::Lazy;
@@ -578,6 +698,7 @@ mod tests {
             &api,
             ConnectionCtx {
                 connection_id,
+                username: None,
                 local_data: &mut (),
             },
         )
@@ -857,6 +978,175 @@ mod tests {
         file.assert("some file contentssome extra contents");
     }
 
+    #[test(tokio::test)]
+    async fn file_tail_should_send_error_if_file_missing() {
+        let (api, ctx, _rx) = setup(1).await;
+        let temp = assert_fs::TempDir::new().unwrap();
+        let path = temp.child("missing-file").path().to_path_buf();
+
+        let _ = api
+            .file_tail(ctx, path, Default::default())
+            .await
+            .unwrap_err();
+    }
+
+    #[test(tokio::test)]
+    async fn file_tail_should_send_last_lines_and_finish_if_not_following() {
+        let (api, ctx, mut rx) = setup(100).await;
+        let temp = assert_fs::TempDir::new().unwrap();
+        let file = temp.child("test-file");
+        file.write_str("line 1\nline 2\nline 3\n").unwrap();
+
+        let id = api
+            .file_tail(
+                ctx,
+                file.path().to_path_buf(),
+                TailOptions {
+                    lines: Some(2),
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(
+            rx.recv().await.unwrap(),
+            DistantResponseData::TailData {
+                id,
+                data: b"line 2\nline 3\n".to_vec()
+            }
+        );
+        assert_eq!(
+            rx.recv().await.unwrap(),
+            DistantResponseData::TailDone { id }
+        );
+    }
+
+    #[test(tokio::test)]
+    async fn file_tail_should_send_appended_data_until_cancelled() {
+        let (api, ctx, mut rx) = setup(100).await;
+        let temp = assert_fs::TempDir::new().unwrap();
+        let file = temp.child("test-file");
+        file.write_str("existing\n").unwrap();
+
+        let id = api
+            .file_tail(
+                ctx,
+                file.path().to_path_buf(),
+                TailOptions {
+                    follow: true,
+                    poll_interval_ms: Some(10),
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+
+        let mut f = std::fs::OpenOptions::new()
+            .append(true)
+            .open(file.path())
+            .unwrap();
+        std::io::Write::write_all(&mut f, b"appended\n").unwrap();
+
+        assert_eq!(
+            rx.recv().await.unwrap(),
+            DistantResponseData::TailData {
+                id,
+                data: b"appended\n".to_vec()
+            }
+        );
+
+        let (reply, _reply_rx) = make_reply(1);
+        api.cancel_tail(
+            DistantCtx {
+                connection_id: rand::random(),
+                reply,
+                local_data: Arc::new(()),
+            },
+            id,
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            rx.recv().await.unwrap(),
+            DistantResponseData::TailDone { id }
+        );
+    }
+
+    #[test(tokio::test)]
+    async fn file_tail_should_stop_following_once_connection_closes() {
+        let (api, ctx, rx) = setup(100).await;
+        let temp = assert_fs::TempDir::new().unwrap();
+        let file = temp.child("test-file");
+        file.write_str("existing\n").unwrap();
+
+        let id = api
+            .file_tail(
+                ctx,
+                file.path().to_path_buf(),
+                TailOptions {
+                    follow: true,
+                    poll_interval_ms: Some(10),
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+
+        // Close the connection and give the tail time to notice
+        drop(rx);
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        // Tail should have finished, so there is nothing left to cancel
+        let (reply, _reply_rx) = make_reply(1);
+        let _ = api
+            .cancel_tail(
+                DistantCtx {
+                    connection_id: rand::random(),
+                    reply,
+                    local_data: Arc::new(()),
+                },
+                id,
+            )
+            .await
+            .unwrap_err();
+    }
+
+    #[test(tokio::test)]
+    async fn file_tail_should_reset_and_send_from_start_if_file_truncated() {
+        let (api, ctx, mut rx) = setup(100).await;
+        let temp = assert_fs::TempDir::new().unwrap();
+        let file = temp.child("test-file");
+        file.write_str("some long existing content\n").unwrap();
+
+        let id = api
+            .file_tail(
+                ctx,
+                file.path().to_path_buf(),
+                TailOptions {
+                    follow: true,
+                    poll_interval_ms: Some(10),
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+
+        file.write_str("new\n").unwrap();
+
+        assert_eq!(
+            rx.recv().await.unwrap(),
+            DistantResponseData::TailReset { id }
+        );
+        assert_eq!(
+            rx.recv().await.unwrap(),
+            DistantResponseData::TailData {
+                id,
+                data: b"new\n".to_vec()
+            }
+        );
+    }
+
     #[test(tokio::test)]
     async fn dir_read_should_send_error_if_directory_does_not_exist() {
         let (api, ctx, _rx) = setup(1).await;
@@ -1386,6 +1676,7 @@ mod tests {
             /* recursive */ false,
             /* only */ Default::default(),
             /* except */ Default::default(),
+            /* options */ Default::default(),
         )
         .await
         .unwrap();
@@ -1422,6 +1713,7 @@ mod tests {
             /* recursive */ true,
             /* only */ Default::default(),
             /* except */ Default::default(),
+            /* options */ Default::default(),
         )
         .await
         .unwrap();
@@ -1514,6 +1806,7 @@ mod tests {
             /* recursive */ false,
             /* only */ Default::default(),
             /* except */ Default::default(),
+            /* options */ Default::default(),
         )
         .await
         .unwrap();
@@ -1525,6 +1818,7 @@ mod tests {
             /* recursive */ false,
             /* only */ Default::default(),
             /* except */ Default::default(),
+            /* options */ Default::default(),
         )
         .await
         .unwrap();
@@ -1554,6 +1848,272 @@ mod tests {
         );
     }
 
+    #[test(tokio::test)]
+    async fn watch_resume_should_replay_changes_reported_after_sequence_number() {
+        let (api, ctx, mut rx) = setup(100).await;
+        let connection_id = ctx.connection_id;
+        let temp = assert_fs::TempDir::new().unwrap();
+
+        let file = temp.child("file");
+        file.touch().unwrap();
+
+        api.watch(
+            ctx,
+            file.path().to_path_buf(),
+            /* recursive */ false,
+            /* only */ Default::default(),
+            /* except */ Default::default(),
+            /* options */ Default::default(),
+        )
+        .await
+        .unwrap();
+
+        file.write_str("some text").unwrap();
+        let seq = match rx.recv().await.unwrap() {
+            DistantResponseData::Changed(change) => change.seq,
+            x => panic!("Unexpected response: {x:?}"),
+        };
+
+        // Resume as if we never received the change, which should be replayed
+        let (reply, mut resume_rx) = make_reply(100);
+        let ctx = DistantCtx {
+            connection_id,
+            reply,
+            local_data: Arc::new(()),
+        };
+        api.watch_resume(ctx, file.path().to_path_buf(), seq - 1)
+            .await
+            .unwrap();
+
+        let changes = match resume_rx.recv().await.unwrap() {
+            DistantResponseData::Changed(change) => vec![change],
+            DistantResponseData::ChangedBatch { changes } => changes,
+            x => panic!("Unexpected response: {x:?}"),
+        };
+        assert_eq!(changes[0].seq, seq);
+        assert!(changes.iter().all(|change| change.seq >= seq));
+    }
+
+    #[test(tokio::test)]
+    async fn watch_resume_should_fail_if_path_not_watched() {
+        let (api, ctx, _rx) = setup(100).await;
+        let temp = assert_fs::TempDir::new().unwrap();
+
+        let _ = api
+            .watch_resume(ctx, temp.path().to_path_buf(), 0)
+            .await
+            .unwrap_err();
+    }
+
+    #[test(tokio::test)]
+    async fn search_index_should_keep_index_up_to_date_as_files_change() {
+        let (api, ctx, _rx) = setup(100).await;
+        let temp = assert_fs::TempDir::new().unwrap();
+        let root = temp.path().canonicalize().unwrap();
+
+        let file = temp.child("file");
+        file.write_str("before").unwrap();
+
+        api.search_index(ctx, root.clone()).await.unwrap();
+
+        // Change the file after it was indexed and give the watcher time to refresh the index
+        file.write_str("after").unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let (reply, mut rx) = make_reply(100);
+        let ctx = DistantCtx {
+            connection_id: rand::random(),
+            reply,
+            local_data: Arc::new(()),
+        };
+        api.search(
+            ctx,
+            SearchQuery {
+                paths: vec![root.clone()],
+                target: SearchQueryTarget::Contents,
+                condition: SearchQueryCondition::contains("after"),
+                options: Default::default(),
+            },
+        )
+        .await
+        .unwrap();
+
+        // If the index was not refreshed, the file would be skipped and we would only get done
+        match rx.recv().await.unwrap() {
+            DistantResponseData::SearchResults { matches, .. } => {
+                let paths = matches
+                    .into_iter()
+                    .filter_map(|m| m.into_contents_match())
+                    .map(|m| m.path)
+                    .collect::<Vec<_>>();
+                assert_eq!(paths, [root.join("file")]);
+            }
+            x => panic!("Unexpected response: {x:?}"),
+        }
+    }
+
+    #[test(tokio::test)]
+    async fn search_index_should_stay_up_to_date_after_watch_within_it_is_removed() {
+        let (api, ctx, _rx) = setup(100).await;
+        let temp = assert_fs::TempDir::new().unwrap();
+        let root = temp.path().canonicalize().unwrap();
+
+        let dir = temp.child("dir");
+        dir.create_dir_all().unwrap();
+        let file = dir.child("file");
+        file.write_str("before").unwrap();
+
+        api.search_index(ctx, root.clone()).await.unwrap();
+
+        // Watch and then unwatch a directory within the index, which shares the native watch
+        // used to keep the index up to date
+        let (reply, _watch_rx) = make_reply(100);
+        let connection_id = rand::random();
+        let make_ctx = |reply| DistantCtx {
+            connection_id,
+            reply,
+            local_data: Arc::new(()),
+        };
+        api.watch(
+            make_ctx(reply.clone_reply()),
+            dir.path().to_path_buf(),
+            /* recursive */ false,
+            /* only */ Default::default(),
+            /* except */ Default::default(),
+            /* options */ Default::default(),
+        )
+        .await
+        .unwrap();
+        api.unwatch(make_ctx(reply), dir.path().to_path_buf())
+            .await
+            .unwrap();
+
+        // Search without waiting, relying on the search to apply changes that are still queued
+        file.write_str("after").unwrap();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        let (reply, mut rx) = make_reply(100);
+        api.search(
+            make_ctx(reply),
+            SearchQuery {
+                paths: vec![root.clone()],
+                target: SearchQueryTarget::Contents,
+                condition: SearchQueryCondition::contains("after"),
+                options: Default::default(),
+            },
+        )
+        .await
+        .unwrap();
+
+        match rx.recv().await.unwrap() {
+            DistantResponseData::SearchResults { matches, .. } => {
+                let paths = matches
+                    .into_iter()
+                    .filter_map(|m| m.into_contents_match())
+                    .map(|m| m.path)
+                    .collect::<Vec<_>>();
+                assert_eq!(paths, [root.join("dir").join("file")]);
+            }
+            x => panic!("Unexpected response: {x:?}"),
+        }
+    }
+
+    #[test(tokio::test)]
+    async fn search_should_find_the_same_matches_with_and_without_an_index() {
+        let (api, ctx, _rx) = setup(100).await;
+        let temp = assert_fs::TempDir::new().unwrap();
+        let root = temp.path().canonicalize().unwrap();
+
+        temp.child("posix").write_str("xfoobar").unwrap();
+        temp.child("nested").write_str("bfoobar").unwrap();
+        temp.child("other").write_str("1foobar").unwrap();
+
+        let search = |regex: &'static str| {
+            let api = &api;
+            let root = root.clone();
+            async move {
+                let (reply, mut rx) = make_reply(100);
+                let ctx = DistantCtx {
+                    connection_id: rand::random(),
+                    reply,
+                    local_data: Arc::new(()),
+                };
+                api.search(
+                    ctx,
+                    SearchQuery {
+                        paths: vec![root],
+                        target: SearchQueryTarget::Contents,
+                        condition: SearchQueryCondition::regex(regex),
+                        options: Default::default(),
+                    },
+                )
+                .await
+                .unwrap();
+
+                let mut paths = Vec::new();
+                while let Some(data) = rx.recv().await {
+                    match data {
+                        DistantResponseData::SearchResults { matches, .. } => paths.extend(
+                            matches
+                                .into_iter()
+                                .filter_map(|m| m.into_contents_match())
+                                .map(|m| m.path),
+                        ),
+                        DistantResponseData::SearchDone { .. } => break,
+                        x => panic!("Unexpected response: {x:?}"),
+                    }
+                }
+                paths.sort();
+                paths
+            }
+        };
+
+        // Character classes containing POSIX or nested classes must not be mistaken for literals
+        let regexes = ["[[:alpha:]]foobar", "[a[b]]foobar"];
+        let mut unindexed = Vec::new();
+        for regex in regexes {
+            unindexed.push(search(regex).await);
+        }
+
+        api.search_index(ctx, root.clone()).await.unwrap();
+
+        for (regex, expected) in regexes.into_iter().zip(unindexed) {
+            assert!(!expected.is_empty(), "No matches for {regex}");
+            assert_eq!(search(regex).await, expected, "Wrong matches for {regex}");
+        }
+    }
+
+    #[test(tokio::test)]
+    async fn search_unindex_should_fail_if_directory_is_not_indexed() {
+        let (api, ctx, _rx) = setup(1).await;
+        let temp = assert_fs::TempDir::new().unwrap();
+
+        let _ = api
+            .search_unindex(ctx, temp.path().to_path_buf())
+            .await
+            .unwrap_err();
+    }
+
+    #[test(tokio::test)]
+    async fn search_unindex_should_remove_index_of_directory() {
+        let (api, ctx, _rx) = setup(1).await;
+        let temp = assert_fs::TempDir::new().unwrap();
+        let root = temp.path().canonicalize().unwrap();
+
+        api.search_index(ctx, root.clone()).await.unwrap();
+
+        let (reply, _rx) = make_reply(1);
+        let make_ctx = || DistantCtx {
+            connection_id: rand::random(),
+            reply: reply.clone_reply(),
+            local_data: Arc::new(()),
+        };
+        api.search_unindex(make_ctx(), root.clone()).await.unwrap();
+
+        // Index is gone, so removing it again fails
+        let _ = api.search_unindex(make_ctx(), root).await.unwrap_err();
+    }
+
     #[test(tokio::test)]
     async fn exists_should_send_true_if_path_exists() {
         let (api, ctx, _rx) = setup(1).await;
@@ -2148,7 +2708,9 @@ mod tests {
                     std::env::var("ComSpec").unwrap_or_else(|_| String::from("cmd.exe"))
                 } else {
                     std::env::var("SHELL").unwrap_or_else(|_| String::from("/bin/sh"))
-                }
+                },
+                protocol_version: Some(distant_net::common::PROTOCOL_VERSION),
+                watch_backend: Some(api.state.watcher.backend().to_string()),
             }
         );
     }
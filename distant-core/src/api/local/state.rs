@@ -6,6 +6,9 @@ pub use process::*;
 mod search;
 pub use search::*;
 
+mod tail;
+pub use tail::*;
+
 mod watcher;
 pub use watcher::*;
 
@@ -17,16 +20,20 @@ pub struct GlobalState {
     /// State that holds information about searches running on the server
     pub search: SearchState,
 
+    /// State that holds information about tails of files on the server
+    pub tail: TailState,
+
     /// Watcher used for filesystem events
     pub watcher: WatcherState,
 }
 
 impl GlobalState {
-    pub fn initialize() -> io::Result<Self> {
+    pub fn initialize(config: WatcherConfig) -> io::Result<Self> {
         Ok(Self {
             process: ProcessState::new(),
             search: SearchState::new(),
-            watcher: WatcherState::initialize()?,
+            tail: TailState::new(),
+            watcher: WatcherState::initialize(config)?,
         })
     }
 }
@@ -1,7 +1,7 @@
 use crate::data::{
-    DistantResponseData, SearchId, SearchQuery, SearchQueryContentsMatch, SearchQueryMatch,
-    SearchQueryMatchData, SearchQueryOptions, SearchQueryPathMatch, SearchQuerySubmatch,
-    SearchQueryTarget,
+    DistantResponseData, FindFilesMatch, FindFilesQuery, SearchId, SearchQuery,
+    SearchQueryContentsMatch, SearchQueryMatch, SearchQueryMatchData, SearchQueryOptions,
+    SearchQueryPathMatch, SearchQuerySubmatch, SearchQueryTarget,
 };
 use distant_net::server::Reply;
 use grep::{
@@ -14,17 +14,57 @@ use ignore::{
     WalkParallel,
 };
 use log::*;
-use std::{cmp, collections::HashMap, io, ops::Deref, path::Path};
+use std::{
+    cmp::{self, Reverse},
+    collections::{BinaryHeap, HashMap},
+    io,
+    ops::Deref,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
 use tokio::{
     sync::{broadcast, mpsc, oneshot},
     task::JoinHandle,
 };
 
+#[cfg(feature = "archive")]
+mod archive;
+
+/// Stand-in for archives when built without the `archive` feature, where no path is treated as an
+/// archive as searching them is rejected upfront
+#[cfg(not(feature = "archive"))]
+mod archive {
+    use std::{io, path::Path};
+
+    pub enum Archive {}
+
+    impl Archive {
+        pub fn from_path(_path: &Path) -> Option<Self> {
+            None
+        }
+
+        pub fn visit<F>(self, _path: &Path, _f: F) -> io::Result<()> {
+            match self {}
+        }
+    }
+}
+
+mod fuzzy;
+mod index;
+use archive::Archive;
+use fuzzy::FuzzyPattern;
+pub use index::SearchIndex;
+use index::TrigramQuery;
+
 const MAXIMUM_SEARCH_THREADS: usize = 12;
 
 /// Holds information related to active searches on the server
 pub struct SearchState {
     channel: SearchChannel,
+    index: SearchIndex,
     task: JoinHandle<()>,
 }
 
@@ -38,14 +78,21 @@ impl Drop for SearchState {
 impl SearchState {
     pub fn new() -> Self {
         let (tx, rx) = mpsc::channel(1);
-        let task = tokio::spawn(search_task(tx.clone(), rx));
+        let index = SearchIndex::default();
+        let task = tokio::spawn(search_task(tx.clone(), rx, index.clone()));
 
         Self {
             channel: SearchChannel { tx },
+            index,
             task,
         }
     }
 
+    /// Returns the indexes of directories consulted by content searches
+    pub fn index(&self) -> &SearchIndex {
+        &self.index
+    }
+
     #[allow(dead_code)]
     pub fn clone_channel(&self) -> SearchChannel {
         self.channel.clone()
@@ -98,6 +145,25 @@ impl SearchChannel {
             .map_err(|_| io::Error::new(io::ErrorKind::Other, "Response to start dropped"))?
     }
 
+    /// Starts finding files that fuzzy match the provided query
+    pub async fn find_files(
+        &self,
+        query: FindFilesQuery,
+        reply: Box<dyn Reply<Data = DistantResponseData>>,
+    ) -> io::Result<SearchId> {
+        let (cb, rx) = oneshot::channel();
+        self.tx
+            .send(InnerSearchMsg::FindFiles {
+                query: Box::new(query),
+                reply,
+                cb,
+            })
+            .await
+            .map_err(|_| io::Error::new(io::ErrorKind::Other, "Internal search task closed"))?;
+        rx.await
+            .map_err(|_| io::Error::new(io::ErrorKind::Other, "Response to find files dropped"))?
+    }
+
     /// Cancels an active search
     pub async fn cancel(&self, id: SearchId) -> io::Result<()> {
         let (cb, rx) = oneshot::channel();
@@ -117,6 +183,11 @@ enum InnerSearchMsg {
         reply: Box<dyn Reply<Data = DistantResponseData>>,
         cb: oneshot::Sender<io::Result<SearchId>>,
     },
+    FindFiles {
+        query: Box<FindFilesQuery>,
+        reply: Box<dyn Reply<Data = DistantResponseData>>,
+        cb: oneshot::Sender<io::Result<SearchId>>,
+    },
     Cancel {
         id: SearchId,
         cb: oneshot::Sender<io::Result<()>>,
@@ -126,7 +197,11 @@ enum InnerSearchMsg {
     },
 }
 
-async fn search_task(tx: mpsc::Sender<InnerSearchMsg>, mut rx: mpsc::Receiver<InnerSearchMsg>) {
+async fn search_task(
+    tx: mpsc::Sender<InnerSearchMsg>,
+    mut rx: mpsc::Receiver<InnerSearchMsg>,
+    index: SearchIndex,
+) {
     let mut searches: HashMap<SearchId, broadcast::Sender<()>> = HashMap::new();
 
     while let Some(msg) = rx.recv().await {
@@ -135,7 +210,7 @@ async fn search_task(tx: mpsc::Sender<InnerSearchMsg>, mut rx: mpsc::Receiver<In
                 let options = query.options.clone();
 
                 // Build our executor and send an error if it fails
-                let mut executor = match SearchQueryExecutor::new(*query) {
+                let mut executor = match SearchQueryExecutor::new(*query, index.clone()) {
                     Ok(executor) => executor,
                     Err(x) => {
                         let _ = cb.send(Err(x));
@@ -154,6 +229,40 @@ async fn search_task(tx: mpsc::Sender<InnerSearchMsg>, mut rx: mpsc::Receiver<In
 
                 // Spawn our reporter of matches coming from the executor
                 SearchQueryReporter {
+                    id,
+                    options,
+                    rx: executor.take_match_rx().unwrap(),
+                    skipped_binary: executor.skipped_binary(),
+                    reply,
+                }
+                .spawn();
+
+                // Spawn our executor to run
+                executor.spawn(tx.clone());
+            }
+            InnerSearchMsg::FindFiles { query, reply, cb } => {
+                let options = query.options.clone();
+
+                // Build our executor and send an error if it fails
+                let mut executor = match FindFilesExecutor::new(*query) {
+                    Ok(executor) => executor,
+                    Err(x) => {
+                        let _ = cb.send(Err(x));
+                        continue;
+                    }
+                };
+
+                // Get the unique search id
+                let id = executor.id();
+
+                // Queue up our search internally with a cancel sender
+                searches.insert(id, executor.take_cancel_tx().unwrap());
+
+                // Report back the search id
+                let _ = cb.send(Ok(id));
+
+                // Spawn our reporter of matches coming from the executor
+                FindFilesReporter {
                     id,
                     options,
                     rx: executor.take_match_rx().unwrap(),
@@ -188,6 +297,7 @@ struct SearchQueryReporter {
     id: SearchId,
     options: SearchQueryOptions,
     rx: mpsc::UnboundedReceiver<SearchQueryMatch>,
+    skipped_binary: Arc<AtomicU64>,
     reply: Box<dyn Reply<Data = DistantResponseData>>,
 }
 
@@ -202,6 +312,7 @@ impl SearchQueryReporter {
             id,
             options,
             mut rx,
+            skipped_binary,
             reply,
         } = self;
 
@@ -251,8 +362,15 @@ impl SearchQueryReporter {
         }
 
         // Report that we are done
-        trace!("[Query {id}] Reporting as done");
-        if let Err(x) = reply.send(DistantResponseData::SearchDone { id }).await {
+        let skipped_binary = skipped_binary.load(Ordering::Relaxed);
+        trace!("[Query {id}] Reporting as done, skipped {skipped_binary} binary files");
+        if let Err(x) = reply
+            .send(DistantResponseData::SearchDone {
+                id,
+                skipped_binary: Some(skipped_binary),
+            })
+            .await
+        {
             error!("[Query {id}] Failed to send done status: {x}");
         }
     }
@@ -263,6 +381,9 @@ struct SearchQueryExecutor {
     query: SearchQuery,
     walker: WalkParallel,
     matcher: RegexMatcher,
+    skipped_binary: Arc<AtomicU64>,
+    index: SearchIndex,
+    index_query: TrigramQuery,
 
     cancel_tx: Option<broadcast::Sender<()>>,
     cancel_rx: broadcast::Receiver<()>,
@@ -272,11 +393,18 @@ struct SearchQueryExecutor {
 }
 
 impl SearchQueryExecutor {
-    /// Creates a new executor
-    pub fn new(query: SearchQuery) -> io::Result<Self> {
+    /// Creates a new executor that uses `index` to skip files that cannot match
+    pub fn new(query: SearchQuery, index: SearchIndex) -> io::Result<Self> {
         let (cancel_tx, cancel_rx) = broadcast::channel(1);
         let (match_tx, match_rx) = mpsc::unbounded_channel();
 
+        if cfg!(not(feature = "archive")) && query.options.search_archives {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "Searching archives requires the archive feature",
+            ));
+        }
+
         let regex = query.condition.to_regex_string();
         let mut matcher_builder = RegexMatcherBuilder::new();
         matcher_builder
@@ -293,39 +421,17 @@ impl SearchQueryExecutor {
             .build(&regex)
             .map_err(|x| io::Error::new(io::ErrorKind::InvalidInput, x))?;
 
-        if query.paths.is_empty() {
-            return Err(io::Error::new(io::ErrorKind::InvalidInput, "missing paths"));
-        }
-
-        let mut walker_builder = WalkBuilder::new(&query.paths[0]);
-        for path in &query.paths[1..] {
-            walker_builder.add(path);
-        }
-
-        walker_builder
-            .follow_links(query.options.follow_symbolic_links)
-            .max_depth(
-                query
-                    .options
-                    .max_depth
-                    .as_ref()
-                    .copied()
-                    .map(|d| d as usize),
-            )
-            .threads(cmp::min(MAXIMUM_SEARCH_THREADS, num_cpus::get()))
-            .types(
-                TypesBuilder::new()
-                    .add_defaults()
-                    .build()
-                    .map_err(|x| io::Error::new(io::ErrorKind::Other, x))?,
-            )
-            .skip_stdout(true);
+        let walker = build_walker(&query.paths, &query.options)?;
+        let index_query = TrigramQuery::from_condition(&query.condition);
 
         Ok(Self {
             id: rand::random(),
             query,
             matcher,
-            walker: walker_builder.build_parallel(),
+            walker,
+            skipped_binary: Arc::new(AtomicU64::new(0)),
+            index,
+            index_query,
             cancel_tx: Some(cancel_tx),
             cancel_rx,
 
@@ -346,6 +452,11 @@ impl SearchQueryExecutor {
         self.match_rx.take()
     }
 
+    /// Returns a counter of files skipped so far because they contain binary data
+    pub fn skipped_binary(&self) -> Arc<AtomicU64> {
+        Arc::clone(&self.skipped_binary)
+    }
+
     /// Runs the executor to completion in another thread
     pub fn spawn(self, tx: mpsc::Sender<InnerSearchMsg>) {
         tokio::task::spawn_blocking(move || {
@@ -408,9 +519,14 @@ impl SearchQueryExecutor {
         let mut builder = SearchQueryExecutorParallelVistorBuilder {
             search_id: self.id,
             target: self.query.target,
+            binary: self.query.options.binary,
+            search_archives: self.query.options.search_archives,
             cancel,
             tx,
             matcher: &matcher,
+            skipped_binary: &self.skipped_binary,
+            index: &self.index,
+            index_query: &self.index_query,
             include_path_filter: &include_path_filter,
             exclude_path_filter: &exclude_path_filter,
             options_filter: &options_filter,
@@ -423,12 +539,58 @@ impl SearchQueryExecutor {
     }
 }
 
+/// Builds a parallel walker across `paths` that respects the walking-related `options`
+fn build_walker(paths: &[PathBuf], options: &SearchQueryOptions) -> io::Result<WalkParallel> {
+    if paths.is_empty() {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "missing paths"));
+    }
+
+    let mut walker_builder = WalkBuilder::new(&paths[0]);
+    for path in &paths[1..] {
+        walker_builder.add(path);
+    }
+
+    for path in &options.ignore_files {
+        if let Some(x) = walker_builder.add_ignore(path) {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, x));
+        }
+    }
+
+    let respect_ignore = !options.no_ignore;
+    let respect_vcs_ignore = respect_ignore && !options.no_ignore_vcs;
+
+    walker_builder
+        .hidden(!options.hidden)
+        .parents(respect_ignore)
+        .ignore(respect_ignore)
+        .git_ignore(respect_vcs_ignore)
+        .git_global(respect_vcs_ignore)
+        .git_exclude(respect_vcs_ignore)
+        .follow_links(options.follow_symbolic_links)
+        .max_depth(options.max_depth.as_ref().copied().map(|d| d as usize))
+        .threads(cmp::min(MAXIMUM_SEARCH_THREADS, num_cpus::get()))
+        .types(
+            TypesBuilder::new()
+                .add_defaults()
+                .build()
+                .map_err(|x| io::Error::new(io::ErrorKind::Other, x))?,
+        )
+        .skip_stdout(true);
+
+    Ok(walker_builder.build_parallel())
+}
+
 struct SearchQueryExecutorParallelVistorBuilder<'a> {
     search_id: SearchId,
     target: SearchQueryTarget,
+    binary: bool,
+    search_archives: bool,
     cancel: broadcast::Receiver<()>,
     tx: mpsc::UnboundedSender<SearchQueryMatch>,
     matcher: &'a RegexMatcher,
+    skipped_binary: &'a AtomicU64,
+    index: &'a SearchIndex,
+    index_query: &'a TrigramQuery,
     include_path_filter: &'a SearchQueryPathFilter,
     exclude_path_filter: &'a SearchQueryPathFilter,
     options_filter: &'a SearchQueryOptionsFilter,
@@ -439,11 +601,17 @@ impl<'a> ParallelVisitorBuilder<'a> for SearchQueryExecutorParallelVistorBuilder
         // For files that are searched as part of a recursive search
         //
         // Details:
-        //     * Will quit early if detecting binary file due to null byte
+        //     * Will quit early if detecting binary file due to null byte, unless binary files
+        //       are explicitly requested to be searched, in which case null bytes are converted
+        //       into newlines
         //
         // NOTE: Searchers are not Send/Sync so we must create them here
         let implicit_searcher = SearcherBuilder::new()
-            .binary_detection(BinaryDetection::quit(0))
+            .binary_detection(if self.binary {
+                BinaryDetection::convert(0)
+            } else {
+                BinaryDetection::quit(0)
+            })
             .build();
 
         // For files that are searched because they are provided as one of our initial paths
@@ -460,9 +628,13 @@ impl<'a> ParallelVisitorBuilder<'a> for SearchQueryExecutorParallelVistorBuilder
         Box::new(SearchQueryExecutorParallelVistor {
             search_id: self.search_id,
             target: self.target,
+            search_archives: self.search_archives,
             cancel: self.cancel.resubscribe(),
             tx: self.tx.clone(),
             matcher: self.matcher,
+            skipped_binary: self.skipped_binary,
+            index: self.index,
+            index_query: self.index_query,
             implicit_searcher,
             explicit_searcher,
             include_path_filter: self.include_path_filter,
@@ -475,9 +647,13 @@ impl<'a> ParallelVisitorBuilder<'a> for SearchQueryExecutorParallelVistorBuilder
 struct SearchQueryExecutorParallelVistor<'a> {
     search_id: SearchId,
     target: SearchQueryTarget,
+    search_archives: bool,
     cancel: broadcast::Receiver<()>,
     tx: mpsc::UnboundedSender<SearchQueryMatch>,
     matcher: &'a RegexMatcher,
+    skipped_binary: &'a AtomicU64,
+    index: &'a SearchIndex,
+    index_query: &'a TrigramQuery,
     implicit_searcher: Searcher,
     explicit_searcher: Searcher,
     include_path_filter: &'a SearchQueryPathFilter,
@@ -541,16 +717,62 @@ impl<'a> ParallelVisitor for SearchQueryExecutorParallelVistor<'a> {
             }
 
             // Perform the search against the file's contents
-            SearchQueryTarget::Contents => searcher.search_path(
-                self.matcher,
-                entry.path(),
-                SearchQueryContentsSink {
-                    search_id: id,
-                    path: entry.path(),
-                    matcher: self.matcher,
-                    callback: |m| Ok(self.tx.send(m).is_ok()),
-                },
-            ),
+            SearchQueryTarget::Contents => {
+                let path = entry.path();
+                let tx = &self.tx;
+                let matcher = self.matcher;
+                let skipped_binary = self.skipped_binary;
+
+                // Searches the contents of `reader`, or the file at our path if no reader is
+                // provided, optionally associating matches with a member of an archive
+                let search_contents = |searcher: &mut Searcher,
+                                       member: Option<&Path>,
+                                       reader: Option<&mut dyn io::Read>|
+                 -> io::Result<()> {
+                    let mut sink = SearchQueryContentsSink {
+                        search_id: id,
+                        path,
+                        archive_member: member,
+                        matcher,
+                        binary_detected: false,
+                        callback: |m| Ok(tx.send(m).is_ok()),
+                    };
+
+                    match reader {
+                        Some(reader) => searcher.search_reader(matcher, reader, &mut sink)?,
+                        None => searcher.search_path(matcher, path, &mut sink)?,
+                    }
+
+                    // If binary data caused us to quit searching early, count it as skipped
+                    if sink.binary_detected && searcher.binary_detection().quit_byte().is_some() {
+                        trace!("[Query {id}] Skipped binary file {path:?} (member {member:?})");
+                        skipped_binary.fetch_add(1, Ordering::Relaxed);
+                    }
+
+                    Ok(())
+                };
+
+                let archive = self
+                    .search_archives
+                    .then(|| Archive::from_path(path))
+                    .flatten();
+
+                // If the file is indexed, we can skip it when it does not contain the text
+                // required by our condition; however, archives are indexed by their compressed
+                // contents and are therefore always searched
+                if archive.is_none() && self.index.may_match(path, self.index_query) == Some(false)
+                {
+                    trace!("[Query {id}] Skipping {path:?} as index shows it cannot match");
+                    return WalkState::Continue;
+                }
+
+                match archive {
+                    Some(archive) => archive.visit(path, |member, reader| {
+                        search_contents(searcher, member, Some(reader))
+                    }),
+                    None => search_contents(searcher, None, None),
+                }
+            }
         };
 
         if let Err(x) = res {
@@ -561,144 +783,466 @@ impl<'a> ParallelVisitor for SearchQueryExecutorParallelVistor<'a> {
     }
 }
 
-struct SearchQueryPathFilter {
-    matcher: Option<RegexMatcher>,
-    default_value: bool,
-}
+/// Wrapper around a [`FindFilesMatch`] that orders matches from worst to best, where a higher
+/// score is better and ties favor shorter paths
+struct RankedFindFilesMatch(FindFilesMatch);
 
-impl SearchQueryPathFilter {
-    pub fn new(regex: &str) -> io::Result<Self> {
-        Ok(Self {
-            matcher: Some(
-                RegexMatcher::new(regex)
-                    .map_err(|x| io::Error::new(io::ErrorKind::InvalidInput, x))?,
-            ),
-            default_value: false,
-        })
+impl RankedFindFilesMatch {
+    fn key(&self) -> (i64, Reverse<usize>, Reverse<&Path>) {
+        let path = self.0.path.as_path();
+        (self.0.score, Reverse(path.as_os_str().len()), Reverse(path))
     }
+}
 
-    /// Returns a filter that always returns `value`
-    pub fn fixed(value: bool) -> Self {
-        Self {
-            matcher: None,
-            default_value: value,
-        }
+impl PartialEq for RankedFindFilesMatch {
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
     }
+}
 
-    /// Returns true if path passes the filter
-    pub fn filter(&self, path: impl AsRef<Path>) -> bool {
-        self.try_filter(path).unwrap_or(false)
+impl Eq for RankedFindFilesMatch {}
+
+impl PartialOrd for RankedFindFilesMatch {
+    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+        Some(self.cmp(other))
     }
+}
 
-    fn try_filter(&self, path: impl AsRef<Path>) -> io::Result<bool> {
-        match &self.matcher {
-            Some(matcher) => matcher
-                .is_match(path.as_ref().to_string_lossy().as_bytes())
-                .map_err(|x| io::Error::new(io::ErrorKind::Other, x)),
-            None => Ok(self.default_value),
-        }
+impl Ord for RankedFindFilesMatch {
+    fn cmp(&self, other: &Self) -> cmp::Ordering {
+        self.key().cmp(&other.key())
     }
 }
 
-struct SearchQueryOptionsFilter {
-    target: SearchQueryTarget,
+struct FindFilesReporter {
+    id: SearchId,
     options: SearchQueryOptions,
+    rx: mpsc::UnboundedReceiver<FindFilesMatch>,
+    reply: Box<dyn Reply<Data = DistantResponseData>>,
 }
 
-impl SearchQueryOptionsFilter {
-    pub fn filter(&self, entry: &DirEntry) -> bool {
-        // Check if filetype is allowed
-        let file_type_allowed = self.options.allowed_file_types.is_empty()
-            || entry
-                .file_type()
-                .map(|ft| self.options.allowed_file_types.contains(&ft.into()))
-                .unwrap_or_default();
-
-        // Check if target is appropriate
-        let targeted = match self.target {
-            SearchQueryTarget::Contents => {
-                entry.file_type().map(|ft| ft.is_file()).unwrap_or_default()
-            }
-            _ => true,
-        };
-
-        file_type_allowed && targeted
+impl FindFilesReporter {
+    /// Runs the reporter to completion in an async task
+    pub fn spawn(self) {
+        tokio::spawn(self.run());
     }
-}
 
-#[derive(Clone, Debug)]
-struct SearchQueryPathSink<'a, M, F>
-where
-    M: Matcher,
-    F: FnMut(SearchQueryMatch) -> Result<bool, io::Error>,
-{
-    search_id: SearchId,
-    path: &'a Path,
-    matcher: &'a M,
-    callback: F,
-}
+    async fn run(self) {
+        let Self {
+            id,
+            options,
+            mut rx,
+            reply,
+        } = self;
 
-impl<'a, M, F> Sink for SearchQueryPathSink<'a, M, F>
-where
-    M: Matcher,
-    F: FnMut(SearchQueryMatch) -> Result<bool, io::Error>,
-{
-    type Error = io::Error;
+        // Best matches seen so far, kept as a min-heap so we can evict the worst match whenever
+        // we exceed the limit
+        let mut best: BinaryHeap<Reverse<RankedFindFilesMatch>> = BinaryHeap::new();
 
-    fn matched(&mut self, _searcher: &Searcher, mat: &SinkMatch<'_>) -> Result<bool, io::Error> {
-        let mut submatches = Vec::new();
+        // Queue of matches that we hold until reaching pagination
+        let mut matches = Vec::new();
 
-        // Find all matches within the line
-        let res = self.matcher.find_iter(mat.bytes(), |m| {
-            let bytes = &mat.bytes()[m];
-            submatches.push(SearchQuerySubmatch {
-                r#match: match std::str::from_utf8(bytes) {
-                    Ok(s) => SearchQueryMatchData::Text(s.to_string()),
-                    Err(_) => SearchQueryMatchData::Bytes(bytes.to_vec()),
-                },
-                start: m.start() as u64,
-                end: m.end() as u64,
-            });
+        trace!("[Query {id}] Starting find files reporter with {options:?}");
+        while let Some(m) = rx.recv().await {
+            let m = RankedFindFilesMatch(m);
 
-            true
-        });
+            // If we have a limit and the new match is no better than the worst of our best
+            // matches, we don't need to report it
+            if let Some(len) = options.limit {
+                if best.len() as u64 >= len {
+                    match best.peek() {
+                        Some(Reverse(worst)) if m > *worst => {
+                            best.pop();
+                        }
+                        _ => continue,
+                    }
+                }
+            }
 
-        if let Err(x) = res {
-            error!(
-                "[Query {}] SearchQueryPathSink encountered matcher error: {x}",
-                self.search_id
-            );
+            matches.push(m.0.clone());
+            best.push(Reverse(m));
+
+            // Stream matches once we reach our pagination size, or immediately if none specified
+            if matches.len() as u64 >= options.pagination.unwrap_or(1) {
+                trace!("[Query {id}] Sending {} streamed matches", matches.len());
+                if let Err(x) = reply
+                    .send(DistantResponseData::FindFilesResults {
+                        id,
+                        matches: std::mem::take(&mut matches),
+                    })
+                    .await
+                {
+                    error!("[Query {id}] Failed to send streamed matches: {x}");
+                }
+            }
         }
 
-        // If we have at least one submatch, then we have a match
-        let should_continue = if !submatches.is_empty() {
-            let r#match = SearchQueryMatch::Path(SearchQueryPathMatch {
-                path: self.path.to_path_buf(),
-                submatches,
-            });
-
-            (self.callback)(r#match)?
-        } else {
-            true
-        };
+        // Send any remaining matches
+        if !matches.is_empty() {
+            trace!("[Query {id}] Sending {} remaining matches", matches.len());
+            if let Err(x) = reply
+                .send(DistantResponseData::FindFilesResults { id, matches })
+                .await
+            {
+                error!("[Query {id}] Failed to send final matches: {x}");
+            }
+        }
 
-        Ok(should_continue)
+        // Report that we are done, including the final ranking of our best matches
+        let matches: Vec<FindFilesMatch> = best
+            .into_sorted_vec()
+            .into_iter()
+            .map(|Reverse(m)| m.0)
+            .collect();
+        trace!(
+            "[Query {id}] Reporting as done with {} ranked matches",
+            matches.len()
+        );
+        if let Err(x) = reply
+            .send(DistantResponseData::FindFilesDone { id, matches })
+            .await
+        {
+            error!("[Query {id}] Failed to send done status: {x}");
+        }
     }
 }
 
-#[derive(Clone, Debug)]
-struct SearchQueryContentsSink<'a, M, F>
-where
-    M: Matcher,
-    F: FnMut(SearchQueryMatch) -> Result<bool, io::Error>,
-{
-    search_id: SearchId,
-    path: &'a Path,
-    matcher: &'a M,
-    callback: F,
+struct FindFilesExecutor {
+    id: SearchId,
+    query: FindFilesQuery,
+    walker: WalkParallel,
+    pattern: FuzzyPattern,
+
+    cancel_tx: Option<broadcast::Sender<()>>,
+    cancel_rx: broadcast::Receiver<()>,
+
+    match_tx: mpsc::UnboundedSender<FindFilesMatch>,
+    match_rx: Option<mpsc::UnboundedReceiver<FindFilesMatch>>,
 }
 
-impl<'a, M, F> Sink for SearchQueryContentsSink<'a, M, F>
+impl FindFilesExecutor {
+    /// Creates a new executor
+    pub fn new(query: FindFilesQuery) -> io::Result<Self> {
+        let (cancel_tx, cancel_rx) = broadcast::channel(1);
+        let (match_tx, match_rx) = mpsc::unbounded_channel();
+
+        let pattern = FuzzyPattern::new(&query.pattern);
+        let walker = build_walker(std::slice::from_ref(&query.root), &query.options)?;
+
+        Ok(Self {
+            id: rand::random(),
+            query,
+            walker,
+            pattern,
+            cancel_tx: Some(cancel_tx),
+            cancel_rx,
+
+            match_tx,
+            match_rx: Some(match_rx),
+        })
+    }
+
+    pub fn id(&self) -> SearchId {
+        self.id
+    }
+
+    pub fn take_cancel_tx(&mut self) -> Option<broadcast::Sender<()>> {
+        self.cancel_tx.take()
+    }
+
+    pub fn take_match_rx(&mut self) -> Option<mpsc::UnboundedReceiver<FindFilesMatch>> {
+        self.match_rx.take()
+    }
+
+    /// Runs the executor to completion in another thread
+    pub fn spawn(self, tx: mpsc::Sender<InnerSearchMsg>) {
+        tokio::task::spawn_blocking(move || {
+            let id = self.id;
+            self.run();
+
+            // Once complete, we need to send a request to remove the search from our list
+            let _ = tx.blocking_send(InnerSearchMsg::InternalRemove { id });
+        });
+    }
+
+    fn run(self) {
+        let id = self.id;
+
+        let include_path_filter = match self.query.options.include.as_ref() {
+            Some(condition) => match SearchQueryPathFilter::new(&condition.to_regex_string()) {
+                Ok(filter) => filter,
+                Err(x) => {
+                    error!("[Query {id}] Failed to instantiate include path filter: {x}");
+                    return;
+                }
+            },
+            None => SearchQueryPathFilter::fixed(true),
+        };
+
+        let exclude_path_filter = match self.query.options.exclude.as_ref() {
+            Some(condition) => match SearchQueryPathFilter::new(&condition.to_regex_string()) {
+                Ok(filter) => filter,
+                Err(x) => {
+                    error!("[Query {id}] Failed to instantiate exclude path filter: {x}");
+                    return;
+                }
+            },
+            None => SearchQueryPathFilter::fixed(false),
+        };
+
+        let options_filter = SearchQueryOptionsFilter {
+            target: SearchQueryTarget::Path,
+            options: self.query.options.clone(),
+        };
+
+        let mut builder = FindFilesParallelVisitorBuilder {
+            search_id: id,
+            root: &self.query.root,
+            pattern: &self.pattern,
+            cancel: self.cancel_rx,
+            tx: self.match_tx,
+            include_path_filter: &include_path_filter,
+            exclude_path_filter: &exclude_path_filter,
+            options_filter: &options_filter,
+        };
+
+        // Score all entries and report the ones that match
+        //
+        // NOTE: This should block waiting for all threads to complete
+        self.walker.visit(&mut builder);
+    }
+}
+
+struct FindFilesParallelVisitorBuilder<'a> {
+    search_id: SearchId,
+    root: &'a Path,
+    pattern: &'a FuzzyPattern,
+    cancel: broadcast::Receiver<()>,
+    tx: mpsc::UnboundedSender<FindFilesMatch>,
+    include_path_filter: &'a SearchQueryPathFilter,
+    exclude_path_filter: &'a SearchQueryPathFilter,
+    options_filter: &'a SearchQueryOptionsFilter,
+}
+
+impl<'a> ParallelVisitorBuilder<'a> for FindFilesParallelVisitorBuilder<'a> {
+    fn build(&mut self) -> Box<dyn ParallelVisitor + 'a> {
+        Box::new(FindFilesParallelVisitor {
+            search_id: self.search_id,
+            root: self.root,
+            pattern: self.pattern,
+            cancel: self.cancel.resubscribe(),
+            tx: self.tx.clone(),
+            include_path_filter: self.include_path_filter,
+            exclude_path_filter: self.exclude_path_filter,
+            options_filter: self.options_filter,
+        })
+    }
+}
+
+struct FindFilesParallelVisitor<'a> {
+    search_id: SearchId,
+    root: &'a Path,
+    pattern: &'a FuzzyPattern,
+    cancel: broadcast::Receiver<()>,
+    tx: mpsc::UnboundedSender<FindFilesMatch>,
+    include_path_filter: &'a SearchQueryPathFilter,
+    exclude_path_filter: &'a SearchQueryPathFilter,
+    options_filter: &'a SearchQueryOptionsFilter,
+}
+
+impl<'a> ParallelVisitor for FindFilesParallelVisitor<'a> {
+    fn visit(&mut self, entry: Result<DirEntry, ignore::Error>) -> ignore::WalkState {
+        use ignore::WalkState;
+        let id = self.search_id;
+
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(_) => return WalkState::Skip,
+        };
+
+        // The root itself is never a candidate, only its descendants
+        if entry.depth() == 0
+            || !self.include_path_filter.filter(entry.path())
+            || self.exclude_path_filter.filter(entry.path())
+            || !self.options_filter.filter(&entry)
+        {
+            return WalkState::Continue;
+        }
+
+        // Check if we are being interrupted, and if so exit our loop early
+        match self.cancel.try_recv() {
+            Err(broadcast::error::TryRecvError::Empty) => (),
+            _ => {
+                debug!("[Query {id}] Cancelled");
+                return WalkState::Quit;
+            }
+        }
+
+        // Score the path relative to the root, and then shift the matched indices so they are
+        // relative to the full path that we report
+        let path = entry.path().to_string_lossy();
+        let relative_path = match entry.path().strip_prefix(self.root) {
+            Ok(relative_path) => relative_path.to_string_lossy(),
+            Err(_) => path.clone(),
+        };
+
+        if let Some(m) = self.pattern.score(&relative_path) {
+            let offset = (path.len() - relative_path.len()) as u64;
+            let m = FindFilesMatch {
+                path: entry.path().to_path_buf(),
+                score: m.score,
+                indices: m.indices.into_iter().map(|i| i as u64 + offset).collect(),
+            };
+
+            if self.tx.send(m).is_err() {
+                return WalkState::Quit;
+            }
+        }
+
+        WalkState::Continue
+    }
+}
+
+struct SearchQueryPathFilter {
+    matcher: Option<RegexMatcher>,
+    default_value: bool,
+}
+
+impl SearchQueryPathFilter {
+    pub fn new(regex: &str) -> io::Result<Self> {
+        Ok(Self {
+            matcher: Some(
+                RegexMatcher::new(regex)
+                    .map_err(|x| io::Error::new(io::ErrorKind::InvalidInput, x))?,
+            ),
+            default_value: false,
+        })
+    }
+
+    /// Returns a filter that always returns `value`
+    pub fn fixed(value: bool) -> Self {
+        Self {
+            matcher: None,
+            default_value: value,
+        }
+    }
+
+    /// Returns true if path passes the filter
+    pub fn filter(&self, path: impl AsRef<Path>) -> bool {
+        self.try_filter(path).unwrap_or(false)
+    }
+
+    fn try_filter(&self, path: impl AsRef<Path>) -> io::Result<bool> {
+        match &self.matcher {
+            Some(matcher) => matcher
+                .is_match(path.as_ref().to_string_lossy().as_bytes())
+                .map_err(|x| io::Error::new(io::ErrorKind::Other, x)),
+            None => Ok(self.default_value),
+        }
+    }
+}
+
+struct SearchQueryOptionsFilter {
+    target: SearchQueryTarget,
+    options: SearchQueryOptions,
+}
+
+impl SearchQueryOptionsFilter {
+    pub fn filter(&self, entry: &DirEntry) -> bool {
+        // Check if filetype is allowed
+        let file_type_allowed = self.options.allowed_file_types.is_empty()
+            || entry
+                .file_type()
+                .map(|ft| self.options.allowed_file_types.contains(&ft.into()))
+                .unwrap_or_default();
+
+        // Check if target is appropriate
+        let targeted = match self.target {
+            SearchQueryTarget::Contents => {
+                entry.file_type().map(|ft| ft.is_file()).unwrap_or_default()
+            }
+            _ => true,
+        };
+
+        file_type_allowed && targeted
+    }
+}
+
+#[derive(Clone, Debug)]
+struct SearchQueryPathSink<'a, M, F>
+where
+    M: Matcher,
+    F: FnMut(SearchQueryMatch) -> Result<bool, io::Error>,
+{
+    search_id: SearchId,
+    path: &'a Path,
+    matcher: &'a M,
+    callback: F,
+}
+
+impl<'a, M, F> Sink for SearchQueryPathSink<'a, M, F>
+where
+    M: Matcher,
+    F: FnMut(SearchQueryMatch) -> Result<bool, io::Error>,
+{
+    type Error = io::Error;
+
+    fn matched(&mut self, _searcher: &Searcher, mat: &SinkMatch<'_>) -> Result<bool, io::Error> {
+        let mut submatches = Vec::new();
+
+        // Find all matches within the line
+        let res = self.matcher.find_iter(mat.bytes(), |m| {
+            let bytes = &mat.bytes()[m];
+            submatches.push(SearchQuerySubmatch {
+                r#match: match std::str::from_utf8(bytes) {
+                    Ok(s) => SearchQueryMatchData::Text(s.to_string()),
+                    Err(_) => SearchQueryMatchData::Bytes(bytes.to_vec()),
+                },
+                start: m.start() as u64,
+                end: m.end() as u64,
+            });
+
+            true
+        });
+
+        if let Err(x) = res {
+            error!(
+                "[Query {}] SearchQueryPathSink encountered matcher error: {x}",
+                self.search_id
+            );
+        }
+
+        // If we have at least one submatch, then we have a match
+        let should_continue = if !submatches.is_empty() {
+            let r#match = SearchQueryMatch::Path(SearchQueryPathMatch {
+                path: self.path.to_path_buf(),
+                submatches,
+            });
+
+            (self.callback)(r#match)?
+        } else {
+            true
+        };
+
+        Ok(should_continue)
+    }
+}
+
+#[derive(Clone, Debug)]
+struct SearchQueryContentsSink<'a, M, F>
+where
+    M: Matcher,
+    F: FnMut(SearchQueryMatch) -> Result<bool, io::Error>,
+{
+    search_id: SearchId,
+    path: &'a Path,
+    archive_member: Option<&'a Path>,
+    matcher: &'a M,
+    binary_detected: bool,
+    callback: F,
+}
+
+impl<'a, M, F> Sink for SearchQueryContentsSink<'a, M, F>
 where
     M: Matcher,
     F: FnMut(SearchQueryMatch) -> Result<bool, io::Error>,
@@ -734,6 +1278,7 @@ where
         let should_continue = if !submatches.is_empty() {
             let r#match = SearchQueryMatch::Contents(SearchQueryContentsMatch {
                 path: self.path.to_path_buf(),
+                archive_member: self.archive_member.map(Path::to_path_buf),
                 lines: match std::str::from_utf8(mat.bytes()) {
                     Ok(s) => SearchQueryMatchData::Text(s.to_string()),
                     Err(_) => SearchQueryMatchData::Bytes(mat.bytes().to_vec()),
@@ -756,12 +1301,21 @@ where
 
         Ok(should_continue)
     }
+
+    fn binary_data(
+        &mut self,
+        _searcher: &Searcher,
+        _binary_byte_offset: u64,
+    ) -> Result<bool, io::Error> {
+        self.binary_detected = true;
+        Ok(true)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::data::{FileType, SearchQueryCondition, SearchQueryMatchData};
+    use crate::data::{FileType, FindFilesQuery, SearchQueryCondition, SearchQueryMatchData};
     use assert_fs::prelude::*;
     use std::path::PathBuf;
     use test_log::test;
@@ -811,7 +1365,10 @@ mod tests {
         let data = rx.recv().await;
         assert_eq!(
             data,
-            Some(DistantResponseData::SearchDone { id: search_id })
+            Some(DistantResponseData::SearchDone {
+                id: search_id,
+                skipped_binary: Some(0)
+            })
         );
 
         assert_eq!(rx.recv().await, None);
@@ -888,7 +1445,10 @@ mod tests {
 
         assert_eq!(
             rx.recv().await,
-            Some(DistantResponseData::SearchDone { id: search_id })
+            Some(DistantResponseData::SearchDone {
+                id: search_id,
+                skipped_binary: Some(0)
+            })
         );
 
         assert_eq!(rx.recv().await, None);
@@ -936,44 +1496,227 @@ mod tests {
                         end: child_start + 4,
                     }]
                 },
-                SearchQueryPathMatch {
-                    path: root.child(make_path("path/to")).to_path_buf(),
-                    submatches: vec![SearchQuerySubmatch {
-                        r#match: SearchQueryMatchData::Text("path".to_string()),
-                        start: child_start,
-                        end: child_start + 4,
-                    }]
+                SearchQueryPathMatch {
+                    path: root.child(make_path("path/to")).to_path_buf(),
+                    submatches: vec![SearchQuerySubmatch {
+                        r#match: SearchQueryMatchData::Text("path".to_string()),
+                        start: child_start,
+                        end: child_start + 4,
+                    }]
+                },
+                SearchQueryPathMatch {
+                    path: root.child(make_path("path/to/file1.txt")).to_path_buf(),
+                    submatches: vec![SearchQuerySubmatch {
+                        r#match: SearchQueryMatchData::Text("path".to_string()),
+                        start: child_start,
+                        end: child_start + 4,
+                    }]
+                },
+                SearchQueryPathMatch {
+                    path: root.child(make_path("path/to/file2.txt")).to_path_buf(),
+                    submatches: vec![SearchQuerySubmatch {
+                        r#match: SearchQueryMatchData::Text("path".to_string()),
+                        start: child_start,
+                        end: child_start + 4,
+                    }]
+                }
+            ]
+        );
+
+        let data = rx.recv().await;
+        assert_eq!(
+            data,
+            Some(DistantResponseData::SearchDone {
+                id: search_id,
+                skipped_binary: Some(0)
+            })
+        );
+
+        assert_eq!(rx.recv().await, None);
+    }
+
+    #[test(tokio::test)]
+    async fn should_support_targeting_contents() {
+        let root = setup_dir(vec![
+            ("path/to/file1.txt", "some\nlines of text in\na\nfile"),
+            ("path/to/file2.txt", "more text"),
+            ("other/file.txt", "some other file with text"),
+            ("other/dir/bin", "asdfasdfasdfasdfasdfasdfasdfasdfasdf"),
+        ]);
+
+        let state = SearchState::new();
+        let (reply, mut rx) = mpsc::channel(100);
+
+        let query = SearchQuery {
+            paths: vec![root.path().to_path_buf()],
+            target: SearchQueryTarget::Contents,
+            condition: SearchQueryCondition::regex("text"),
+            options: Default::default(),
+        };
+
+        let search_id = state.start(query, Box::new(reply)).await.unwrap();
+
+        let mut matches = get_matches(rx.recv().await.unwrap())
+            .into_iter()
+            .filter_map(|m| m.into_contents_match())
+            .collect::<Vec<_>>();
+
+        matches.sort_unstable_by_key(|m| m.path.to_path_buf());
+
+        assert_eq!(
+            matches,
+            vec![
+                SearchQueryContentsMatch {
+                    path: root.child(make_path("other/file.txt")).to_path_buf(),
+                    archive_member: None,
+                    lines: SearchQueryMatchData::text("some other file with text"),
+                    line_number: 1,
+                    absolute_offset: 0,
+                    submatches: vec![SearchQuerySubmatch {
+                        r#match: SearchQueryMatchData::Text("text".to_string()),
+                        start: 21,
+                        end: 25,
+                    }]
+                },
+                SearchQueryContentsMatch {
+                    path: root.child(make_path("path/to/file1.txt")).to_path_buf(),
+                    archive_member: None,
+                    lines: SearchQueryMatchData::text("lines of text in\n"),
+                    line_number: 2,
+                    absolute_offset: 5,
+                    submatches: vec![SearchQuerySubmatch {
+                        r#match: SearchQueryMatchData::Text("text".to_string()),
+                        start: 9,
+                        end: 13,
+                    }]
+                },
+                SearchQueryContentsMatch {
+                    path: root.child(make_path("path/to/file2.txt")).to_path_buf(),
+                    archive_member: None,
+                    lines: SearchQueryMatchData::text("more text"),
+                    line_number: 1,
+                    absolute_offset: 0,
+                    submatches: vec![SearchQuerySubmatch {
+                        r#match: SearchQueryMatchData::Text("text".to_string()),
+                        start: 5,
+                        end: 9,
+                    }]
+                }
+            ]
+        );
+
+        let data = rx.recv().await;
+        assert_eq!(
+            data,
+            Some(DistantResponseData::SearchDone {
+                id: search_id,
+                skipped_binary: Some(0)
+            })
+        );
+
+        assert_eq!(rx.recv().await, None);
+    }
+
+    #[test(tokio::test)]
+    async fn should_support_multiple_submatches() {
+        let root = setup_dir(vec![("path/to/file.txt", "aa ab ac\nba bb bc\nca cb cc")]);
+
+        let state = SearchState::new();
+        let (reply, mut rx) = mpsc::channel(100);
+
+        let query = SearchQuery {
+            paths: vec![root.path().to_path_buf()],
+            target: SearchQueryTarget::Contents,
+            condition: SearchQueryCondition::regex(r"[abc][ab]"),
+            options: Default::default(),
+        };
+
+        let search_id = state.start(query, Box::new(reply)).await.unwrap();
+
+        let mut matches = get_matches(rx.recv().await.unwrap())
+            .into_iter()
+            .filter_map(|m| m.into_contents_match())
+            .collect::<Vec<_>>();
+
+        matches.sort_unstable_by_key(|m| m.line_number);
+
+        assert_eq!(
+            matches,
+            vec![
+                SearchQueryContentsMatch {
+                    path: root.child(make_path("path/to/file.txt")).to_path_buf(),
+                    archive_member: None,
+                    lines: SearchQueryMatchData::text("aa ab ac\n"),
+                    line_number: 1,
+                    absolute_offset: 0,
+                    submatches: vec![
+                        SearchQuerySubmatch {
+                            r#match: SearchQueryMatchData::Text("aa".to_string()),
+                            start: 0,
+                            end: 2,
+                        },
+                        SearchQuerySubmatch {
+                            r#match: SearchQueryMatchData::Text("ab".to_string()),
+                            start: 3,
+                            end: 5,
+                        }
+                    ]
+                },
+                SearchQueryContentsMatch {
+                    path: root.child(make_path("path/to/file.txt")).to_path_buf(),
+                    archive_member: None,
+                    lines: SearchQueryMatchData::text("ba bb bc\n"),
+                    line_number: 2,
+                    absolute_offset: 9,
+                    submatches: vec![
+                        SearchQuerySubmatch {
+                            r#match: SearchQueryMatchData::Text("ba".to_string()),
+                            start: 0,
+                            end: 2,
+                        },
+                        SearchQuerySubmatch {
+                            r#match: SearchQueryMatchData::Text("bb".to_string()),
+                            start: 3,
+                            end: 5,
+                        }
+                    ]
                 },
-                SearchQueryPathMatch {
-                    path: root.child(make_path("path/to/file1.txt")).to_path_buf(),
-                    submatches: vec![SearchQuerySubmatch {
-                        r#match: SearchQueryMatchData::Text("path".to_string()),
-                        start: child_start,
-                        end: child_start + 4,
-                    }]
+                SearchQueryContentsMatch {
+                    path: root.child(make_path("path/to/file.txt")).to_path_buf(),
+                    archive_member: None,
+                    lines: SearchQueryMatchData::text("ca cb cc"),
+                    line_number: 3,
+                    absolute_offset: 18,
+                    submatches: vec![
+                        SearchQuerySubmatch {
+                            r#match: SearchQueryMatchData::Text("ca".to_string()),
+                            start: 0,
+                            end: 2,
+                        },
+                        SearchQuerySubmatch {
+                            r#match: SearchQueryMatchData::Text("cb".to_string()),
+                            start: 3,
+                            end: 5,
+                        }
+                    ]
                 },
-                SearchQueryPathMatch {
-                    path: root.child(make_path("path/to/file2.txt")).to_path_buf(),
-                    submatches: vec![SearchQuerySubmatch {
-                        r#match: SearchQueryMatchData::Text("path".to_string()),
-                        start: child_start,
-                        end: child_start + 4,
-                    }]
-                }
             ]
         );
 
         let data = rx.recv().await;
         assert_eq!(
             data,
-            Some(DistantResponseData::SearchDone { id: search_id })
+            Some(DistantResponseData::SearchDone {
+                id: search_id,
+                skipped_binary: Some(0)
+            })
         );
 
         assert_eq!(rx.recv().await, None);
     }
 
     #[test(tokio::test)]
-    async fn should_support_targeting_contents() {
+    async fn should_send_paginated_results_if_specified() {
         let root = setup_dir(vec![
             ("path/to/file1.txt", "some\nlines of text in\na\nfile"),
             ("path/to/file2.txt", "more text"),
@@ -988,16 +1731,36 @@ mod tests {
             paths: vec![root.path().to_path_buf()],
             target: SearchQueryTarget::Contents,
             condition: SearchQueryCondition::regex("text"),
-            options: Default::default(),
+            options: SearchQueryOptions {
+                pagination: Some(2),
+                ..Default::default()
+            },
         };
 
         let search_id = state.start(query, Box::new(reply)).await.unwrap();
 
-        let mut matches = get_matches(rx.recv().await.unwrap())
+        // Collect all matches here
+        let mut matches = Vec::new();
+
+        // Get first two matches
+        let paginated_matches = get_matches(rx.recv().await.unwrap())
+            .into_iter()
+            .filter_map(|m| m.into_contents_match())
+            .collect::<Vec<_>>();
+
+        assert_eq!(paginated_matches.len(), 2);
+        matches.extend(paginated_matches);
+
+        // Get last match
+        let paginated_matches = get_matches(rx.recv().await.unwrap())
             .into_iter()
             .filter_map(|m| m.into_contents_match())
             .collect::<Vec<_>>();
 
+        assert_eq!(paginated_matches.len(), 1);
+        matches.extend(paginated_matches);
+
+        // Sort our matches so we can check them all
         matches.sort_unstable_by_key(|m| m.path.to_path_buf());
 
         assert_eq!(
@@ -1005,6 +1768,7 @@ mod tests {
             vec![
                 SearchQueryContentsMatch {
                     path: root.child(make_path("other/file.txt")).to_path_buf(),
+                    archive_member: None,
                     lines: SearchQueryMatchData::text("some other file with text"),
                     line_number: 1,
                     absolute_offset: 0,
@@ -1016,6 +1780,7 @@ mod tests {
                 },
                 SearchQueryContentsMatch {
                     path: root.child(make_path("path/to/file1.txt")).to_path_buf(),
+                    archive_member: None,
                     lines: SearchQueryMatchData::text("lines of text in\n"),
                     line_number: 2,
                     absolute_offset: 5,
@@ -1027,6 +1792,7 @@ mod tests {
                 },
                 SearchQueryContentsMatch {
                     path: root.child(make_path("path/to/file2.txt")).to_path_buf(),
+                    archive_member: None,
                     lines: SearchQueryMatchData::text("more text"),
                     line_number: 1,
                     absolute_offset: 0,
@@ -1042,15 +1808,206 @@ mod tests {
         let data = rx.recv().await;
         assert_eq!(
             data,
-            Some(DistantResponseData::SearchDone { id: search_id })
+            Some(DistantResponseData::SearchDone {
+                id: search_id,
+                skipped_binary: Some(0)
+            })
+        );
+
+        assert_eq!(rx.recv().await, None);
+    }
+
+    #[test(tokio::test)]
+    async fn should_send_maximum_of_limit_results_if_specified() {
+        let root = setup_dir(vec![
+            ("path/to/file1.txt", "some\nlines of text in\na\nfile"),
+            ("path/to/file2.txt", "more text"),
+            ("other/file.txt", "some other file with text"),
+            ("other/dir/bin", "asdfasdfasdfasdfasdfasdfasdfasdfasdf"),
+        ]);
+
+        let state = SearchState::new();
+        let (reply, mut rx) = mpsc::channel(100);
+
+        let query = SearchQuery {
+            paths: vec![root.path().to_path_buf()],
+            target: SearchQueryTarget::Contents,
+            condition: SearchQueryCondition::regex("text"),
+            options: SearchQueryOptions {
+                limit: Some(2),
+                ..Default::default()
+            },
+        };
+
+        let search_id = state.start(query, Box::new(reply)).await.unwrap();
+
+        // Get all matches and verify the len
+        let matches = get_matches(rx.recv().await.unwrap());
+        assert_eq!(matches.len(), 2);
+
+        let data = rx.recv().await;
+        assert_eq!(
+            data,
+            Some(DistantResponseData::SearchDone {
+                id: search_id,
+                skipped_binary: Some(0)
+            })
+        );
+
+        assert_eq!(rx.recv().await, None);
+    }
+
+    #[test(tokio::test)]
+    async fn should_send_maximum_of_limit_results_with_pagination_if_specified() {
+        let root = setup_dir(vec![
+            ("path/to/file1.txt", "some\nlines of text in\na\nfile"),
+            ("path/to/file2.txt", "more text"),
+            ("other/file.txt", "some other file with text"),
+            ("other/dir/bin", "asdfasdfasdfasdfasdfasdfasdfasdfasdf"),
+        ]);
+
+        let state = SearchState::new();
+        let (reply, mut rx) = mpsc::channel(100);
+
+        let query = SearchQuery {
+            paths: vec![root.path().to_path_buf()],
+            target: SearchQueryTarget::Contents,
+            condition: SearchQueryCondition::regex("text"),
+            options: SearchQueryOptions {
+                pagination: Some(1),
+                limit: Some(2),
+                ..Default::default()
+            },
+        };
+
+        let search_id = state.start(query, Box::new(reply)).await.unwrap();
+
+        // Verify that we get one match at a time up to the limit
+        let matches = get_matches(rx.recv().await.unwrap());
+        assert_eq!(matches.len(), 1);
+
+        let matches = get_matches(rx.recv().await.unwrap());
+        assert_eq!(matches.len(), 1);
+
+        let data = rx.recv().await;
+        assert_eq!(
+            data,
+            Some(DistantResponseData::SearchDone {
+                id: search_id,
+                skipped_binary: Some(0)
+            })
         );
 
         assert_eq!(rx.recv().await, None);
     }
 
     #[test(tokio::test)]
-    async fn should_support_multiple_submatches() {
-        let root = setup_dir(vec![("path/to/file.txt", "aa ab ac\nba bb bc\nca cb cc")]);
+    async fn should_traverse_no_deeper_than_max_depth_if_specified() {
+        let root = setup_dir(vec![
+            ("path/to/file1.txt", ""),
+            ("path/to/file2.txt", ""),
+            ("other/file.txt", ""),
+            ("other/dir/bin", ""),
+        ]);
+
+        async fn test_max_depth(
+            root: &assert_fs::TempDir,
+            depth: u64,
+            expected_paths: Vec<PathBuf>,
+        ) {
+            let state = SearchState::new();
+            let (reply, mut rx) = mpsc::channel(100);
+            let query = SearchQuery {
+                paths: vec![root.path().to_path_buf()],
+                target: SearchQueryTarget::Path,
+                condition: SearchQueryCondition::regex(".*"),
+                options: SearchQueryOptions {
+                    max_depth: Some(depth),
+                    ..Default::default()
+                },
+            };
+
+            let search_id = state.start(query, Box::new(reply)).await.unwrap();
+
+            let mut paths = get_matches(rx.recv().await.unwrap())
+                .into_iter()
+                .filter_map(|m| m.into_path_match())
+                .map(|m| m.path)
+                .collect::<Vec<_>>();
+
+            paths.sort_unstable();
+
+            assert_eq!(paths, expected_paths);
+
+            let data = rx.recv().await;
+            assert_eq!(
+                data,
+                Some(DistantResponseData::SearchDone {
+                    id: search_id,
+                    skipped_binary: Some(0)
+                })
+            );
+
+            assert_eq!(rx.recv().await, None);
+        }
+
+        // Maximum depth of 0 should only include root
+        test_max_depth(&root, 0, vec![root.to_path_buf()]).await;
+
+        // Maximum depth of 1 should only include root and children
+        test_max_depth(
+            &root,
+            1,
+            vec![
+                root.to_path_buf(),
+                root.child(make_path("other")).to_path_buf(),
+                root.child(make_path("path")).to_path_buf(),
+            ],
+        )
+        .await;
+
+        // Maximum depth of 2 should only include root and children and grandchildren
+        test_max_depth(
+            &root,
+            2,
+            vec![
+                root.to_path_buf(),
+                root.child(make_path("other")).to_path_buf(),
+                root.child(make_path("other/dir")).to_path_buf(),
+                root.child(make_path("other/file.txt")).to_path_buf(),
+                root.child(make_path("path")).to_path_buf(),
+                root.child(make_path("path/to")).to_path_buf(),
+            ],
+        )
+        .await;
+
+        // Maximum depth of 3 should include everything we have in our test
+        test_max_depth(
+            &root,
+            3,
+            vec![
+                root.to_path_buf(),
+                root.child(make_path("other")).to_path_buf(),
+                root.child(make_path("other/dir")).to_path_buf(),
+                root.child(make_path("other/dir/bin")).to_path_buf(),
+                root.child(make_path("other/file.txt")).to_path_buf(),
+                root.child(make_path("path")).to_path_buf(),
+                root.child(make_path("path/to")).to_path_buf(),
+                root.child(make_path("path/to/file1.txt")).to_path_buf(),
+                root.child(make_path("path/to/file2.txt")).to_path_buf(),
+            ],
+        )
+        .await;
+    }
+
+    #[test(tokio::test)]
+    async fn should_filter_searched_paths_to_only_those_that_match_include_regex() {
+        let root = setup_dir(vec![
+            ("path/to/file1.txt", "some\nlines of text in\na\nfile"),
+            ("path/to/file2.txt", "more text"),
+            ("other/file.txt", "some other file with text"),
+            ("other/dir/bin", "asdfasdfasdfasdfasdfasdfasdfasdfasdf"),
+        ]);
 
         let state = SearchState::new();
         let (reply, mut rx) = mpsc::channel(100);
@@ -1058,8 +2015,11 @@ mod tests {
         let query = SearchQuery {
             paths: vec![root.path().to_path_buf()],
             target: SearchQueryTarget::Contents,
-            condition: SearchQueryCondition::regex(r"[abc][ab]"),
-            options: Default::default(),
+            condition: SearchQueryCondition::regex("text"),
+            options: SearchQueryOptions {
+                include: Some(SearchQueryCondition::regex("other")),
+                ..Default::default()
+            },
         };
 
         let search_id = state.start(query, Box::new(reply)).await.unwrap();
@@ -1069,79 +2029,38 @@ mod tests {
             .filter_map(|m| m.into_contents_match())
             .collect::<Vec<_>>();
 
-        matches.sort_unstable_by_key(|m| m.line_number);
+        matches.sort_unstable_by_key(|m| m.path.to_path_buf());
 
         assert_eq!(
             matches,
-            vec![
-                SearchQueryContentsMatch {
-                    path: root.child(make_path("path/to/file.txt")).to_path_buf(),
-                    lines: SearchQueryMatchData::text("aa ab ac\n"),
-                    line_number: 1,
-                    absolute_offset: 0,
-                    submatches: vec![
-                        SearchQuerySubmatch {
-                            r#match: SearchQueryMatchData::Text("aa".to_string()),
-                            start: 0,
-                            end: 2,
-                        },
-                        SearchQuerySubmatch {
-                            r#match: SearchQueryMatchData::Text("ab".to_string()),
-                            start: 3,
-                            end: 5,
-                        }
-                    ]
-                },
-                SearchQueryContentsMatch {
-                    path: root.child(make_path("path/to/file.txt")).to_path_buf(),
-                    lines: SearchQueryMatchData::text("ba bb bc\n"),
-                    line_number: 2,
-                    absolute_offset: 9,
-                    submatches: vec![
-                        SearchQuerySubmatch {
-                            r#match: SearchQueryMatchData::Text("ba".to_string()),
-                            start: 0,
-                            end: 2,
-                        },
-                        SearchQuerySubmatch {
-                            r#match: SearchQueryMatchData::Text("bb".to_string()),
-                            start: 3,
-                            end: 5,
-                        }
-                    ]
-                },
-                SearchQueryContentsMatch {
-                    path: root.child(make_path("path/to/file.txt")).to_path_buf(),
-                    lines: SearchQueryMatchData::text("ca cb cc"),
-                    line_number: 3,
-                    absolute_offset: 18,
-                    submatches: vec![
-                        SearchQuerySubmatch {
-                            r#match: SearchQueryMatchData::Text("ca".to_string()),
-                            start: 0,
-                            end: 2,
-                        },
-                        SearchQuerySubmatch {
-                            r#match: SearchQueryMatchData::Text("cb".to_string()),
-                            start: 3,
-                            end: 5,
-                        }
-                    ]
-                },
-            ]
+            vec![SearchQueryContentsMatch {
+                path: root.child(make_path("other/file.txt")).to_path_buf(),
+                archive_member: None,
+                lines: SearchQueryMatchData::text("some other file with text"),
+                line_number: 1,
+                absolute_offset: 0,
+                submatches: vec![SearchQuerySubmatch {
+                    r#match: SearchQueryMatchData::Text("text".to_string()),
+                    start: 21,
+                    end: 25,
+                }]
+            }]
         );
 
         let data = rx.recv().await;
         assert_eq!(
             data,
-            Some(DistantResponseData::SearchDone { id: search_id })
+            Some(DistantResponseData::SearchDone {
+                id: search_id,
+                skipped_binary: Some(0)
+            })
         );
 
         assert_eq!(rx.recv().await, None);
     }
 
     #[test(tokio::test)]
-    async fn should_send_paginated_results_if_specified() {
+    async fn should_filter_searched_paths_to_only_those_that_do_not_match_exclude_regex() {
         let root = setup_dir(vec![
             ("path/to/file1.txt", "some\nlines of text in\na\nfile"),
             ("path/to/file2.txt", "more text"),
@@ -1157,53 +2076,26 @@ mod tests {
             target: SearchQueryTarget::Contents,
             condition: SearchQueryCondition::regex("text"),
             options: SearchQueryOptions {
-                pagination: Some(2),
+                exclude: Some(SearchQueryCondition::regex("other")),
                 ..Default::default()
             },
         };
 
         let search_id = state.start(query, Box::new(reply)).await.unwrap();
 
-        // Collect all matches here
-        let mut matches = Vec::new();
-
-        // Get first two matches
-        let paginated_matches = get_matches(rx.recv().await.unwrap())
-            .into_iter()
-            .filter_map(|m| m.into_contents_match())
-            .collect::<Vec<_>>();
-
-        assert_eq!(paginated_matches.len(), 2);
-        matches.extend(paginated_matches);
-
-        // Get last match
-        let paginated_matches = get_matches(rx.recv().await.unwrap())
+        let mut matches = get_matches(rx.recv().await.unwrap())
             .into_iter()
             .filter_map(|m| m.into_contents_match())
             .collect::<Vec<_>>();
 
-        assert_eq!(paginated_matches.len(), 1);
-        matches.extend(paginated_matches);
-
-        // Sort our matches so we can check them all
         matches.sort_unstable_by_key(|m| m.path.to_path_buf());
 
         assert_eq!(
             matches,
             vec![
-                SearchQueryContentsMatch {
-                    path: root.child(make_path("other/file.txt")).to_path_buf(),
-                    lines: SearchQueryMatchData::text("some other file with text"),
-                    line_number: 1,
-                    absolute_offset: 0,
-                    submatches: vec![SearchQuerySubmatch {
-                        r#match: SearchQueryMatchData::Text("text".to_string()),
-                        start: 21,
-                        end: 25,
-                    }]
-                },
                 SearchQueryContentsMatch {
                     path: root.child(make_path("path/to/file1.txt")).to_path_buf(),
+                    archive_member: None,
                     lines: SearchQueryMatchData::text("lines of text in\n"),
                     line_number: 2,
                     absolute_offset: 5,
@@ -1215,6 +2107,7 @@ mod tests {
                 },
                 SearchQueryContentsMatch {
                     path: root.child(make_path("path/to/file2.txt")).to_path_buf(),
+                    archive_member: None,
                     lines: SearchQueryMatchData::text("more text"),
                     line_number: 1,
                     absolute_offset: 0,
@@ -1230,112 +2123,135 @@ mod tests {
         let data = rx.recv().await;
         assert_eq!(
             data,
-            Some(DistantResponseData::SearchDone { id: search_id })
+            Some(DistantResponseData::SearchDone {
+                id: search_id,
+                skipped_binary: Some(0)
+            })
         );
 
         assert_eq!(rx.recv().await, None);
     }
 
     #[test(tokio::test)]
-    async fn should_send_maximum_of_limit_results_if_specified() {
-        let root = setup_dir(vec![
-            ("path/to/file1.txt", "some\nlines of text in\na\nfile"),
-            ("path/to/file2.txt", "more text"),
-            ("other/file.txt", "some other file with text"),
-            ("other/dir/bin", "asdfasdfasdfasdfasdfasdfasdfasdfasdf"),
-        ]);
+    async fn should_return_binary_match_data_if_match_is_not_utf8_but_path_is_explicit() {
+        let root = assert_fs::TempDir::new().unwrap();
+        let bin_file = root.child(make_path("file.bin"));
+
+        // Write some invalid bytes, a newline, and then "hello"
+        bin_file
+            .write_binary(&[0, 159, 146, 150, 10, 72, 69, 76, 76, 79])
+            .unwrap();
 
         let state = SearchState::new();
         let (reply, mut rx) = mpsc::channel(100);
 
+        // NOTE: We provide regex that matches an invalid UTF-8 character by disabling the u flag
+        //       and checking for 0x9F (159)
         let query = SearchQuery {
-            paths: vec![root.path().to_path_buf()],
+            paths: vec![bin_file.path().to_path_buf()],
             target: SearchQueryTarget::Contents,
-            condition: SearchQueryCondition::regex("text"),
-            options: SearchQueryOptions {
-                limit: Some(2),
-                ..Default::default()
-            },
+            condition: SearchQueryCondition::regex(r"(?-u:\x9F)"),
+            options: Default::default(),
         };
 
         let search_id = state.start(query, Box::new(reply)).await.unwrap();
 
-        // Get all matches and verify the len
-        let matches = get_matches(rx.recv().await.unwrap());
-        assert_eq!(matches.len(), 2);
+        let matches = get_matches(rx.recv().await.unwrap())
+            .into_iter()
+            .filter_map(|m| m.into_contents_match())
+            .collect::<Vec<_>>();
+
+        // NOTE: Null bytes are treated as newlines, so that shifts us to being on "line 2"
+        //       and associated other shifts
+        assert_eq!(
+            matches,
+            vec![SearchQueryContentsMatch {
+                path: root.child(make_path("file.bin")).to_path_buf(),
+                archive_member: None,
+                lines: SearchQueryMatchData::bytes([159, 146, 150, 10]),
+                line_number: 2,
+                absolute_offset: 1,
+                submatches: vec![SearchQuerySubmatch {
+                    r#match: SearchQueryMatchData::bytes([159]),
+                    start: 0,
+                    end: 1,
+                }]
+            },]
+        );
 
         let data = rx.recv().await;
         assert_eq!(
             data,
-            Some(DistantResponseData::SearchDone { id: search_id })
+            Some(DistantResponseData::SearchDone {
+                id: search_id,
+                skipped_binary: Some(0)
+            })
         );
 
         assert_eq!(rx.recv().await, None);
     }
 
     #[test(tokio::test)]
-    async fn should_send_maximum_of_limit_results_with_pagination_if_specified() {
-        let root = setup_dir(vec![
-            ("path/to/file1.txt", "some\nlines of text in\na\nfile"),
-            ("path/to/file2.txt", "more text"),
-            ("other/file.txt", "some other file with text"),
-            ("other/dir/bin", "asdfasdfasdfasdfasdfasdfasdfasdfasdf"),
-        ]);
+    async fn should_not_return_binary_match_data_if_match_is_not_utf8_and_not_explicit_path() {
+        let root = assert_fs::TempDir::new().unwrap();
+        let bin_file = root.child(make_path("file.bin"));
+
+        // Write some invalid bytes, a newline, and then "hello"
+        bin_file
+            .write_binary(&[0, 159, 146, 150, 10, 72, 69, 76, 76, 79])
+            .unwrap();
 
         let state = SearchState::new();
         let (reply, mut rx) = mpsc::channel(100);
 
+        // NOTE: We provide regex that matches an invalid UTF-8 character by disabling the u flag
+        //       and checking for 0x9F (159)
         let query = SearchQuery {
             paths: vec![root.path().to_path_buf()],
             target: SearchQueryTarget::Contents,
-            condition: SearchQueryCondition::regex("text"),
-            options: SearchQueryOptions {
-                pagination: Some(1),
-                limit: Some(2),
-                ..Default::default()
-            },
+            condition: SearchQueryCondition::regex(r"(?-u:\x9F)"),
+            options: Default::default(),
         };
 
         let search_id = state.start(query, Box::new(reply)).await.unwrap();
 
-        // Verify that we get one match at a time up to the limit
-        let matches = get_matches(rx.recv().await.unwrap());
-        assert_eq!(matches.len(), 1);
-
-        let matches = get_matches(rx.recv().await.unwrap());
-        assert_eq!(matches.len(), 1);
-
+        // Get done indicator next as there were no matches, reporting the skipped binary file
         let data = rx.recv().await;
         assert_eq!(
             data,
-            Some(DistantResponseData::SearchDone { id: search_id })
+            Some(DistantResponseData::SearchDone {
+                id: search_id,
+                skipped_binary: Some(1)
+            })
         );
 
         assert_eq!(rx.recv().await, None);
     }
 
     #[test(tokio::test)]
-    async fn should_traverse_no_deeper_than_max_depth_if_specified() {
-        let root = setup_dir(vec![
-            ("path/to/file1.txt", ""),
-            ("path/to/file2.txt", ""),
-            ("other/file.txt", ""),
-            ("other/dir/bin", ""),
-        ]);
+    async fn should_filter_searched_paths_to_only_those_are_an_allowed_file_type() {
+        let root = assert_fs::TempDir::new().unwrap();
+        let file = root.child(make_path("file"));
+        file.touch().unwrap();
+        root.child(make_path("dir")).create_dir_all().unwrap();
+        root.child(make_path("symlink"))
+            .symlink_to_file(file.path())
+            .unwrap();
 
-        async fn test_max_depth(
+        async fn test_allowed_file_types(
             root: &assert_fs::TempDir,
-            depth: u64,
+            allowed_file_types: Vec<FileType>,
             expected_paths: Vec<PathBuf>,
         ) {
             let state = SearchState::new();
             let (reply, mut rx) = mpsc::channel(100);
+
             let query = SearchQuery {
                 paths: vec![root.path().to_path_buf()],
                 target: SearchQueryTarget::Path,
                 condition: SearchQueryCondition::regex(".*"),
                 options: SearchQueryOptions {
-                    max_depth: Some(depth),
+                    allowed_file_types: allowed_file_types.iter().copied().collect(),
                     ..Default::default()
                 },
             };
@@ -1350,141 +2266,203 @@ mod tests {
 
             paths.sort_unstable();
 
-            assert_eq!(paths, expected_paths);
+            assert_eq!(
+                paths, expected_paths,
+                "Path types did not match allowed: {allowed_file_types:?}"
+            );
 
             let data = rx.recv().await;
             assert_eq!(
                 data,
-                Some(DistantResponseData::SearchDone { id: search_id })
+                Some(DistantResponseData::SearchDone {
+                    id: search_id,
+                    skipped_binary: Some(0)
+                })
             );
 
             assert_eq!(rx.recv().await, None);
         }
 
-        // Maximum depth of 0 should only include root
-        test_max_depth(&root, 0, vec![root.to_path_buf()]).await;
-
-        // Maximum depth of 1 should only include root and children
-        test_max_depth(
+        // Empty set of allowed types falls back to allowing everything
+        test_allowed_file_types(
             &root,
-            1,
+            vec![],
             vec![
                 root.to_path_buf(),
-                root.child(make_path("other")).to_path_buf(),
-                root.child(make_path("path")).to_path_buf(),
+                root.child("dir").to_path_buf(),
+                root.child("file").to_path_buf(),
+                root.child("symlink").to_path_buf(),
             ],
         )
         .await;
 
-        // Maximum depth of 2 should only include root and children and grandchildren
-        test_max_depth(
+        test_allowed_file_types(
+            &root,
+            vec![FileType::File],
+            vec![root.child("file").to_path_buf()],
+        )
+        .await;
+
+        test_allowed_file_types(
             &root,
-            2,
-            vec![
-                root.to_path_buf(),
-                root.child(make_path("other")).to_path_buf(),
-                root.child(make_path("other/dir")).to_path_buf(),
-                root.child(make_path("other/file.txt")).to_path_buf(),
-                root.child(make_path("path")).to_path_buf(),
-                root.child(make_path("path/to")).to_path_buf(),
-            ],
+            vec![FileType::Dir],
+            vec![root.to_path_buf(), root.child("dir").to_path_buf()],
         )
         .await;
 
-        // Maximum depth of 3 should include everything we have in our test
-        test_max_depth(
+        test_allowed_file_types(
             &root,
-            3,
-            vec![
-                root.to_path_buf(),
-                root.child(make_path("other")).to_path_buf(),
-                root.child(make_path("other/dir")).to_path_buf(),
-                root.child(make_path("other/dir/bin")).to_path_buf(),
-                root.child(make_path("other/file.txt")).to_path_buf(),
-                root.child(make_path("path")).to_path_buf(),
-                root.child(make_path("path/to")).to_path_buf(),
-                root.child(make_path("path/to/file1.txt")).to_path_buf(),
-                root.child(make_path("path/to/file2.txt")).to_path_buf(),
-            ],
+            vec![FileType::Symlink],
+            vec![root.child("symlink").to_path_buf()],
         )
         .await;
     }
 
     #[test(tokio::test)]
-    async fn should_filter_searched_paths_to_only_those_that_match_include_regex() {
-        let root = setup_dir(vec![
-            ("path/to/file1.txt", "some\nlines of text in\na\nfile"),
-            ("path/to/file2.txt", "more text"),
-            ("other/file.txt", "some other file with text"),
-            ("other/dir/bin", "asdfasdfasdfasdfasdfasdfasdfasdfasdf"),
-        ]);
+    async fn should_follow_not_symbolic_links_if_specified_in_options() {
+        let root = assert_fs::TempDir::new().unwrap();
+
+        let file = root.child(make_path("file"));
+        file.touch().unwrap();
+        let dir = root.child(make_path("dir"));
+        dir.create_dir_all().unwrap();
+        root.child(make_path("file_symlink"))
+            .symlink_to_file(file.path())
+            .unwrap();
+        root.child(make_path("dir_symlink"))
+            .symlink_to_dir(dir.path())
+            .unwrap();
 
         let state = SearchState::new();
         let (reply, mut rx) = mpsc::channel(100);
 
         let query = SearchQuery {
             paths: vec![root.path().to_path_buf()],
-            target: SearchQueryTarget::Contents,
-            condition: SearchQueryCondition::regex("text"),
+            target: SearchQueryTarget::Path,
+            condition: SearchQueryCondition::regex(".*"),
             options: SearchQueryOptions {
-                include: Some(SearchQueryCondition::regex("other")),
+                follow_symbolic_links: true,
                 ..Default::default()
             },
         };
 
         let search_id = state.start(query, Box::new(reply)).await.unwrap();
 
-        let mut matches = get_matches(rx.recv().await.unwrap())
+        let mut paths = get_matches(rx.recv().await.unwrap())
             .into_iter()
-            .filter_map(|m| m.into_contents_match())
+            .filter_map(|m| m.into_path_match())
+            .map(|m| m.path)
             .collect::<Vec<_>>();
 
-        matches.sort_unstable_by_key(|m| m.path.to_path_buf());
+        paths.sort_unstable();
 
         assert_eq!(
-            matches,
-            vec![SearchQueryContentsMatch {
-                path: root.child(make_path("other/file.txt")).to_path_buf(),
-                lines: SearchQueryMatchData::text("some other file with text"),
-                line_number: 1,
-                absolute_offset: 0,
-                submatches: vec![SearchQuerySubmatch {
-                    r#match: SearchQueryMatchData::Text("text".to_string()),
-                    start: 21,
-                    end: 25,
-                }]
-            }]
+            paths,
+            vec![
+                root.to_path_buf(),
+                root.child("dir").to_path_buf(),
+                root.child("dir_symlink").to_path_buf(),
+                root.child("file").to_path_buf(),
+                root.child("file_symlink").to_path_buf(),
+            ]
         );
 
         let data = rx.recv().await;
         assert_eq!(
             data,
-            Some(DistantResponseData::SearchDone { id: search_id })
+            Some(DistantResponseData::SearchDone {
+                id: search_id,
+                skipped_binary: Some(0)
+            })
         );
 
         assert_eq!(rx.recv().await, None);
     }
 
     #[test(tokio::test)]
-    async fn should_filter_searched_paths_to_only_those_that_do_not_match_exclude_regex() {
+    async fn should_follow_symbolic_links_if_specified_in_options() {
+        let root = assert_fs::TempDir::new().unwrap();
+
+        let file = root.child(make_path("file"));
+        file.touch().unwrap();
+        let dir = root.child(make_path("dir"));
+        dir.create_dir_all().unwrap();
+        root.child(make_path("file_symlink"))
+            .symlink_to_file(file.path())
+            .unwrap();
+        root.child(make_path("dir_symlink"))
+            .symlink_to_dir(dir.path())
+            .unwrap();
+
+        let state = SearchState::new();
+        let (reply, mut rx) = mpsc::channel(100);
+
+        // NOTE: Following symlobic links on its own does nothing, but when combined with a file
+        //       type filter, it will evaluate the underlying type of symbolic links and filter
+        //       based on that instead of the the symbolic link
+        let query = SearchQuery {
+            paths: vec![root.path().to_path_buf()],
+            target: SearchQueryTarget::Path,
+            condition: SearchQueryCondition::regex(".*"),
+            options: SearchQueryOptions {
+                allowed_file_types: vec![FileType::File].into_iter().collect(),
+                follow_symbolic_links: true,
+                ..Default::default()
+            },
+        };
+
+        let search_id = state.start(query, Box::new(reply)).await.unwrap();
+
+        let mut paths = get_matches(rx.recv().await.unwrap())
+            .into_iter()
+            .filter_map(|m| m.into_path_match())
+            .map(|m| m.path)
+            .collect::<Vec<_>>();
+
+        paths.sort_unstable();
+
+        assert_eq!(
+            paths,
+            vec![
+                root.child("file").to_path_buf(),
+                root.child("file_symlink").to_path_buf(),
+            ]
+        );
+
+        let data = rx.recv().await;
+        assert_eq!(
+            data,
+            Some(DistantResponseData::SearchDone {
+                id: search_id,
+                skipped_binary: Some(0)
+            })
+        );
+
+        assert_eq!(rx.recv().await, None);
+    }
+
+    #[test(tokio::test)]
+    async fn should_support_being_supplied_more_than_one_path() {
         let root = setup_dir(vec![
             ("path/to/file1.txt", "some\nlines of text in\na\nfile"),
             ("path/to/file2.txt", "more text"),
-            ("other/file.txt", "some other file with text"),
-            ("other/dir/bin", "asdfasdfasdfasdfasdfasdfasdfasdfasdf"),
         ]);
 
         let state = SearchState::new();
         let (reply, mut rx) = mpsc::channel(100);
 
         let query = SearchQuery {
-            paths: vec![root.path().to_path_buf()],
+            paths: vec![
+                root.child(make_path("path/to/file1.txt"))
+                    .path()
+                    .to_path_buf(),
+                root.child(make_path("path/to/file2.txt"))
+                    .path()
+                    .to_path_buf(),
+            ],
             target: SearchQueryTarget::Contents,
             condition: SearchQueryCondition::regex("text"),
-            options: SearchQueryOptions {
-                exclude: Some(SearchQueryCondition::regex("other")),
-                ..Default::default()
-            },
+            options: Default::default(),
         };
 
         let search_id = state.start(query, Box::new(reply)).await.unwrap();
@@ -1501,6 +2479,7 @@ mod tests {
             vec![
                 SearchQueryContentsMatch {
                     path: root.child(make_path("path/to/file1.txt")).to_path_buf(),
+                    archive_member: None,
                     lines: SearchQueryMatchData::text("lines of text in\n"),
                     line_number: 2,
                     absolute_offset: 5,
@@ -1512,6 +2491,7 @@ mod tests {
                 },
                 SearchQueryContentsMatch {
                     path: root.child(make_path("path/to/file2.txt")).to_path_buf(),
+                    archive_member: None,
                     lines: SearchQueryMatchData::text("more text"),
                     line_number: 1,
                     absolute_offset: 0,
@@ -1527,14 +2507,17 @@ mod tests {
         let data = rx.recv().await;
         assert_eq!(
             data,
-            Some(DistantResponseData::SearchDone { id: search_id })
+            Some(DistantResponseData::SearchDone {
+                id: search_id,
+                skipped_binary: Some(0)
+            })
         );
 
         assert_eq!(rx.recv().await, None);
     }
 
     #[test(tokio::test)]
-    async fn should_return_binary_match_data_if_match_is_not_utf8_but_path_is_explicit() {
+    async fn should_search_binary_files_if_specified_in_options() {
         let root = assert_fs::TempDir::new().unwrap();
         let bin_file = root.child(make_path("file.bin"));
 
@@ -1546,13 +2529,14 @@ mod tests {
         let state = SearchState::new();
         let (reply, mut rx) = mpsc::channel(100);
 
-        // NOTE: We provide regex that matches an invalid UTF-8 character by disabling the u flag
-        //       and checking for 0x9F (159)
         let query = SearchQuery {
-            paths: vec![bin_file.path().to_path_buf()],
+            paths: vec![root.path().to_path_buf()],
             target: SearchQueryTarget::Contents,
-            condition: SearchQueryCondition::regex(r"(?-u:\x9F)"),
-            options: Default::default(),
+            condition: SearchQueryCondition::regex("HELLO"),
+            options: SearchQueryOptions {
+                binary: true,
+                ..Default::default()
+            },
         };
 
         let search_id = state.start(query, Box::new(reply)).await.unwrap();
@@ -1562,90 +2546,205 @@ mod tests {
             .filter_map(|m| m.into_contents_match())
             .collect::<Vec<_>>();
 
-        // NOTE: Null bytes are treated as newlines, so that shifts us to being on "line 2"
-        //       and associated other shifts
+        // NOTE: Null bytes are treated as newlines, so that shifts us to being on "line 3"
         assert_eq!(
             matches,
             vec![SearchQueryContentsMatch {
                 path: root.child(make_path("file.bin")).to_path_buf(),
-                lines: SearchQueryMatchData::bytes([159, 146, 150, 10]),
-                line_number: 2,
-                absolute_offset: 1,
+                archive_member: None,
+                lines: SearchQueryMatchData::text("HELLO"),
+                line_number: 3,
+                absolute_offset: 5,
                 submatches: vec![SearchQuerySubmatch {
-                    r#match: SearchQueryMatchData::bytes([159]),
+                    r#match: SearchQueryMatchData::text("HELLO"),
                     start: 0,
-                    end: 1,
+                    end: 5,
                 }]
-            },]
+            }]
         );
 
         let data = rx.recv().await;
         assert_eq!(
             data,
-            Some(DistantResponseData::SearchDone { id: search_id })
+            Some(DistantResponseData::SearchDone {
+                id: search_id,
+                skipped_binary: Some(0)
+            })
         );
 
         assert_eq!(rx.recv().await, None);
     }
 
+    #[cfg(feature = "archive")]
     #[test(tokio::test)]
-    async fn should_not_return_binary_match_data_if_match_is_not_utf8_and_not_explicit_path() {
+    async fn should_search_within_compressed_files_and_archives_if_specified_in_options() {
+        use std::io::Write;
+
         let root = assert_fs::TempDir::new().unwrap();
-        let bin_file = root.child(make_path("file.bin"));
 
-        // Write some invalid bytes, a newline, and then "hello"
-        bin_file
-            .write_binary(&[0, 159, 146, 150, 10, 72, 69, 76, 76, 79])
+        // Single file compressed as a whole
+        let mut gzip = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        gzip.write_all(b"compressed hello\n").unwrap();
+        root.child(make_path("file.txt.gz"))
+            .write_binary(&gzip.finish().unwrap())
+            .unwrap();
+
+        // Tarball with a nested member
+        let mut tar = tar::Builder::new(Vec::new());
+        let data = b"tar hello\n";
+        let mut header = tar::Header::new_gnu();
+        header.set_size(data.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        tar.append_data(&mut header, "dir/member.txt", &data[..])
+            .unwrap();
+        root.child(make_path("archive.tar"))
+            .write_binary(&tar.into_inner().unwrap())
+            .unwrap();
+
+        // Zip archive with a deflated member
+        let mut zip = zip::ZipWriter::new(io::Cursor::new(Vec::new()));
+        zip.start_file(
+            "member.txt",
+            zip::write::FileOptions::default().compression_method(zip::CompressionMethod::Deflated),
+        )
+        .unwrap();
+        zip.write_all(b"zip hello\n").unwrap();
+        root.child(make_path("archive.zip"))
+            .write_binary(&zip.finish().unwrap().into_inner())
+            .unwrap();
+
+        let state = SearchState::new();
+        let (reply, mut rx) = mpsc::channel(100);
+
+        let query = SearchQuery {
+            paths: vec![root.path().to_path_buf()],
+            target: SearchQueryTarget::Contents,
+            condition: SearchQueryCondition::regex("hello"),
+            options: SearchQueryOptions {
+                search_archives: true,
+                ..Default::default()
+            },
+        };
+
+        let search_id = state.start(query, Box::new(reply)).await.unwrap();
+
+        let mut matches = get_matches(rx.recv().await.unwrap())
+            .into_iter()
+            .filter_map(|m| m.into_contents_match())
+            .map(|m| (m.path, m.archive_member, m.lines))
+            .collect::<Vec<_>>();
+
+        matches.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+
+        assert_eq!(
+            matches,
+            vec![
+                (
+                    root.child(make_path("archive.tar")).to_path_buf(),
+                    Some(PathBuf::from("dir/member.txt")),
+                    SearchQueryMatchData::text("tar hello\n"),
+                ),
+                (
+                    root.child(make_path("archive.zip")).to_path_buf(),
+                    Some(PathBuf::from("member.txt")),
+                    SearchQueryMatchData::text("zip hello\n"),
+                ),
+                (
+                    root.child(make_path("file.txt.gz")).to_path_buf(),
+                    None,
+                    SearchQueryMatchData::text("compressed hello\n"),
+                ),
+            ]
+        );
+
+        let data = rx.recv().await;
+        assert_eq!(
+            data,
+            Some(DistantResponseData::SearchDone {
+                id: search_id,
+                skipped_binary: Some(0)
+            })
+        );
+
+        assert_eq!(rx.recv().await, None);
+    }
+
+    #[cfg(feature = "archive")]
+    #[test(tokio::test)]
+    async fn should_not_search_within_compressed_files_and_archives_by_default() {
+        use std::io::Write;
+
+        let root = assert_fs::TempDir::new().unwrap();
+
+        let mut gzip = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        gzip.write_all(b"compressed hello\n").unwrap();
+        root.child(make_path("file.txt.gz"))
+            .write_binary(&gzip.finish().unwrap())
             .unwrap();
 
         let state = SearchState::new();
-        let (reply, mut rx) = mpsc::channel(100);
+        let (reply, mut rx) = mpsc::channel(100);
+
+        let query = SearchQuery {
+            paths: vec![root.path().to_path_buf()],
+            target: SearchQueryTarget::Contents,
+            condition: SearchQueryCondition::regex("hello"),
+            options: Default::default(),
+        };
+
+        let search_id = state.start(query, Box::new(reply)).await.unwrap();
+
+        // NOTE: Compressed data is not guaranteed to contain a null byte, so we only check that
+        //       no matches were reported rather than how many files were skipped
+        match rx.recv().await {
+            Some(DistantResponseData::SearchDone { id, .. }) => assert_eq!(id, search_id),
+            x => panic!("Unexpected response: {x:?}"),
+        }
+
+        assert_eq!(rx.recv().await, None);
+    }
+
+    #[cfg(not(feature = "archive"))]
+    #[test(tokio::test)]
+    async fn should_fail_to_search_archives_without_archive_feature() {
+        let root = setup_dir(vec![("file.txt", "hello")]);
+
+        let state = SearchState::new();
+        let (reply, _rx) = mpsc::channel(100);
 
-        // NOTE: We provide regex that matches an invalid UTF-8 character by disabling the u flag
-        //       and checking for 0x9F (159)
         let query = SearchQuery {
             paths: vec![root.path().to_path_buf()],
             target: SearchQueryTarget::Contents,
-            condition: SearchQueryCondition::regex(r"(?-u:\x9F)"),
-            options: Default::default(),
+            condition: SearchQueryCondition::regex("hello"),
+            options: SearchQueryOptions {
+                search_archives: true,
+                ..Default::default()
+            },
         };
 
-        let search_id = state.start(query, Box::new(reply)).await.unwrap();
-
-        // Get done indicator next as there were no matches
-        let data = rx.recv().await;
-        assert_eq!(
-            data,
-            Some(DistantResponseData::SearchDone { id: search_id })
-        );
-
-        assert_eq!(rx.recv().await, None);
+        let err = state.start(query, Box::new(reply)).await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Unsupported, "{:?}", err);
     }
 
     #[test(tokio::test)]
-    async fn should_filter_searched_paths_to_only_those_are_an_allowed_file_type() {
-        let root = assert_fs::TempDir::new().unwrap();
-        let file = root.child(make_path("file"));
-        file.touch().unwrap();
-        root.child(make_path("dir")).create_dir_all().unwrap();
-        root.child(make_path("symlink"))
-            .symlink_to_file(file.path())
-            .unwrap();
+    async fn should_skip_hidden_files_unless_specified_in_options() {
+        let root = setup_dir(vec![
+            (".hidden/file.txt", "text"),
+            (".file.txt", "text"),
+            ("file.txt", "text"),
+        ]);
 
-        async fn test_allowed_file_types(
-            root: &assert_fs::TempDir,
-            allowed_file_types: Vec<FileType>,
-            expected_paths: Vec<PathBuf>,
-        ) {
+        async fn test_hidden(root: &assert_fs::TempDir, hidden: bool, expected: Vec<PathBuf>) {
             let state = SearchState::new();
             let (reply, mut rx) = mpsc::channel(100);
 
             let query = SearchQuery {
                 paths: vec![root.path().to_path_buf()],
-                target: SearchQueryTarget::Path,
-                condition: SearchQueryCondition::regex(".*"),
+                target: SearchQueryTarget::Contents,
+                condition: SearchQueryCondition::regex("text"),
                 options: SearchQueryOptions {
-                    allowed_file_types: allowed_file_types.iter().copied().collect(),
+                    hidden,
                     ..Default::default()
                 },
             };
@@ -1654,245 +2753,253 @@ mod tests {
 
             let mut paths = get_matches(rx.recv().await.unwrap())
                 .into_iter()
-                .filter_map(|m| m.into_path_match())
+                .filter_map(|m| m.into_contents_match())
                 .map(|m| m.path)
                 .collect::<Vec<_>>();
 
             paths.sort_unstable();
 
+            assert_eq!(paths, expected);
+
+            let data = rx.recv().await;
             assert_eq!(
-                paths, expected_paths,
-                "Path types did not match allowed: {allowed_file_types:?}"
+                data,
+                Some(DistantResponseData::SearchDone {
+                    id: search_id,
+                    skipped_binary: Some(0)
+                })
             );
 
+            assert_eq!(rx.recv().await, None);
+        }
+
+        test_hidden(&root, false, vec![root.child("file.txt").to_path_buf()]).await;
+        test_hidden(
+            &root,
+            true,
+            vec![
+                root.child(".file.txt").to_path_buf(),
+                root.child(make_path(".hidden/file.txt")).to_path_buf(),
+                root.child("file.txt").to_path_buf(),
+            ],
+        )
+        .await;
+    }
+
+    #[test(tokio::test)]
+    async fn should_respect_ignore_files_unless_specified_in_options() {
+        let root = setup_dir(vec![
+            ("ignored/file.txt", "text"),
+            ("other.txt", "text"),
+            ("file.txt", "text"),
+        ]);
+        root.child(".ignore").write_str("ignored/\n").unwrap();
+
+        // NOTE: Custom ignore files are always respected, so we place this outside of the root
+        let custom = assert_fs::TempDir::new().unwrap();
+        let custom_ignore = custom.child("custom-ignore");
+        custom_ignore.write_str("other.txt\n").unwrap();
+
+        async fn test_ignore(
+            root: &assert_fs::TempDir,
+            options: SearchQueryOptions,
+            expected: Vec<PathBuf>,
+        ) {
+            let state = SearchState::new();
+            let (reply, mut rx) = mpsc::channel(100);
+
+            let query = SearchQuery {
+                paths: vec![root.path().to_path_buf()],
+                target: SearchQueryTarget::Contents,
+                condition: SearchQueryCondition::regex("text"),
+                options,
+            };
+
+            let search_id = state.start(query, Box::new(reply)).await.unwrap();
+
+            let mut paths = get_matches(rx.recv().await.unwrap())
+                .into_iter()
+                .filter_map(|m| m.into_contents_match())
+                .map(|m| m.path)
+                .collect::<Vec<_>>();
+
+            paths.sort_unstable();
+
+            assert_eq!(paths, expected);
+
             let data = rx.recv().await;
             assert_eq!(
                 data,
-                Some(DistantResponseData::SearchDone { id: search_id })
+                Some(DistantResponseData::SearchDone {
+                    id: search_id,
+                    skipped_binary: Some(0)
+                })
             );
 
             assert_eq!(rx.recv().await, None);
         }
 
-        // Empty set of allowed types falls back to allowing everything
-        test_allowed_file_types(
+        // By default, .ignore files are respected
+        test_ignore(
             &root,
-            vec![],
+            SearchQueryOptions::default(),
             vec![
-                root.to_path_buf(),
-                root.child("dir").to_path_buf(),
-                root.child("file").to_path_buf(),
-                root.child("symlink").to_path_buf(),
+                root.child("file.txt").to_path_buf(),
+                root.child("other.txt").to_path_buf(),
             ],
         )
         .await;
 
-        test_allowed_file_types(
+        // Turning off ignore files should include everything
+        test_ignore(
             &root,
-            vec![FileType::File],
-            vec![root.child("file").to_path_buf()],
+            SearchQueryOptions {
+                no_ignore: true,
+                ..Default::default()
+            },
+            vec![
+                root.child("file.txt").to_path_buf(),
+                root.child(make_path("ignored/file.txt")).to_path_buf(),
+                root.child("other.txt").to_path_buf(),
+            ],
         )
         .await;
 
-        test_allowed_file_types(
+        // Turning off version control ignore files should still respect .ignore files
+        test_ignore(
             &root,
-            vec![FileType::Dir],
-            vec![root.to_path_buf(), root.child("dir").to_path_buf()],
+            SearchQueryOptions {
+                no_ignore_vcs: true,
+                ..Default::default()
+            },
+            vec![
+                root.child("file.txt").to_path_buf(),
+                root.child("other.txt").to_path_buf(),
+            ],
         )
         .await;
 
-        test_allowed_file_types(
+        // Custom ignore files are applied on top of everything else
+        test_ignore(
             &root,
-            vec![FileType::Symlink],
-            vec![root.child("symlink").to_path_buf()],
+            SearchQueryOptions {
+                ignore_files: vec![custom_ignore.to_path_buf()],
+                ..Default::default()
+            },
+            vec![root.child("file.txt").to_path_buf()],
         )
         .await;
     }
 
     #[test(tokio::test)]
-    async fn should_follow_not_symbolic_links_if_specified_in_options() {
-        let root = assert_fs::TempDir::new().unwrap();
-
-        let file = root.child(make_path("file"));
-        file.touch().unwrap();
-        let dir = root.child(make_path("dir"));
-        dir.create_dir_all().unwrap();
-        root.child(make_path("file_symlink"))
-            .symlink_to_file(file.path())
-            .unwrap();
-        root.child(make_path("dir_symlink"))
-            .symlink_to_dir(dir.path())
-            .unwrap();
+    async fn find_files_should_stream_matches_and_send_ranked_matches_when_done() {
+        let root = setup_dir(vec![
+            ("src/lib.rs", ""),
+            ("src/api/local.rs", ""),
+            ("tests/lib_test.rs", ""),
+            ("README.md", ""),
+        ]);
 
         let state = SearchState::new();
         let (reply, mut rx) = mpsc::channel(100);
 
-        let query = SearchQuery {
-            paths: vec![root.path().to_path_buf()],
-            target: SearchQueryTarget::Path,
-            condition: SearchQueryCondition::regex(".*"),
-            options: SearchQueryOptions {
-                follow_symbolic_links: true,
-                ..Default::default()
-            },
+        let query = FindFilesQuery {
+            pattern: String::from("lib"),
+            root: root.path().to_path_buf(),
+            options: Default::default(),
         };
 
-        let search_id = state.start(query, Box::new(reply)).await.unwrap();
-
-        let mut paths = get_matches(rx.recv().await.unwrap())
-            .into_iter()
-            .filter_map(|m| m.into_path_match())
-            .map(|m| m.path)
-            .collect::<Vec<_>>();
+        let search_id = state.find_files(query, Box::new(reply)).await.unwrap();
 
-        paths.sort_unstable();
+        // Without pagination, each match is streamed individually as it is found
+        let mut streamed = Vec::new();
+        let ranked = loop {
+            match rx.recv().await.unwrap() {
+                DistantResponseData::FindFilesResults { id, matches } => {
+                    assert_eq!(id, search_id);
+                    assert_eq!(matches.len(), 1);
+                    streamed.extend(matches.into_iter().map(|m| m.path));
+                }
+                DistantResponseData::FindFilesDone { id, matches } => {
+                    assert_eq!(id, search_id);
+                    break matches;
+                }
+                x => panic!("Unexpected response: {x:?}"),
+            }
+        };
 
+        streamed.sort_unstable();
         assert_eq!(
-            paths,
+            streamed,
             vec![
-                root.to_path_buf(),
-                root.child("dir").to_path_buf(),
-                root.child("dir_symlink").to_path_buf(),
-                root.child("file").to_path_buf(),
-                root.child("file_symlink").to_path_buf(),
+                root.child(make_path("src/lib.rs")).to_path_buf(),
+                root.child(make_path("tests/lib_test.rs")).to_path_buf(),
             ]
         );
 
-        let data = rx.recv().await;
-        assert_eq!(
-            data,
-            Some(DistantResponseData::SearchDone { id: search_id })
-        );
-
-        assert_eq!(rx.recv().await, None);
-    }
-
-    #[test(tokio::test)]
-    async fn should_follow_symbolic_links_if_specified_in_options() {
-        let root = assert_fs::TempDir::new().unwrap();
-
-        let file = root.child(make_path("file"));
-        file.touch().unwrap();
-        let dir = root.child(make_path("dir"));
-        dir.create_dir_all().unwrap();
-        root.child(make_path("file_symlink"))
-            .symlink_to_file(file.path())
-            .unwrap();
-        root.child(make_path("dir_symlink"))
-            .symlink_to_dir(dir.path())
-            .unwrap();
-
-        let state = SearchState::new();
-        let (reply, mut rx) = mpsc::channel(100);
-
-        // NOTE: Following symlobic links on its own does nothing, but when combined with a file
-        //       type filter, it will evaluate the underlying type of symbolic links and filter
-        //       based on that instead of the the symbolic link
-        let query = SearchQuery {
-            paths: vec![root.path().to_path_buf()],
-            target: SearchQueryTarget::Path,
-            condition: SearchQueryCondition::regex(".*"),
-            options: SearchQueryOptions {
-                allowed_file_types: vec![FileType::File].into_iter().collect(),
-                follow_symbolic_links: true,
-                ..Default::default()
-            },
-        };
-
-        let search_id = state.start(query, Box::new(reply)).await.unwrap();
-
-        let mut paths = get_matches(rx.recv().await.unwrap())
-            .into_iter()
-            .filter_map(|m| m.into_path_match())
-            .map(|m| m.path)
-            .collect::<Vec<_>>();
-
-        paths.sort_unstable();
-
+        // Ranked matches place the shorter file name match first
         assert_eq!(
-            paths,
+            ranked.iter().map(|m| m.path.clone()).collect::<Vec<_>>(),
             vec![
-                root.child("file").to_path_buf(),
-                root.child("file_symlink").to_path_buf(),
+                root.child(make_path("src/lib.rs")).to_path_buf(),
+                root.child(make_path("tests/lib_test.rs")).to_path_buf(),
             ]
         );
+        assert!(ranked[0].score >= ranked[1].score);
 
-        let data = rx.recv().await;
+        // Indices are relative to the full path that is reported
+        let child_start = (root.path().to_string_lossy().len() + 1) as u64;
         assert_eq!(
-            data,
-            Some(DistantResponseData::SearchDone { id: search_id })
+            ranked[0].indices,
+            vec![child_start + 4, child_start + 5, child_start + 6]
         );
 
         assert_eq!(rx.recv().await, None);
     }
 
     #[test(tokio::test)]
-    async fn should_support_being_supplied_more_than_one_path() {
+    async fn find_files_should_only_return_up_to_limit_best_matches() {
         let root = setup_dir(vec![
-            ("path/to/file1.txt", "some\nlines of text in\na\nfile"),
-            ("path/to/file2.txt", "more text"),
+            ("a/b/c/match.txt", ""),
+            ("match.txt", ""),
+            ("m_a_t_c_h.txt", ""),
+            ("other.txt", ""),
         ]);
 
         let state = SearchState::new();
         let (reply, mut rx) = mpsc::channel(100);
 
-        let query = SearchQuery {
-            paths: vec![
-                root.child(make_path("path/to/file1.txt"))
-                    .path()
-                    .to_path_buf(),
-                root.child(make_path("path/to/file2.txt"))
-                    .path()
-                    .to_path_buf(),
-            ],
-            target: SearchQueryTarget::Contents,
-            condition: SearchQueryCondition::regex("text"),
-            options: Default::default(),
+        let query = FindFilesQuery {
+            pattern: String::from("match"),
+            root: root.path().to_path_buf(),
+            options: SearchQueryOptions {
+                limit: Some(2),
+                pagination: Some(100),
+                ..Default::default()
+            },
         };
 
-        let search_id = state.start(query, Box::new(reply)).await.unwrap();
-
-        let mut matches = get_matches(rx.recv().await.unwrap())
-            .into_iter()
-            .filter_map(|m| m.into_contents_match())
-            .collect::<Vec<_>>();
+        let search_id = state.find_files(query, Box::new(reply)).await.unwrap();
 
-        matches.sort_unstable_by_key(|m| m.path.to_path_buf());
+        let ranked = loop {
+            match rx.recv().await.unwrap() {
+                DistantResponseData::FindFilesResults { id, .. } => assert_eq!(id, search_id),
+                DistantResponseData::FindFilesDone { id, matches } => {
+                    assert_eq!(id, search_id);
+                    break matches;
+                }
+                x => panic!("Unexpected response: {x:?}"),
+            }
+        };
 
         assert_eq!(
-            matches,
+            ranked.into_iter().map(|m| m.path).collect::<Vec<_>>(),
             vec![
-                SearchQueryContentsMatch {
-                    path: root.child(make_path("path/to/file1.txt")).to_path_buf(),
-                    lines: SearchQueryMatchData::text("lines of text in\n"),
-                    line_number: 2,
-                    absolute_offset: 5,
-                    submatches: vec![SearchQuerySubmatch {
-                        r#match: SearchQueryMatchData::Text("text".to_string()),
-                        start: 9,
-                        end: 13,
-                    }]
-                },
-                SearchQueryContentsMatch {
-                    path: root.child(make_path("path/to/file2.txt")).to_path_buf(),
-                    lines: SearchQueryMatchData::text("more text"),
-                    line_number: 1,
-                    absolute_offset: 0,
-                    submatches: vec![SearchQuerySubmatch {
-                        r#match: SearchQueryMatchData::Text("text".to_string()),
-                        start: 5,
-                        end: 9,
-                    }]
-                }
+                root.child(make_path("match.txt")).to_path_buf(),
+                root.child(make_path("a/b/c/match.txt")).to_path_buf(),
             ]
         );
 
-        let data = rx.recv().await;
-        assert_eq!(
-            data,
-            Some(DistantResponseData::SearchDone { id: search_id })
-        );
-
         assert_eq!(rx.recv().await, None);
     }
 }
@@ -0,0 +1,215 @@
+use std::{
+    fs::File,
+    io::{self, Read},
+    path::Path,
+};
+
+/// Represents a compression format that wraps a single stream of data
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Compression {
+    Bzip2,
+    Gzip,
+    Xz,
+    Zstd,
+}
+
+impl Compression {
+    /// Wraps `reader` with a decoder that decompresses its data
+    pub fn decode<'a>(self, reader: impl Read + 'a) -> io::Result<Box<dyn Read + 'a>> {
+        Ok(match self {
+            Self::Bzip2 => Box::new(bzip2::read::BzDecoder::new(reader)),
+            Self::Gzip => Box::new(flate2::read::MultiGzDecoder::new(reader)),
+            Self::Xz => Box::new(xz2::read::XzDecoder::new_multi_decoder(reader)),
+            Self::Zstd => Box::new(zstd::stream::read::Decoder::new(reader)?),
+        })
+    }
+}
+
+/// Represents a compressed file or archive whose contents can be searched
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Archive {
+    /// Single file compressed as a whole
+    Compressed(Compression),
+
+    /// Tarball containing many members, optionally compressed as a whole
+    Tar(Option<Compression>),
+
+    /// Zip archive containing many individually-compressed members
+    Zip,
+}
+
+impl Archive {
+    /// Determines the kind of archive based on the extension of `path`, returning none if the
+    /// path is not a supported compressed file or archive
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_string_lossy().to_lowercase();
+
+        // Check longest extensions first so compressed tarballs are not treated as a single
+        // compressed file
+        let archive = if name.ends_with(".tar") {
+            Self::Tar(None)
+        } else if name.ends_with(".tar.bz2") || name.ends_with(".tbz2") || name.ends_with(".tbz") {
+            Self::Tar(Some(Compression::Bzip2))
+        } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Self::Tar(Some(Compression::Gzip))
+        } else if name.ends_with(".tar.xz") || name.ends_with(".txz") {
+            Self::Tar(Some(Compression::Xz))
+        } else if name.ends_with(".tar.zst") || name.ends_with(".tzst") {
+            Self::Tar(Some(Compression::Zstd))
+        } else if name.ends_with(".zip") {
+            Self::Zip
+        } else if name.ends_with(".bz2") {
+            Self::Compressed(Compression::Bzip2)
+        } else if name.ends_with(".gz") {
+            Self::Compressed(Compression::Gzip)
+        } else if name.ends_with(".xz") {
+            Self::Compressed(Compression::Xz)
+        } else if name.ends_with(".zst") {
+            Self::Compressed(Compression::Zstd)
+        } else {
+            return None;
+        };
+
+        Some(archive)
+    }
+
+    /// Visits the decompressed contents of the archive at `path`, invoking `f` with the path of
+    /// each member (none for a single compressed file) and a reader of its contents
+    pub fn visit<F>(self, path: &Path, mut f: F) -> io::Result<()>
+    where
+        F: FnMut(Option<&Path>, &mut dyn Read) -> io::Result<()>,
+    {
+        let file = File::open(path)?;
+
+        match self {
+            Self::Compressed(compression) => f(None, &mut compression.decode(file)?),
+            Self::Tar(compression) => {
+                let reader = match compression {
+                    Some(compression) => compression.decode(file)?,
+                    None => Box::new(file),
+                };
+
+                let mut archive = tar::Archive::new(reader);
+                for entry in archive.entries()? {
+                    let mut entry = entry?;
+                    if !entry.header().entry_type().is_file() {
+                        continue;
+                    }
+
+                    let member = entry.path()?.into_owned();
+                    f(Some(&member), &mut entry)?;
+                }
+
+                Ok(())
+            }
+            Self::Zip => {
+                let mut archive = zip::ZipArchive::new(file)?;
+                for i in 0..archive.len() {
+                    let mut member = archive.by_index(i)?;
+                    if !member.is_file() {
+                        continue;
+                    }
+
+                    // NOTE: Members with unsafe paths (absolute or escaping the archive) are
+                    //       skipped as we cannot report a sensible path for them
+                    let path = match member.enclosed_name() {
+                        Some(path) => path.to_path_buf(),
+                        None => continue,
+                    };
+
+                    f(Some(&path), &mut member)?;
+                }
+
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_log::test;
+
+    #[test]
+    fn from_path_should_detect_archives_by_extension() {
+        let archive = |path: &str| Archive::from_path(Path::new(path));
+
+        assert_eq!(archive("file.txt"), None);
+        assert_eq!(archive("file"), None);
+        assert_eq!(archive("file.tar"), Some(Archive::Tar(None)));
+        assert_eq!(archive("file.zip"), Some(Archive::Zip));
+        assert_eq!(
+            archive("file.log.gz"),
+            Some(Archive::Compressed(Compression::Gzip))
+        );
+        assert_eq!(
+            archive("file.BZ2"),
+            Some(Archive::Compressed(Compression::Bzip2))
+        );
+        assert_eq!(
+            archive("file.xz"),
+            Some(Archive::Compressed(Compression::Xz))
+        );
+        assert_eq!(
+            archive("file.zst"),
+            Some(Archive::Compressed(Compression::Zstd))
+        );
+        assert_eq!(
+            archive("file.tar.gz"),
+            Some(Archive::Tar(Some(Compression::Gzip)))
+        );
+        assert_eq!(
+            archive("file.tgz"),
+            Some(Archive::Tar(Some(Compression::Gzip)))
+        );
+        assert_eq!(
+            archive("file.tar.bz2"),
+            Some(Archive::Tar(Some(Compression::Bzip2)))
+        );
+        assert_eq!(
+            archive("file.tar.xz"),
+            Some(Archive::Tar(Some(Compression::Xz)))
+        );
+        assert_eq!(
+            archive("file.tar.zst"),
+            Some(Archive::Tar(Some(Compression::Zstd)))
+        );
+    }
+
+    #[test]
+    fn decode_should_decompress_each_supported_compression() {
+        use std::io::Write;
+
+        let data = b"some compressed data";
+
+        let mut gzip = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        gzip.write_all(data).unwrap();
+        let gzip = gzip.finish().unwrap();
+
+        let mut bzip2 = bzip2::write::BzEncoder::new(Vec::new(), bzip2::Compression::default());
+        bzip2.write_all(data).unwrap();
+        let bzip2 = bzip2.finish().unwrap();
+
+        let mut xz = xz2::write::XzEncoder::new(Vec::new(), 6);
+        xz.write_all(data).unwrap();
+        let xz = xz.finish().unwrap();
+
+        let zstd = zstd::stream::encode_all(&data[..], 0).unwrap();
+
+        for (compression, bytes) in [
+            (Compression::Gzip, gzip),
+            (Compression::Bzip2, bzip2),
+            (Compression::Xz, xz),
+            (Compression::Zstd, zstd),
+        ] {
+            let mut decoded = Vec::new();
+            compression
+                .decode(bytes.as_slice())
+                .unwrap()
+                .read_to_end(&mut decoded)
+                .unwrap();
+            assert_eq!(decoded, data, "{compression:?} failed to decode");
+        }
+    }
+}
@@ -0,0 +1,294 @@
+/// Score for each character of the pattern that is matched
+const SCORE_MATCH: i64 = 16;
+
+/// Penalty for the first character skipped between two matched characters
+const SCORE_GAP_START: i64 = -3;
+
+/// Penalty for each additional character skipped between two matched characters
+const SCORE_GAP_EXTENSION: i64 = -1;
+
+/// Bonus for matching the first character after a path separator (or the start of the path)
+const BONUS_PATH_BOUNDARY: i64 = 10;
+
+/// Bonus for matching the first character after a delimiter like `_`, `-`, `.`, or a space
+const BONUS_BOUNDARY: i64 = 8;
+
+/// Bonus for matching an uppercase character following a lowercase one, or a digit following a
+/// non-digit
+const BONUS_CAMEL: i64 = 7;
+
+/// Minimum bonus for matching a character immediately after the previously-matched character,
+/// where consecutive characters otherwise share the bonus of the first character in their chunk
+const BONUS_CONSECUTIVE: i64 = 4;
+
+/// Bonus for matching a character within the final component of the path
+const BONUS_FILE_NAME: i64 = 2;
+
+/// Multiplier applied to the bonus of the first character of the pattern
+const BONUS_FIRST_CHAR_MULTIPLIER: i64 = 2;
+
+/// Represents a match of a [`FuzzyPattern`] against some text
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FuzzyMatch {
+    /// Score of the match where higher is better
+    pub score: i64,
+
+    /// Byte offsets within the text of each character that matched the pattern
+    pub indices: Vec<usize>,
+}
+
+/// Represents a pattern whose characters must all appear, in order, within some text to match,
+/// scoring matches similar to fzf and Sublime Text
+///
+/// Matching is case-insensitive unless the pattern contains an uppercase character. Forward and
+/// backward slashes are treated as equivalent so patterns work across platforms.
+#[derive(Clone, Debug)]
+pub struct FuzzyPattern {
+    chars: Vec<char>,
+    case_sensitive: bool,
+}
+
+impl FuzzyPattern {
+    pub fn new(pattern: &str) -> Self {
+        Self {
+            chars: pattern.chars().collect(),
+            case_sensitive: pattern.chars().any(char::is_uppercase),
+        }
+    }
+
+    /// Scores `text` against the pattern, returning the best possible match or none if the text
+    /// does not contain every character of the pattern in order
+    pub fn score(&self, text: &str) -> Option<FuzzyMatch> {
+        let (offsets, chars): (Vec<usize>, Vec<char>) = text.char_indices().unzip();
+        let m = self.chars.len();
+        let n = chars.len();
+
+        if m == 0 {
+            return Some(FuzzyMatch {
+                score: 0,
+                indices: Vec::new(),
+            });
+        }
+
+        // Quickly reject text that cannot contain the pattern before doing the more expensive
+        // scoring below
+        let mut remaining = self.chars.iter().peekable();
+        for c in chars.iter() {
+            if let Some(p) = remaining.peek() {
+                if self.eq(**p, *c) {
+                    remaining.next();
+                }
+            }
+        }
+        if remaining.peek().is_some() {
+            return None;
+        }
+
+        // Pre-compute the bonus of matching each character in the text
+        let file_name_start = chars
+            .iter()
+            .rposition(|c| is_path_separator(*c))
+            .map(|i| i + 1)
+            .unwrap_or(0);
+        let bonuses = (0..n)
+            .map(|j| {
+                let bonus = bonus(if j == 0 { None } else { Some(chars[j - 1]) }, chars[j]);
+                if j >= file_name_start {
+                    bonus + BONUS_FILE_NAME
+                } else {
+                    bonus
+                }
+            })
+            .collect::<Vec<_>>();
+
+        // scores[i * n + j] is the best score where the pattern's ith character is matched
+        // against the text's jth character, from[i * n + j] is the position of the text
+        // matched by the pattern's previous character in that best score, and chunks[i * n + j]
+        // is the bonus of the first character in the consecutive chunk ending at that position
+        let mut scores = vec![None; m * n];
+        let mut from = vec![0; m * n];
+        let mut chunks = vec![0; m * n];
+
+        for i in 0..m {
+            // Best score (and its position) of matching the previous pattern character at least
+            // two characters back, including the penalty of the gap in between
+            let mut gap: Option<(i64, usize)> = None;
+
+            for j in i..n {
+                if i > 0 && j >= 2 {
+                    let extended = gap.map(|(score, k)| (score + SCORE_GAP_EXTENSION, k));
+                    let started = scores[(i - 1) * n + j - 2].map(|s| (s + SCORE_GAP_START, j - 2));
+                    gap = match (extended, started) {
+                        (Some(a), Some(b)) => Some(if b.0 >= a.0 { b } else { a }),
+                        (a, b) => a.or(b),
+                    };
+                }
+
+                if !self.eq(self.chars[i], chars[j]) {
+                    continue;
+                }
+
+                if i == 0 {
+                    scores[j] = Some(SCORE_MATCH + bonuses[j] * BONUS_FIRST_CHAR_MULTIPLIER);
+                    chunks[j] = bonuses[j];
+                    continue;
+                }
+
+                let consecutive = scores[(i - 1) * n + j - 1].map(|s| {
+                    let chunk = chunks[(i - 1) * n + j - 1];
+                    let bonus = bonuses[j].max(chunk).max(BONUS_CONSECUTIVE);
+                    (s + SCORE_MATCH + bonus, j - 1, chunk)
+                });
+                let gapped = gap.map(|(s, k)| (s + SCORE_MATCH + bonuses[j], k, bonuses[j]));
+                let best = match (consecutive, gapped) {
+                    (Some(a), Some(b)) => Some(if a.0 >= b.0 { a } else { b }),
+                    (a, b) => a.or(b),
+                };
+
+                if let Some((score, k, chunk)) = best {
+                    scores[i * n + j] = Some(score);
+                    from[i * n + j] = k;
+                    chunks[i * n + j] = chunk;
+                }
+            }
+        }
+
+        // Find the best ending position for the last character of the pattern and walk back to
+        // figure out which characters were matched
+        let (score, mut j) = (0..n)
+            .filter_map(|j| scores[(m - 1) * n + j].map(|score| (score, j)))
+            .max_by(|a, b| a.0.cmp(&b.0).then(b.1.cmp(&a.1)))?;
+
+        let mut indices = vec![0; m];
+        for i in (0..m).rev() {
+            indices[i] = offsets[j];
+            j = from[i * n + j];
+        }
+
+        Some(FuzzyMatch { score, indices })
+    }
+
+    fn eq(&self, p: char, c: char) -> bool {
+        if is_path_separator(p) && is_path_separator(c) {
+            true
+        } else if self.case_sensitive {
+            p == c
+        } else {
+            fold(p) == fold(c)
+        }
+    }
+}
+
+/// Returns the bonus for matching `cur` based on the character that precedes it
+fn bonus(prev: Option<char>, cur: char) -> i64 {
+    if !cur.is_alphanumeric() {
+        return 0;
+    }
+
+    match prev {
+        None => BONUS_PATH_BOUNDARY,
+        Some(prev) if is_path_separator(prev) => BONUS_PATH_BOUNDARY,
+        Some('_' | '-' | '.' | ' ') => BONUS_BOUNDARY,
+        Some(prev) if prev.is_lowercase() && cur.is_uppercase() => BONUS_CAMEL,
+        Some(prev) if !prev.is_numeric() && cur.is_numeric() => BONUS_CAMEL,
+        _ => 0,
+    }
+}
+
+fn is_path_separator(c: char) -> bool {
+    c == '/' || c == '\\'
+}
+
+fn fold(c: char) -> char {
+    c.to_lowercase().next().unwrap_or(c)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_log::test;
+
+    #[test]
+    fn score_should_return_none_if_pattern_is_not_a_subsequence() {
+        let pattern = FuzzyPattern::new("abc");
+        assert_eq!(pattern.score("acb"), None);
+        assert_eq!(pattern.score("ab"), None);
+        assert_eq!(pattern.score(""), None);
+    }
+
+    #[test]
+    fn score_should_match_everything_with_empty_pattern() {
+        let pattern = FuzzyPattern::new("");
+        assert_eq!(
+            pattern.score("some/path"),
+            Some(FuzzyMatch {
+                score: 0,
+                indices: Vec::new(),
+            })
+        );
+    }
+
+    #[test]
+    fn score_should_be_case_insensitive_unless_pattern_has_uppercase() {
+        assert!(FuzzyPattern::new("readme").score("README.md").is_some());
+        assert!(FuzzyPattern::new("ReadMe").score("README.md").is_none());
+        assert!(FuzzyPattern::new("ReadMe").score("ReadMe.md").is_some());
+    }
+
+    #[test]
+    fn score_should_treat_forward_and_backward_slashes_as_equivalent() {
+        assert!(FuzzyPattern::new("src/lib").score(r"src\lib.rs").is_some());
+        assert!(FuzzyPattern::new(r"src\lib").score("src/lib.rs").is_some());
+    }
+
+    #[test]
+    fn score_should_report_byte_offsets_of_matched_characters() {
+        let m = FuzzyPattern::new("mod")
+            .score("src/main/öther/mod.rs")
+            .unwrap();
+
+        // NOTE: ö takes up two bytes, so the file name starts at byte 16
+        assert_eq!(m.indices, vec![16, 17, 18]);
+    }
+
+    #[test]
+    fn score_should_prefer_consecutive_matches() {
+        let pattern = FuzzyPattern::new("abc");
+        let consecutive = pattern.score("xabcx").unwrap();
+        let spread = pattern.score("xaxbxcx").unwrap();
+        assert!(consecutive.score > spread.score);
+    }
+
+    #[test]
+    fn score_should_prefer_consecutive_matches_over_scattered_word_boundaries() {
+        let pattern = FuzzyPattern::new("match");
+        let consecutive = pattern.score("match.txt").unwrap();
+        let scattered = pattern.score("m_a_t_c_h.txt").unwrap();
+        assert!(consecutive.score > scattered.score);
+    }
+
+    #[test]
+    fn score_should_prefer_matches_on_word_boundaries() {
+        let pattern = FuzzyPattern::new("fb");
+        let boundary = pattern.score("foo_bar").unwrap();
+        let inner = pattern.score("xfxxbx").unwrap();
+        assert!(boundary.score > inner.score);
+        assert_eq!(boundary.indices, vec![0, 4]);
+    }
+
+    #[test]
+    fn score_should_prefer_matches_within_file_name() {
+        let pattern = FuzzyPattern::new("lib");
+        let file_name = pattern.score("src/api/lib.rs").unwrap();
+        let directory = pattern.score("lib/api/src.rs").unwrap();
+        assert!(file_name.score > directory.score);
+    }
+
+    #[test]
+    fn score_should_choose_the_best_alignment_of_characters() {
+        // Greedy matching would pick the first "a" and "b", but matching the boundary-aligned
+        // characters later in the path scores better
+        let m = FuzzyPattern::new("ab").score("xaxxb/a_b").unwrap();
+        assert_eq!(m.indices, vec![6, 8]);
+    }
+}
@@ -0,0 +1,467 @@
+use super::build_walker;
+use crate::data::{SearchQueryCondition, SearchQueryOptions};
+use ignore::WalkState;
+use log::*;
+use std::{
+    collections::HashMap,
+    fs, io,
+    iter::Peekable,
+    path::{Path, PathBuf},
+    str::Chars,
+    sync::{mpsc, Arc, RwLock},
+};
+
+/// Files larger than this are not indexed and are always searched
+const MAXIMUM_INDEXED_FILE_SIZE: u64 = 1024 * 1024;
+
+/// Three consecutive bytes packed into the lower 24 bits, with ASCII letters lowercased
+type Trigram = u32;
+
+/// Holds trigram indexes of directories on the server, shared between searches that consult the
+/// indexes and the watcher that keeps them up to date
+#[derive(Clone, Default)]
+pub struct SearchIndex {
+    roots: Arc<RwLock<HashMap<PathBuf, TrigramIndex>>>,
+}
+
+/// Index of a single directory, mapping each indexed file to the sorted, distinct trigrams found
+/// within its contents
+#[derive(Default)]
+struct TrigramIndex {
+    files: HashMap<PathBuf, Vec<Trigram>>,
+}
+
+impl SearchIndex {
+    /// Builds (or rebuilds) the index for the directory at `root`, which is expected to be
+    /// canonicalized, blocking until all files have been read
+    ///
+    /// Files are discovered using the default search options, meaning that hidden and ignored
+    /// files are not indexed. Files that are too large or that contain binary data are also not
+    /// indexed. Any file that is not indexed is always searched.
+    pub fn build(&self, root: PathBuf) -> io::Result<()> {
+        if !root.is_dir() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{root:?} is not a directory"),
+            ));
+        }
+
+        let walker = build_walker(std::slice::from_ref(&root), &SearchQueryOptions::default())?;
+        let (tx, rx) = mpsc::channel();
+
+        walker.run(|| {
+            let tx = tx.clone();
+            Box::new(move |entry| {
+                if let Ok(entry) = entry {
+                    if entry.file_type().map_or(false, |ft| ft.is_file()) {
+                        if let Some(trigrams) = read_trigrams(entry.path()) {
+                            let _ = tx.send((entry.into_path(), trigrams));
+                        }
+                    }
+                }
+
+                WalkState::Continue
+            })
+        });
+
+        // Drop our copy of the sender so the iterator below stops once the walk has finished
+        drop(tx);
+        let index = TrigramIndex {
+            files: rx.into_iter().collect(),
+        };
+
+        debug!("Indexed {} files beneath {root:?}", index.files.len());
+        self.write().insert(root, index);
+        Ok(())
+    }
+
+    /// Refreshes the index for each of `paths` that falls within an indexed directory, blocking
+    /// until all of the paths have been read
+    ///
+    /// Files are re-read, directories have all of their files re-read, and paths that no longer
+    /// exist are removed from the index along with anything beneath them.
+    pub fn update(&self, paths: &[PathBuf]) {
+        let roots = self.read().keys().cloned().collect::<Vec<_>>();
+
+        // Read everything before acquiring our write lock so searches are not blocked
+        let mut removed = Vec::new();
+        let mut updated = Vec::new();
+        for path in paths {
+            if !roots.iter().any(|root| path.starts_with(root)) {
+                continue;
+            }
+
+            match fs::metadata(path) {
+                Ok(metadata) if metadata.is_dir() => {
+                    for entry in walkdir::WalkDir::new(path).into_iter().flatten() {
+                        if entry.file_type().is_file() {
+                            updated.push((entry.path().to_path_buf(), read_trigrams(entry.path())));
+                        }
+                    }
+                }
+                Ok(_) => updated.push((path.to_path_buf(), read_trigrams(path))),
+                Err(_) => removed.push(path.as_path()),
+            }
+        }
+
+        let mut roots = self.write();
+        for (root, index) in roots.iter_mut() {
+            for path in removed.iter().filter(|p| p.starts_with(root)) {
+                index.files.retain(|file, _| !file.starts_with(path));
+            }
+
+            for (path, trigrams) in updated.iter().filter(|(p, _)| p.starts_with(root)) {
+                match trigrams {
+                    Some(trigrams) => {
+                        index.files.insert(path.to_path_buf(), trigrams.clone());
+                    }
+
+                    // File can no longer be indexed, so remove it so it will always be searched
+                    None => {
+                        index.files.remove(path);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Removes the index for the directory at `root`, returning true if it was indexed
+    pub fn remove(&self, root: &Path) -> bool {
+        self.write().remove(root).is_some()
+    }
+
+    /// Returns `Some(false)` if `path` is indexed and cannot match `query`, `Some(true)` if it is
+    /// indexed and may match, or none if the path is not indexed
+    pub fn may_match(&self, path: &Path, query: &TrigramQuery) -> Option<bool> {
+        let roots = self.read();
+        roots
+            .iter()
+            .filter(|(root, _)| path.starts_with(root))
+            .find_map(|(_, index)| index.files.get(path))
+            .map(|trigrams| query.matches(trigrams))
+    }
+
+    fn read(&self) -> std::sync::RwLockReadGuard<'_, HashMap<PathBuf, TrigramIndex>> {
+        self.roots.read().unwrap_or_else(|x| x.into_inner())
+    }
+
+    fn write(&self) -> std::sync::RwLockWriteGuard<'_, HashMap<PathBuf, TrigramIndex>> {
+        self.roots.write().unwrap_or_else(|x| x.into_inner())
+    }
+}
+
+/// Represents the trigrams that a file must contain to possibly match a search condition
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TrigramQuery {
+    /// Any file could match
+    Any,
+
+    /// File must contain every one of the trigrams
+    All(Vec<Trigram>),
+
+    /// File must satisfy at least one of the queries
+    Or(Vec<TrigramQuery>),
+}
+
+impl TrigramQuery {
+    /// Determines the trigrams required by `condition`, falling back to [`TrigramQuery::Any`]
+    /// whenever the required text cannot be determined
+    pub fn from_condition(condition: &SearchQueryCondition) -> Self {
+        match condition {
+            SearchQueryCondition::Contains { value }
+            | SearchQueryCondition::EndsWith { value }
+            | SearchQueryCondition::Equals { value }
+            | SearchQueryCondition::StartsWith { value } => Self::from_literals([value.as_str()]),
+            SearchQueryCondition::Regex { value } => match required_literals(value) {
+                Some(literals) => Self::from_literals(literals.iter().map(String::as_str)),
+                None => Self::Any,
+            },
+            SearchQueryCondition::Or { value } => {
+                let queries = value.iter().map(Self::from_condition).collect::<Vec<_>>();
+                if queries.is_empty() || queries.contains(&Self::Any) {
+                    Self::Any
+                } else {
+                    Self::Or(queries)
+                }
+            }
+        }
+    }
+
+    fn from_literals<'a>(literals: impl IntoIterator<Item = &'a str>) -> Self {
+        let mut trigrams = literals
+            .into_iter()
+            .flat_map(|literal| trigrams(literal.as_bytes()))
+            .collect::<Vec<_>>();
+        trigrams.sort_unstable();
+        trigrams.dedup();
+
+        if trigrams.is_empty() {
+            Self::Any
+        } else {
+            Self::All(trigrams)
+        }
+    }
+
+    /// Returns true if a file with the sorted `trigrams` may satisfy the query
+    fn matches(&self, trigrams: &[Trigram]) -> bool {
+        match self {
+            Self::Any => true,
+            Self::All(required) => required.iter().all(|t| trigrams.binary_search(t).is_ok()),
+            Self::Or(queries) => queries.iter().any(|q| q.matches(trigrams)),
+        }
+    }
+}
+
+/// Reads the file at `path`, returning its sorted, distinct trigrams, or none if the file cannot
+/// be read, is too large, or contains binary data
+fn read_trigrams(path: &Path) -> Option<Vec<Trigram>> {
+    if fs::metadata(path).ok()?.len() > MAXIMUM_INDEXED_FILE_SIZE {
+        return None;
+    }
+
+    let bytes = fs::read(path).ok()?;
+    if bytes.contains(&0) {
+        return None;
+    }
+
+    let mut trigrams = trigrams(&bytes).collect::<Vec<_>>();
+    trigrams.sort_unstable();
+    trigrams.dedup();
+    Some(trigrams)
+}
+
+fn trigrams(bytes: &[u8]) -> impl Iterator<Item = Trigram> + '_ {
+    bytes.windows(3).map(|w| {
+        let [a, b, c] = [w[0], w[1], w[2]].map(|b| b.to_ascii_lowercase() as u32);
+        (a << 16) | (b << 8) | c
+    })
+}
+
+/// Scans `regex` for literal text that must appear within any match, returning none if the
+/// regex uses syntax that is not understood well enough to be certain
+///
+/// This is intentionally conservative: alternation, inline flags, and anything else that could
+/// change the meaning of literal text results in none, while characters made optional by a
+/// repetition are dropped from the literal text that precedes them.
+fn required_literals(regex: &str) -> Option<Vec<String>> {
+    // Literals found at each group depth, where literals within a group are only kept if the
+    // group itself is not optional
+    let mut groups: Vec<Vec<String>> = vec![Vec::new()];
+    let mut current = String::new();
+    let mut chars = regex.chars().peekable();
+
+    macro_rules! end_literal {
+        () => {{
+            if !current.is_empty() {
+                groups.last_mut()?.push(std::mem::take(&mut current));
+            }
+        }};
+    }
+
+    while let Some(c) = chars.next() {
+        match c {
+            '|' => return None,
+            '\\' => match chars.next()? {
+                c if c.is_ascii_punctuation() => current.push(c),
+                'b' | 'B' | 'd' | 'D' | 's' | 'S' | 'w' | 'W' | 'A' | 'z' => end_literal!(),
+                _ => return None,
+            },
+            '[' => {
+                end_literal!();
+                skip_class(&mut chars)?;
+            }
+            '(' => {
+                if chars.peek() == Some(&'?') {
+                    return None;
+                }
+                end_literal!();
+                groups.push(Vec::new());
+            }
+            ')' => {
+                end_literal!();
+                let group = groups.pop()?;
+                if !matches!(chars.peek(), Some('?' | '*' | '{')) {
+                    groups.last_mut()?.extend(group);
+                }
+            }
+            '?' | '*' | '{' => {
+                // The preceding character is optional, so it cannot be part of a literal
+                current.pop();
+                end_literal!();
+
+                if c == '{' {
+                    loop {
+                        if chars.next()? == '}' {
+                            break;
+                        }
+                    }
+                }
+            }
+            '+' | '.' | '^' | '$' => end_literal!(),
+            c => current.push(c),
+        }
+
+        // Lazy quantifiers do not change which text is required
+        if matches!(c, '?' | '*' | '+' | '{') && chars.peek() == Some(&'?') {
+            chars.next();
+        }
+    }
+
+    end_literal!();
+    if groups.len() == 1 {
+        groups.pop()
+    } else {
+        None
+    }
+}
+
+/// Skips the rest of a character class whose opening [ has already been read, including nested
+/// classes such as `[a[b]]` and POSIX classes such as `[[:alpha:]]`, returning none if the class
+/// is never closed
+fn skip_class(chars: &mut Peekable<Chars<'_>>) -> Option<()> {
+    // A ] at the start of a class, even after negation, is literal
+    if chars.peek() == Some(&'^') {
+        chars.next();
+    }
+    if chars.peek() == Some(&']') {
+        chars.next();
+    }
+
+    loop {
+        match chars.next()? {
+            '\\' => {
+                chars.next()?;
+            }
+            '[' if chars.peek() == Some(&':') => {
+                while chars.next()? != ':' || chars.peek() != Some(&']') {}
+                chars.next();
+            }
+            '[' => skip_class(chars)?,
+            ']' => return Some(()),
+            _ => (),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_fs::prelude::*;
+    use test_log::test;
+
+    fn assert_literals(regex: &str, expected: Option<&[&str]>) {
+        let expected = expected.map(|x| x.iter().map(ToString::to_string).collect::<Vec<_>>());
+        assert_eq!(
+            required_literals(regex),
+            expected,
+            "Wrong literals for {regex}"
+        );
+    }
+
+    #[test]
+    fn required_literals_should_find_text_that_must_appear_in_regex() {
+        assert_literals("hello", Some(&["hello"]));
+        assert_literals(r"fn \w+\(\)", Some(&["fn ", "()"]));
+        assert_literals("foo.*bar", Some(&["foo", "bar"]));
+        assert_literals("colou?r", Some(&["colo", "r"]));
+        assert_literals("ab+c", Some(&["ab", "c"]));
+        assert_literals("x{2}yz", Some(&["yz"]));
+        assert_literals("a*?bcd", Some(&["bcd"]));
+        assert_literals("^start[a-z]*end$", Some(&["start", "end"]));
+        assert_literals("(required)text", Some(&["required", "text"]));
+        assert_literals("(optional)?text", Some(&["text"]));
+        assert_literals("[]x]abc", Some(&["abc"]));
+        assert_literals("[[:alpha:]]foo", Some(&["foo"]));
+        assert_literals("[^[:space:]x]foo", Some(&["foo"]));
+        assert_literals("[a[b]]xyz", Some(&["xyz"]));
+        assert_literals(r"[\]]abc", Some(&["abc"]));
+    }
+
+    #[test]
+    fn required_literals_should_give_up_on_syntax_it_does_not_understand() {
+        assert_literals("foo|bar", None);
+        assert_literals("(?i)foo", None);
+        assert_literals(r"\x41bc", None);
+        assert_literals("(unclosed", None);
+        assert_literals("[unclosed", None);
+        assert_literals("[a[b]unclosed", None);
+        assert_literals("[[:alpha:]unclosed", None);
+    }
+
+    #[test]
+    fn trigram_query_should_support_or_conditions() {
+        let query = TrigramQuery::from_condition(&SearchQueryCondition::or([
+            SearchQueryCondition::contains("abc"),
+            SearchQueryCondition::equals("xyz"),
+        ]));
+        assert!(query.matches(&trigrams(b"abc").collect::<Vec<_>>()));
+        assert!(query.matches(&trigrams(b"xyz").collect::<Vec<_>>()));
+        assert!(!query.matches(&trigrams(b"abz").collect::<Vec<_>>()));
+
+        // If any condition can match anything, the whole query can match anything
+        let query = TrigramQuery::from_condition(&SearchQueryCondition::or([
+            SearchQueryCondition::contains("abc"),
+            SearchQueryCondition::regex("a|b"),
+        ]));
+        assert_eq!(query, TrigramQuery::Any);
+    }
+
+    #[test]
+    fn trigram_query_should_match_anything_if_text_is_too_short() {
+        let query = TrigramQuery::from_condition(&SearchQueryCondition::contains("ab"));
+        assert_eq!(query, TrigramQuery::Any);
+    }
+
+    #[test]
+    fn may_match_should_use_index_for_files_within_indexed_directories() {
+        let root = assert_fs::TempDir::new().unwrap();
+        root.child("match.txt").write_str("Some Text").unwrap();
+        root.child("other.txt").write_str("other").unwrap();
+        root.child("binary.bin")
+            .write_binary(&[0, 1, 2, 3])
+            .unwrap();
+
+        let root_path = root.path().canonicalize().unwrap();
+        let index = SearchIndex::default();
+        index.build(root_path.clone()).unwrap();
+
+        let query = TrigramQuery::from_condition(&SearchQueryCondition::contains("some text"));
+        assert_eq!(
+            index.may_match(&root_path.join("match.txt"), &query),
+            Some(true)
+        );
+        assert_eq!(
+            index.may_match(&root_path.join("other.txt"), &query),
+            Some(false)
+        );
+        assert_eq!(index.may_match(&root_path.join("binary.bin"), &query), None);
+        assert_eq!(index.may_match(Path::new("/some/other/file"), &query), None);
+    }
+
+    #[test]
+    fn update_should_refresh_changed_files_and_remove_missing_files() {
+        let root = assert_fs::TempDir::new().unwrap();
+        root.child("file.txt").write_str("before").unwrap();
+        root.child("dir/nested.txt").write_str("nested").unwrap();
+
+        let root_path = root.path().canonicalize().unwrap();
+        let index = SearchIndex::default();
+        index.build(root_path.clone()).unwrap();
+
+        let before = TrigramQuery::from_condition(&SearchQueryCondition::contains("before"));
+        let after = TrigramQuery::from_condition(&SearchQueryCondition::contains("after"));
+        let file = root_path.join("file.txt");
+        let nested = root_path.join("dir").join("nested.txt");
+        let new = root_path.join("new.txt");
+
+        root.child("file.txt").write_str("after").unwrap();
+        root.child("new.txt").write_str("after").unwrap();
+        std::fs::remove_dir_all(root.child("dir").path()).unwrap();
+        index.update(&[file.clone(), new.clone(), root_path.join("dir")]);
+
+        assert_eq!(index.may_match(&file, &before), Some(false));
+        assert_eq!(index.may_match(&file, &after), Some(true));
+        assert_eq!(index.may_match(&new, &after), Some(true));
+        assert_eq!(index.may_match(&nested, &after), None);
+    }
+}
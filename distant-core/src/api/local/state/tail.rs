@@ -0,0 +1,419 @@
+use crate::{
+    constants::{MAX_PIPE_CHUNK_SIZE, SERVER_TAIL_POLL_INTERVAL},
+    data::{DistantResponseData, TailId, TailOptions},
+};
+use distant_net::server::Reply;
+use log::*;
+use std::{collections::HashMap, io, io::SeekFrom, ops::Deref, path::PathBuf, time::Duration};
+use tokio::{
+    fs::File,
+    io::{AsyncReadExt, AsyncSeekExt},
+    sync::{mpsc, oneshot},
+    task::JoinHandle,
+};
+
+/// Holds information related to tails of files on the server
+pub struct TailState {
+    channel: TailChannel,
+    task: JoinHandle<()>,
+}
+
+impl Drop for TailState {
+    /// Aborts the task that handles tail operations and management
+    fn drop(&mut self) {
+        self.abort();
+    }
+}
+
+impl TailState {
+    pub fn new() -> Self {
+        let (tx, rx) = mpsc::channel(1);
+        let task = tokio::spawn(tail_task(tx.clone(), rx));
+
+        Self {
+            channel: TailChannel { tx },
+            task,
+        }
+    }
+
+    /// Aborts the tail task
+    pub fn abort(&self) {
+        self.task.abort();
+    }
+}
+
+impl Deref for TailState {
+    type Target = TailChannel;
+
+    fn deref(&self) -> &Self::Target {
+        &self.channel
+    }
+}
+
+#[derive(Clone)]
+pub struct TailChannel {
+    tx: mpsc::Sender<InnerTailMsg>,
+}
+
+impl Default for TailChannel {
+    /// Creates a new channel that is closed by default
+    fn default() -> Self {
+        let (tx, _) = mpsc::channel(1);
+        Self { tx }
+    }
+}
+
+impl TailChannel {
+    /// Starts a tail of the file at `path`, returning the id associated with it
+    pub async fn start(
+        &self,
+        path: PathBuf,
+        options: TailOptions,
+        reply: Box<dyn Reply<Data = DistantResponseData>>,
+    ) -> io::Result<TailId> {
+        let (cb, rx) = oneshot::channel();
+        self.tx
+            .send(InnerTailMsg::Start {
+                path,
+                options,
+                reply,
+                cb,
+            })
+            .await
+            .map_err(|_| io::Error::new(io::ErrorKind::Other, "Internal tail task closed"))?;
+        rx.await
+            .map_err(|_| io::Error::new(io::ErrorKind::Other, "Response to start dropped"))?
+    }
+
+    /// Cancels an active tail
+    pub async fn cancel(&self, id: TailId) -> io::Result<()> {
+        let (cb, rx) = oneshot::channel();
+        self.tx
+            .send(InnerTailMsg::Cancel { id, cb })
+            .await
+            .map_err(|_| io::Error::new(io::ErrorKind::Other, "Internal tail task closed"))?;
+        rx.await
+            .map_err(|_| io::Error::new(io::ErrorKind::Other, "Response to cancel dropped"))?
+    }
+}
+
+/// Internal message to pass to our task below to perform some action
+enum InnerTailMsg {
+    Start {
+        path: PathBuf,
+        options: TailOptions,
+        reply: Box<dyn Reply<Data = DistantResponseData>>,
+        cb: oneshot::Sender<io::Result<TailId>>,
+    },
+    Cancel {
+        id: TailId,
+        cb: oneshot::Sender<io::Result<()>>,
+    },
+    InternalRemove {
+        id: TailId,
+    },
+}
+
+async fn tail_task(tx: mpsc::Sender<InnerTailMsg>, mut rx: mpsc::Receiver<InnerTailMsg>) {
+    let mut tails: HashMap<TailId, oneshot::Sender<()>> = HashMap::new();
+
+    while let Some(msg) = rx.recv().await {
+        match msg {
+            InnerTailMsg::Start {
+                path,
+                options,
+                reply,
+                cb,
+            } => {
+                let file = match TailFile::open(path, &options).await {
+                    Ok(file) => file,
+                    Err(x) => {
+                        let _ = cb.send(Err(x));
+                        continue;
+                    }
+                };
+
+                let id = rand::random();
+                let (cancel_tx, cancel_rx) = oneshot::channel();
+                let interval = options
+                    .poll_interval_ms
+                    .map(Duration::from_millis)
+                    .unwrap_or(SERVER_TAIL_POLL_INTERVAL);
+
+                // Spawn a task to send data from the file, removing the tail from our list
+                // once it has finished
+                let tx = tx.clone();
+                tokio::spawn(async move {
+                    follow_file(id, file, options.follow, interval, reply, cancel_rx).await;
+                    let _ = tx.send(InnerTailMsg::InternalRemove { id }).await;
+                });
+
+                tails.insert(id, cancel_tx);
+                let _ = cb.send(Ok(id));
+            }
+            InnerTailMsg::Cancel { id, cb } => {
+                let _ = cb.send(match tails.remove(&id) {
+                    Some(cancel_tx) => {
+                        let _ = cancel_tx.send(());
+                        Ok(())
+                    }
+                    None => Err(io::Error::new(
+                        io::ErrorKind::Other,
+                        format!("No tail found with id {id}"),
+                    )),
+                });
+            }
+            InnerTailMsg::InternalRemove { id } => {
+                tails.remove(&id);
+            }
+        }
+    }
+}
+
+/// Sends data from `file` until all of it has been read, or until cancelled if `follow` is true,
+/// checking for appended data every `interval`
+///
+/// Tailing also stops once the connection that requested it has closed, as nothing would receive
+/// the data.
+async fn follow_file(
+    id: TailId,
+    mut file: TailFile,
+    follow: bool,
+    interval: Duration,
+    reply: Box<dyn Reply<Data = DistantResponseData>>,
+    mut cancel_rx: oneshot::Receiver<()>,
+) {
+    loop {
+        // Send everything that is currently available to read, stopping early if cancelled as a
+        // file that keeps growing may never run out of data
+        loop {
+            let res = tokio::select! {
+                _ = &mut cancel_rx => {
+                    trace!("[Tail {id}] Cancelled while reading {:?}", file.path);
+                    let _ = reply.send(DistantResponseData::TailDone { id }).await;
+                    return;
+                }
+                res = file.read_chunk() => res,
+            };
+
+            match res {
+                Ok(data) if data.is_empty() => break,
+                Ok(data) => {
+                    if let Err(x) = reply.send(DistantResponseData::TailData { id, data }).await {
+                        error!("[Tail {id}] Failed to send data: {x}");
+                        return;
+                    }
+                }
+                Err(x) => {
+                    error!("[Tail {id}] Failed to read {:?}: {x}", file.path);
+                    break;
+                }
+            }
+        }
+
+        if !follow {
+            break;
+        }
+
+        if reply.is_closed() {
+            trace!(
+                "[Tail {id}] Connection closed, so no longer tailing {:?}",
+                file.path
+            );
+            return;
+        }
+
+        // If the file was truncated or replaced, read the new file from the start immediately
+        if file.reopen_if_changed().await {
+            trace!("[Tail {id}] {:?} was truncated or replaced", file.path);
+            if let Err(x) = reply.send(DistantResponseData::TailReset { id }).await {
+                error!("[Tail {id}] Failed to send reset: {x}");
+                return;
+            }
+            continue;
+        }
+
+        tokio::select! {
+            _ = &mut cancel_rx => break,
+            _ = tokio::time::sleep(interval) => {}
+        }
+    }
+
+    trace!("[Tail {id}] Finished tailing {:?}", file.path);
+    let _ = reply.send(DistantResponseData::TailDone { id }).await;
+}
+
+/// Identifies a file independent of its path, used to detect a path being replaced
+type FileId = (u64, u64);
+
+#[cfg(unix)]
+fn file_id(metadata: &std::fs::Metadata) -> Option<FileId> {
+    use std::os::unix::fs::MetadataExt;
+    Some((metadata.dev(), metadata.ino()))
+}
+
+/// Uses the creation time of the file, as the volume and index of a file are not available from
+/// stable metadata on other platforms, which still changes when a file is replaced by a new one
+#[cfg(not(unix))]
+fn file_id(metadata: &std::fs::Metadata) -> Option<FileId> {
+    let created = metadata
+        .created()
+        .ok()?
+        .duration_since(std::time::UNIX_EPOCH)
+        .ok()?;
+    Some((created.as_secs(), created.subsec_nanos() as u64))
+}
+
+/// File being tailed along with the position of the next byte to read
+struct TailFile {
+    path: PathBuf,
+    file: File,
+    id: Option<FileId>,
+    pos: u64,
+}
+
+impl TailFile {
+    /// Opens the file at `path`, positioned where `options` says to start reading
+    async fn open(path: PathBuf, options: &TailOptions) -> io::Result<Self> {
+        let mut file = File::open(&path).await?;
+        let metadata = file.metadata().await?;
+        if metadata.is_dir() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{path:?} is a directory"),
+            ));
+        }
+
+        let len = metadata.len();
+        let pos = match (options.offset, options.lines) {
+            (Some(offset), _) => offset.min(len),
+            (None, Some(lines)) => offset_of_last_lines(&mut file, len, lines).await?,
+            (None, None) => len,
+        };
+        file.seek(SeekFrom::Start(pos)).await?;
+
+        Ok(Self {
+            path,
+            file,
+            id: file_id(&metadata),
+            pos,
+        })
+    }
+
+    /// Reads the next chunk of data from the file, returning an empty chunk if there is nothing
+    /// more to read
+    async fn read_chunk(&mut self) -> io::Result<Vec<u8>> {
+        let mut buf = vec![0; MAX_PIPE_CHUNK_SIZE];
+        let n = self.file.read(&mut buf).await?;
+        buf.truncate(n);
+        self.pos += n as u64;
+        Ok(buf)
+    }
+
+    /// Reopens the file from the start if the file at our path was truncated or replaced,
+    /// returning true if it was reopened
+    ///
+    /// If the path is missing, such as in the middle of log rotation, the current file is kept
+    /// until a new file appears at the path.
+    async fn reopen_if_changed(&mut self) -> bool {
+        let metadata = match tokio::fs::metadata(&self.path).await {
+            Ok(metadata) => metadata,
+            Err(_) => return false,
+        };
+
+        let replaced = match (self.id, file_id(&metadata)) {
+            (Some(current), Some(new)) => current != new,
+            _ => false,
+        };
+        if !replaced && metadata.len() >= self.pos {
+            return false;
+        }
+
+        match File::open(&self.path).await {
+            Ok(file) => {
+                self.id = match file.metadata().await {
+                    Ok(metadata) => file_id(&metadata),
+                    Err(_) => None,
+                };
+                self.file = file;
+                self.pos = 0;
+                true
+            }
+            Err(x) => {
+                warn!("Failed to reopen {:?}: {x}", self.path);
+                false
+            }
+        }
+    }
+}
+
+/// Returns the offset of the start of the last `lines` lines of `file` whose length is `len`,
+/// where a newline at the very end of the file does not start a new line
+async fn offset_of_last_lines(file: &mut File, len: u64, lines: u64) -> io::Result<u64> {
+    if lines == 0 {
+        return Ok(len);
+    }
+
+    let mut remaining = lines;
+    let mut buf = vec![0; MAX_PIPE_CHUNK_SIZE];
+    let mut end = len;
+    while end > 0 {
+        let start = end.saturating_sub(buf.len() as u64);
+        let chunk = &mut buf[..(end - start) as usize];
+        file.seek(SeekFrom::Start(start)).await?;
+        file.read_exact(chunk).await?;
+
+        for (i, byte) in chunk.iter().enumerate().rev() {
+            let next = start + i as u64 + 1;
+            if *byte == b'\n' && next < len {
+                remaining -= 1;
+                if remaining == 0 {
+                    return Ok(next);
+                }
+            }
+        }
+
+        end = start;
+    }
+
+    Ok(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_fs::prelude::*;
+    use test_log::test;
+
+    async fn last_lines(contents: &str, lines: u64) -> String {
+        let temp = assert_fs::TempDir::new().unwrap();
+        let path = temp.child("file");
+        path.write_str(contents).unwrap();
+
+        let mut file = File::open(path.path()).await.unwrap();
+        let offset = offset_of_last_lines(&mut file, contents.len() as u64, lines)
+            .await
+            .unwrap();
+        contents[offset as usize..].to_string()
+    }
+
+    #[test(tokio::test)]
+    async fn offset_of_last_lines_should_find_start_of_last_lines() {
+        assert_eq!(last_lines("a\nb\nc\n", 2).await, "b\nc\n");
+        assert_eq!(last_lines("a\nb\nc", 2).await, "b\nc");
+        assert_eq!(last_lines("a\nb\nc\n", 0).await, "");
+    }
+
+    #[test(tokio::test)]
+    async fn offset_of_last_lines_should_return_entire_file_if_fewer_lines() {
+        assert_eq!(last_lines("a\nb\n", 5).await, "a\nb\n");
+        assert_eq!(last_lines("", 5).await, "");
+    }
+
+    #[test(tokio::test)]
+    async fn offset_of_last_lines_should_support_lines_spanning_chunks() {
+        let long = "x".repeat(MAX_PIPE_CHUNK_SIZE * 2);
+        let contents = format!("first\n{long}\nlast\n");
+        assert_eq!(last_lines(&contents, 2).await, format!("{long}\nlast\n"));
+    }
+}
@@ -1,15 +1,24 @@
-use crate::{constants::SERVER_WATCHER_CAPACITY, data::ChangeKind};
+use super::SearchIndex;
+use crate::{
+    constants::SERVER_WATCHER_CAPACITY,
+    data::{Change, ChangeKind},
+};
 use distant_net::common::ConnectionId;
 use log::*;
 use notify::{
-    Config as WatcherConfig, Error as WatcherError, ErrorKind as WatcherErrorKind,
-    Event as WatcherEvent, PollWatcher, RecursiveMode, Watcher,
+    Config as NotifyConfig, Error as WatcherError, ErrorKind as WatcherErrorKind,
+    Event as WatcherEvent, PollWatcher, RecommendedWatcher, RecursiveMode, Watcher, WatcherKind,
 };
 use std::{
-    collections::HashMap,
+    collections::{hash_map::Entry, HashMap, VecDeque},
     io,
     ops::Deref,
     path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
 };
 use tokio::{
     sync::{
@@ -17,15 +26,43 @@ use tokio::{
         oneshot,
     },
     task::JoinHandle,
+    time::Instant,
 };
 
 mod path;
 pub use path::*;
 
+/// Configuration of the watcher used by the server to detect changes
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct WatcherConfig {
+    /// If true, changes are detected by polling instead of using the native watcher of the
+    /// platform, which is needed when serving network filesystems such as NFS or SMB
+    pub poll: bool,
+
+    /// How often to poll for changes, using notify's default interval if none
+    pub poll_interval: Option<Duration>,
+
+    /// If true, polling compares the contents of files to detect changes
+    pub compare_contents: bool,
+}
+
+impl WatcherConfig {
+    /// Creates the configuration for a polling watcher, using `interval` in place of the
+    /// configured interval if provided
+    fn to_notify_config(self, interval: Option<Duration>, compare_contents: bool) -> NotifyConfig {
+        let config = NotifyConfig::default().with_compare_contents(compare_contents);
+        match interval.or(self.poll_interval) {
+            Some(interval) => config.with_poll_interval(interval),
+            None => config,
+        }
+    }
+}
+
 /// Holds information related to watched paths on the server
 pub struct WatcherState {
     channel: WatcherChannel,
     task: JoinHandle<()>,
+    kind: WatcherKind,
 }
 
 impl Drop for WatcherState {
@@ -37,48 +74,33 @@ impl Drop for WatcherState {
 
 impl WatcherState {
     /// Will create a watcher and initialize watched paths to be empty
-    pub fn initialize() -> io::Result<Self> {
+    pub fn initialize(config: WatcherConfig) -> io::Result<Self> {
         // NOTE: Cannot be something small like 1 as this seems to cause a deadlock sometimes
         //       with a large volume of watch requests
         let (tx, rx) = mpsc::channel(SERVER_WATCHER_CAPACITY);
 
+        // Set whenever an event is dropped because the channel is full
+        let overflowed = Arc::new(AtomicBool::new(false));
+
         macro_rules! spawn_watcher {
-            ($watcher:ident) => {{
+            ($watcher:ident, $kind:expr) => {{
                 Self {
-                    channel: WatcherChannel { tx },
-                    task: tokio::spawn(watcher_task($watcher, rx)),
+                    channel: WatcherChannel { tx: tx.clone() },
+                    task: tokio::spawn(watcher_task($watcher, config, tx, rx, overflowed)),
+                    kind: $kind,
                 }
             }};
         }
 
-        macro_rules! event_handler {
-            ($tx:ident) => {
-                move |res| match $tx.try_send(match res {
-                    Ok(x) => InnerWatcherMsg::Event { ev: x },
-                    Err(x) => InnerWatcherMsg::Error { err: x },
-                }) {
-                    Ok(_) => (),
-                    Err(TrySendError::Full(_)) => {
-                        warn!(
-                            "Reached watcher capacity of {}! Dropping watcher event!",
-                            SERVER_WATCHER_CAPACITY,
-                        );
-                    }
-                    Err(TrySendError::Closed(_)) => {
-                        warn!("Skipping watch event because watcher channel closed");
-                    }
-                }
-            };
+        let poll_config = config.to_notify_config(None, config.compare_contents);
+        if config.poll {
+            let watcher = PollWatcher::new(event_handler(tx.clone(), &overflowed), poll_config)
+                .map_err(|x| io::Error::new(io::ErrorKind::Other, x))?;
+            return Ok(spawn_watcher!(watcher, PollWatcher::kind()));
         }
 
-        let tx = tx.clone();
-        let result = {
-            let tx = tx.clone();
-            notify::recommended_watcher(event_handler!(tx))
-        };
-
-        match result {
-            Ok(watcher) => Ok(spawn_watcher!(watcher)),
+        match notify::recommended_watcher(event_handler(tx.clone(), &overflowed)) {
+            Ok(watcher) => Ok(spawn_watcher!(watcher, RecommendedWatcher::kind())),
             Err(x) => match x.kind {
                 // notify-rs has a bug on Mac M1 with Docker and Linux, so we detect that error
                 // and fall back to the poll watcher if this occurs
@@ -86,21 +108,60 @@ impl WatcherState {
                 // https://github.com/notify-rs/notify/issues/423
                 WatcherErrorKind::Io(x) if x.raw_os_error() == Some(38) => {
                     warn!("Recommended watcher is unsupported! Falling back to polling watcher!");
-                    let watcher = PollWatcher::new(event_handler!(tx), WatcherConfig::default())
-                        .map_err(|x| io::Error::new(io::ErrorKind::Other, x))?;
-                    Ok(spawn_watcher!(watcher))
+                    let watcher =
+                        PollWatcher::new(event_handler(tx.clone(), &overflowed), poll_config)
+                            .map_err(|x| io::Error::new(io::ErrorKind::Other, x))?;
+                    Ok(spawn_watcher!(watcher, PollWatcher::kind()))
                 }
                 _ => Err(io::Error::new(io::ErrorKind::Other, x)),
             },
         }
     }
 
+    /// Returns the name of the backend used to detect changes by default
+    pub fn backend(&self) -> &'static str {
+        match self.kind {
+            WatcherKind::Inotify => "inotify",
+            WatcherKind::Fsevent => "fsevent",
+            WatcherKind::Kqueue => "kqueue",
+            WatcherKind::PollWatcher => "polling",
+            WatcherKind::ReadDirectoryChangesWatcher => "ReadDirectoryChangesW",
+            _ => "unknown",
+        }
+    }
+
     /// Aborts the watcher task
     pub fn abort(&self) {
         self.task.abort();
     }
 }
 
+/// Creates a handler that forwards events from a watcher to our task, setting `overflowed` if an
+/// event has to be dropped so our task can tell watchers to resync
+fn event_handler(
+    tx: mpsc::Sender<InnerWatcherMsg>,
+    overflowed: &Arc<AtomicBool>,
+) -> impl FnMut(notify::Result<WatcherEvent>) + Send + 'static {
+    let overflowed = Arc::clone(overflowed);
+    move |res| match tx.try_send(match res {
+        Ok(x) => InnerWatcherMsg::Event { ev: x },
+        Err(x) => InnerWatcherMsg::Error { err: x },
+    }) {
+        Ok(_) => (),
+        Err(TrySendError::Full(_)) => {
+            if !overflowed.swap(true, Ordering::Relaxed) {
+                warn!(
+                    "Reached watcher capacity of {}! Dropping watcher events!",
+                    SERVER_WATCHER_CAPACITY,
+                );
+            }
+        }
+        Err(TrySendError::Closed(_)) => {
+            warn!("Skipping watch event because watcher channel closed");
+        }
+    }
+}
+
 impl Deref for WatcherState {
     type Target = WatcherChannel;
 
@@ -150,9 +211,82 @@ impl WatcherChannel {
         rx.await
             .map_err(|_| io::Error::new(io::ErrorKind::Other, "Response to unwatch dropped"))?
     }
+
+    /// Get the changes to replay to a connection resuming its watch of a path, denoted by the id
+    pub async fn resume(
+        &self,
+        id: ConnectionId,
+        path: impl AsRef<Path>,
+        since_seq: u64,
+    ) -> io::Result<Replay> {
+        let (cb, rx) = oneshot::channel();
+        let path = tokio::fs::canonicalize(path.as_ref())
+            .await
+            .unwrap_or_else(|_| path.as_ref().to_path_buf());
+        self.tx
+            .send(InnerWatcherMsg::Resume {
+                id,
+                path,
+                since_seq,
+                cb,
+            })
+            .await
+            .map_err(|_| io::Error::new(io::ErrorKind::Other, "Internal watcher task closed"))?;
+        let (mut replay, metadata) = rx
+            .await
+            .map_err(|_| io::Error::new(io::ErrorKind::Other, "Response to resume dropped"))??;
+
+        // History is kept without metadata, so read it here rather than in the watcher task
+        if let Replay::Changes(changes) = &mut replay {
+            if metadata {
+                for change in changes.iter_mut() {
+                    read_metadata(change).await;
+                }
+            }
+        }
+
+        Ok(replay)
+    }
+
+    /// Recursively watch the canonicalized `root` directory, keeping `index` up to date for any
+    /// changes beneath it until the server shuts down
+    pub async fn index(&self, root: PathBuf, index: SearchIndex) -> io::Result<()> {
+        let (cb, rx) = oneshot::channel();
+        self.tx
+            .send(InnerWatcherMsg::Index { root, index, cb })
+            .await
+            .map_err(|_| io::Error::new(io::ErrorKind::Other, "Internal watcher task closed"))?;
+        rx.await
+            .map_err(|_| io::Error::new(io::ErrorKind::Other, "Response to index dropped"))?
+    }
+
+    /// Stop keeping `index` up to date for the canonicalized `root` directory, removing its index
+    /// and no longer watching it unless the directory is still watched for other reasons
+    pub async fn unindex(&self, root: PathBuf, index: SearchIndex) -> io::Result<()> {
+        let (cb, rx) = oneshot::channel();
+        self.tx
+            .send(InnerWatcherMsg::Unindex { root, index, cb })
+            .await
+            .map_err(|_| io::Error::new(io::ErrorKind::Other, "Internal watcher task closed"))?;
+        rx.await
+            .map_err(|_| io::Error::new(io::ErrorKind::Other, "Response to unindex dropped"))?
+    }
+
+    /// Waits until every change observed so far within indexed directories has been applied to
+    /// the search index, returning immediately if nothing is indexed
+    pub async fn flush_index(&self) -> io::Result<()> {
+        let (cb, rx) = oneshot::channel();
+        self.tx
+            .send(InnerWatcherMsg::FlushIndex { cb })
+            .await
+            .map_err(|_| io::Error::new(io::ErrorKind::Other, "Internal watcher task closed"))?;
+        rx.await
+            .map_err(|_| io::Error::new(io::ErrorKind::Other, "Response to flush index dropped"))
+    }
 }
 
 /// Internal message to pass to our task below to perform some action
+#[allow(clippy::large_enum_variant)]
 enum InnerWatcherMsg {
     Watch {
         registered_path: RegisteredPath,
@@ -163,6 +297,25 @@ enum InnerWatcherMsg {
         path: PathBuf,
         cb: oneshot::Sender<io::Result<()>>,
     },
+    Resume {
+        id: ConnectionId,
+        path: PathBuf,
+        since_seq: u64,
+        cb: oneshot::Sender<io::Result<(Replay, bool)>>,
+    },
+    Index {
+        root: PathBuf,
+        index: SearchIndex,
+        cb: oneshot::Sender<io::Result<()>>,
+    },
+    Unindex {
+        root: PathBuf,
+        index: SearchIndex,
+        cb: oneshot::Sender<io::Result<()>>,
+    },
+    FlushIndex {
+        cb: oneshot::Sender<()>,
+    },
     Event {
         ev: WatcherEvent,
     },
@@ -171,20 +324,87 @@ enum InnerWatcherMsg {
     },
 }
 
-async fn watcher_task(mut watcher: impl Watcher, mut rx: mpsc::Receiver<InnerWatcherMsg>) {
+async fn watcher_task<W: Watcher>(
+    mut watcher: W,
+    config: WatcherConfig,
+    tx: mpsc::Sender<InnerWatcherMsg>,
+    mut rx: mpsc::Receiver<InnerWatcherMsg>,
+    overflowed: Arc<AtomicBool>,
+) {
     // TODO: Optimize this in some way to be more performant than
     //       checking every path whenever an event comes in
     let mut registered_paths: Vec<RegisteredPath> = Vec::new();
-    let mut path_cnt: HashMap<PathBuf, usize> = HashMap::new();
+    let mut path_cnt: HashMap<(PathBuf, WatchBackend), usize> = HashMap::new();
+
+    // Watchers that poll for changes on behalf of paths that requested polling
+    let mut pollers: HashMap<WatchBackend, PollWatcher> = HashMap::new();
+
+    // Directories whose search index we keep up to date, and the channel used to queue paths
+    // that need to be refreshed within the index
+    let mut index_roots: Vec<PathBuf> = Vec::new();
+    let mut index_tx: Option<mpsc::UnboundedSender<IndexUpdate>> = None;
+
+    // Sequence number of the last change observed, and the renames awaiting their destination
+    let mut seq: u64 = 0;
+    let mut renames = RenameTracker::default();
+
+    loop {
+        // Events were dropped, so any of the watched paths may have missed changes and we no
+        // longer know which files within indexed directories need to be refreshed
+        if overflowed.swap(false, Ordering::Relaxed) {
+            for registered_path in registered_paths.iter_mut() {
+                if let Err(x) = registered_path.send_overflow(seq).await {
+                    error!(
+                        "[Conn {}] Failed to forward overflow to paths: {}",
+                        registered_path.id(),
+                        x
+                    );
+                }
+            }
+
+            if let Some(tx) = index_tx.as_ref() {
+                let _ = tx.send(IndexUpdate::Paths(index_roots.clone()));
+            }
+        }
+
+        // Wait for the next message, or until the soonest time that changes collected while
+        // debouncing need to be reported
+        let deadline = registered_paths.iter().filter_map(|p| p.deadline()).min();
+        let msg = tokio::select! {
+            msg = rx.recv() => match msg {
+                Some(msg) => msg,
+                None => break,
+            },
+            _ = sleep_until_deadline(deadline) => {
+                let now = Instant::now();
+                for registered_path in registered_paths.iter_mut() {
+                    if !matches!(registered_path.deadline(), Some(d) if d <= now) {
+                        continue;
+                    }
+
+                    if let Err(x) = registered_path.flush().await {
+                        error!(
+                            "[Conn {}] Failed to forward changes to paths: {}",
+                            registered_path.id(),
+                            x
+                        );
+                    }
+                }
+                continue;
+            }
+        };
 
-    while let Some(msg) = rx.recv().await {
         match msg {
             InnerWatcherMsg::Watch {
                 registered_path,
                 cb,
             } => {
                 // Check if we are tracking the path across any connection
-                if let Some(cnt) = path_cnt.get_mut(registered_path.path()) {
+                let key = (
+                    registered_path.path().to_path_buf(),
+                    registered_path.backend(),
+                );
+                if let Some(cnt) = path_cnt.get_mut(&key) {
                     // Increment the count of times we are watching that path
                     *cnt += 1;
 
@@ -195,20 +415,30 @@ async fn watcher_task(mut watcher: impl Watcher, mut rx: mpsc::Receiver<InnerWat
                     // Send an okay because we always succeed in this case
                     let _ = cb.send(Ok(()));
                 } else {
-                    let res = watcher
-                        .watch(
-                            registered_path.path(),
-                            if registered_path.is_recursive() {
-                                RecursiveMode::Recursive
-                            } else {
-                                RecursiveMode::NonRecursive
-                            },
-                        )
-                        .map_err(|x| io::Error::new(io::ErrorKind::Other, x));
+                    let res = backend_watcher(
+                        key.1,
+                        &mut watcher,
+                        &mut pollers,
+                        config,
+                        &tx,
+                        &overflowed,
+                    )
+                    .and_then(|watcher| {
+                        watcher
+                            .watch(
+                                registered_path.path(),
+                                if registered_path.is_recursive() {
+                                    RecursiveMode::Recursive
+                                } else {
+                                    RecursiveMode::NonRecursive
+                                },
+                            )
+                            .map_err(|x| io::Error::new(io::ErrorKind::Other, x))
+                    });
 
                     // If we succeeded, store our registered path and set the tracking cnt to 1
                     if res.is_ok() {
-                        path_cnt.insert(registered_path.path().to_path_buf(), 1);
+                        path_cnt.insert(key, 1);
                         registered_paths.push(registered_path);
                     }
 
@@ -217,51 +447,196 @@ async fn watcher_task(mut watcher: impl Watcher, mut rx: mpsc::Receiver<InnerWat
                 }
             }
             InnerWatcherMsg::Unwatch { id, path, cb } => {
-                // Check if we are tracking the path across any connection
-                if let Some(cnt) = path_cnt.get(path.as_path()) {
-                    // Cycle through and remove all paths that match the given id and path,
-                    // capturing how many paths we removed
-                    let removed_cnt = {
-                        let old_len = registered_paths.len();
-                        registered_paths
-                            .retain(|p| p.id() != id || (p.path() != path && p.raw_path() != path));
-                        let new_len = registered_paths.len();
-                        old_len - new_len
+                // Cycle through and remove all paths that match the given id and path,
+                // capturing what each removed path was being watched with
+                let mut removed = Vec::new();
+                registered_paths.retain(|p| {
+                    let matches = p.id() == id && (p.path() == path || p.raw_path() == path);
+                    if matches {
+                        removed.push((p.path().to_path_buf(), p.backend()));
+                    }
+                    !matches
+                });
+
+                // Send a failure as there was nothing to unwatch for this connection
+                if removed.is_empty() {
+                    let _ = cb.send(Err(io::Error::new(
+                        io::ErrorKind::Other,
+                        format!("{:?} is not being watched", path),
+                    )));
+                    continue;
+                }
+
+                // Actually unwatch any path that is no longer being watched by anyone
+                let mut res = Ok(());
+                for key in removed {
+                    let cnt = match path_cnt.get_mut(&key) {
+                        Some(cnt) => cnt,
+                        None => continue,
                     };
 
-                    // 1. If we are now at zero cnt for our path, we want to actually unwatch the
-                    //    path with our watcher
-                    // 2. If we removed nothing from our path list, we want to return an error
-                    // 3. Otherwise, we return okay because we succeeded
-                    if *cnt <= removed_cnt {
-                        let _ = cb.send(
+                    *cnt = cnt.saturating_sub(1);
+                    if *cnt == 0 {
+                        path_cnt.remove(&key);
+
+                        // The recursive watch of an indexed directory shares the native watch of
+                        // anything beneath it, so leave it in place to keep the index up to date
+                        if key.1 == WatchBackend::Default
+                            && index_roots.iter().any(|root| key.0.starts_with(root))
+                        {
+                            continue;
+                        }
+
+                        if let Err(x) = backend_watcher(
+                            key.1,
+                            &mut watcher,
+                            &mut pollers,
+                            config,
+                            &tx,
+                            &overflowed,
+                        )
+                        .and_then(|watcher| {
                             watcher
-                                .unwatch(&path)
-                                .map_err(|x| io::Error::new(io::ErrorKind::Other, x)),
-                        );
-                    } else if removed_cnt == 0 {
-                        // Send a failure as there was nothing to unwatch for this connection
-                        let _ = cb.send(Err(io::Error::new(
+                                .unwatch(&key.0)
+                                .map_err(|x| io::Error::new(io::ErrorKind::Other, x))
+                        }) {
+                            res = Err(x);
+                        }
+
+                        // Unwatching a directory also drops the watches beneath it, so restore
+                        // those of any indexed directories within it
+                        if key.1 == WatchBackend::Default {
+                            rewatch_within(&key.0, &mut watcher, &registered_paths, &index_roots);
+                        }
+                    }
+                }
+
+                let _ = cb.send(res);
+            }
+            InnerWatcherMsg::Resume {
+                id,
+                path,
+                since_seq,
+                cb,
+            } => {
+                let replay = registered_paths
+                    .iter()
+                    .find(|p| p.id() == id && (p.path() == path || p.raw_path() == path))
+                    .map(|p| match p.changes_since(since_seq) {
+                        Some(changes) => (Replay::Changes(changes), p.includes_metadata()),
+                        None => (Replay::Overflow { seq }, false),
+                    })
+                    .ok_or_else(|| {
+                        io::Error::new(
                             io::ErrorKind::Other,
                             format!("{:?} is not being watched", path),
-                        )));
-                    } else {
-                        // Send a success as we removed some paths
-                        let _ = cb.send(Ok(()));
-                    }
-                } else {
-                    // Send a failure as there was nothing to unwatch
+                        )
+                    });
+
+                let _ = cb.send(replay);
+            }
+            InnerWatcherMsg::Index { root, index, cb } => {
+                // Already keeping the index up to date, so nothing more to do
+                if index_roots.contains(&root) {
+                    let _ = cb.send(Ok(()));
+                    continue;
+                }
+
+                // NOTE: We always watch here, even if the path is already being watched, as the
+                //       existing watch may not be recursive
+                let res = watcher
+                    .watch(&root, RecursiveMode::Recursive)
+                    .map_err(|x| io::Error::new(io::ErrorKind::Other, x));
+
+                if res.is_ok() {
+                    *path_cnt
+                        .entry((root.clone(), WatchBackend::Default))
+                        .or_insert(0) += 1;
+                    index_roots.push(root);
+                    index_tx.get_or_insert_with(|| spawn_index_updater(index));
+                }
+
+                let _ = cb.send(res);
+            }
+            InnerWatcherMsg::Unindex { root, index, cb } => {
+                if !index_roots.contains(&root) {
                     let _ = cb.send(Err(io::Error::new(
                         io::ErrorKind::Other,
-                        format!("{:?} is not being watched", path),
+                        format!("{:?} is not being indexed", root),
                     )));
+                    continue;
                 }
+
+                index_roots.retain(|r| r != &root);
+                index.remove(&root);
+
+                // Only stop watching the directory once nothing else relies on the watch, which
+                // includes connections watching it and indexed directories containing it
+                let key = (root, WatchBackend::Default);
+                let cnt = path_cnt.get_mut(&key).map_or(0, |cnt| {
+                    *cnt = cnt.saturating_sub(1);
+                    *cnt
+                });
+
+                let res = if cnt == 0 {
+                    path_cnt.remove(&key);
+                    if index_roots.iter().any(|root| key.0.starts_with(root)) {
+                        Ok(())
+                    } else {
+                        let res = watcher
+                            .unwatch(&key.0)
+                            .map_err(|x| io::Error::new(io::ErrorKind::Other, x));
+                        rewatch_within(&key.0, &mut watcher, &registered_paths, &index_roots);
+                        res
+                    }
+                } else {
+                    // Others still watch the directory, but possibly not recursively
+                    rewatch_within(&key.0, &mut watcher, &registered_paths, &index_roots);
+                    Ok(())
+                };
+
+                let _ = cb.send(res);
             }
+            InnerWatcherMsg::FlushIndex { cb } => match index_tx.as_ref() {
+                Some(tx) => {
+                    if let Err(mpsc::error::SendError(IndexUpdate::Flush(cb))) =
+                        tx.send(IndexUpdate::Flush(cb))
+                    {
+                        let _ = cb.send(());
+                    }
+                }
+                None => {
+                    let _ = cb.send(());
+                }
+            },
             InnerWatcherMsg::Event { ev } => {
-                let kind = ChangeKind::from(ev.kind);
+                let kind = ChangeKind::from(ev.kind.clone());
 
-                for registered_path in registered_paths.iter() {
-                    match registered_path.filter_and_send(kind, &ev.paths).await {
+                // Queue up changed paths within indexed directories to be refreshed, ignoring
+                // events that cannot have changed the contents of a file
+                if let Some(tx) = index_tx.as_ref() {
+                    if !kind.is_access_kind() || kind == ChangeKind::AccessCloseWrite {
+                        let paths: Vec<PathBuf> = ev
+                            .paths
+                            .iter()
+                            .filter(|p| index_roots.iter().any(|root| p.starts_with(root)))
+                            .cloned()
+                            .collect();
+
+                        if !paths.is_empty() {
+                            let _ = tx.send(IndexUpdate::Paths(paths));
+                        }
+                    }
+                }
+
+                seq += 1;
+                let tracker = ev.attrs.tracker();
+                let mut change = Change::from(ev);
+                change.seq = seq;
+                renames.pair(tracker, &mut change);
+
+                for registered_path in registered_paths.iter_mut() {
+                    match registered_path.filter_and_send(&change).await {
                         Ok(_) => (),
                         Err(x) => error!(
                             "[Conn {}] Failed to forward changes to paths: {}",
@@ -292,3 +667,202 @@ async fn watcher_task(mut watcher: impl Watcher, mut rx: mpsc::Receiver<InnerWat
         }
     }
 }
+
+/// Changes to replay to a connection resuming its watch of a path
+#[derive(Debug, PartialEq, Eq)]
+pub enum Replay {
+    /// Changes that were reported after the sequence number provided when resuming
+    Changes(Vec<Change>),
+
+    /// Some changes after the sequence number provided when resuming are no longer remembered,
+    /// where `seq` is the sequence number of the latest change observed
+    Overflow { seq: u64 },
+}
+
+/// Maximum number of renames to remember while waiting for the event reporting their destination
+const MAX_PENDING_RENAMES: usize = 64;
+
+/// Pairs the source and destination of renames that are reported as separate events, which are
+/// tied together by a tracker id provided by the watcher
+#[derive(Default)]
+struct RenameTracker {
+    pending: VecDeque<(usize, PathBuf)>,
+}
+
+impl RenameTracker {
+    /// Remembers the source of a rename, or fills in the source of a rename to a destination
+    /// using the source previously seen with the same `tracker`
+    fn pair(&mut self, tracker: Option<usize>, change: &mut Change) {
+        let tracker = match tracker {
+            Some(tracker) => tracker,
+            None => return,
+        };
+
+        match change.kind {
+            ChangeKind::RenameFrom => {
+                if let Some(from) = change.from.clone() {
+                    if self.pending.len() >= MAX_PENDING_RENAMES {
+                        self.pending.pop_front();
+                    }
+                    self.pending.push_back((tracker, from));
+                }
+            }
+            ChangeKind::RenameTo if change.from.is_none() => {
+                if let Some(i) = self.pending.iter().position(|(t, _)| *t == tracker) {
+                    change.from = self.pending.remove(i).map(|(_, from)| from);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Returns the watcher used to detect changes with `backend`, creating a polling watcher if
+/// needed
+fn backend_watcher<'a, W: Watcher>(
+    backend: WatchBackend,
+    watcher: &'a mut W,
+    pollers: &'a mut HashMap<WatchBackend, PollWatcher>,
+    config: WatcherConfig,
+    tx: &mpsc::Sender<InnerWatcherMsg>,
+    overflowed: &Arc<AtomicBool>,
+) -> io::Result<&'a mut dyn Watcher> {
+    match backend {
+        WatchBackend::Default => Ok(watcher),
+        WatchBackend::Poll {
+            interval,
+            compare_contents,
+        } => match pollers.entry(backend) {
+            Entry::Occupied(x) => Ok(x.into_mut()),
+            Entry::Vacant(x) => {
+                let poller = PollWatcher::new(
+                    event_handler(tx.clone(), overflowed),
+                    config.to_notify_config(interval, compare_contents),
+                )
+                .map_err(|x| io::Error::new(io::ErrorKind::Other, x))?;
+                Ok(x.insert(poller))
+            }
+        },
+    }
+}
+
+/// Sleeps until `deadline`, or forever if there is no deadline
+async fn sleep_until_deadline(deadline: Option<Instant>) {
+    match deadline {
+        Some(deadline) => tokio::time::sleep_until(deadline).await,
+        None => std::future::pending().await,
+    }
+}
+
+/// Re-establishes the native watches of indexed directories and of paths watched by connections
+/// that are at or beneath `path`, which are lost when `path` is unwatched
+fn rewatch_within<W: Watcher>(
+    path: &Path,
+    watcher: &mut W,
+    registered_paths: &[RegisteredPath],
+    index_roots: &[PathBuf],
+) {
+    let registered = registered_paths
+        .iter()
+        .filter(|p| p.backend() == WatchBackend::Default)
+        .map(|p| (p.path(), p.is_recursive()));
+    let indexed = index_roots.iter().map(|root| (root.as_path(), true));
+
+    for (p, recursive) in registered.chain(indexed) {
+        if !p.starts_with(path) {
+            continue;
+        }
+
+        let mode = if recursive {
+            RecursiveMode::Recursive
+        } else {
+            RecursiveMode::NonRecursive
+        };
+
+        if let Err(x) = watcher.watch(p, mode) {
+            error!("Failed to restore watch of {p:?}: {x}");
+        }
+    }
+}
+
+/// Work queued for the task that keeps the search index up to date
+enum IndexUpdate {
+    /// Paths whose entries within the index need to be refreshed
+    Paths(Vec<PathBuf>),
+
+    /// Request to be notified once every refresh queued before it has been applied
+    Flush(oneshot::Sender<()>),
+}
+
+/// Spawns a task that refreshes `index` for each batch of paths sent to it, merging batches that
+/// queue up while a refresh is in progress
+fn spawn_index_updater(index: SearchIndex) -> mpsc::UnboundedSender<IndexUpdate> {
+    let (tx, mut rx) = mpsc::unbounded_channel::<IndexUpdate>();
+
+    tokio::spawn(async move {
+        while let Some(update) = rx.recv().await {
+            let mut paths = Vec::new();
+            let mut flushes = Vec::new();
+            for update in std::iter::once(update).chain(std::iter::from_fn(|| rx.try_recv().ok())) {
+                match update {
+                    IndexUpdate::Paths(more) => paths.extend(more),
+                    IndexUpdate::Flush(cb) => flushes.push(cb),
+                }
+            }
+            paths.sort();
+            paths.dedup();
+
+            if !paths.is_empty() {
+                trace!("Refreshing search index for {} paths", paths.len());
+                let index = index.clone();
+                if let Err(x) = tokio::task::spawn_blocking(move || index.update(&paths)).await {
+                    error!("Failed to refresh search index: {x}");
+                }
+            }
+
+            for cb in flushes {
+                let _ = cb.send(());
+            }
+        }
+    });
+
+    tx
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use notify::event::{EventKind as WatcherEventKind, ModifyKind, RenameMode};
+    use test_log::test;
+
+    fn rename_event(mode: RenameMode, path: &str, tracker: usize) -> WatcherEvent {
+        WatcherEvent::new(WatcherEventKind::Modify(ModifyKind::Name(mode)))
+            .add_path(PathBuf::from(path))
+            .set_tracker(tracker)
+    }
+
+    #[test]
+    fn rename_tracker_should_pair_renames_reported_as_separate_events() {
+        let mut renames = RenameTracker::default();
+
+        let ev = rename_event(RenameMode::From, "/a", 1);
+        let mut from = Change::from(ev.clone());
+        renames.pair(ev.attrs.tracker(), &mut from);
+        assert_eq!(from.from, Some(PathBuf::from("/a")));
+        assert_eq!(from.to, None);
+
+        // Destination of a different rename is left unpaired
+        let ev = rename_event(RenameMode::To, "/c", 2);
+        let mut other = Change::from(ev.clone());
+        renames.pair(ev.attrs.tracker(), &mut other);
+        assert_eq!(other.from, None);
+        assert_eq!(other.to, Some(PathBuf::from("/c")));
+
+        let ev = rename_event(RenameMode::To, "/b", 1);
+        let mut to = Change::from(ev.clone());
+        renames.pair(ev.attrs.tracker(), &mut to);
+        assert_eq!(to.from, Some(PathBuf::from("/a")));
+        assert_eq!(to.to, Some(PathBuf::from("/b")));
+        assert!(renames.pending.is_empty());
+    }
+}
@@ -1,12 +1,33 @@
-use crate::data::{Change, ChangeKind, ChangeKindSet, DistantResponseData, Error};
+use crate::{
+    constants::SERVER_WATCHER_HISTORY_CAPACITY,
+    data::{Change, ChangeKindSet, DistantResponseData, Error, Metadata, WatchOptions},
+};
 use distant_net::common::ConnectionId;
 use distant_net::server::Reply;
+use globset::{Glob, GlobSet, GlobSetBuilder};
 use std::{
+    collections::VecDeque,
     fmt,
     hash::{Hash, Hasher},
-    io,
+    io, mem,
     path::{Path, PathBuf},
+    time::Duration,
 };
+use tokio::{sync::mpsc, time::Instant};
+
+/// Backend used to detect changes to a registered path
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum WatchBackend {
+    /// Watcher configured for the server
+    Default,
+
+    /// Watcher that polls for changes every `interval`, or the server's poll interval if none,
+    /// optionally comparing the contents of files
+    Poll {
+        interval: Option<Duration>,
+        compare_contents: bool,
+    },
+}
 
 /// Represents a path registered with a watcher that includes relevant state including
 /// the ability to reply with
@@ -25,12 +46,56 @@ pub struct RegisteredPath {
     /// Whether or not the path was set to be recursive
     recursive: bool,
 
+    /// Backend used to detect changes to the path
+    backend: WatchBackend,
+
     /// Specific filter for path (only the allowed change kinds are tracked)
     /// NOTE: This is a combination of only and except filters
     allowed: ChangeKindSet,
 
+    /// Globs of paths relative to the watched path whose changes are reported, where an empty
+    /// set means all paths are reported
+    include: GlobSet,
+
+    /// Globs of paths relative to the watched path whose changes are never reported
+    exclude: GlobSet,
+
+    /// How long to wait for changes to stop before reporting them, where zero means changes are
+    /// reported immediately
+    debounce: Duration,
+
+    /// Longest to hold collected changes when new changes keep arriving
+    max_debounce_wait: Duration,
+
+    /// Whether or not to merge changes to the same paths collected while debouncing
+    coalesce: bool,
+
+    /// Whether or not to include a snapshot of the metadata of the changed path with each change
+    metadata: bool,
+
+    /// Changes collected while debouncing that have not yet been reported
+    pending: Vec<Change>,
+
+    /// When the collected changes should be reported
+    deadline: Option<Instant>,
+
+    /// When the oldest of the collected changes happened
+    pending_since: Option<Instant>,
+
+    /// Most recent changes reported for this path, oldest first, kept to replay to clients
+    /// resuming the watch
+    history: VecDeque<Change>,
+
+    /// Sequence number of the most recent change dropped from the history, or zero if no change
+    /// has been dropped
+    evicted_seq: u64,
+
     /// Used to send a reply through the connection watching this path
     reply: Box<dyn Reply<Data = DistantResponseData>>,
+
+    /// Used to send replies through a task that first reads the metadata of the changed paths,
+    /// present only if metadata is requested so reading from disk never holds up the watcher
+    metadata_tx: Option<mpsc::UnboundedSender<DistantResponseData>>,
 }
 
 impl fmt::Debug for RegisteredPath {
@@ -39,7 +104,13 @@ impl fmt::Debug for RegisteredPath {
             .field("raw_path", &self.raw_path)
             .field("path", &self.path)
             .field("recursive", &self.recursive)
+            .field("backend", &self.backend)
             .field("allowed", &self.allowed)
+            .field("include", &self.include)
+            .field("exclude", &self.exclude)
+            .field("debounce", &self.debounce)
+            .field("coalesce", &self.coalesce)
+            .field("metadata", &self.metadata)
             .finish()
     }
 }
@@ -70,6 +141,7 @@ impl RegisteredPath {
         recursive: bool,
         only: impl Into<ChangeKindSet>,
         except: impl Into<ChangeKindSet>,
+        options: WatchOptions,
         reply: Box<dyn Reply<Data = DistantResponseData>>,
     ) -> io::Result<Self> {
         let raw_path = path.into();
@@ -84,13 +156,43 @@ impl RegisteredPath {
             only - except
         };
 
+        let backend = if options.poll {
+            WatchBackend::Poll {
+                interval: options.poll_interval_ms.map(Duration::from_millis),
+                compare_contents: options.compare_contents,
+            }
+        } else {
+            WatchBackend::Default
+        };
+
+        let include = build_glob_set(&options.include)?;
+        let exclude = build_glob_set(&options.exclude)?;
+        let metadata_tx = if options.metadata {
+            Some(spawn_metadata_reader(reply.clone_reply()))
+        } else {
+            None
+        };
+
         Ok(Self {
             id,
             raw_path,
             path,
             recursive,
+            backend,
             allowed,
+            include,
+            exclude,
+            debounce: Duration::from_millis(options.debounce_ms),
+            max_debounce_wait: options.max_debounce_wait(),
+            coalesce: options.coalesce,
+            metadata: options.metadata,
+            pending: Vec::new(),
+            deadline: None,
+            pending_since: None,
+            history: VecDeque::new(),
+            evicted_seq: 0,
             reply,
+            metadata_tx,
         })
     }
 
@@ -115,40 +217,151 @@ impl RegisteredPath {
         self.recursive
     }
 
+    /// Returns the backend used to detect changes to this path
+    pub fn backend(&self) -> WatchBackend {
+        self.backend
+    }
+
     /// Returns reference to set of [`ChangeKind`] that this path watches
     pub fn allowed(&self) -> &ChangeKindSet {
         &self.allowed
     }
 
+    /// Returns true if a snapshot of the metadata of the changed path is included with each change
+    pub fn includes_metadata(&self) -> bool {
+        self.metadata
+    }
+
+    /// Returns when changes collected while debouncing should be reported, or none if there are
+    /// no changes waiting to be reported
+    pub fn deadline(&self) -> Option<Instant> {
+        self.deadline
+    }
+
     /// Sends a reply for a change tied to this registered path, filtering
-    /// out any paths that are not applicable
+    /// out any paths that are not applicable or are excluded by globs
+    ///
+    /// If debouncing, the change is instead collected to be sent later via [`Self::flush`].
     ///
     /// Returns true if message was sent, and false if not
-    pub async fn filter_and_send<T>(&self, kind: ChangeKind, paths: T) -> io::Result<bool>
-    where
-        T: IntoIterator,
-        T::Item: AsRef<Path>,
-    {
-        if !self.allowed().contains(&kind) {
+    pub async fn filter_and_send(&mut self, change: &Change) -> io::Result<bool> {
+        if !self.allowed().contains(&change.kind) {
             return Ok(false);
         }
 
-        let paths: Vec<PathBuf> = paths
-            .into_iter()
-            .filter(|p| self.applies_to_path(p.as_ref()))
-            .map(|p| p.as_ref().to_path_buf())
+        let paths: Vec<PathBuf> = change
+            .paths
+            .iter()
+            .filter(|p| self.applies_to_path(p) && self.matches_globs(p))
+            .cloned()
             .collect();
 
-        if !paths.is_empty() {
-            self.reply
-                .send(DistantResponseData::Changed(Change { kind, paths }))
+        if paths.is_empty() {
+            return Ok(false);
+        }
+
+        // The source or destination of a rename may be excluded even if the other is not
+        let filter = |path: &Option<PathBuf>| {
+            path.as_ref()
+                .filter(|p| self.applies_to_path(p) && self.matches_globs(p))
+                .cloned()
+        };
+        let change = Change {
+            paths,
+            from: filter(&change.from),
+            to: filter(&change.to),
+            ..change.clone()
+        };
+
+        if self.history.len() >= SERVER_WATCHER_HISTORY_CAPACITY {
+            if let Some(evicted) = self.history.pop_front() {
+                self.evicted_seq = evicted.seq;
+            }
+        }
+        self.history.push_back(change.clone());
+
+        if self.debounce.is_zero() {
+            self.send(DistantResponseData::Changed(change))
                 .await
                 .map(|_| true)
         } else {
+            // Wait for changes to stop, but never hold changes longer than the maximum wait
+            let now = Instant::now();
+            let since = *self.pending_since.get_or_insert(now);
+            self.deadline = Some((now + self.debounce).min(since + self.max_debounce_wait));
+            self.pending.push(change);
             Ok(false)
         }
     }
 
+    /// Sends all changes collected while debouncing as a single reply, merging changes to the
+    /// same paths if coalescing
+    ///
+    /// Returns true if message was sent, and false if not
+    pub async fn flush(&mut self) -> io::Result<bool> {
+        self.deadline = None;
+        self.pending_since = None;
+        let mut changes = mem::take(&mut self.pending);
+        if self.coalesce {
+            changes = coalesce(changes);
+        }
+
+        if changes.is_empty() {
+            Ok(false)
+        } else if changes.len() == 1 {
+            self.send(DistantResponseData::Changed(changes.remove(0)))
+                .await
+                .map(|_| true)
+        } else {
+            self.send(DistantResponseData::ChangedBatch { changes })
+                .await
+                .map(|_| true)
+        }
+    }
+
+    /// Sends a reply through the connection watching this path, reading the metadata of any
+    /// changes first if requested
+    async fn send(&self, data: DistantResponseData) -> io::Result<()> {
+        match self.metadata_tx.as_ref() {
+            Some(tx) => tx.send(data).map_err(|_| {
+                io::Error::new(io::ErrorKind::BrokenPipe, "Metadata reader task closed")
+            }),
+            None => self.reply.send(data).await,
+        }
+    }
+
+    /// Reports that changes to this path may have been missed up through the change numbered
+    /// `seq`, meaning the path needs to be resynced, after first sending any changes collected
+    /// while debouncing
+    ///
+    /// As the missed changes can never be replayed, resuming from before `seq` also overflows.
+    pub async fn send_overflow(&mut self, seq: u64) -> io::Result<()> {
+        self.flush().await?;
+        self.history.clear();
+        self.evicted_seq = seq;
+        self.send(DistantResponseData::WatchOverflow {
+            path: self.raw_path.to_path_buf(),
+            seq,
+        })
+        .await
+    }
+
+    /// Returns the changes reported for this path with a sequence number after `since_seq`, or
+    /// none if some of those changes are no longer remembered and cannot be replayed
+    pub fn changes_since(&self, since_seq: u64) -> Option<Vec<Change>> {
+        if since_seq < self.evicted_seq {
+            return None;
+        }
+
+        Some(
+            self.history
+                .iter()
+                .filter(|change| change.seq > since_seq)
+                .cloned()
+                .collect(),
+        )
+    }
+
     /// Sends an error message and includes paths if provided, skipping sending the message if
     /// no paths match and `skip_if_no_paths` is true
     ///
@@ -170,14 +383,13 @@ impl RegisteredPath {
             .collect();
 
         if !paths.is_empty() || !skip_if_no_paths {
-            self.reply
-                .send(if paths.is_empty() {
-                    DistantResponseData::Error(Error::from(msg))
-                } else {
-                    DistantResponseData::Error(Error::from(format!("{} about {:?}", msg, paths)))
-                })
-                .await
-                .map(|_| true)
+            self.send(if paths.is_empty() {
+                DistantResponseData::Error(Error::from(msg))
+            } else {
+                DistantResponseData::Error(Error::from(format!("{} about {:?}", msg, paths)))
+            })
+            .await
+            .map(|_| true)
         } else {
             Ok(false)
         }
@@ -207,4 +419,532 @@ impl RegisteredPath {
             (Err(_), Err(_)) => false,
         }
     }
+
+    /// Returns true if the given path, relative to this path, is included and not excluded by
+    /// the globs of this path. A glob matches if it matches the path or any of its parent
+    /// directories beneath this path, so globs like `target` exclude everything within `target/`
+    pub fn matches_globs(&self, path: &Path) -> bool {
+        let relative = match path
+            .strip_prefix(self.path())
+            .or_else(|_| path.strip_prefix(self.raw_path()))
+        {
+            Ok(relative) => relative,
+            Err(_) => return false,
+        };
+
+        let is_match = |set: &GlobSet| {
+            relative
+                .ancestors()
+                .filter(|p| !p.as_os_str().is_empty())
+                .any(|p| set.is_match(p))
+        };
+
+        (self.include.is_empty() || is_match(&self.include)) && !is_match(&self.exclude)
+    }
+}
+
+/// Snapshots the metadata of the path as it is after `change`, which for renames is the path that
+/// was renamed to, clearing it if the path no longer exists
+pub async fn read_metadata(change: &mut Change) {
+    change.metadata = match change.to.as_ref().or_else(|| change.paths.last()) {
+        Some(path) => Metadata::read(path, false, false).await.ok(),
+        None => None,
+    };
+}
+
+/// Spawns a task that reads the metadata of the changes within each response before sending it
+/// through `reply`, keeping responses in order
+fn spawn_metadata_reader(
+    reply: Box<dyn Reply<Data = DistantResponseData>>,
+) -> mpsc::UnboundedSender<DistantResponseData> {
+    let (tx, mut rx) = mpsc::unbounded_channel();
+
+    tokio::spawn(async move {
+        while let Some(mut data) = rx.recv().await {
+            match &mut data {
+                DistantResponseData::Changed(change) => read_metadata(change).await,
+                DistantResponseData::ChangedBatch { changes } => {
+                    for change in changes.iter_mut() {
+                        read_metadata(change).await;
+                    }
+                }
+                _ => {}
+            }
+
+            if reply.send(data).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    tx
+}
+
+/// Compiles `globs` into a single set, ignoring trailing separators so `target/` and `target`
+/// are treated the same
+fn build_glob_set(globs: &[String]) -> io::Result<GlobSet> {
+    let mut builder = GlobSetBuilder::new();
+    for glob in globs {
+        let glob = glob.trim_end_matches('/');
+        builder.add(Glob::new(glob).map_err(|x| io::Error::new(io::ErrorKind::InvalidInput, x))?);
+    }
+
+    builder
+        .build()
+        .map_err(|x| io::Error::new(io::ErrorKind::InvalidInput, x))
+}
+
+/// Merges changes with the same paths into a single change, ordered by when each set of paths
+/// first changed, where the merged change is the most recent change alongside every kind of
+/// change that occurred
+fn coalesce(changes: Vec<Change>) -> Vec<Change> {
+    let mut coalesced: Vec<Change> = Vec::new();
+
+    for change in changes {
+        match coalesced.iter_mut().find(|c| c.paths == change.paths) {
+            Some(existing) => {
+                let mut kinds = mem::take(&mut existing.kinds);
+                kinds.insert(change.kind);
+                *existing = Change { kinds, ..change };
+            }
+            None => coalesced.push(Change {
+                kinds: ChangeKindSet::from(change.kind),
+                ..change
+            }),
+        }
+    }
+
+    coalesced
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::ChangeKind;
+    use test_log::test;
+    use tokio::sync::mpsc;
+
+    fn change<P: AsRef<Path>>(kind: ChangeKind, paths: impl IntoIterator<Item = P>) -> Change {
+        Change {
+            seq: 0,
+            timestamp: 0,
+            kind,
+            paths: paths
+                .into_iter()
+                .map(|p| p.as_ref().to_path_buf())
+                .collect(),
+            from: None,
+            to: None,
+            metadata: None,
+            kinds: ChangeKindSet::empty(),
+        }
+    }
+
+    async fn register(
+        options: WatchOptions,
+    ) -> (
+        assert_fs::TempDir,
+        RegisteredPath,
+        mpsc::Receiver<DistantResponseData>,
+    ) {
+        let temp = assert_fs::TempDir::new().unwrap();
+        let (tx, rx) = mpsc::channel(100);
+        let path = RegisteredPath::register(
+            0,
+            temp.path(),
+            /* recursive */ true,
+            ChangeKindSet::empty(),
+            ChangeKindSet::empty(),
+            options,
+            Box::new(tx),
+        )
+        .await
+        .unwrap();
+        (temp, path, rx)
+    }
+
+    #[test]
+    fn coalesce_should_merge_changes_with_the_same_paths() {
+        let changes = coalesce(vec![
+            change(ChangeKind::Create, ["a"]),
+            change(ChangeKind::Create, ["b"]),
+            change(ChangeKind::Content, ["a"]),
+            change(ChangeKind::Remove, ["a"]),
+        ]);
+
+        assert_eq!(
+            changes,
+            vec![
+                Change {
+                    kinds: ChangeKind::Create | ChangeKind::Content | ChangeKind::Remove,
+                    ..change(ChangeKind::Remove, ["a"])
+                },
+                Change {
+                    kinds: ChangeKindSet::from(ChangeKind::Create),
+                    ..change(ChangeKind::Create, ["b"])
+                },
+            ]
+        );
+    }
+
+    #[test(tokio::test)]
+    async fn filter_and_send_should_send_immediately_if_not_debouncing() {
+        let (_temp, mut path, mut rx) = register(WatchOptions::default()).await;
+        let file = path.path().join("file");
+
+        assert!(path
+            .filter_and_send(&change(ChangeKind::Create, [&file]))
+            .await
+            .unwrap());
+        assert_eq!(path.deadline(), None);
+        assert_eq!(
+            rx.try_recv().unwrap(),
+            DistantResponseData::Changed(change(ChangeKind::Create, [file]))
+        );
+    }
+
+    #[test(tokio::test)]
+    async fn flush_should_send_changes_collected_while_debouncing_as_one_batch() {
+        let (_temp, mut path, mut rx) = register(WatchOptions {
+            debounce_ms: 1000,
+            coalesce: false,
+            ..Default::default()
+        })
+        .await;
+        let file = path.path().join("file");
+        let other = path.path().join("other");
+
+        assert!(!path
+            .filter_and_send(&change(ChangeKind::Create, [&file]))
+            .await
+            .unwrap());
+        assert!(!path
+            .filter_and_send(&change(ChangeKind::Create, [&other]))
+            .await
+            .unwrap());
+        assert!(path.deadline().is_some());
+        assert!(rx.try_recv().is_err(), "Change sent before flushed");
+
+        assert!(path.flush().await.unwrap());
+        assert_eq!(path.deadline(), None);
+        assert_eq!(
+            rx.try_recv().unwrap(),
+            DistantResponseData::ChangedBatch {
+                changes: vec![
+                    change(ChangeKind::Create, [file]),
+                    change(ChangeKind::Create, [other]),
+                ]
+            }
+        );
+
+        // Nothing left to flush
+        assert!(!path.flush().await.unwrap());
+    }
+
+    #[test(tokio::test)]
+    async fn filter_and_send_should_push_back_deadline_until_maximum_wait() {
+        let (_temp, mut path, _rx) = register(WatchOptions {
+            debounce_ms: 100,
+            ..Default::default()
+        })
+        .await;
+        let file = path.path().join("file");
+
+        let start = Instant::now();
+        path.filter_and_send(&change(ChangeKind::Content, [&file]))
+            .await
+            .unwrap();
+        let first = path.deadline().unwrap();
+        assert!(first >= start + Duration::from_millis(100));
+
+        // Each new change waits for the debounce window to pass again
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        path.filter_and_send(&change(ChangeKind::Content, [&file]))
+            .await
+            .unwrap();
+        assert!(path.deadline().unwrap() > first);
+
+        // Changes that keep arriving are still reported within the maximum wait of the first
+        for _ in 0..25 {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            path.filter_and_send(&change(ChangeKind::Content, [&file]))
+                .await
+                .unwrap();
+        }
+        assert_eq!(
+            path.deadline().unwrap(),
+            first - Duration::from_millis(100) + Duration::from_secs(1)
+        );
+    }
+
+    #[test(tokio::test)]
+    async fn flush_should_send_a_single_change_if_all_changes_are_coalesced() {
+        let (_temp, mut path, mut rx) = register(WatchOptions {
+            debounce_ms: 1000,
+            coalesce: true,
+            ..Default::default()
+        })
+        .await;
+        let file = path.path().join("file");
+
+        path.filter_and_send(&change(ChangeKind::Create, [&file]))
+            .await
+            .unwrap();
+        path.filter_and_send(&change(ChangeKind::Content, [&file]))
+            .await
+            .unwrap();
+
+        assert!(path.flush().await.unwrap());
+        assert_eq!(
+            rx.try_recv().unwrap(),
+            DistantResponseData::Changed(Change {
+                kinds: ChangeKind::Create | ChangeKind::Content,
+                ..change(ChangeKind::Content, [file])
+            })
+        );
+    }
+
+    #[test(tokio::test)]
+    async fn register_should_fail_if_a_glob_is_invalid() {
+        let temp = assert_fs::TempDir::new().unwrap();
+        let (tx, _rx) = mpsc::channel(100);
+        let err = RegisteredPath::register(
+            0,
+            temp.path(),
+            /* recursive */ true,
+            ChangeKindSet::empty(),
+            ChangeKindSet::empty(),
+            WatchOptions {
+                exclude: vec![String::from("[")],
+                ..Default::default()
+            },
+            Box::new(tx),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test(tokio::test)]
+    async fn matches_globs_should_skip_paths_within_excluded_directories() {
+        let (_temp, path, _rx) = register(WatchOptions {
+            exclude: vec![String::from("target/"), String::from("**/node_modules")],
+            ..Default::default()
+        })
+        .await;
+        let root = path.path().to_path_buf();
+
+        assert!(path.matches_globs(&root.join("src").join("lib.rs")));
+        assert!(path.matches_globs(&root.join("targets")));
+        assert!(!path.matches_globs(&root.join("target")));
+        assert!(!path.matches_globs(&root.join("target").join("debug").join("app")));
+        assert!(!path.matches_globs(&root.join("node_modules").join("pkg")));
+        assert!(!path.matches_globs(&root.join("web").join("node_modules").join("pkg")));
+    }
+
+    #[test(tokio::test)]
+    async fn matches_globs_should_only_allow_included_paths_that_are_not_excluded() {
+        let (_temp, path, _rx) = register(WatchOptions {
+            include: vec![String::from("src"), String::from("*.toml")],
+            exclude: vec![String::from("src/generated")],
+            ..Default::default()
+        })
+        .await;
+        let root = path.path().to_path_buf();
+
+        assert!(path.matches_globs(&root.join("src").join("lib.rs")));
+        assert!(path.matches_globs(&root.join("Cargo.toml")));
+        assert!(path.matches_globs(&root.join("nested").join("Cargo.toml")));
+        assert!(!path.matches_globs(&root.join("README.md")));
+        assert!(!path.matches_globs(&root.join("src").join("generated").join("api.rs")));
+    }
+
+    #[test(tokio::test)]
+    async fn filter_and_send_should_not_send_changes_to_excluded_paths() {
+        let (_temp, mut path, mut rx) = register(WatchOptions {
+            exclude: vec![String::from(".git")],
+            ..Default::default()
+        })
+        .await;
+        let excluded = path.path().join(".git").join("index");
+        let file = path.path().join("file");
+
+        assert!(!path
+            .filter_and_send(&change(ChangeKind::Content, [&excluded]))
+            .await
+            .unwrap());
+        assert!(rx.try_recv().is_err(), "Change sent for excluded path");
+
+        assert!(path
+            .filter_and_send(&change(ChangeKind::RenameBoth, [&excluded, &file]))
+            .await
+            .unwrap());
+        assert_eq!(
+            rx.try_recv().unwrap(),
+            DistantResponseData::Changed(change(ChangeKind::RenameBoth, [file]))
+        );
+    }
+
+    #[test(tokio::test)]
+    async fn filter_and_send_should_not_reveal_excluded_destination_of_rename() {
+        let (_temp, mut path, mut rx) = register(WatchOptions {
+            exclude: vec![String::from("node_modules")],
+            metadata: true,
+            ..Default::default()
+        })
+        .await;
+        let file = path.path().join("file");
+        let excluded = path.path().join("node_modules").join("file");
+        std::fs::create_dir(path.path().join("node_modules")).unwrap();
+        std::fs::write(&excluded, "some text").unwrap();
+
+        assert!(path
+            .filter_and_send(&Change {
+                from: Some(file.clone()),
+                to: Some(excluded.clone()),
+                ..change(ChangeKind::RenameBoth, [&file, &excluded])
+            })
+            .await
+            .unwrap());
+
+        // Metadata would only be found by reading the excluded destination
+        assert_eq!(
+            rx.recv().await.unwrap(),
+            DistantResponseData::Changed(Change {
+                from: Some(file.clone()),
+                ..change(ChangeKind::RenameBoth, [&file])
+            })
+        );
+    }
+
+    #[test(tokio::test)]
+    async fn filter_and_send_should_include_metadata_of_changed_path_if_requested() {
+        let (_temp, mut path, mut rx) = register(WatchOptions {
+            metadata: true,
+            ..Default::default()
+        })
+        .await;
+        let file = path.path().join("file");
+        std::fs::write(&file, "some text").unwrap();
+
+        assert!(path
+            .filter_and_send(&Change {
+                seq: 3,
+                timestamp: 123,
+                ..change(ChangeKind::Create, [&file])
+            })
+            .await
+            .unwrap());
+
+        let changed = match rx.recv().await.unwrap() {
+            DistantResponseData::Changed(change) => change,
+            x => panic!("Unexpected response: {:?}", x),
+        };
+        assert_eq!(changed.seq, 3);
+        assert_eq!(changed.timestamp, 123);
+        assert_eq!(changed.metadata.expect("Missing metadata").len, 9);
+
+        // Removed paths have no metadata to report
+        std::fs::remove_file(&file).unwrap();
+        path.filter_and_send(&change(ChangeKind::Remove, [&file]))
+            .await
+            .unwrap();
+        match rx.recv().await.unwrap() {
+            DistantResponseData::Changed(change) => assert_eq!(change.metadata, None),
+            x => panic!("Unexpected response: {:?}", x),
+        }
+    }
+
+    #[test(tokio::test)]
+    async fn changes_since_should_return_changes_after_the_sequence_number() {
+        let (_temp, mut path, _rx) = register(WatchOptions::default()).await;
+        let file = path.path().join("file");
+
+        for seq in 1..=3 {
+            path.filter_and_send(&Change {
+                seq,
+                ..change(ChangeKind::Content, [&file])
+            })
+            .await
+            .unwrap();
+        }
+
+        let seqs = |changes: Vec<Change>| changes.into_iter().map(|c| c.seq).collect::<Vec<_>>();
+        assert_eq!(path.changes_since(0).map(seqs), Some(vec![1, 2, 3]));
+        assert_eq!(path.changes_since(2).map(seqs), Some(vec![3]));
+        assert_eq!(path.changes_since(3).map(seqs), Some(vec![]));
+    }
+
+    #[test(tokio::test)]
+    async fn send_overflow_should_report_overflow_and_forget_earlier_changes() {
+        let (_temp, mut path, mut rx) = register(WatchOptions::default()).await;
+        let file = path.path().join("file");
+
+        path.filter_and_send(&Change {
+            seq: 1,
+            ..change(ChangeKind::Content, [&file])
+        })
+        .await
+        .unwrap();
+        rx.try_recv().unwrap();
+
+        path.send_overflow(5).await.unwrap();
+        assert_eq!(
+            rx.try_recv().unwrap(),
+            DistantResponseData::WatchOverflow {
+                path: path.raw_path().to_path_buf(),
+                seq: 5,
+            }
+        );
+
+        // Changes up through the overflow can no longer be replayed
+        assert_eq!(path.changes_since(1), None);
+        assert_eq!(path.changes_since(5), Some(vec![]));
+    }
+
+    #[test(tokio::test)]
+    async fn changes_since_should_return_none_if_missed_changes_were_forgotten() {
+        let (_temp, mut path, mut rx) = register(WatchOptions::default()).await;
+        let file = path.path().join("file");
+
+        let total = SERVER_WATCHER_HISTORY_CAPACITY as u64 + 2;
+        for seq in 1..=total {
+            path.filter_and_send(&Change {
+                seq,
+                ..change(ChangeKind::Content, [&file])
+            })
+            .await
+            .unwrap();
+            rx.try_recv().unwrap();
+        }
+
+        // Changes 1 and 2 have been forgotten
+        assert_eq!(path.changes_since(0), None);
+        assert_eq!(path.changes_since(1), None);
+        assert_eq!(
+            path.changes_since(2).map(|changes| changes.len()),
+            Some(SERVER_WATCHER_HISTORY_CAPACITY)
+        );
+    }
+
+    #[test(tokio::test)]
+    async fn register_should_use_polling_backend_if_requested() {
+        let (_temp, path, _rx) = register(WatchOptions::default()).await;
+        assert_eq!(path.backend(), WatchBackend::Default);
+
+        let (_temp, path, _rx) = register(WatchOptions {
+            poll: true,
+            poll_interval_ms: Some(500),
+            compare_contents: true,
+            ..Default::default()
+        })
+        .await;
+        assert_eq!(
+            path.backend(),
+            WatchBackend::Poll {
+                interval: Some(Duration::from_millis(500)),
+                compare_contents: true,
+            }
+        );
+    }
 }
@@ -5,6 +5,7 @@ mod ext;
 mod lsp;
 mod process;
 mod searcher;
+mod tailer;
 mod watcher;
 
 /// Represents a [`Client`] that communicates using the distant protocol
@@ -17,4 +18,5 @@ pub use ext::*;
 pub use lsp::*;
 pub use process::*;
 pub use searcher::*;
+pub use tailer::*;
 pub use watcher::*;
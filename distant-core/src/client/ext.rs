@@ -1,11 +1,12 @@
 use crate::{
     client::{
         RemoteCommand, RemoteLspCommand, RemoteLspProcess, RemoteOutput, RemoteProcess, Searcher,
-        Watcher,
+        Tailer, Watcher,
     },
     data::{
         Capabilities, ChangeKindSet, DirEntry, DistantRequestData, DistantResponseData,
-        Environment, Error as Failure, Metadata, PtySize, SearchId, SearchQuery, SystemInfo,
+        Environment, Error as Failure, FindFilesMatch, FindFilesQuery, Metadata, PtySize, SearchId,
+        SearchQuery, SystemInfo, TailId, TailOptions, WatchOptions,
     },
     DistantMsg,
 };
@@ -46,6 +47,13 @@ pub trait DistantChannelExt {
 
     fn exists(&mut self, path: impl Into<PathBuf>) -> AsyncReturn<'_, bool>;
 
+    /// Finds paths that fuzzy match a query, returning the best-scoring paths once the search
+    /// has completed
+    fn find_files(
+        &mut self,
+        query: impl Into<FindFilesQuery>,
+    ) -> AsyncReturn<'_, Vec<FindFilesMatch>>;
+
     /// Retrieves metadata about a path on a remote machine
     fn metadata(
         &mut self,
@@ -60,6 +68,12 @@ pub trait DistantChannelExt {
     /// Cancel an active search query
     fn cancel_search(&mut self, id: SearchId) -> AsyncReturn<'_, ()>;
 
+    /// Builds an index of a remote directory that speeds up future content searches within it
+    fn search_index(&mut self, path: impl Into<PathBuf>) -> AsyncReturn<'_, ()>;
+
+    /// Removes the index of a remote directory built by [`DistantChannelExt::search_index`]
+    fn search_unindex(&mut self, path: impl Into<PathBuf>) -> AsyncReturn<'_, ()>;
+
     /// Reads entries from a directory, returning a tuple of directory entries and failures
     fn read_dir(
         &mut self,
@@ -90,6 +104,7 @@ pub trait DistantChannelExt {
         recursive: bool,
         only: impl Into<ChangeKindSet>,
         except: impl Into<ChangeKindSet>,
+        options: WatchOptions,
     ) -> AsyncReturn<'_, Watcher>;
 
     /// Unwatches a remote file or directory
@@ -125,6 +140,12 @@ pub trait DistantChannelExt {
     /// Retrieves information about the remote system
     fn system_info(&mut self) -> AsyncReturn<'_, SystemInfo>;
 
+    /// Reads data from the end of a remote file, optionally following it for appended data
+    fn tail(&mut self, path: impl Into<PathBuf>, options: TailOptions) -> AsyncReturn<'_, Tailer>;
+
+    /// Cancel an active tail of a remote file
+    fn cancel_tail(&mut self, id: TailId) -> AsyncReturn<'_, ()>;
+
     /// Writes a remote file with the data from a collection of bytes
     fn write_file(
         &mut self,
@@ -233,6 +254,36 @@ impl DistantChannelExt
         )
     }
 
+    fn find_files(
+        &mut self,
+        query: impl Into<FindFilesQuery>,
+    ) -> AsyncReturn<'_, Vec<FindFilesMatch>> {
+        let req = Request::new(DistantMsg::Single(DistantRequestData::FindFiles {
+            query: query.into(),
+        }));
+        Box::pin(async move {
+            let mut mailbox = self.mail(req).await?;
+
+            // Streamed results are ignored as the final response includes the ranked matches
+            while let Some(res) = mailbox.next().await {
+                for data in res.payload.into_vec() {
+                    match data {
+                        DistantResponseData::FindFilesDone { matches, .. } => return Ok(matches),
+                        DistantResponseData::Error(x) => return Err(io::Error::from(x)),
+                        DistantResponseData::SearchStarted { .. }
+                        | DistantResponseData::FindFilesResults { .. } => continue,
+                        _ => return Err(mismatched_response()),
+                    }
+                }
+            }
+
+            Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "Finding files concluded without results",
+            ))
+        })
+    }
+
     fn metadata(
         &mut self,
         path: impl Into<PathBuf>,
@@ -267,6 +318,22 @@ impl DistantChannelExt
         )
     }
 
+    fn search_index(&mut self, path: impl Into<PathBuf>) -> AsyncReturn<'_, ()> {
+        make_body!(
+            self,
+            DistantRequestData::SearchIndex { path: path.into() },
+            @ok
+        )
+    }
+
+    fn search_unindex(&mut self, path: impl Into<PathBuf>) -> AsyncReturn<'_, ()> {
+        make_body!(
+            self,
+            DistantRequestData::SearchUnindex { path: path.into() },
+            @ok
+        )
+    }
+
     fn read_dir(
         &mut self,
         path: impl Into<PathBuf>,
@@ -338,11 +405,14 @@ impl DistantChannelExt
         recursive: bool,
         only: impl Into<ChangeKindSet>,
         except: impl Into<ChangeKindSet>,
+        options: WatchOptions,
     ) -> AsyncReturn<'_, Watcher> {
         let path = path.into();
         let only = only.into();
         let except = except.into();
-        Box::pin(async move { Watcher::watch(self.clone(), path, recursive, only, except).await })
+        Box::pin(async move {
+            Watcher::watch(self.clone(), path, recursive, only, except, options).await
+        })
     }
 
     fn unwatch(&mut self, path: impl Into<PathBuf>) -> AsyncReturn<'_, ()> {
@@ -426,6 +496,19 @@ impl DistantChannelExt
         })
     }
 
+    fn tail(&mut self, path: impl Into<PathBuf>, options: TailOptions) -> AsyncReturn<'_, Tailer> {
+        let path = path.into();
+        Box::pin(async move { Tailer::tail(self.clone(), path, options).await })
+    }
+
+    fn cancel_tail(&mut self, id: TailId) -> AsyncReturn<'_, ()> {
+        make_body!(
+            self,
+            DistantRequestData::CancelTail { id },
+            @ok
+        )
+    }
+
     fn write_file(
         &mut self,
         path: impl Into<PathBuf>,
@@ -0,0 +1,359 @@
+use crate::{
+    client::{DistantChannel, DistantChannelExt},
+    constants::CLIENT_TAILER_CAPACITY,
+    data::{DistantRequestData, DistantResponseData, TailId, TailOptions},
+    DistantMsg,
+};
+use distant_net::common::Request;
+use log::*;
+use std::{
+    fmt, io,
+    path::{Path, PathBuf},
+};
+use tokio::{sync::mpsc, task::JoinHandle};
+
+/// Represents something that happened to a file being tailed
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TailEvent {
+    /// Data was read from the file
+    Data(Vec<u8>),
+
+    /// The file was truncated or replaced, such as during log rotation, and data that follows is
+    /// from the start of the file
+    Reset,
+}
+
+/// Represents a tailer of a remote file, receiving data as it is appended
+pub struct Tailer {
+    channel: DistantChannel,
+    id: TailId,
+    path: PathBuf,
+    task: JoinHandle<()>,
+    rx: mpsc::Receiver<TailEvent>,
+}
+
+impl fmt::Debug for Tailer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Tailer")
+            .field("id", &self.id)
+            .field("path", &self.path)
+            .finish()
+    }
+}
+
+impl Tailer {
+    /// Creates a tailer of the file at `path`
+    pub async fn tail(
+        mut channel: DistantChannel,
+        path: impl Into<PathBuf>,
+        options: TailOptions,
+    ) -> io::Result<Self> {
+        let path = path.into();
+        trace!("Tailing {path:?} using {options:?}");
+
+        // Submit our tail request and get back a mailbox for responses
+        let mut mailbox = channel
+            .mail(Request::new(DistantMsg::Single(
+                DistantRequestData::FileTail {
+                    path: path.clone(),
+                    options,
+                },
+            )))
+            .await?;
+
+        let (tx, rx) = mpsc::channel(CLIENT_TAILER_CAPACITY);
+
+        // Wait to get the confirmation of the tail as either started or error
+        let mut queue: Vec<TailEvent> = Vec::new();
+        let mut done = false;
+        let mut tail_id = None;
+        while let Some(res) = mailbox.next().await {
+            for data in res.payload.into_vec() {
+                match data {
+                    // If we get events before the started indicator, queue them up
+                    DistantResponseData::TailData { data, .. } => queue.push(TailEvent::Data(data)),
+                    DistantResponseData::TailReset { .. } => queue.push(TailEvent::Reset),
+                    DistantResponseData::TailDone { .. } => done = true,
+
+                    // Once we get the started indicator, mark as ready to go
+                    DistantResponseData::TailStarted { id } => {
+                        trace!("[Tail {id}] Tailer has started");
+                        tail_id = Some(id);
+                    }
+
+                    // If we get an explicit error, convert and return it
+                    DistantResponseData::Error(x) => return Err(io::Error::from(x)),
+
+                    // Otherwise, we got something unexpected, and report as such
+                    x => {
+                        return Err(io::Error::new(
+                            io::ErrorKind::Other,
+                            format!("Unexpected response: {:?}", x),
+                        ))
+                    }
+                }
+            }
+
+            // Exit if we got the confirmation
+            // NOTE: Doing this later because we want to make sure the entire payload is processed
+            //       first before exiting the loop
+            if tail_id.is_some() {
+                break;
+            }
+        }
+
+        let tail_id = match tail_id {
+            // Send out any of our queued events that we got prior to the acknowledgement
+            Some(id) => {
+                trace!("[Tail {id}] Forwarding {} queued events", queue.len());
+                for event in queue.drain(..) {
+                    if tx.send(event).await.is_err() {
+                        return Err(io::Error::new(
+                            io::ErrorKind::Other,
+                            format!("[Tail {id}] Queue tail event dropped"),
+                        ));
+                    }
+                }
+                id
+            }
+
+            // If we never received an acknowledgement of the tail before the mailbox closed,
+            // fail with a missing confirmation error
+            None => {
+                return Err(io::Error::new(
+                    io::ErrorKind::Other,
+                    "Tail missing started confirmation",
+                ))
+            }
+        };
+
+        // Spawn a task that continues to look for tail events and the conclusion of the tail,
+        // discarding anything else that it gets
+        let task = tokio::spawn({
+            async move {
+                while !done {
+                    let res = match mailbox.next().await {
+                        Some(res) => res,
+                        None => break,
+                    };
+
+                    for data in res.payload.into_vec() {
+                        let event = match data {
+                            DistantResponseData::TailData { data, .. } => TailEvent::Data(data),
+                            DistantResponseData::TailReset { .. } => TailEvent::Reset,
+
+                            // Received completion indicator, so close out
+                            DistantResponseData::TailDone { .. } => {
+                                trace!("[Tail {tail_id}] Tailer has finished");
+                                done = true;
+                                break;
+                            }
+
+                            _ => continue,
+                        };
+
+                        // If we can't queue up an event anymore, we've been closed and
+                        // therefore want to quit
+                        if let Err(x) = tx.send(event).await {
+                            error!("[Tail {tail_id}] Tailer failed to send event {:?}", x.0);
+                            done = true;
+                            break;
+                        }
+                    }
+                }
+            }
+        });
+
+        Ok(Self {
+            id: tail_id,
+            path,
+            channel,
+            task,
+            rx,
+        })
+    }
+
+    /// Returns the id of the tail on the server
+    pub fn id(&self) -> TailId {
+        self.id
+    }
+
+    /// Returns a reference to the path of the file being tailed
+    pub fn path(&self) -> &Path {
+        self.path.as_path()
+    }
+
+    /// Returns true if the tailer is still actively receiving data
+    pub fn is_active(&self) -> bool {
+        !self.task.is_finished()
+    }
+
+    /// Returns the next event for the file, or none if the tail has concluded
+    pub async fn next(&mut self) -> Option<TailEvent> {
+        self.rx.recv().await
+    }
+
+    /// Cancels the tail, meaning no additional data will be received
+    pub async fn cancel(&mut self) -> io::Result<()> {
+        trace!("[Tail {}] Cancelling tail", self.id);
+        self.channel.cancel_tail(self.id).await?;
+
+        // Kill our task that processes inbound events if we have successfully stopped tailing
+        self.task.abort();
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DistantClient;
+    use distant_net::{
+        common::{FramedTransport, InmemoryTransport, Response},
+        Client,
+    };
+    use std::sync::Arc;
+    use test_log::test;
+    use tokio::sync::Mutex;
+
+    fn make_session() -> (FramedTransport<InmemoryTransport>, DistantClient) {
+        let (t1, t2) = FramedTransport::pair(100);
+        (t1, Client::spawn_inmemory(t2, Default::default()))
+    }
+
+    #[test(tokio::test)]
+    async fn tailer_should_fail_if_server_responds_with_error() {
+        let (mut transport, session) = make_session();
+
+        let tail_task = tokio::spawn(async move {
+            Tailer::tail(session.clone_channel(), "/some/path", Default::default()).await
+        });
+
+        let req: Request<DistantRequestData> = transport.read_frame_as().await.unwrap().unwrap();
+        transport
+            .write_frame_for(&Response::new(
+                req.id,
+                DistantResponseData::from(io::Error::new(io::ErrorKind::NotFound, "missing")),
+            ))
+            .await
+            .unwrap();
+
+        let err = tail_task.await.unwrap().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+    }
+
+    #[test(tokio::test)]
+    async fn tailer_should_support_getting_next_event_until_done() {
+        let (mut transport, session) = make_session();
+
+        let tail_task = tokio::spawn(async move {
+            Tailer::tail(session.clone_channel(), "/some/path", Default::default()).await
+        });
+
+        let req: Request<DistantRequestData> = transport.read_frame_as().await.unwrap().unwrap();
+        assert_eq!(
+            req.payload,
+            DistantRequestData::FileTail {
+                path: PathBuf::from("/some/path"),
+                options: Default::default(),
+            }
+        );
+
+        // Send back data that arrives before the acknowledgement alongside it
+        let id = rand::random::<TailId>();
+        transport
+            .write_frame_for(&Response::new(
+                req.id.clone(),
+                vec![
+                    DistantResponseData::TailData {
+                        id,
+                        data: b"hello".to_vec(),
+                    },
+                    DistantResponseData::TailStarted { id },
+                ],
+            ))
+            .await
+            .unwrap();
+
+        let mut tailer = tail_task.await.unwrap().unwrap();
+        assert_eq!(tailer.id(), id);
+        assert_eq!(tailer.path(), Path::new("/some/path"));
+
+        transport
+            .write_frame_for(&Response::new(
+                req.id,
+                vec![
+                    DistantResponseData::TailReset { id },
+                    DistantResponseData::TailData {
+                        id,
+                        data: b"world".to_vec(),
+                    },
+                    DistantResponseData::TailDone { id },
+                ],
+            ))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            tailer.next().await,
+            Some(TailEvent::Data(b"hello".to_vec()))
+        );
+        assert_eq!(tailer.next().await, Some(TailEvent::Reset));
+        assert_eq!(
+            tailer.next().await,
+            Some(TailEvent::Data(b"world".to_vec()))
+        );
+        assert_eq!(tailer.next().await, None);
+    }
+
+    #[test(tokio::test)]
+    async fn tailer_should_stop_receiving_events_if_cancelled() {
+        let (mut transport, session) = make_session();
+
+        let tail_task = tokio::spawn(async move {
+            Tailer::tail(session.clone_channel(), "/some/path", Default::default()).await
+        });
+
+        let req: Request<DistantRequestData> = transport.read_frame_as().await.unwrap().unwrap();
+        let id = rand::random::<TailId>();
+        transport
+            .write_frame_for(&Response::new(
+                req.id.clone(),
+                DistantResponseData::TailStarted { id },
+            ))
+            .await
+            .unwrap();
+
+        let tailer = Arc::new(Mutex::new(tail_task.await.unwrap().unwrap()));
+
+        // Cancel the tail, verify the request is sent out, and respond with ok
+        let tailer_2 = Arc::clone(&tailer);
+        let cancel_task = tokio::spawn(async move { tailer_2.lock().await.cancel().await });
+
+        let cancel_req: Request<DistantRequestData> =
+            transport.read_frame_as().await.unwrap().unwrap();
+        assert_eq!(cancel_req.payload, DistantRequestData::CancelTail { id });
+
+        transport
+            .write_frame_for(&Response::new(cancel_req.id, DistantResponseData::Ok))
+            .await
+            .unwrap();
+
+        cancel_task.await.unwrap().unwrap();
+
+        // Send data that will get ignored
+        transport
+            .write_frame_for(&Response::new(
+                req.id,
+                DistantResponseData::TailData {
+                    id,
+                    data: b"ignored".to_vec(),
+                },
+            ))
+            .await
+            .unwrap();
+
+        assert_eq!(tailer.lock().await.next().await, None);
+    }
+}
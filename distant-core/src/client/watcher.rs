@@ -1,10 +1,10 @@
 use crate::{
     client::{DistantChannel, DistantChannelExt},
     constants::CLIENT_WATCHER_CAPACITY,
-    data::{Change, ChangeKindSet, DistantRequestData, DistantResponseData},
+    data::{Change, ChangeKindSet, DistantRequestData, DistantResponseData, WatchOptions},
     DistantMsg,
 };
-use distant_net::common::Request;
+use distant_net::{client::ConnectionWatcher, common::Request};
 use log::*;
 use std::{
     fmt, io,
@@ -12,12 +12,27 @@ use std::{
 };
 use tokio::{sync::mpsc, task::JoinHandle};
 
+/// Represents an event reported by a [`Watcher`]
+#[allow(clippy::large_enum_variant)]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum WatchEvent {
+    /// A change to the watched path
+    Changed(Change),
+
+    /// Changes were missed while resuming the watch that can no longer be replayed, or were
+    /// dropped by the server as they arrived faster than it could handle, meaning that the
+    /// watched path needs to be resynced, where `seq` is the sequence number of the latest change
+    /// observed by the server
+    Overflow { seq: u64 },
+}
+
 /// Represents a watcher of some path on a remote machine
 pub struct Watcher {
     channel: DistantChannel,
     path: PathBuf,
     task: JoinHandle<()>,
-    rx: mpsc::Receiver<Change>,
+    resume_task: Option<JoinHandle<()>>,
+    rx: mpsc::Receiver<WatchEvent>,
     active: bool,
 }
 
@@ -35,6 +50,7 @@ impl Watcher {
         recursive: bool,
         only: impl Into<ChangeKindSet>,
         except: impl Into<ChangeKindSet>,
+        options: WatchOptions,
     ) -> io::Result<Self> {
         let path = path.into();
         let only = only.into();
@@ -63,6 +79,7 @@ impl Watcher {
                     recursive,
                     only: only.into_vec(),
                     except: except.into_vec(),
+                    options,
                 },
             )))
             .await?;
@@ -76,6 +93,7 @@ impl Watcher {
             for data in res.payload.into_vec() {
                 match data {
                     DistantResponseData::Changed(change) => queue.push(change),
+                    DistantResponseData::ChangedBatch { changes } => queue.extend(changes),
                     DistantResponseData::Ok => {
                         confirmed = true;
                     }
@@ -99,8 +117,10 @@ impl Watcher {
 
         // Send out any of our queued changes that we got prior to the acknowledgement
         trace!("Forwarding {} queued changes for {:?}", queue.len(), path);
+        let mut last_seq = 0;
         for change in queue {
-            if tx.send(change).await.is_err() {
+            last_seq = last_seq.max(change.seq);
+            if tx.send(WatchEvent::Changed(change)).await.is_err() {
                 return Err(io::Error::new(io::ErrorKind::Other, "Queue change dropped"));
             }
         }
@@ -112,30 +132,52 @@ impl Watcher {
         }
 
         // Spawn a task that continues to look for change events, discarding anything
-        // else that it gets
+        // else that it gets, and resumes the watch whenever we reconnect
+        let (reconnect_tx, mut reconnect_rx) = mpsc::channel(1);
+        let resume_task = channel
+            .connection_watcher()
+            .map(|connection| spawn_resume_task(connection, reconnect_tx));
         let task = tokio::spawn({
             let path = path.clone();
+            let mut channel = channel.clone();
             async move {
-                while let Some(res) = mailbox.next().await {
-                    for data in res.payload.into_vec() {
-                        match data {
-                            DistantResponseData::Changed(change) => {
-                                // If we can't queue up a change anymore, we've
-                                // been closed and therefore want to quit
-                                if tx.is_closed() {
-                                    break;
+                loop {
+                    let events = tokio::select! {
+                        res = mailbox.next() => match res {
+                            Some(res) => res.payload.into_vec().into_iter().flat_map(into_events).collect(),
+                            None => break,
+                        },
+                        Some(()) = reconnect_rx.recv() => {
+                            trace!("Resuming watch of {:?} since {}", path, last_seq);
+                            match resume(&mut channel, &path, last_seq).await {
+                                Ok(events) => events,
+                                Err(x) => {
+                                    error!("Watcher for {:?} failed to resume: {}", path, x);
+                                    continue;
                                 }
-
-                                // Otherwise, send over the change
-                                if let Err(x) = tx.send(change).await {
-                                    error!(
-                                        "Watcher for {:?} failed to send change {:?}",
-                                        path, x.0
-                                    );
-                                    break;
+                            }
+                        }
+                    };
+
+                    for event in events {
+                        // Skip changes that we have already forwarded, which can be reported
+                        // again when resuming
+                        match &event {
+                            WatchEvent::Changed(change) if change.seq != 0 => {
+                                if change.seq <= last_seq {
+                                    continue;
                                 }
+                                last_seq = change.seq;
                             }
-                            _ => continue,
+                            WatchEvent::Changed(_) => (),
+                            WatchEvent::Overflow { seq } => last_seq = *seq,
+                        }
+
+                        // If we can't queue up a change anymore, we've
+                        // been closed and therefore want to quit
+                        if let Err(x) = tx.send(event).await {
+                            error!("Watcher for {:?} failed to send event {:?}", path, x.0);
+                            return;
                         }
                     }
                 }
@@ -146,6 +188,7 @@ impl Watcher {
             path,
             channel,
             task,
+            resume_task,
             rx,
             active: true,
         })
@@ -162,7 +205,20 @@ impl Watcher {
     }
 
     /// Returns the next change detected by the watcher, or none if the watcher has concluded
+    ///
+    /// Skips over [`WatchEvent::Overflow`], so use [`Self::next_event`] to learn when the watched
+    /// path needs to be resynced.
     pub async fn next(&mut self) -> Option<Change> {
+        loop {
+            match self.rx.recv().await? {
+                WatchEvent::Changed(change) => return Some(change),
+                WatchEvent::Overflow { .. } => continue,
+            }
+        }
+    }
+
+    /// Returns the next event reported by the watcher, or none if the watcher has concluded
+    pub async fn next_event(&mut self) -> Option<WatchEvent> {
         self.rx.recv().await
     }
 
@@ -173,22 +229,90 @@ impl Watcher {
 
         // Kill our task that processes inbound changes if we have successfully unwatched the path
         self.task.abort();
+        if let Some(task) = self.resume_task.take() {
+            task.abort();
+        }
         self.active = false;
 
         Ok(())
     }
 }
 
+/// Spawns a task that signals `reconnect_tx` whenever `connection` reconnects, so the watch is
+/// resumed by replaying any changes that were missed while disconnected or reporting
+/// [`WatchEvent::Overflow`] if they cannot be replayed
+fn spawn_resume_task(
+    mut connection: ConnectionWatcher,
+    reconnect_tx: mpsc::Sender<()>,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        // Ignore any reconnect that finished before we started watching
+        if connection.has_changed() && connection.next().await.is_none() {
+            return;
+        }
+
+        // The connection only becomes connected again by reconnecting, and as we are only told
+        // the latest state, we may never see it reconnecting if it reconnects quickly
+        while let Some(state) = connection.next().await {
+            if state.is_connected() && reconnect_tx.send(()).await.is_err() {
+                break;
+            }
+        }
+    })
+}
+
+/// Converts a response into the events it reports, discarding anything else
+fn into_events(data: DistantResponseData) -> Vec<WatchEvent> {
+    match data {
+        DistantResponseData::Changed(change) => vec![WatchEvent::Changed(change)],
+        DistantResponseData::ChangedBatch { changes } => {
+            changes.into_iter().map(WatchEvent::Changed).collect()
+        }
+        DistantResponseData::WatchOverflow { seq, .. } => vec![WatchEvent::Overflow { seq }],
+        _ => Vec::new(),
+    }
+}
+
+/// Requests to resume watching `path`, returning the events that were missed since `since_seq`
+async fn resume(
+    channel: &mut DistantChannel,
+    path: &Path,
+    since_seq: u64,
+) -> io::Result<Vec<WatchEvent>> {
+    let mut mailbox = channel
+        .mail(Request::new(DistantMsg::Single(
+            DistantRequestData::WatchResume {
+                path: path.to_path_buf(),
+                since_seq,
+            },
+        )))
+        .await?;
+
+    let mut events = Vec::new();
+    while let Some(res) = mailbox.next().await {
+        for data in res.payload.into_vec() {
+            match data {
+                DistantResponseData::Ok => return Ok(events),
+                DistantResponseData::Error(x) => return Err(io::Error::from(x)),
+                x => events.extend(into_events(x)),
+            }
+        }
+    }
+
+    Err(io::Error::new(io::ErrorKind::Other, "Missing confirmation"))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::data::ChangeKind;
     use crate::DistantClient;
     use distant_net::{
+        client::{ClientConfig, ReconnectStrategy},
         common::{FramedTransport, InmemoryTransport, Response},
         Client,
     };
-    use std::sync::Arc;
+    use std::{sync::Arc, time::Duration};
     use test_log::test;
     use tokio::sync::Mutex;
 
@@ -211,6 +335,7 @@ mod tests {
                 true,
                 ChangeKindSet::empty(),
                 ChangeKindSet::empty(),
+                Default::default(),
             )
             .await
         });
@@ -243,6 +368,7 @@ mod tests {
                 true,
                 ChangeKindSet::empty(),
                 ChangeKindSet::empty(),
+                Default::default(),
             )
             .await
         });
@@ -265,12 +391,24 @@ mod tests {
                 req.id,
                 vec![
                     DistantResponseData::Changed(Change {
+                        seq: 0,
+                        timestamp: 0,
                         kind: ChangeKind::Access,
                         paths: vec![test_path.to_path_buf()],
+                        from: None,
+                        to: None,
+                        metadata: None,
+                        kinds: ChangeKindSet::empty(),
                     }),
                     DistantResponseData::Changed(Change {
+                        seq: 0,
+                        timestamp: 0,
                         kind: ChangeKind::Content,
                         paths: vec![test_path.to_path_buf()],
+                        from: None,
+                        to: None,
+                        metadata: None,
+                        kinds: ChangeKindSet::empty(),
                     }),
                 ],
             ))
@@ -282,8 +420,14 @@ mod tests {
         assert_eq!(
             change,
             Change {
+                seq: 0,
+                timestamp: 0,
                 kind: ChangeKind::Access,
-                paths: vec![test_path.to_path_buf()]
+                paths: vec![test_path.to_path_buf()],
+                from: None,
+                to: None,
+                metadata: None,
+                kinds: ChangeKindSet::empty(),
             }
         );
 
@@ -291,8 +435,14 @@ mod tests {
         assert_eq!(
             change,
             Change {
+                seq: 0,
+                timestamp: 0,
                 kind: ChangeKind::Content,
-                paths: vec![test_path.to_path_buf()]
+                paths: vec![test_path.to_path_buf()],
+                from: None,
+                to: None,
+                metadata: None,
+                kinds: ChangeKindSet::empty(),
             }
         );
     }
@@ -311,6 +461,7 @@ mod tests {
                 true,
                 ChangeKindSet::empty(),
                 ChangeKindSet::empty(),
+                Default::default(),
             )
             .await
         });
@@ -332,8 +483,14 @@ mod tests {
             .write_frame_for(&Response::new(
                 req.id.clone(),
                 DistantResponseData::Changed(Change {
+                    seq: 0,
+                    timestamp: 0,
                     kind: ChangeKind::Access,
                     paths: vec![test_path.to_path_buf()],
+                    from: None,
+                    to: None,
+                    metadata: None,
+                    kinds: ChangeKindSet::empty(),
                 }),
             ))
             .await
@@ -344,8 +501,14 @@ mod tests {
             .write_frame_for(&Response::new(
                 req.id.clone() + "1",
                 DistantResponseData::Changed(Change {
+                    seq: 0,
+                    timestamp: 0,
                     kind: ChangeKind::Content,
                     paths: vec![test_path.to_path_buf()],
+                    from: None,
+                    to: None,
+                    metadata: None,
+                    kinds: ChangeKindSet::empty(),
                 }),
             ))
             .await
@@ -356,8 +519,14 @@ mod tests {
             .write_frame_for(&Response::new(
                 req.id,
                 DistantResponseData::Changed(Change {
+                    seq: 0,
+                    timestamp: 0,
                     kind: ChangeKind::Remove,
                     paths: vec![test_path.to_path_buf()],
+                    from: None,
+                    to: None,
+                    metadata: None,
+                    kinds: ChangeKindSet::empty(),
                 }),
             ))
             .await
@@ -368,8 +537,14 @@ mod tests {
         assert_eq!(
             change,
             Change {
+                seq: 0,
+                timestamp: 0,
                 kind: ChangeKind::Access,
-                paths: vec![test_path.to_path_buf()]
+                paths: vec![test_path.to_path_buf()],
+                from: None,
+                to: None,
+                metadata: None,
+                kinds: ChangeKindSet::empty(),
             }
         );
 
@@ -377,8 +552,14 @@ mod tests {
         assert_eq!(
             change,
             Change {
+                seq: 0,
+                timestamp: 0,
                 kind: ChangeKind::Remove,
-                paths: vec![test_path.to_path_buf()]
+                paths: vec![test_path.to_path_buf()],
+                from: None,
+                to: None,
+                metadata: None,
+                kinds: ChangeKindSet::empty(),
             }
         );
     }
@@ -397,6 +578,7 @@ mod tests {
                 true,
                 ChangeKindSet::empty(),
                 ChangeKindSet::empty(),
+                Default::default(),
             )
             .await
         });
@@ -416,16 +598,34 @@ mod tests {
                 req.id,
                 vec![
                     DistantResponseData::Changed(Change {
+                        seq: 0,
+                        timestamp: 0,
                         kind: ChangeKind::Access,
                         paths: vec![test_path.to_path_buf()],
+                        from: None,
+                        to: None,
+                        metadata: None,
+                        kinds: ChangeKindSet::empty(),
                     }),
                     DistantResponseData::Changed(Change {
+                        seq: 0,
+                        timestamp: 0,
                         kind: ChangeKind::Content,
                         paths: vec![test_path.to_path_buf()],
+                        from: None,
+                        to: None,
+                        metadata: None,
+                        kinds: ChangeKindSet::empty(),
                     }),
                     DistantResponseData::Changed(Change {
+                        seq: 0,
+                        timestamp: 0,
                         kind: ChangeKind::Remove,
                         paths: vec![test_path.to_path_buf()],
+                        from: None,
+                        to: None,
+                        metadata: None,
+                        kinds: ChangeKindSet::empty(),
                     }),
                 ],
             ))
@@ -449,8 +649,14 @@ mod tests {
         assert_eq!(
             change,
             Change {
+                seq: 0,
+                timestamp: 0,
                 kind: ChangeKind::Access,
-                paths: vec![test_path.to_path_buf()]
+                paths: vec![test_path.to_path_buf()],
+                from: None,
+                to: None,
+                metadata: None,
+                kinds: ChangeKindSet::empty(),
             }
         );
 
@@ -472,8 +678,14 @@ mod tests {
             .write_frame_for(&Response::new(
                 req.id,
                 DistantResponseData::Changed(Change {
+                    seq: 0,
+                    timestamp: 0,
                     kind: ChangeKind::Unknown,
                     paths: vec![test_path.to_path_buf()],
+                    from: None,
+                    to: None,
+                    metadata: None,
+                    kinds: ChangeKindSet::empty(),
                 }),
             ))
             .await
@@ -484,17 +696,202 @@ mod tests {
         assert_eq!(
             watcher.lock().await.next().await,
             Some(Change {
+                seq: 0,
+                timestamp: 0,
                 kind: ChangeKind::Content,
-                paths: vec![test_path.to_path_buf()]
+                paths: vec![test_path.to_path_buf()],
+                from: None,
+                to: None,
+                metadata: None,
+                kinds: ChangeKindSet::empty(),
             })
         );
         assert_eq!(
             watcher.lock().await.next().await,
             Some(Change {
+                seq: 0,
+                timestamp: 0,
                 kind: ChangeKind::Remove,
-                paths: vec![test_path.to_path_buf()]
+                paths: vec![test_path.to_path_buf()],
+                from: None,
+                to: None,
+                metadata: None,
+                kinds: ChangeKindSet::empty(),
             })
         );
         assert_eq!(watcher.lock().await.next().await, None);
     }
+
+    fn change_with_seq(path: &Path, seq: u64) -> Change {
+        Change {
+            seq,
+            timestamp: 0,
+            kind: ChangeKind::Content,
+            paths: vec![path.to_path_buf()],
+            from: None,
+            to: None,
+            metadata: None,
+            kinds: ChangeKindSet::empty(),
+        }
+    }
+
+    /// Creates a session that reconnects after hearing nothing from the server for a while, which
+    /// the server side completes using [`accept_reconnect`]
+    fn make_reconnecting_session() -> (FramedTransport<InmemoryTransport>, DistantClient) {
+        let (t1, t2) = FramedTransport::pair(100);
+        let config = ClientConfig {
+            silence_duration: Duration::from_millis(250),
+            reconnect_strategy: ReconnectStrategy::FixedInterval {
+                interval: Duration::from_millis(50),
+                max_retries: Some(3),
+                timeout: None,
+            },
+            ..Default::default()
+        };
+        (t1, Client::spawn_inmemory(t2, config))
+    }
+
+    /// Waits for `connection` to start reconnecting and then performs the server side of the
+    /// reconnect over `transport`
+    async fn accept_reconnect(
+        transport: &mut FramedTransport<InmemoryTransport>,
+        connection: &mut ConnectionWatcher,
+    ) {
+        while !connection.last().is_reconnecting() {
+            connection.next().await.unwrap();
+        }
+
+        // Frames exchanged while reconnecting are not part of the synchronized state
+        transport.backup.freeze();
+        transport.server_handshake().await.unwrap();
+        transport.read_frame().await.unwrap().unwrap();
+        transport.exchange_keys().await.unwrap();
+        transport.backup.unfreeze();
+
+        transport.synchronize().await.unwrap();
+    }
+
+    async fn watch_and_confirm(
+        transport: &mut FramedTransport<InmemoryTransport>,
+        session: DistantClient,
+        test_path: &'static Path,
+    ) -> (Watcher, Request<DistantRequestData>) {
+        let watch_task = tokio::spawn(async move {
+            Watcher::watch(
+                session.clone_channel(),
+                test_path,
+                true,
+                ChangeKindSet::empty(),
+                ChangeKindSet::empty(),
+                Default::default(),
+            )
+            .await
+        });
+
+        let req: Request<DistantRequestData> = transport.read_frame_as().await.unwrap().unwrap();
+        transport
+            .write_frame_for(&Response::new(req.id.clone(), DistantResponseData::Ok))
+            .await
+            .unwrap();
+
+        (watch_task.await.unwrap().unwrap(), req)
+    }
+
+    #[test(tokio::test)]
+    async fn watcher_should_replay_missed_changes_when_resuming_after_reconnect() {
+        let (mut transport, session) = make_reconnecting_session();
+        let mut connection = session.clone_connection_watcher();
+        let test_path = Path::new("/some/test/path");
+        let (mut watcher, req) = watch_and_confirm(&mut transport, session, test_path).await;
+
+        // Watches resume on their own as the channel reports the state of its connection
+        assert!(watcher.resume_task.is_some());
+
+        transport
+            .write_frame_for(&Response::new(
+                req.id,
+                DistantResponseData::Changed(change_with_seq(test_path, 1)),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(watcher.next().await, Some(change_with_seq(test_path, 1)));
+
+        // Go quiet so the client reconnects, after which the watch should resume
+        accept_reconnect(&mut transport, &mut connection).await;
+
+        let req: Request<DistantRequestData> = transport.read_frame_as().await.unwrap().unwrap();
+        assert_eq!(
+            req.payload,
+            DistantRequestData::WatchResume {
+                path: test_path.to_path_buf(),
+                since_seq: 1,
+            }
+        );
+
+        // Replay includes a change we already received, which should be skipped
+        transport
+            .write_frame_for(&Response::new(
+                req.id.clone(),
+                DistantResponseData::ChangedBatch {
+                    changes: vec![
+                        change_with_seq(test_path, 1),
+                        change_with_seq(test_path, 2),
+                        change_with_seq(test_path, 3),
+                    ],
+                },
+            ))
+            .await
+            .unwrap();
+        transport
+            .write_frame_for(&Response::new(req.id, DistantResponseData::Ok))
+            .await
+            .unwrap();
+
+        assert_eq!(watcher.next().await, Some(change_with_seq(test_path, 2)));
+        assert_eq!(watcher.next().await, Some(change_with_seq(test_path, 3)));
+    }
+
+    #[test(tokio::test)]
+    async fn watcher_should_report_overflow_if_missed_changes_cannot_be_replayed() {
+        let (mut transport, session) = make_reconnecting_session();
+        let mut connection = session.clone_connection_watcher();
+        let test_path = Path::new("/some/test/path");
+        let (mut watcher, watch_req) = watch_and_confirm(&mut transport, session, test_path).await;
+
+        accept_reconnect(&mut transport, &mut connection).await;
+
+        let req: Request<DistantRequestData> = transport.read_frame_as().await.unwrap().unwrap();
+        transport
+            .write_frame_for(&Response::new(
+                req.id.clone(),
+                DistantResponseData::WatchOverflow {
+                    path: test_path.to_path_buf(),
+                    seq: 5,
+                },
+            ))
+            .await
+            .unwrap();
+        transport
+            .write_frame_for(&Response::new(req.id, DistantResponseData::Ok))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            watcher.next_event().await,
+            Some(WatchEvent::Overflow { seq: 5 })
+        );
+
+        // Changes from before the overflow are skipped as we continue from the latest change
+        transport
+            .write_frame_for(&Response::new(
+                watch_req.id,
+                vec![
+                    DistantResponseData::Changed(change_with_seq(test_path, 4)),
+                    DistantResponseData::Changed(change_with_seq(test_path, 6)),
+                ],
+            ))
+            .await
+            .unwrap();
+        assert_eq!(watcher.next().await, Some(change_with_seq(test_path, 6)));
+    }
 }
@@ -9,9 +9,20 @@ pub const CLIENT_WATCHER_CAPACITY: usize = 100;
 /// Capacity associated with a client searcher receiving matches
 pub const CLIENT_SEARCHER_CAPACITY: usize = 10000;
 
-/// Capacity associated with the server's file watcher to pass events outbound
+/// Capacity associated with a client tailer receiving data from a file
+pub const CLIENT_TAILER_CAPACITY: usize = 100;
+
+/// Capacity associated with the server's file watcher to pass events outbound, beyond which
+/// watchers are told that they need to resync
 pub const SERVER_WATCHER_CAPACITY: usize = 10000;
 
+/// Capacity associated with each path watched by the server to remember changes that can be
+/// replayed to clients resuming a watch
+pub const SERVER_WATCHER_HISTORY_CAPACITY: usize = 1000;
+
+/// Duration to wait between checks of a file being tailed for appended data
+pub const SERVER_TAIL_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
 /// Represents the maximum size (in bytes) that data will be read from pipes
 /// per individual `read` call
 ///
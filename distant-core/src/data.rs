@@ -36,6 +36,9 @@ pub use search::*;
 mod system;
 pub use system::*;
 
+mod tail;
+pub use tail::*;
+
 mod utils;
 pub(crate) use utils::*;
 
@@ -230,6 +233,27 @@ pub enum DistantRequestData {
         text: String,
     },
 
+    /// Reads data from the end of a file on the remote machine, optionally following the file
+    /// to stream data as it is appended
+    #[cfg_attr(feature = "clap", clap(visible_aliases = &["tail"]))]
+    #[strum_discriminants(strum(message = "Supports following a file for appended data"))]
+    FileTail {
+        /// The path to the file on the remote machine
+        path: PathBuf,
+
+        /// Options that customize where the tail starts and whether it follows the file
+        #[serde(default)]
+        #[cfg_attr(feature = "clap", clap(flatten))]
+        options: TailOptions,
+    },
+
+    /// Cancels an active tail of a file
+    #[strum_discriminants(strum(message = "Supports canceling an active tail of a file"))]
+    CancelTail {
+        /// Id of the tail to cancel
+        id: TailId,
+    },
+
     /// Reads a directory from the specified path on the remote machine
     #[cfg_attr(feature = "clap", clap(visible_aliases = &["ls"]))]
     #[strum_discriminants(strum(message = "Supports reading directory"))]
@@ -345,6 +369,28 @@ pub enum DistantRequestData {
             clap(long, value_parser = clap::builder::PossibleValuesParser::new(ChangeKind::VARIANTS))
         )]
         except: Vec<ChangeKind>,
+
+        /// Options that customize how changes are reported
+        #[serde(default)]
+        #[cfg_attr(feature = "clap", clap(flatten))]
+        options: WatchOptions,
+    },
+
+    /// Resumes a watch of a path, replaying changes that were reported after a sequence number,
+    /// such as changes missed while reconnecting
+    #[strum_discriminants(strum(
+        message = "Supports resuming a watch by replaying missed changes"
+    ))]
+    WatchResume {
+        /// The path to the file, directory, or symlink on the remote machine that is being
+        /// watched
+        path: PathBuf,
+
+        /// Sequence number of the last change received, where all changes after this one will
+        /// be replayed
+        #[serde(default)]
+        #[cfg_attr(feature = "clap", clap(long, default_value_t = 0))]
+        since_seq: u64,
     },
 
     /// Unwatches a path for changes, meaning no additional changes will be reported
@@ -399,6 +445,37 @@ pub enum DistantRequestData {
         id: SearchId,
     },
 
+    /// Builds an index of the contents of files beneath a directory that is kept up to date as
+    /// files change, used to narrow the files searched by future content searches of the directory
+    #[strum_discriminants(strum(
+        message = "Supports indexing a directory to speed up content searches"
+    ))]
+    SearchIndex {
+        /// The path to the directory on the remote machine to index
+        path: PathBuf,
+    },
+
+    /// Removes the index of a directory built by an earlier request, no longer keeping it up to
+    /// date as files change
+    #[strum_discriminants(strum(
+        message = "Supports removing the index of a directory used to speed up content searches"
+    ))]
+    SearchUnindex {
+        /// The path to the indexed directory on the remote machine
+        path: PathBuf,
+    },
+
+    /// Finds paths beneath a directory whose relative path fuzzy matches a pattern, ranking them
+    /// from best to worst match
+    ///
+    /// Can be cancelled like a search using the id returned when started
+    #[strum_discriminants(strum(message = "Supports finding paths using fuzzy matching"))]
+    FindFiles {
+        /// Query to perform against the filesystem
+        #[cfg_attr(feature = "clap", clap(flatten))]
+        query: FindFilesQuery,
+    },
+
     /// Spawns a new process on the remote machine
     #[cfg_attr(feature = "clap", clap(visible_aliases = &["spawn", "run"]))]
     #[strum_discriminants(strum(message = "Supports spawning a process"))]
@@ -505,6 +582,56 @@ pub enum DistantResponseData {
     /// Response to a filesystem change for some watched file, directory, or symlink
     Changed(Change),
 
+    /// Response to changes collected together for a watched path, such as when changes are
+    /// debounced, listed in the order that they occurred
+    ChangedBatch {
+        /// Changes that occurred
+        changes: Vec<Change>,
+    },
+
+    /// Response to resuming a watch when changes were missed that can no longer be replayed, or
+    /// to a watch when the server dropped changes it could not keep up with, meaning that the
+    /// watched path needs to be resynced
+    WatchOverflow {
+        /// The path being watched
+        path: PathBuf,
+
+        /// Sequence number of the latest change observed, from which the watch can continue
+        /// once resynced
+        seq: u64,
+    },
+
+    /// Represents a tail of a file being started
+    TailStarted {
+        /// Arbitrary id associated with the tail
+        id: TailId,
+    },
+
+    /// Represents data read from a file being tailed
+    TailData {
+        /// Arbitrary id associated with the tail
+        id: TailId,
+
+        /// Data read from the file
+        #[serde(with = "serde_bytes")]
+        #[cfg_attr(feature = "schemars", schemars(with = "Vec<u8>"))]
+        data: Vec<u8>,
+    },
+
+    /// Represents a file being tailed having been truncated or replaced, such as during log
+    /// rotation, meaning that data will now be sent from the start of the file
+    TailReset {
+        /// Arbitrary id associated with the tail
+        id: TailId,
+    },
+
+    /// Represents a tail of a file being completed, either because it was cancelled or all data
+    /// was sent when not following the file
+    TailDone {
+        /// Arbitrary id associated with the tail
+        id: TailId,
+    },
+
     /// Response to checking if a path exists
     Exists { value: bool },
 
@@ -530,6 +657,29 @@ pub enum DistantResponseData {
     SearchDone {
         /// Arbitrary id associated with search
         id: SearchId,
+
+        /// Total files skipped during the search because they were detected to contain binary
+        /// data, or none if the server is unable to tell
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        skipped_binary: Option<u64>,
+    },
+
+    /// Represents paths found while finding files, sent as they are discovered and not ranked
+    FindFilesResults {
+        /// Arbitrary id associated with the search
+        id: SearchId,
+
+        /// Collection of paths that matched since the last results were sent
+        matches: Vec<FindFilesMatch>,
+    },
+
+    /// Represents finding files being completed
+    FindFilesDone {
+        /// Arbitrary id associated with the search
+        id: SearchId,
+
+        /// Best-scoring paths found, ordered from best to worst
+        matches: Vec<FindFilesMatch>,
     },
 
     /// Response to starting a new process
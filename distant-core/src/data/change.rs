@@ -1,3 +1,4 @@
+use super::Metadata;
 use derive_more::{Deref, DerefMut, IntoIterator};
 use notify::{event::Event as NotifyEvent, EventKind as NotifyEventKind};
 use serde::{Deserialize, Serialize};
@@ -9,19 +10,47 @@ use std::{
     ops::{BitOr, Sub},
     path::PathBuf,
     str::FromStr,
+    time::{Duration, SystemTime},
 };
 use strum::{EnumString, EnumVariantNames};
 
 /// Change to one or more paths on the filesystem
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
-#[serde(rename_all = "snake_case", deny_unknown_fields)]
+#[serde(rename_all = "snake_case")]
 pub struct Change {
+    /// Sequence number of the change, which increases with every change observed by the server
+    /// and is shared by all watches that report the same change
+    #[serde(default)]
+    pub seq: u64,
+
+    /// Time (in milliseconds since the unix epoch) when the server observed the change
+    #[serde(default)]
+    pub timestamp: u64,
+
     /// Label describing the kind of change
     pub kind: ChangeKind,
 
     /// Paths that were changed
     pub paths: Vec<PathBuf>,
+
+    /// Path before it was renamed, only included for renames where it is known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub from: Option<PathBuf>,
+
+    /// Path after it was renamed, only included for renames where it is known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub to: Option<PathBuf>,
+
+    /// Snapshot of metadata about the changed path taken when the change was observed, only
+    /// included if requested by the watch and the path still exists
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<Metadata>,
+
+    /// All kinds of changes that were coalesced into this change, including `kind`, which is
+    /// only populated when a watch coalesces changes
+    #[serde(default, skip_serializing_if = "ChangeKindSet::is_empty")]
+    pub kinds: ChangeKindSet,
 }
 
 #[cfg(feature = "schemars")]
@@ -31,15 +60,116 @@ impl Change {
     }
 }
 
+impl Change {
+    /// Returns the current time in milliseconds since the unix epoch, used as the timestamp of
+    /// newly-observed changes
+    pub fn now() -> u64 {
+        SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or_default()
+    }
+}
+
 impl From<NotifyEvent> for Change {
     fn from(x: NotifyEvent) -> Self {
+        let kind = ChangeKind::from(x.kind);
+        let (from, to) = match (kind, x.paths.as_slice()) {
+            (ChangeKind::RenameBoth, [from, to]) => (Some(from.clone()), Some(to.clone())),
+            (ChangeKind::RenameFrom, [from]) => (Some(from.clone()), None),
+            (ChangeKind::RenameTo, [to]) => (None, Some(to.clone())),
+            _ => (None, None),
+        };
+
         Self {
-            kind: x.kind.into(),
+            seq: 0,
+            timestamp: Self::now(),
+            kind,
             paths: x.paths,
+            from,
+            to,
+            metadata: None,
+            kinds: ChangeKindSet::empty(),
         }
     }
 }
 
+/// Options to customize how changes to a watched path are reported
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "clap", derive(clap::Args))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(default)]
+pub struct WatchOptions {
+    /// Milliseconds to wait for changes to stop before reporting them, collecting all changes that
+    /// happen until then and reporting them together in a single response
+    ///
+    /// Changes that keep happening are never held for longer than ten times this window, see
+    /// [`WatchOptions::max_debounce_wait`]. By default, this is zero, meaning that changes are
+    /// reported as soon as they happen.
+    #[cfg_attr(feature = "clap", clap(long, default_value_t = 0))]
+    pub debounce_ms: u64,
+
+    /// Merge changes to the same paths that are collected within the debounce window into a
+    /// single change whose `kinds` includes every kind of change that occurred, reporting the
+    /// most recent kind as its `kind`
+    ///
+    /// Has no effect unless `debounce_ms` is greater than zero.
+    #[cfg_attr(feature = "clap", clap(long))]
+    pub coalesce: bool,
+
+    /// Globs of paths to report changes for, relative to the watched path, where a change is
+    /// reported if its path or any of its parent directories match one of the globs
+    ///
+    /// By default, this is empty, meaning that changes to all paths are reported.
+    #[cfg_attr(feature = "clap", clap(long))]
+    pub include: Vec<String>,
+
+    /// Globs of paths to not report changes for, relative to the watched path, where a change is
+    /// not reported if its path or any of its parent directories match one of the globs
+    ///
+    /// Use this to ignore directories such as `target`, `node_modules`, or `.git` when watching
+    /// recursively. Takes precedence over `include`.
+    #[cfg_attr(feature = "clap", clap(long))]
+    pub exclude: Vec<String>,
+
+    /// Include a snapshot of the metadata of the changed path with each change
+    #[cfg_attr(feature = "clap", clap(long))]
+    pub metadata: bool,
+
+    /// Detect changes by polling the path instead of using the server's watcher, which is needed
+    /// for filesystems that do not report changes such as NFS, SMB, and FUSE mounts
+    #[cfg_attr(feature = "clap", clap(long))]
+    pub poll: bool,
+
+    /// Milliseconds to wait between polls for changes, defaulting to the server's poll interval
+    ///
+    /// Has no effect unless `poll` is true.
+    #[cfg_attr(feature = "clap", clap(long))]
+    pub poll_interval_ms: Option<u64>,
+
+    /// Compare the contents of files when polling to detect changes that do not update the
+    /// modification time, which is more expensive
+    ///
+    /// Has no effect unless `poll` is true.
+    #[cfg_attr(feature = "clap", clap(long))]
+    pub compare_contents: bool,
+}
+
+impl WatchOptions {
+    /// Returns the longest that changes are held while debouncing when new changes keep arriving
+    /// before they are reported anyway, which is ten times `debounce_ms`
+    pub fn max_debounce_wait(&self) -> Duration {
+        Duration::from_millis(self.debounce_ms.saturating_mul(10))
+    }
+}
+
+#[cfg(feature = "schemars")]
+impl WatchOptions {
+    pub fn root_schema() -> schemars::schema::RootSchema {
+        schemars::schema_for!(WatchOptions)
+    }
+}
+
 #[derive(
     Copy,
     Clone,
@@ -56,7 +186,7 @@ impl From<NotifyEvent> for Change {
     Deserialize,
 )]
 #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
-#[serde(rename_all = "snake_case", deny_unknown_fields)]
+#[serde(rename_all = "snake_case")]
 #[strum(serialize_all = "snake_case")]
 #[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
 #[cfg_attr(feature = "clap", clap(rename_all = "snake_case"))]
@@ -375,6 +505,11 @@ impl ChangeKindSet {
         ChangeKind::Rename | ChangeKind::RenameBoth | ChangeKind::RenameFrom | ChangeKind::RenameTo
     }
 
+    /// Returns true if the set contains no kinds of changes
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
     /// Consumes set and returns a vec of the kinds of changes
     pub fn into_vec(self) -> Vec<ChangeKind> {
         self.0.into_iter().collect()
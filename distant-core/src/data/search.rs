@@ -222,6 +222,55 @@ pub struct SearchQueryOptions {
     #[cfg_attr(feature = "clap", clap(long))]
     #[serde(default)]
     pub pagination: Option<u64>,
+
+    /// Search hidden files and directories (those whose names start with a dot)
+    ///
+    /// By default, hidden files and directories are skipped.
+    #[cfg_attr(feature = "clap", clap(long))]
+    #[serde(default)]
+    pub hidden: bool,
+
+    /// Do not respect ignore files such as `.ignore` and `.gitignore`, including those found in
+    /// parent directories, global git ignore files, and `.git/info/exclude`
+    ///
+    /// By default, all of these ignore files are respected. Files provided via `ignore_files` are
+    /// always respected.
+    #[cfg_attr(feature = "clap", clap(long))]
+    #[serde(default)]
+    pub no_ignore: bool,
+
+    /// Do not respect version control ignore files such as `.gitignore`, global git ignore files,
+    /// and `.git/info/exclude`, while still respecting `.ignore` files
+    ///
+    /// By default, version control ignore files are respected.
+    #[cfg_attr(feature = "clap", clap(long))]
+    #[serde(default)]
+    pub no_ignore_vcs: bool,
+
+    /// Additional ignore files, using gitignore syntax, whose rules are applied to every path
+    /// being searched
+    #[cfg_attr(feature = "clap", clap(long))]
+    #[serde(default)]
+    pub ignore_files: Vec<PathBuf>,
+
+    /// Search files that contain binary data when they are found while traversing directories,
+    /// treating null bytes as line terminators
+    ///
+    /// By default, files discovered to contain binary data are skipped, and the total skipped is
+    /// reported when the search completes. Files explicitly provided as paths to the search are
+    /// always searched.
+    #[cfg_attr(feature = "clap", clap(long))]
+    #[serde(default)]
+    pub binary: bool,
+
+    /// Search within compressed files (`.gz`, `.bz2`, `.xz`, `.zst`) and the members of archives
+    /// (`.zip`, `.tar`, and compressed tarballs) when searching contents
+    ///
+    /// Matches found within an archive report the path of the archive alongside the path of the
+    /// member within it. By default, compressed files and archives are searched as-is.
+    #[cfg_attr(feature = "clap", clap(long))]
+    #[serde(default)]
+    pub search_archives: bool,
 }
 
 #[cfg(feature = "schemars")]
@@ -231,6 +280,58 @@ impl SearchQueryOptions {
     }
 }
 
+/// Represents a fuzzy query to find paths beneath a root directory, ranking them by how well they
+/// match a pattern
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "clap", derive(clap::Args))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct FindFilesQuery {
+    /// Pattern whose characters must all appear, in order, within a path relative to the root
+    ///
+    /// Matching is case-insensitive unless the pattern contains an uppercase character.
+    pub pattern: String,
+
+    /// Directory whose descendants are matched against the pattern
+    #[cfg_attr(feature = "clap", clap(default_value = "."))]
+    pub root: PathBuf,
+
+    /// Options to apply to the query
+    ///
+    /// Here, `limit` is the maximum number of best-scoring paths to return and `pagination` is the
+    /// amount of paths to batch while streaming results as they are found.
+    #[serde(default)]
+    #[cfg_attr(feature = "clap", clap(flatten))]
+    pub options: SearchQueryOptions,
+}
+
+#[cfg(feature = "schemars")]
+impl FindFilesQuery {
+    pub fn root_schema() -> schemars::schema::RootSchema {
+        schemars::schema_for!(FindFilesQuery)
+    }
+}
+
+/// Represents a path found by a fuzzy query
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct FindFilesMatch {
+    /// Path that matched the pattern
+    pub path: PathBuf,
+
+    /// Score of the match where higher is better
+    pub score: i64,
+
+    /// Byte offsets within `path` of each character that matched the pattern
+    pub indices: Vec<u64>,
+}
+
+#[cfg(feature = "schemars")]
+impl FindFilesMatch {
+    pub fn root_schema() -> schemars::schema::RootSchema {
+        schemars::schema_for!(FindFilesMatch)
+    }
+}
+
 /// Represents a match for a search query
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
@@ -292,6 +393,13 @@ pub struct SearchQueryContentsMatch {
     /// Path to file whose contents match
     pub path: PathBuf,
 
+    /// Path of the member within the archive at `path` whose contents match, or none if the
+    /// match was not found within an archive
+    ///
+    /// Matches within a single compressed file (e.g. `.gz`) have no member path.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub archive_member: Option<PathBuf>,
+
     /// Line(s) that matched
     pub lines: SearchQueryMatchData,
 
@@ -1,3 +1,4 @@
+use distant_net::common::{Version, PROTOCOL_VERSION};
 use serde::{Deserialize, Serialize};
 use std::{env, path::PathBuf};
 
@@ -29,6 +30,17 @@ pub struct SystemInfo {
 
     /// Default shell tied to user running the server process
     pub shell: String,
+
+    /// Version of the protocol spoken by the server, missing if the server is too old to report it
+    #[serde(default)]
+    pub protocol_version: Option<Version>,
+
+    /// Name of the backend used by the server to detect filesystem changes, such as `inotify`,
+    /// missing if the server does not report it
+    ///
+    /// Reported here rather than with the watch capability, as capabilities deny unknown fields
+    #[serde(default)]
+    pub watch_backend: Option<String>,
 }
 
 #[cfg(feature = "schemars")]
@@ -52,6 +64,8 @@ impl Default for SystemInfo {
             } else {
                 env::var("SHELL").unwrap_or_else(|_| String::from("/bin/sh"))
             },
+            protocol_version: Some(PROTOCOL_VERSION),
+            watch_backend: None,
         }
     }
 }
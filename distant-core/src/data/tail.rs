@@ -0,0 +1,58 @@
+use serde::{Deserialize, Serialize};
+
+/// Id associated with a tail of a file
+pub type TailId = u32;
+
+/// Options to customize where a tail starts and whether it follows a file for new data
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "clap", derive(clap::Args))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(default, deny_unknown_fields)]
+pub struct TailOptions {
+    /// Byte offset within the file to start reading from, taking precedence over `lines`
+    #[cfg_attr(feature = "clap", clap(long))]
+    pub offset: Option<u64>,
+
+    /// Start with the last N lines of the file
+    ///
+    /// If neither this nor `offset` is provided, only data appended after the tail starts is
+    /// sent.
+    #[cfg_attr(feature = "clap", clap(short = 'n', long))]
+    pub lines: Option<u64>,
+
+    /// Continue to send data as it is appended to the file until the tail is cancelled, following
+    /// the file by name when it is truncated or replaced such as during log rotation
+    ///
+    /// Otherwise, the tail finishes once the existing data of the file has been sent.
+    #[cfg_attr(feature = "clap", clap(short = 'f', long))]
+    pub follow: bool,
+
+    /// Milliseconds to wait between checks of the file for new data when following
+    ///
+    /// By default, the server's interval is used.
+    #[cfg_attr(feature = "clap", clap(long))]
+    pub poll_interval_ms: Option<u64>,
+}
+
+#[cfg(feature = "schemars")]
+impl TailOptions {
+    pub fn root_schema() -> schemars::schema::RootSchema {
+        schemars::schema_for!(TailOptions)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_deserialize_from_empty_object_using_defaults() {
+        let options: TailOptions = serde_json::from_str("{}").unwrap();
+        assert_eq!(options, TailOptions::default());
+    }
+
+    #[test]
+    fn should_fail_to_deserialize_unknown_fields() {
+        serde_json::from_str::<TailOptions>(r#"{"unknown": true}"#).unwrap_err();
+    }
+}
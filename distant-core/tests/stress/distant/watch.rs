@@ -29,6 +29,7 @@ async fn should_handle_large_volume_of_file_watching(#[future] ctx: DistantClien
                 false,
                 ChangeKindSet::modify_set(),
                 ChangeKindSet::empty(),
+                Default::default(),
             )
             .await
             .unwrap();
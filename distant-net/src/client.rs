@@ -1,6 +1,6 @@
 use crate::common::{
-    Connection, FramedTransport, HeapSecretKey, InmemoryTransport, Interest, Reconnectable,
-    Transport, UntypedRequest, UntypedResponse,
+    Connection, FramedTransport, HeapSecretKey, InmemoryTransport, Interest, KeyExchangeType,
+    Reconnectable, TlsClientConfig, Transport, UntypedRequest, UntypedResponse, Version,
 };
 use log::*;
 use serde::{de::DeserializeOwned, Serialize};
@@ -53,6 +53,12 @@ pub struct UntypedClient {
 
     /// Contains the task that is running to send requests and receive responses from a server.
     task: Option<JoinHandle<io::Result<()>>>,
+
+    /// Key exchange algorithm negotiated with the server when the client was spawned.
+    key_exchange_type: Option<KeyExchangeType>,
+
+    /// Protocol version reported by the server when the client was spawned.
+    peer_version: Option<Version>,
 }
 
 impl fmt::Debug for UntypedClient {
@@ -87,6 +93,8 @@ impl UntypedClient {
             shutdown: self.shutdown.clone(),
             shutdown_on_drop: self.shutdown_on_drop,
             task: self.task.take(),
+            key_exchange_type: self.key_exchange_type,
+            peer_version: self.peer_version.take(),
         }
     }
 
@@ -159,6 +167,18 @@ impl UntypedClient {
         self.task.is_none() || self.task.as_ref().unwrap().is_finished()
     }
 
+    /// Returns the key exchange algorithm negotiated with the server during the handshake that
+    /// established the connection, or `None` if no key exchange took place.
+    pub fn key_exchange_type(&self) -> Option<KeyExchangeType> {
+        self.key_exchange_type
+    }
+
+    /// Returns the protocol version reported by the server during the handshake that established
+    /// the connection, or `None` if the server did not report one.
+    pub fn peer_version(&self) -> Option<&Version> {
+        self.peer_version.as_ref()
+    }
+
     /// Spawns a client using the provided [`FramedTransport`] of [`InmemoryTransport`] and a
     /// specific [`ReconnectStrategy`].
     ///
@@ -191,12 +211,16 @@ impl UntypedClient {
 
         // Ensure that our transport starts off clean (nothing in buffers or backup)
         connection.clear();
+        let key_exchange_type = connection.key_exchange_type();
+        let peer_version = connection.peer_version().cloned();
 
         let ClientConfig {
             mut reconnect_strategy,
             shutdown_on_drop,
             silence_duration,
+            rekey,
         } = config;
+        connection.set_rekey_config(rekey);
 
         // Start a task that continually checks for responses and delivers them using the
         // post office
@@ -385,17 +409,21 @@ impl UntypedClient {
             }
         });
 
+        let watcher = ConnectionWatcher(watcher_rx);
         let channel = UntypedChannel {
             tx,
             post_office: weak_post_office,
+            watcher: Some(watcher.clone()),
         };
 
         Self {
             channel,
-            watcher: ConnectionWatcher(watcher_rx),
+            watcher,
             shutdown: Box::new(shutdown_tx),
             shutdown_on_drop,
             task: Some(task),
+            key_exchange_type,
+            peer_version,
         }
     }
 }
@@ -436,6 +464,12 @@ pub struct Client<T, U> {
 
     /// Contains the task that is running to send requests and receive responses from a server.
     task: Option<JoinHandle<io::Result<()>>>,
+
+    /// Key exchange algorithm negotiated with the server when the client was spawned.
+    key_exchange_type: Option<KeyExchangeType>,
+
+    /// Protocol version reported by the server when the client was spawned.
+    peer_version: Option<Version>,
 }
 
 impl<T, U> fmt::Debug for Client<T, U> {
@@ -474,6 +508,8 @@ where
             shutdown: self.shutdown.clone(),
             shutdown_on_drop: self.shutdown_on_drop,
             task: self.task.take(),
+            key_exchange_type: self.key_exchange_type,
+            peer_version: self.peer_version.take(),
         }
     }
 
@@ -504,6 +540,19 @@ impl Client<(), ()> {
         ClientBuilder::new().connector(connector.into())
     }
 
+    /// Creates a new [`ClientBuilder`] configured to use a [`TlsConnector`] that connects to
+    /// `addr` and verifies the server using `config`.
+    pub fn tls<T>(addr: T, config: TlsClientConfig) -> ClientBuilder<(), TlsConnector<T>> {
+        ClientBuilder::new().connector(TlsConnector::new(addr, config))
+    }
+
+    /// Creates a new [`ClientBuilder`] configured to use a [`WebSocketConnector`].
+    pub fn websocket(
+        connector: impl Into<WebSocketConnector>,
+    ) -> ClientBuilder<(), WebSocketConnector> {
+        ClientBuilder::new().connector(connector.into())
+    }
+
     /// Creates a new [`ClientBuilder`] configured to use a [`UnixSocketConnector`].
     #[cfg(unix)]
     pub fn unix_socket(
@@ -600,6 +649,18 @@ impl<T, U> Client<T, U> {
     pub fn is_finished(&self) -> bool {
         self.task.is_none() || self.task.as_ref().unwrap().is_finished()
     }
+
+    /// Returns the key exchange algorithm negotiated with the server during the handshake that
+    /// established the connection, or `None` if no key exchange took place.
+    pub fn key_exchange_type(&self) -> Option<KeyExchangeType> {
+        self.key_exchange_type
+    }
+
+    /// Returns the protocol version reported by the server during the handshake that established
+    /// the connection, or `None` if the server did not report one.
+    pub fn peer_version(&self) -> Option<&Version> {
+        self.peer_version.as_ref()
+    }
 }
 
 impl<T, U> Deref for Client<T, U> {
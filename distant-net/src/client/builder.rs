@@ -1,6 +1,12 @@
 mod tcp;
 pub use tcp::*;
 
+mod tls;
+pub use tls::*;
+
+mod websocket;
+pub use websocket::*;
+
 #[cfg(unix)]
 mod unix;
 
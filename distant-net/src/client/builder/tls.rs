@@ -0,0 +1,27 @@
+use super::Connector;
+use crate::common::{TcpTransport, TlsClientConfig, TlsTransport};
+use async_trait::async_trait;
+use std::io;
+use tokio::net::ToSocketAddrs;
+
+/// Implementation of [`Connector`] to support connecting via TCP wrapped in TLS.
+pub struct TlsConnector<T> {
+    addr: T,
+    config: TlsClientConfig,
+}
+
+impl<T> TlsConnector<T> {
+    pub fn new(addr: T, config: TlsClientConfig) -> Self {
+        Self { addr, config }
+    }
+}
+
+#[async_trait]
+impl<T: ToSocketAddrs + Send> Connector for TlsConnector<T> {
+    type Transport = TlsTransport<TcpTransport>;
+
+    async fn connect(self) -> io::Result<Self::Transport> {
+        let transport = TcpTransport::connect(self.addr).await?;
+        TlsTransport::connect(transport, &self.config).await
+    }
+}
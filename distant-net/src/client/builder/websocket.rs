@@ -0,0 +1,100 @@
+use super::Connector;
+use crate::common::{
+    TcpTransport, TlsClientConfig, TlsTransport, TlsVerification, Transport, WebSocketTransport,
+};
+use async_trait::async_trait;
+use std::{future::Future, io, pin::Pin};
+use tungstenite::client::IntoClientRequest;
+
+/// Implementation of [`Connector`] to support connecting via WebSocket to a `ws://` or `wss://`
+/// url, where `wss://` wraps the TCP connection in TLS.
+pub struct WebSocketConnector {
+    url: String,
+    tls: Option<TlsClientConfig>,
+}
+
+impl WebSocketConnector {
+    /// Creates a connector to `url`, which when using `wss://` verifies the server using the CA
+    /// certificates trusted by the operating system
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            tls: None,
+        }
+    }
+
+    /// Configures how to verify the server when using `wss://`
+    pub fn tls(self, config: TlsClientConfig) -> Self {
+        Self {
+            tls: Some(config),
+            ..self
+        }
+    }
+}
+
+impl From<String> for WebSocketConnector {
+    fn from(url: String) -> Self {
+        Self::new(url)
+    }
+}
+
+impl From<&str> for WebSocketConnector {
+    fn from(url: &str) -> Self {
+        Self::new(url)
+    }
+}
+
+#[async_trait]
+impl Connector for WebSocketConnector {
+    type Transport = WebSocketTransport<Box<dyn Transport>>;
+
+    async fn connect(self) -> io::Result<Self::Transport> {
+        let request = self
+            .url
+            .as_str()
+            .into_client_request()
+            .map_err(|x| io::Error::new(io::ErrorKind::InvalidInput, x))?;
+        let uri = request.uri();
+
+        let secure = match uri.scheme_str() {
+            Some("ws") => false,
+            Some("wss") => true,
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("Url must start with ws:// or wss://: {}", self.url),
+                ))
+            }
+        };
+
+        // IPv6 addresses keep their brackets within the url
+        let host = uri
+            .host()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "Url is missing host"))?
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .to_string();
+        let port = uri.port_u16().unwrap_or(if secure { 443 } else { 80 });
+
+        let transport = TcpTransport::connect((host.as_str(), port)).await?;
+        let transport: Box<dyn Transport> = if secure {
+            let config = self
+                .tls
+                .unwrap_or_else(|| TlsClientConfig::new(host, TlsVerification::Native));
+            Box::new(TlsTransport::connect(transport, &config).await?)
+        } else {
+            Box::new(transport)
+        };
+
+        connect_websocket(transport, self.url).await
+    }
+}
+
+type BoxedConnect =
+    Pin<Box<dyn Future<Output = io::Result<WebSocketTransport<Box<dyn Transport>>>> + Send>>;
+
+/// Performs the WebSocket handshake behind a boxed future, whose explicit `Send` bound avoids the
+/// compiler failing to prove that the future of the handshake over a boxed transport is `Send`
+fn connect_websocket(transport: Box<dyn Transport>, url: String) -> BoxedConnect {
+    Box::pin(WebSocketTransport::connect(transport, url))
+}
@@ -1,3 +1,4 @@
+use super::ConnectionWatcher;
 use crate::common::{Request, Response, UntypedRequest, UntypedResponse};
 use log::*;
 use serde::{de::DeserializeOwned, Serialize};
@@ -53,6 +54,12 @@ where
         self.inner.is_closed()
     }
 
+    /// Returns a watcher over the state of the connection used by this channel, or none if the
+    /// channel is not tied to a connection that reports its state
+    pub fn connection_watcher(&self) -> Option<ConnectionWatcher> {
+        self.inner.connection_watcher()
+    }
+
     /// Consumes this channel, returning an untyped variant
     pub fn into_untyped_channel(self) -> UntypedChannel {
         self.inner
@@ -182,6 +189,9 @@ pub struct UntypedChannel {
 
     /// Collection of mailboxes for receiving responses to requests
     pub(crate) post_office: Weak<PostOffice<UntypedResponse<'static>>>,
+
+    /// Used to watch for changes in the state of the connection, such as reconnecting
+    pub(crate) watcher: Option<ConnectionWatcher>,
 }
 
 // NOTE: Implemented manually to avoid needing clone to be defined on generic types
@@ -190,6 +200,7 @@ impl Clone for UntypedChannel {
         Self {
             tx: self.tx.clone(),
             post_office: Weak::clone(&self.post_office),
+            watcher: self.watcher.clone(),
         }
     }
 }
@@ -200,6 +211,12 @@ impl UntypedChannel {
         self.tx.is_closed()
     }
 
+    /// Returns a watcher over the state of the connection used by this channel, or none if the
+    /// channel is not tied to a connection that reports its state
+    pub fn connection_watcher(&self) -> Option<ConnectionWatcher> {
+        self.watcher.clone()
+    }
+
     /// Consumes this channel, returning a typed variant
     pub fn into_typed_channel<T, U>(self) -> Channel<T, U> {
         Channel {
@@ -353,7 +370,11 @@ mod tests {
             let (tx, rx) = mpsc::channel(buffer);
             let channel = {
                 let post_office = Arc::downgrade(&post_office);
-                UntypedChannel { tx, post_office }
+                UntypedChannel {
+                    tx,
+                    post_office,
+                    watcher: None,
+                }
             };
 
             (channel.into_typed_channel(), rx, post_office)
@@ -463,7 +484,11 @@ mod tests {
             let (tx, rx) = mpsc::channel(buffer);
             let channel = {
                 let post_office = Arc::downgrade(&post_office);
-                TestChannel { tx, post_office }
+                TestChannel {
+                    tx,
+                    post_office,
+                    watcher: None,
+                }
             };
 
             (channel, rx, post_office)
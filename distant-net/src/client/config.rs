@@ -1,4 +1,5 @@
 use super::ReconnectStrategy;
+use crate::common::RekeyConfig;
 use std::time::Duration;
 
 const DEFAULT_SILENCE_DURATION: Duration = Duration::from_secs(20);
@@ -17,14 +18,17 @@ pub struct ClientConfig {
     /// A maximum duration to not receive any response/heartbeat from a server before deeming the
     /// server as lost and triggering a reconnect.
     pub silence_duration: Duration,
+
+    /// Thresholds at which the client replaces its encryption key with a new one derived from a
+    /// fresh key exchange with the server.
+    pub rekey: RekeyConfig,
 }
 
 impl ClientConfig {
     pub fn with_maximum_silence_duration(self) -> Self {
         Self {
-            reconnect_strategy: self.reconnect_strategy,
-            shutdown_on_drop: self.shutdown_on_drop,
             silence_duration: MAXIMUM_SILENCE_DURATION,
+            ..self
         }
     }
 }
@@ -35,6 +39,7 @@ impl Default for ClientConfig {
             reconnect_strategy: ReconnectStrategy::Fail,
             shutdown_on_drop: false,
             silence_duration: DEFAULT_SILENCE_DURATION,
+            rekey: RekeyConfig::default(),
         }
     }
 }
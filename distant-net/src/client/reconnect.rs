@@ -1,7 +1,7 @@
 use super::Reconnectable;
 use log::*;
-use std::io;
 use std::time::Duration;
+use std::{fmt, io};
 use strum::Display;
 use tokio::sync::watch;
 use tokio::task::JoinHandle;
@@ -44,6 +44,14 @@ impl ConnectionWatcher {
     }
 }
 
+impl fmt::Debug for ConnectionWatcher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("ConnectionWatcher")
+            .field(&self.last())
+            .finish()
+    }
+}
+
 /// Represents the state of a connection.
 #[derive(Copy, Clone, Debug, Display, PartialEq, Eq)]
 #[strum(serialize_all = "snake_case")]
@@ -8,6 +8,7 @@ mod packet;
 mod port;
 mod transport;
 pub(crate) mod utils;
+mod version;
 
 pub use any::*;
 pub(crate) use connection::Connection;
@@ -18,3 +19,4 @@ pub use map::*;
 pub use packet::*;
 pub use port::*;
 pub use transport::*;
+pub use version::*;
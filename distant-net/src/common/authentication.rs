@@ -1,10 +1,16 @@
 mod authenticator;
+mod ed25519;
 mod handler;
 mod keychain;
 mod methods;
 pub mod msg;
+mod password;
+mod totp;
 
 pub use authenticator::*;
+pub use ed25519::*;
 pub use handler::*;
 pub use keychain::*;
 pub use methods::*;
+pub use password::*;
+pub use totp::*;
@@ -1,5 +1,5 @@
 use super::{msg::*, AuthHandler};
-use crate::common::{utils, FramedTransport, Transport};
+use crate::common::{utils, FramedTransport, PeerCredentials, Transport};
 use async_trait::async_trait;
 use log::*;
 use std::io;
@@ -40,6 +40,12 @@ pub trait Authenticator: Send {
     /// Reports that the authentication has finished successfully, consuming the authenticator
     /// since no more challenges should be issued.
     async fn finished(&mut self) -> io::Result<()>;
+
+    /// Returns the credentials of the process being authenticated as reported by the operating
+    /// system, if available. Defaults to `None`.
+    fn peer_credentials(&self) -> Option<PeerCredentials> {
+        None
+    }
 }
 
 macro_rules! write_frame {
@@ -196,6 +202,10 @@ where
         write_frame!(self, Authentication::Finished);
         Ok(())
     }
+
+    fn peer_credentials(&self) -> Option<PeerCredentials> {
+        self.as_inner().peer_credentials()
+    }
 }
 
 #[cfg(test)]
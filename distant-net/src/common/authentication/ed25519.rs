@@ -0,0 +1,215 @@
+use derive_more::{Display, Error};
+use ed25519_dalek::{Signature, Signer, SigningKey, VerifyingKey};
+use rand::rngs::OsRng;
+use std::{fmt, fs, io, path::Path, str::FromStr};
+
+/// Prepended to a nonce before it is signed so that a signature produced to authenticate with a
+/// distant server cannot be used for anything else
+const SIGNATURE_CONTEXT: &[u8] = b"distant-public-key-authentication:";
+
+#[derive(Debug, Display, Error)]
+pub struct Ed25519KeyError;
+
+impl From<Ed25519KeyError> for io::Error {
+    fn from(_: Ed25519KeyError) -> Self {
+        io::Error::new(io::ErrorKind::InvalidData, "not valid ed25519 key format")
+    }
+}
+
+/// Represents a private Ed25519 key used to prove ownership of its public key during
+/// authentication
+#[derive(Clone)]
+pub struct Ed25519SigningKey(SigningKey);
+
+impl fmt::Debug for Ed25519SigningKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Ed25519SigningKey")
+            .field(&"**OMITTED**".to_string())
+            .finish()
+    }
+}
+
+impl Ed25519SigningKey {
+    /// Generates a new random key
+    pub fn generate() -> Self {
+        Self(SigningKey::generate(&mut OsRng))
+    }
+
+    /// Loads a key from the file at `path`, which contains the key as a hex string
+    pub fn from_file(path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(fs::read_to_string(path)?.trim().parse()?)
+    }
+
+    /// Returns the public key associated with this key
+    pub fn public_key(&self) -> Ed25519PublicKey {
+        Ed25519PublicKey(self.0.verifying_key())
+    }
+
+    /// Signs the `nonce` provided by a server, returning the bytes of the signature
+    pub fn sign_nonce(&self, nonce: &[u8]) -> Vec<u8> {
+        self.0
+            .sign(&[SIGNATURE_CONTEXT, nonce].concat())
+            .to_bytes()
+            .to_vec()
+    }
+}
+
+impl FromStr for Ed25519SigningKey {
+    type Err = Ed25519KeyError;
+
+    /// Parse a str of hex as the 32 bytes of a private key
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bytes: [u8; 32] = hex::decode(s)
+            .map_err(|_| Ed25519KeyError)?
+            .try_into()
+            .map_err(|_| Ed25519KeyError)?;
+        Ok(Self(SigningKey::from_bytes(&bytes)))
+    }
+}
+
+impl fmt::Display for Ed25519SigningKey {
+    /// Display the private key as a hex string
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", hex::encode(self.0.to_bytes()))
+    }
+}
+
+/// Represents a public Ed25519 key used to verify signatures during authentication
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Ed25519PublicKey(VerifyingKey);
+
+impl Ed25519PublicKey {
+    /// Returns true if `signature` is a valid signature of `nonce` made by the private key
+    /// associated with this key
+    pub fn verify_nonce(&self, nonce: &[u8], signature: &[u8]) -> bool {
+        match Signature::from_slice(signature) {
+            Ok(signature) => self
+                .0
+                .verify_strict(&[SIGNATURE_CONTEXT, nonce].concat(), &signature)
+                .is_ok(),
+            Err(_) => false,
+        }
+    }
+}
+
+impl FromStr for Ed25519PublicKey {
+    type Err = Ed25519KeyError;
+
+    /// Parse a str of hex as the 32 bytes of a public key
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bytes: [u8; 32] = hex::decode(s)
+            .map_err(|_| Ed25519KeyError)?
+            .try_into()
+            .map_err(|_| Ed25519KeyError)?;
+        Ok(Self(
+            VerifyingKey::from_bytes(&bytes).map_err(|_| Ed25519KeyError)?,
+        ))
+    }
+}
+
+impl fmt::Display for Ed25519PublicKey {
+    /// Display the public key as a hex string
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", hex::encode(self.0.as_bytes()))
+    }
+}
+
+/// Represents the public keys allowed to authenticate with a server
+///
+/// When parsed, each line contains a public key as a hex string optionally followed by whitespace
+/// and a comment such as the name of the key's owner. Blank lines and lines starting with `#` are
+/// ignored.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct AuthorizedKeys(Vec<Ed25519PublicKey>);
+
+impl AuthorizedKeys {
+    /// Creates a new collection of authorized keys
+    pub fn new(keys: impl IntoIterator<Item = Ed25519PublicKey>) -> Self {
+        Self(keys.into_iter().collect())
+    }
+
+    /// Loads the authorized keys from the file at `path`
+    pub fn from_file(path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(fs::read_to_string(path)?.parse()?)
+    }
+
+    /// Returns true if `key` is authorized
+    pub fn contains(&self, key: &Ed25519PublicKey) -> bool {
+        self.0.contains(key)
+    }
+
+    /// Returns the total authorized keys
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns true if there are no authorized keys
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl From<Vec<Ed25519PublicKey>> for AuthorizedKeys {
+    fn from(keys: Vec<Ed25519PublicKey>) -> Self {
+        Self(keys)
+    }
+}
+
+impl FromStr for AuthorizedKeys {
+    type Err = Ed25519KeyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut keys = Vec::new();
+        for line in s.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let key = line.split_whitespace().next().unwrap_or_default();
+            keys.push(key.parse()?);
+        }
+
+        Ok(Self(keys))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signing_key_should_round_trip_through_string() {
+        let key = Ed25519SigningKey::generate();
+        let parsed: Ed25519SigningKey = key.to_string().parse().unwrap();
+        assert_eq!(parsed.public_key(), key.public_key());
+    }
+
+    #[test]
+    fn public_key_should_verify_signature_of_nonce_from_signing_key() {
+        let key = Ed25519SigningKey::generate();
+        let signature = key.sign_nonce(b"nonce");
+
+        assert!(key.public_key().verify_nonce(b"nonce", &signature));
+        assert!(!key.public_key().verify_nonce(b"other", &signature));
+        assert!(!Ed25519SigningKey::generate()
+            .public_key()
+            .verify_nonce(b"nonce", &signature));
+    }
+
+    #[test]
+    fn authorized_keys_should_parse_keys_ignoring_comments_and_blank_lines() {
+        let key1 = Ed25519SigningKey::generate().public_key();
+        let key2 = Ed25519SigningKey::generate().public_key();
+
+        let keys: AuthorizedKeys = format!("# team keys\n\n{key1} alice\n  {key2}\n")
+            .parse()
+            .unwrap();
+        assert_eq!(keys, AuthorizedKeys::new([key1, key2]));
+        assert!(!keys.contains(&Ed25519SigningKey::generate().public_key()));
+    }
+
+    #[test]
+    fn authorized_keys_should_fail_to_parse_invalid_key() {
+        "not-a-key comment".parse::<AuthorizedKeys>().unwrap_err();
+    }
+}
@@ -1,6 +1,6 @@
 use super::msg::*;
 use crate::common::authentication::Authenticator;
-use crate::common::HeapSecretKey;
+use crate::common::{authentication::Ed25519SigningKey, HeapSecretKey};
 use async_trait::async_trait;
 use std::collections::HashMap;
 use std::io;
@@ -178,6 +178,18 @@ impl AuthHandlerMap {
         self.insert_method_handler("static_key", StaticKeyAuthMethodHandler::simple(key));
         self
     }
+
+    /// Consumes the map, returning a new map that supports the `public_key` method.
+    pub fn with_public_key(mut self, key: Ed25519SigningKey) -> Self {
+        self.insert_method_handler("public_key", PublicKeyAuthMethodHandler::simple(key));
+        self
+    }
+
+    /// Consumes the map, returning a new map that supports the `peer_credentials` method.
+    pub fn with_peer_credentials(mut self) -> Self {
+        self.insert_method_handler("peer_credentials", PeerCredentialsAuthMethodHandler::new());
+        self
+    }
 }
 
 impl Default for AuthHandlerMap {
@@ -26,8 +26,14 @@ pub trait AuthMethodHandler: Send {
     async fn on_error(&mut self, error: Error) -> io::Result<()>;
 }
 
+mod peer_credentials;
+pub use peer_credentials::*;
+
 mod prompt;
 pub use prompt::*;
 
+mod public_key;
+pub use public_key::*;
+
 mod static_key;
 pub use static_key::*;
@@ -0,0 +1,68 @@
+use super::{
+    AuthMethodHandler, Challenge, ChallengeResponse, Error, Info, Verification,
+    VerificationResponse,
+};
+use async_trait::async_trait;
+use log::*;
+use std::io;
+
+/// Implementation of [`AuthMethodHandler`] for the `peer_credentials` method, where the server
+/// identifies the client through the operating system without asking it anything. Challenges and
+/// verifications are therefore refused, while info and errors are ignored.
+#[derive(Default)]
+pub struct PeerCredentialsAuthMethodHandler;
+
+impl PeerCredentialsAuthMethodHandler {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+#[async_trait]
+impl AuthMethodHandler for PeerCredentialsAuthMethodHandler {
+    async fn on_challenge(&mut self, challenge: Challenge) -> io::Result<ChallengeResponse> {
+        trace!("on_challenge({challenge:?})");
+        Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "Peer credentials do not involve challenges",
+        ))
+    }
+
+    async fn on_verification(
+        &mut self,
+        verification: Verification,
+    ) -> io::Result<VerificationResponse> {
+        trace!("on_verify({verification:?})");
+        Ok(VerificationResponse { valid: false })
+    }
+
+    async fn on_info(&mut self, info: Info) -> io::Result<()> {
+        trace!("on_info({info:?})");
+        Ok(())
+    }
+
+    async fn on_error(&mut self, error: Error) -> io::Result<()> {
+        trace!("on_error({error:?})");
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::authentication::msg::Question;
+    use test_log::test;
+
+    #[test(tokio::test)]
+    async fn on_challenge_should_fail_as_no_questions_are_expected() {
+        let mut handler = PeerCredentialsAuthMethodHandler::new();
+
+        handler
+            .on_challenge(Challenge {
+                questions: vec![Question::new("key")],
+                options: Default::default(),
+            })
+            .await
+            .unwrap_err();
+    }
+}
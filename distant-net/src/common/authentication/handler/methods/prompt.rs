@@ -44,8 +44,14 @@ where
             }
 
             // Get an answer from user input, or use a blank string as an answer
-            // if we fail to get input from the user
-            let answer = (self.password_prompt)(line).unwrap_or_default();
+            // if we fail to get input from the user, only showing the input if
+            // the question explicitly asks for it to be echoed
+            let answer = match question.options.get("echo").map(String::as_str) {
+                Some("true") => (self.text_prompt)(line)
+                    .map(|x| x.trim_end_matches(&['\r', '\n'][..]).to_string())
+                    .unwrap_or_default(),
+                _ => (self.password_prompt)(line).unwrap_or_default(),
+            };
 
             answers.push(answer);
         }
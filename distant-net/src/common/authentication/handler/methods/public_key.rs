@@ -0,0 +1,163 @@
+use super::{
+    AuthMethodHandler, Challenge, ChallengeResponse, Error, Info, Verification,
+    VerificationResponse,
+};
+use crate::common::authentication::Ed25519SigningKey;
+use async_trait::async_trait;
+use log::*;
+use std::io;
+
+/// Implementation of [`AuthMethodHandler`] that answers challenge requests by providing the public
+/// key of an [`Ed25519SigningKey`] and signing the nonce of the challenge. All other portions of
+/// method authentication are handled by another [`AuthMethodHandler`].
+pub struct PublicKeyAuthMethodHandler {
+    key: Ed25519SigningKey,
+    handler: Box<dyn AuthMethodHandler>,
+}
+
+impl PublicKeyAuthMethodHandler {
+    /// Creates a new [`PublicKeyAuthMethodHandler`] that responds to challenges using `key`. All
+    /// other requests are passed to the `handler`.
+    pub fn new<T: AuthMethodHandler + 'static>(key: Ed25519SigningKey, handler: T) -> Self {
+        Self {
+            key,
+            handler: Box::new(handler),
+        }
+    }
+
+    /// Creates a new [`PublicKeyAuthMethodHandler`] that responds to challenges using `key`. All
+    /// other requests are passed automatically, meaning that verification is always approved and
+    /// info/errors are ignored.
+    pub fn simple(key: Ed25519SigningKey) -> Self {
+        Self::new(key, {
+            struct __AuthMethodHandler;
+
+            #[async_trait]
+            impl AuthMethodHandler for __AuthMethodHandler {
+                async fn on_challenge(&mut self, _: Challenge) -> io::Result<ChallengeResponse> {
+                    unreachable!("on_challenge should be handled by PublicKeyAuthMethodHandler");
+                }
+
+                async fn on_verification(
+                    &mut self,
+                    _: Verification,
+                ) -> io::Result<VerificationResponse> {
+                    Ok(VerificationResponse { valid: true })
+                }
+
+                async fn on_info(&mut self, _: Info) -> io::Result<()> {
+                    Ok(())
+                }
+
+                async fn on_error(&mut self, _: Error) -> io::Result<()> {
+                    Ok(())
+                }
+            }
+
+            __AuthMethodHandler
+        })
+    }
+}
+
+#[async_trait]
+impl AuthMethodHandler for PublicKeyAuthMethodHandler {
+    async fn on_challenge(&mut self, challenge: Challenge) -> io::Result<ChallengeResponse> {
+        trace!("on_challenge({challenge:?})");
+        let mut answers = Vec::new();
+        for question in challenge.questions.iter() {
+            // Only challenges for the public key and a signature of the nonce are allowed
+            match question.label.as_str() {
+                "public_key" => answers.push(self.key.public_key().to_string()),
+                "signature" => {
+                    let nonce = challenge
+                        .options
+                        .get("nonce")
+                        .and_then(|nonce| hex::decode(nonce).ok())
+                        .ok_or_else(|| {
+                            io::Error::new(io::ErrorKind::InvalidInput, "Missing valid nonce")
+                        })?;
+                    answers.push(hex::encode(self.key.sign_nonce(&nonce)));
+                }
+                _ => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        "Only 'public_key' and 'signature' challenges are supported",
+                    ))
+                }
+            }
+        }
+        Ok(ChallengeResponse { answers })
+    }
+
+    async fn on_verification(
+        &mut self,
+        verification: Verification,
+    ) -> io::Result<VerificationResponse> {
+        trace!("on_verify({verification:?})");
+        self.handler.on_verification(verification).await
+    }
+
+    async fn on_info(&mut self, info: Info) -> io::Result<()> {
+        trace!("on_info({info:?})");
+        self.handler.on_info(info).await
+    }
+
+    async fn on_error(&mut self, error: Error) -> io::Result<()> {
+        trace!("on_error({error:?})");
+        self.handler.on_error(error).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::authentication::msg::Question;
+    use std::collections::HashMap;
+    use test_log::test;
+
+    #[test(tokio::test)]
+    async fn on_challenge_should_fail_if_unsupported_question_received() {
+        let mut handler = PublicKeyAuthMethodHandler::simple(Ed25519SigningKey::generate());
+
+        handler
+            .on_challenge(Challenge {
+                questions: vec![Question::new("key")],
+                options: Default::default(),
+            })
+            .await
+            .unwrap_err();
+    }
+
+    #[test(tokio::test)]
+    async fn on_challenge_should_fail_if_signature_requested_without_nonce() {
+        let mut handler = PublicKeyAuthMethodHandler::simple(Ed25519SigningKey::generate());
+
+        handler
+            .on_challenge(Challenge {
+                questions: vec![Question::new("signature")],
+                options: Default::default(),
+            })
+            .await
+            .unwrap_err();
+    }
+
+    #[test(tokio::test)]
+    async fn on_challenge_should_answer_with_public_key_and_signature_of_nonce() {
+        let key = Ed25519SigningKey::generate();
+        let mut handler = PublicKeyAuthMethodHandler::simple(key.clone());
+
+        let response = handler
+            .on_challenge(Challenge {
+                questions: vec![Question::new("public_key"), Question::new("signature")],
+                options: HashMap::from([("nonce".to_string(), hex::encode(b"nonce"))]),
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(response.answers.len(), 2, "Wrong answer set received");
+        assert_eq!(response.answers[0], key.public_key().to_string());
+        assert!(key
+            .public_key()
+            .verify_nonce(b"nonce", &hex::decode(&response.answers[1]).unwrap()));
+    }
+}
@@ -1,18 +1,38 @@
-use super::{super::HeapSecretKey, msg::*, Authenticator};
+use super::{super::HeapSecretKey, msg::*, Authenticator, AuthorizedKeys, PasswordCredentials};
 use async_trait::async_trait;
 use log::*;
 use std::collections::HashMap;
 use std::io;
 
 mod none;
+mod password;
+mod peer_credentials;
+mod public_key;
 mod static_key;
+mod totp;
 
 pub use none::*;
+pub use password::*;
+pub use peer_credentials::*;
+pub use public_key::*;
 pub use static_key::*;
+pub use totp::*;
+
+/// Determines which authentication methods must succeed for a [`Verifier`] to pass
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum VerificationPolicy {
+    /// Passes once any one of the methods succeeds, trying them in the order chosen by the client
+    #[default]
+    Any,
+
+    /// Passes only once every listed method succeeds, performing them in the order given
+    All(Vec<String>),
+}
 
 /// Supports authenticating using a variety of methods
 pub struct Verifier {
     methods: HashMap<&'static str, Box<dyn AuthenticationMethod>>,
+    policy: VerificationPolicy,
 }
 
 impl Verifier {
@@ -26,16 +46,31 @@ impl Verifier {
             m.insert(method.id(), method);
         }
 
-        Self { methods: m }
+        Self {
+            methods: m,
+            policy: VerificationPolicy::Any,
+        }
     }
 
     /// Creates a verifier with no methods.
     pub fn empty() -> Self {
         Self {
             methods: HashMap::new(),
+            policy: VerificationPolicy::Any,
         }
     }
 
+    /// Consumes the verifier, returning a new verifier that uses the given `policy` to determine
+    /// which methods must succeed.
+    pub fn with_policy(self, policy: VerificationPolicy) -> Self {
+        Self { policy, ..self }
+    }
+
+    /// Returns the policy used to determine which methods must succeed
+    pub fn policy(&self) -> &VerificationPolicy {
+        &self.policy
+    }
+
     /// Creates a verifier that uses the [`NoneAuthenticationMethod`] exclusively.
     pub fn none() -> Self {
         Self::new(vec![
@@ -50,14 +85,43 @@ impl Verifier {
         ])
     }
 
+    /// Creates a verifier that uses the [`PublicKeyAuthenticationMethod`] exclusively.
+    pub fn public_key(authorized_keys: impl Into<AuthorizedKeys>) -> Self {
+        Self::new(vec![
+            Box::new(PublicKeyAuthenticationMethod::new(authorized_keys))
+                as Box<dyn AuthenticationMethod>,
+        ])
+    }
+
+    /// Creates a verifier that uses the [`PasswordAuthenticationMethod`] exclusively.
+    pub fn password(credentials: impl Into<PasswordCredentials>) -> Self {
+        Self::new(vec![
+            Box::new(PasswordAuthenticationMethod::new(credentials))
+                as Box<dyn AuthenticationMethod>,
+        ])
+    }
+
+    /// Creates a verifier that uses the [`PeerCredentialsAuthenticationMethod`] exclusively.
+    pub fn peer_credentials(method: PeerCredentialsAuthenticationMethod) -> Self {
+        Self::new(vec![Box::new(method) as Box<dyn AuthenticationMethod>])
+    }
+
     /// Returns an iterator over the ids of the methods supported by the verifier
     pub fn methods(&self) -> impl Iterator<Item = &'static str> + '_ {
         self.methods.keys().copied()
     }
 
     /// Attempts to verify by submitting challenges using the `authenticator` provided. Returns the
-    /// id of the authentication method that succeeded. Fails if no authentication method succeeds.
-    pub async fn verify(&self, authenticator: &mut dyn Authenticator) -> io::Result<&'static str> {
+    /// ids of the authentication methods that succeeded alongside the user they identified, if
+    /// any. Fails if the methods that succeed do not satisfy the verifier's policy.
+    pub async fn verify(&self, authenticator: &mut dyn Authenticator) -> io::Result<Verified> {
+        match &self.policy {
+            VerificationPolicy::Any => self.verify_any(authenticator).await,
+            VerificationPolicy::All(ids) => self.verify_all(authenticator, ids).await,
+        }
+    }
+
+    async fn verify_any(&self, authenticator: &mut dyn Authenticator) -> io::Result<Verified> {
         // Initiate the process to get methods to use
         let response = authenticator
             .initialize(Initialization {
@@ -76,9 +140,12 @@ impl Verifier {
                         .await?;
 
                     // Perform the actual authentication
-                    if method.authenticate(authenticator).await.is_ok() {
+                    if let Ok(username) = method.authenticate(authenticator).await {
                         authenticator.finished().await?;
-                        return Ok(method.id());
+                        return Ok(Verified {
+                            methods: vec![method.id()],
+                            username,
+                        });
                     }
                 }
                 None => {
@@ -92,6 +159,82 @@ impl Verifier {
             "No authentication method succeeded",
         ))
     }
+
+    async fn verify_all(
+        &self,
+        authenticator: &mut dyn Authenticator,
+        ids: &[String],
+    ) -> io::Result<Verified> {
+        macro_rules! permission_denied {
+            ($($arg:tt)*) => {
+                io::Error::new(io::ErrorKind::PermissionDenied, format!($($arg)*))
+            };
+        }
+
+        // Ensure that every required method is available before involving the client
+        let methods = ids
+            .iter()
+            .map(|id| {
+                self.methods
+                    .get(id.as_str())
+                    .ok_or_else(|| permission_denied!("Required method {id} is not available"))
+            })
+            .collect::<io::Result<Vec<_>>>()?;
+
+        if methods.is_empty() {
+            return Err(permission_denied!("No authentication method is required"));
+        }
+
+        // Initiate the process, where the client must support every required method
+        let response = authenticator
+            .initialize(Initialization {
+                methods: ids.to_vec(),
+            })
+            .await?;
+
+        if let Some(id) = ids.iter().find(|id| !response.methods.contains(id)) {
+            return Err(permission_denied!(
+                "Client does not support required method {id}"
+            ));
+        }
+
+        let mut verified = Verified {
+            methods: Vec::new(),
+            username: None,
+        };
+
+        for method in methods {
+            // Report the authentication method
+            authenticator
+                .start_method(StartMethod {
+                    method: method.id().to_string(),
+                })
+                .await?;
+
+            // Perform the actual authentication, where every method must succeed
+            let username = method
+                .authenticate(authenticator)
+                .await
+                .map_err(|x| permission_denied!("Required method {} failed: {x}", method.id()))?;
+
+            // Methods that identify a user must all agree on who that user is
+            match (&verified.username, username) {
+                (Some(a), Some(b)) if *a != b => {
+                    return Err(permission_denied!(
+                        "Required method {} identified a different user",
+                        method.id()
+                    ));
+                }
+                (None, Some(b)) => verified.username = Some(b),
+                _ => {}
+            }
+
+            verified.methods.push(method.id());
+        }
+
+        authenticator.finished().await?;
+        Ok(verified)
+    }
 }
 
 impl From<Vec<Box<dyn AuthenticationMethod>>> for Verifier {
@@ -100,6 +243,16 @@ impl From<Vec<Box<dyn AuthenticationMethod>>> for Verifier {
     }
 }
 
+/// Represents the outcome of a successful verification
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Verified {
+    /// Ids of the authentication methods that succeeded, in the order they were performed
+    pub methods: Vec<&'static str>,
+
+    /// Name of the user that authenticated, if the method identifies one
+    pub username: Option<String>,
+}
+
 /// Represents an interface to authenticate using some method
 #[async_trait]
 pub trait AuthenticationMethod: Send + Sync {
@@ -107,8 +260,12 @@ pub trait AuthenticationMethod: Send + Sync {
     fn id(&self) -> &'static str;
 
     /// Performs authentication using the `authenticator` to submit challenges and other
-    /// information based on the authentication method
-    async fn authenticate(&self, authenticator: &mut dyn Authenticator) -> io::Result<()>;
+    /// information based on the authentication method, returning the name of the user that
+    /// authenticated if the method identifies one
+    async fn authenticate(
+        &self,
+        authenticator: &mut dyn Authenticator,
+    ) -> io::Result<Option<String>>;
 }
 
 #[cfg(test)]
@@ -125,8 +282,21 @@ mod tests {
             "success"
         }
 
-        async fn authenticate(&self, _: &mut dyn Authenticator) -> io::Result<()> {
-            Ok(())
+        async fn authenticate(&self, _: &mut dyn Authenticator) -> io::Result<Option<String>> {
+            Ok(None)
+        }
+    }
+
+    struct UserAuthenticationMethod;
+
+    #[async_trait]
+    impl AuthenticationMethod for UserAuthenticationMethod {
+        fn id(&self) -> &'static str {
+            "user"
+        }
+
+        async fn authenticate(&self, _: &mut dyn Authenticator) -> io::Result<Option<String>> {
+            Ok(Some("alice".to_string()))
         }
     }
 
@@ -138,7 +308,7 @@ mod tests {
             "fail"
         }
 
-        async fn authenticate(&self, _: &mut dyn Authenticator) -> io::Result<()> {
+        async fn authenticate(&self, _: &mut dyn Authenticator) -> io::Result<Option<String>> {
             Err(io::Error::from(io::ErrorKind::Other))
         }
     }
@@ -264,8 +434,8 @@ mod tests {
             vec![Box::new(SuccessAuthenticationMethod)];
         let verifier = Verifier::from(methods);
         assert_eq!(
-            verifier.verify(&mut t1).await.unwrap(),
-            SuccessAuthenticationMethod.id()
+            verifier.verify(&mut t1).await.unwrap().methods,
+            [SuccessAuthenticationMethod.id()]
         );
     }
 
@@ -293,8 +463,8 @@ mod tests {
         ];
         let verifier = Verifier::from(methods);
         assert_eq!(
-            verifier.verify(&mut t1).await.unwrap(),
-            SuccessAuthenticationMethod.id()
+            verifier.verify(&mut t1).await.unwrap().methods,
+            [SuccessAuthenticationMethod.id()]
         );
     }
 
@@ -373,4 +543,107 @@ mod tests {
             x => panic!("Unexpected response: {x:?}"),
         }
     }
+
+    #[test(tokio::test)]
+    async fn verifier_with_all_policy_should_fail_if_client_does_not_support_a_required_method() {
+        let (mut t1, mut t2) = FramedTransport::test_pair(100);
+
+        // Queue up a response to the initialization request missing a required method
+        t2.write_frame_for(&AuthenticationResponse::Initialization(
+            InitializationResponse {
+                methods: vec![SuccessAuthenticationMethod.id().to_string()],
+            },
+        ))
+        .await
+        .unwrap();
+
+        let methods: Vec<Box<dyn AuthenticationMethod>> = vec![
+            Box::new(SuccessAuthenticationMethod),
+            Box::new(UserAuthenticationMethod),
+        ];
+        let verifier = Verifier::from(methods).with_policy(VerificationPolicy::All(vec![
+            SuccessAuthenticationMethod.id().to_string(),
+            UserAuthenticationMethod.id().to_string(),
+        ]));
+        assert_eq!(
+            verifier.verify(&mut t1).await.unwrap_err().kind(),
+            io::ErrorKind::PermissionDenied
+        );
+    }
+
+    #[test(tokio::test)]
+    async fn verifier_with_all_policy_should_fail_if_any_required_method_fails() {
+        let (mut t1, mut t2) = FramedTransport::test_pair(100);
+
+        // Queue up a response to the initialization request
+        t2.write_frame_for(&AuthenticationResponse::Initialization(
+            InitializationResponse {
+                methods: vec![
+                    SuccessAuthenticationMethod.id().to_string(),
+                    FailAuthenticationMethod.id().to_string(),
+                ],
+            },
+        ))
+        .await
+        .unwrap();
+
+        let methods: Vec<Box<dyn AuthenticationMethod>> = vec![
+            Box::new(SuccessAuthenticationMethod),
+            Box::new(FailAuthenticationMethod),
+        ];
+        let verifier = Verifier::from(methods).with_policy(VerificationPolicy::All(vec![
+            SuccessAuthenticationMethod.id().to_string(),
+            FailAuthenticationMethod.id().to_string(),
+        ]));
+        assert_eq!(
+            verifier.verify(&mut t1).await.unwrap_err().kind(),
+            io::ErrorKind::PermissionDenied
+        );
+    }
+
+    #[test(tokio::test)]
+    async fn verifier_with_all_policy_should_perform_every_required_method_in_order() {
+        let (mut t1, mut t2) = FramedTransport::test_pair(100);
+
+        // Queue up a response to the initialization request
+        t2.write_frame_for(&AuthenticationResponse::Initialization(
+            InitializationResponse {
+                methods: vec![
+                    SuccessAuthenticationMethod.id().to_string(),
+                    UserAuthenticationMethod.id().to_string(),
+                ],
+            },
+        ))
+        .await
+        .unwrap();
+
+        let methods: Vec<Box<dyn AuthenticationMethod>> = vec![
+            Box::new(SuccessAuthenticationMethod),
+            Box::new(UserAuthenticationMethod),
+        ];
+        let verifier = Verifier::from(methods).with_policy(VerificationPolicy::All(vec![
+            UserAuthenticationMethod.id().to_string(),
+            SuccessAuthenticationMethod.id().to_string(),
+        ]));
+        let verified = verifier.verify(&mut t1).await.unwrap();
+        assert_eq!(
+            verified.methods,
+            [
+                UserAuthenticationMethod.id(),
+                SuccessAuthenticationMethod.id()
+            ]
+        );
+        assert_eq!(verified.username.as_deref(), Some("alice"));
+
+        // Verify that each method was started in the policy's order
+        let mut started = Vec::new();
+        while let Some(frame) = t2.read_frame_as::<Authentication>().await.unwrap() {
+            match frame {
+                Authentication::StartMethod(x) => started.push(x.method),
+                Authentication::Finished => break,
+                _ => {}
+            }
+        }
+        assert_eq!(started, ["user", "success"]);
+    }
 }
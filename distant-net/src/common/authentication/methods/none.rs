@@ -26,7 +26,7 @@ impl AuthenticationMethod for NoneAuthenticationMethod {
         "none"
     }
 
-    async fn authenticate(&self, _: &mut dyn Authenticator) -> io::Result<()> {
-        Ok(())
+    async fn authenticate(&self, _: &mut dyn Authenticator) -> io::Result<Option<String>> {
+        Ok(None)
     }
 }
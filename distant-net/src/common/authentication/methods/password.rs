@@ -0,0 +1,181 @@
+use super::{AuthenticationMethod, Authenticator, Challenge, Error, Question};
+use crate::common::authentication::PasswordCredentials;
+use async_trait::async_trait;
+use std::{collections::HashMap, io, path::PathBuf};
+
+/// Authentication method for a username and password checked against Argon2 hashes
+#[derive(Clone, Debug)]
+pub struct PasswordAuthenticationMethod {
+    source: PasswordCredentialsSource,
+}
+
+#[derive(Clone, Debug)]
+enum PasswordCredentialsSource {
+    Credentials(PasswordCredentials),
+    File(PathBuf),
+}
+
+impl PasswordAuthenticationMethod {
+    /// Creates a new method that checks passwords against the given credentials
+    #[inline]
+    pub fn new(credentials: impl Into<PasswordCredentials>) -> Self {
+        Self {
+            source: PasswordCredentialsSource::Credentials(credentials.into()),
+        }
+    }
+
+    /// Creates a new method that checks passwords against the credentials in the file at `path`,
+    /// which is read each time authentication is performed so that users can be added or removed
+    /// without restarting
+    #[inline]
+    pub fn from_file(path: impl Into<PathBuf>) -> Self {
+        Self {
+            source: PasswordCredentialsSource::File(path.into()),
+        }
+    }
+}
+
+#[async_trait]
+impl AuthenticationMethod for PasswordAuthenticationMethod {
+    fn id(&self) -> &'static str {
+        "password"
+    }
+
+    async fn authenticate(
+        &self,
+        authenticator: &mut dyn Authenticator,
+    ) -> io::Result<Option<String>> {
+        let response = authenticator
+            .challenge(Challenge {
+                questions: vec![
+                    Question {
+                        label: "username".to_string(),
+                        text: "Username: ".to_string(),
+                        options: HashMap::from([("echo".to_string(), "true".to_string())]),
+                    },
+                    Question {
+                        label: "password".to_string(),
+                        text: "Password: ".to_string(),
+                        options: HashMap::from([("echo".to_string(), "false".to_string())]),
+                    },
+                ],
+                options: Default::default(),
+            })
+            .await?;
+
+        let mut answers = response.answers.into_iter();
+        let (username, password) = match (answers.next(), answers.next()) {
+            (Some(username), Some(password)) => (username, password),
+            _ => return Err(Error::non_fatal("missing answer").into_io_permission_denied()),
+        };
+
+        // Hashing is intentionally expensive, so we avoid blocking the runtime while verifying
+        let source = self.source.clone();
+        let valid = tokio::task::spawn_blocking({
+            let username = username.clone();
+            move || {
+                let credentials = match source {
+                    PasswordCredentialsSource::Credentials(x) => x,
+                    PasswordCredentialsSource::File(path) => PasswordCredentials::from_file(path)?,
+                };
+                io::Result::Ok(credentials.verify(&username, &password))
+            }
+        })
+        .await
+        .map_err(|x| io::Error::new(io::ErrorKind::Other, x))??;
+
+        if valid {
+            Ok(Some(username))
+        } else {
+            Err(Error::non_fatal("invalid username or password").into_io_permission_denied())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::{
+        authentication::msg::{AuthenticationResponse, ChallengeResponse},
+        FramedTransport,
+    };
+    use test_log::test;
+
+    fn credentials() -> PasswordCredentials {
+        let mut credentials = PasswordCredentials::new();
+        credentials.insert("alice", "secret").unwrap();
+        credentials
+    }
+
+    #[test(tokio::test)]
+    async fn authenticate_should_fail_if_no_answer_included_in_challenge_response() {
+        let method = PasswordAuthenticationMethod::new(credentials());
+        let (mut t1, mut t2) = FramedTransport::test_pair(100);
+
+        t2.write_frame_for(&AuthenticationResponse::Challenge(ChallengeResponse {
+            answers: vec!["alice".to_string()],
+        }))
+        .await
+        .unwrap();
+
+        assert_eq!(
+            method.authenticate(&mut t1).await.unwrap_err().kind(),
+            io::ErrorKind::PermissionDenied
+        );
+    }
+
+    #[test(tokio::test)]
+    async fn authenticate_should_fail_if_password_does_not_match() {
+        let method = PasswordAuthenticationMethod::new(credentials());
+        let (mut t1, mut t2) = FramedTransport::test_pair(100);
+
+        t2.write_frame_for(&AuthenticationResponse::Challenge(ChallengeResponse {
+            answers: vec!["alice".to_string(), "other".to_string()],
+        }))
+        .await
+        .unwrap();
+
+        assert_eq!(
+            method.authenticate(&mut t1).await.unwrap_err().kind(),
+            io::ErrorKind::PermissionDenied
+        );
+    }
+
+    #[test(tokio::test)]
+    async fn authenticate_should_succeed_with_username_if_password_matches() {
+        let method = PasswordAuthenticationMethod::new(credentials());
+        let (mut t1, mut t2) = FramedTransport::test_pair(100);
+
+        t2.write_frame_for(&AuthenticationResponse::Challenge(ChallengeResponse {
+            answers: vec!["alice".to_string(), "secret".to_string()],
+        }))
+        .await
+        .unwrap();
+
+        assert_eq!(
+            method.authenticate(&mut t1).await.unwrap().as_deref(),
+            Some("alice")
+        );
+    }
+
+    #[test(tokio::test)]
+    async fn authenticate_should_read_credentials_from_file_each_time() {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        let method = PasswordAuthenticationMethod::from_file(file.path());
+
+        // Add the user after the method has been created
+        credentials().save(file.path()).unwrap();
+
+        let (mut t1, mut t2) = FramedTransport::test_pair(100);
+        t2.write_frame_for(&AuthenticationResponse::Challenge(ChallengeResponse {
+            answers: vec!["alice".to_string(), "secret".to_string()],
+        }))
+        .await
+        .unwrap();
+
+        assert_eq!(
+            method.authenticate(&mut t1).await.unwrap().as_deref(),
+            Some("alice")
+        );
+    }
+}
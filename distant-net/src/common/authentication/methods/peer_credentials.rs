@@ -0,0 +1,161 @@
+use super::{AuthenticationMethod, Authenticator, Error};
+use crate::common::PeerCredentials;
+use async_trait::async_trait;
+use std::{collections::HashSet, io};
+
+/// Authentication method that relies on the operating system to identify the process on the
+/// other side of a local connection such as a unix socket, allowing it if its user or group is
+/// permitted. No secrets are exchanged with the client.
+///
+/// Only the primary group of a peer is known, so groups with supplementary members are better
+/// restricted through the permissions of the socket alongside [`Self::any`].
+#[derive(Clone, Debug, Default)]
+pub struct PeerCredentialsAuthenticationMethod {
+    any: bool,
+    uids: HashSet<u32>,
+    gids: HashSet<u32>,
+}
+
+impl PeerCredentialsAuthenticationMethod {
+    /// Creates a new method that allows peers whose user id is within `uids` or whose group id is
+    /// within `gids`
+    pub fn new(uids: impl IntoIterator<Item = u32>, gids: impl IntoIterator<Item = u32>) -> Self {
+        Self {
+            any: false,
+            uids: uids.into_iter().collect(),
+            gids: gids.into_iter().collect(),
+        }
+    }
+
+    /// Creates a new method that allows every peer whose credentials are known, for use when the
+    /// permissions of a unix socket already restrict who is able to connect to it
+    pub fn any() -> Self {
+        Self {
+            any: true,
+            ..Default::default()
+        }
+    }
+
+    /// Consumes the method, returning a new method that also allows peers with the user id `uid`
+    pub fn with_uid(mut self, uid: u32) -> Self {
+        self.uids.insert(uid);
+        self
+    }
+
+    /// Consumes the method, returning a new method that also allows peers with the group id `gid`
+    pub fn with_gid(mut self, gid: u32) -> Self {
+        self.gids.insert(gid);
+        self
+    }
+
+    /// Returns true if a peer with the given `credentials` is allowed. Root is treated like any
+    /// other user, so it must be allowed explicitly through [`Self::with_uid`] with a uid of `0`
+    pub fn allows(&self, credentials: &PeerCredentials) -> bool {
+        self.any || self.uids.contains(&credentials.uid) || self.gids.contains(&credentials.gid)
+    }
+}
+
+#[async_trait]
+impl AuthenticationMethod for PeerCredentialsAuthenticationMethod {
+    fn id(&self) -> &'static str {
+        "peer_credentials"
+    }
+
+    async fn authenticate(
+        &self,
+        authenticator: &mut dyn Authenticator,
+    ) -> io::Result<Option<String>> {
+        match authenticator.peer_credentials() {
+            Some(credentials) if self.allows(&credentials) => Ok(None),
+            Some(credentials) => Err(Error::non_fatal(format!(
+                "peer with uid {} and gid {} is not allowed",
+                credentials.uid, credentials.gid
+            ))
+            .into_io_permission_denied()),
+            None => {
+                Err(Error::non_fatal("peer credentials unavailable").into_io_permission_denied())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::FramedTransport;
+    use test_log::test;
+
+    #[test]
+    fn allows_should_match_either_uid_or_gid() {
+        let method = PeerCredentialsAuthenticationMethod::new([1000], [100]);
+        let credentials = |uid, gid| PeerCredentials {
+            uid,
+            gid,
+            pid: None,
+        };
+
+        assert!(method.allows(&credentials(1, 100)));
+        assert!(method.allows(&credentials(1000, 1)));
+        assert!(!method.allows(&credentials(1, 1)));
+        assert!(method.clone().with_uid(1).allows(&credentials(1, 1)));
+        assert!(method.with_gid(1).allows(&credentials(1, 1)));
+    }
+
+    #[test]
+    fn allows_should_only_allow_root_if_explicitly_allowed_or_any() {
+        let credentials = |uid, gid| PeerCredentials {
+            uid,
+            gid,
+            pid: None,
+        };
+
+        assert!(!PeerCredentialsAuthenticationMethod::default().allows(&credentials(0, 0)));
+        assert!(!PeerCredentialsAuthenticationMethod::default().allows(&credentials(1, 1)));
+        assert!(PeerCredentialsAuthenticationMethod::default()
+            .with_uid(0)
+            .allows(&credentials(0, 0)));
+        assert!(PeerCredentialsAuthenticationMethod::any().allows(&credentials(0, 0)));
+        assert!(PeerCredentialsAuthenticationMethod::any().allows(&credentials(1, 1)));
+    }
+
+    #[test(tokio::test)]
+    async fn authenticate_should_fail_if_transport_has_no_peer_credentials() {
+        let method = PeerCredentialsAuthenticationMethod::new([0], [0]);
+        let (mut t1, _t2) = FramedTransport::test_pair(100);
+
+        assert_eq!(
+            method.authenticate(&mut t1).await.unwrap_err().kind(),
+            io::ErrorKind::PermissionDenied
+        );
+    }
+
+    #[cfg(unix)]
+    #[test(tokio::test)]
+    async fn authenticate_should_use_credentials_of_unix_socket_peer() {
+        use crate::common::{Listener, UnixSocketListener, UnixSocketTransport};
+
+        let path = tempfile::NamedTempFile::new().unwrap().path().to_path_buf();
+        let mut listener = UnixSocketListener::bind(&path).await.unwrap();
+        let owner = listener.owner_credentials().unwrap();
+
+        let _conn = UnixSocketTransport::connect(&path).await.unwrap();
+        let mut transport = FramedTransport::plain(listener.accept().await.unwrap());
+
+        // Some other user is not allowed, while the user of this process is (root is always
+        // allowed, so only check the former when not running as root)
+        if owner.uid != 0 {
+            let method = PeerCredentialsAuthenticationMethod::new([owner.uid.wrapping_add(1)], []);
+            assert_eq!(
+                method
+                    .authenticate(&mut transport)
+                    .await
+                    .unwrap_err()
+                    .kind(),
+                io::ErrorKind::PermissionDenied
+            );
+        }
+
+        let method = PeerCredentialsAuthenticationMethod::new([owner.uid], []);
+        assert_eq!(method.authenticate(&mut transport).await.unwrap(), None);
+    }
+}
@@ -0,0 +1,225 @@
+use super::{AuthenticationMethod, Authenticator, Challenge, Error, Question};
+use crate::common::authentication::{AuthorizedKeys, Ed25519PublicKey};
+use async_trait::async_trait;
+use rand::{rngs::OsRng, RngCore};
+use std::{collections::HashMap, io, path::PathBuf};
+
+/// Authentication method for proving ownership of the private key of an authorized Ed25519
+/// public key by signing a random nonce
+#[derive(Clone, Debug)]
+pub struct PublicKeyAuthenticationMethod {
+    source: AuthorizedKeysSource,
+}
+
+#[derive(Clone, Debug)]
+enum AuthorizedKeysSource {
+    Keys(AuthorizedKeys),
+    File(PathBuf),
+}
+
+impl PublicKeyAuthenticationMethod {
+    /// Creates a new method that authorizes the given keys
+    #[inline]
+    pub fn new(authorized_keys: impl Into<AuthorizedKeys>) -> Self {
+        Self {
+            source: AuthorizedKeysSource::Keys(authorized_keys.into()),
+        }
+    }
+
+    /// Creates a new method that authorizes the keys in the file at `path`, which is read each
+    /// time authentication is performed so that keys can be added or revoked without restarting
+    #[inline]
+    pub fn from_file(path: impl Into<PathBuf>) -> Self {
+        Self {
+            source: AuthorizedKeysSource::File(path.into()),
+        }
+    }
+
+    fn authorized_keys(&self) -> io::Result<AuthorizedKeys> {
+        match &self.source {
+            AuthorizedKeysSource::Keys(keys) => Ok(keys.clone()),
+            AuthorizedKeysSource::File(path) => AuthorizedKeys::from_file(path),
+        }
+    }
+}
+
+#[async_trait]
+impl AuthenticationMethod for PublicKeyAuthenticationMethod {
+    fn id(&self) -> &'static str {
+        "public_key"
+    }
+
+    async fn authenticate(
+        &self,
+        authenticator: &mut dyn Authenticator,
+    ) -> io::Result<Option<String>> {
+        let mut nonce = [0; 32];
+        OsRng.fill_bytes(&mut nonce);
+
+        let response = authenticator
+            .challenge(Challenge {
+                questions: vec![
+                    Question {
+                        label: "public_key".to_string(),
+                        text: "Provide a public key: ".to_string(),
+                        options: Default::default(),
+                    },
+                    Question {
+                        label: "signature".to_string(),
+                        text: "Provide a signature of the nonce: ".to_string(),
+                        options: Default::default(),
+                    },
+                ],
+                options: HashMap::from([("nonce".to_string(), hex::encode(nonce))]),
+            })
+            .await?;
+
+        let mut answers = response.answers.into_iter();
+        let (key, signature) = match (answers.next(), answers.next()) {
+            (Some(key), Some(signature)) => (key, signature),
+            _ => return Err(Error::non_fatal("missing answer").into_io_permission_denied()),
+        };
+
+        let key = match key.parse::<Ed25519PublicKey>() {
+            Ok(key) if self.authorized_keys()?.contains(&key) => key,
+            _ => return Err(Error::non_fatal("key is not authorized").into_io_permission_denied()),
+        };
+
+        match hex::decode(signature) {
+            Ok(signature) if key.verify_nonce(&nonce, &signature) => Ok(None),
+            _ => Err(Error::non_fatal("signature does not match key").into_io_permission_denied()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::{
+        authentication::{
+            msg::{Authentication, AuthenticationResponse, ChallengeResponse},
+            Ed25519SigningKey,
+        },
+        FramedTransport,
+    };
+    use test_log::test;
+
+    #[test(tokio::test)]
+    async fn authenticate_should_fail_if_no_answer_included_in_challenge_response() {
+        let key = Ed25519SigningKey::generate();
+        let method = PublicKeyAuthenticationMethod::new(vec![key.public_key()]);
+        let (mut t1, mut t2) = FramedTransport::test_pair(100);
+
+        t2.write_frame_for(&AuthenticationResponse::Challenge(ChallengeResponse {
+            answers: Vec::new(),
+        }))
+        .await
+        .unwrap();
+
+        assert_eq!(
+            method.authenticate(&mut t1).await.unwrap_err().kind(),
+            io::ErrorKind::PermissionDenied
+        );
+    }
+
+    #[test(tokio::test)]
+    async fn authenticate_should_fail_if_key_is_not_authorized() {
+        let key = Ed25519SigningKey::generate();
+        let method =
+            PublicKeyAuthenticationMethod::new(vec![Ed25519SigningKey::generate().public_key()]);
+        let (mut t1, mut t2) = FramedTransport::test_pair(100);
+
+        let task = tokio::spawn(async move { method.authenticate(&mut t1).await });
+        let nonce = match t2.read_frame_as::<Authentication>().await.unwrap().unwrap() {
+            Authentication::Challenge(x) => hex::decode(&x.options["nonce"]).unwrap(),
+            x => panic!("Unexpected request: {x:?}"),
+        };
+
+        t2.write_frame_for(&AuthenticationResponse::Challenge(ChallengeResponse {
+            answers: vec![
+                key.public_key().to_string(),
+                hex::encode(key.sign_nonce(&nonce)),
+            ],
+        }))
+        .await
+        .unwrap();
+
+        assert_eq!(
+            task.await.unwrap().unwrap_err().kind(),
+            io::ErrorKind::PermissionDenied
+        );
+    }
+
+    #[test(tokio::test)]
+    async fn authenticate_should_fail_if_signature_is_not_of_nonce() {
+        let key = Ed25519SigningKey::generate();
+        let method = PublicKeyAuthenticationMethod::new(vec![key.public_key()]);
+        let (mut t1, mut t2) = FramedTransport::test_pair(100);
+
+        t2.write_frame_for(&AuthenticationResponse::Challenge(ChallengeResponse {
+            answers: vec![
+                key.public_key().to_string(),
+                hex::encode(key.sign_nonce(b"other nonce")),
+            ],
+        }))
+        .await
+        .unwrap();
+
+        assert_eq!(
+            method.authenticate(&mut t1).await.unwrap_err().kind(),
+            io::ErrorKind::PermissionDenied
+        );
+    }
+
+    #[test(tokio::test)]
+    async fn authenticate_should_succeed_if_authorized_key_signs_nonce() {
+        let key = Ed25519SigningKey::generate();
+        let method = PublicKeyAuthenticationMethod::new(vec![key.public_key()]);
+        let (mut t1, mut t2) = FramedTransport::test_pair(100);
+
+        let task = tokio::spawn(async move { method.authenticate(&mut t1).await });
+        let nonce = match t2.read_frame_as::<Authentication>().await.unwrap().unwrap() {
+            Authentication::Challenge(x) => hex::decode(&x.options["nonce"]).unwrap(),
+            x => panic!("Unexpected request: {x:?}"),
+        };
+
+        t2.write_frame_for(&AuthenticationResponse::Challenge(ChallengeResponse {
+            answers: vec![
+                key.public_key().to_string(),
+                hex::encode(key.sign_nonce(&nonce)),
+            ],
+        }))
+        .await
+        .unwrap();
+
+        task.await.unwrap().unwrap();
+    }
+
+    #[test(tokio::test)]
+    async fn authenticate_should_read_authorized_keys_from_file_each_time() {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        let method = PublicKeyAuthenticationMethod::from_file(file.path());
+        let key = Ed25519SigningKey::generate();
+
+        // Authorize the key after the method has been created
+        std::fs::write(file.path(), format!("{} someone\n", key.public_key())).unwrap();
+
+        let (mut t1, mut t2) = FramedTransport::test_pair(100);
+        let task = tokio::spawn(async move { method.authenticate(&mut t1).await });
+        let nonce = match t2.read_frame_as::<Authentication>().await.unwrap().unwrap() {
+            Authentication::Challenge(x) => hex::decode(&x.options["nonce"]).unwrap(),
+            x => panic!("Unexpected request: {x:?}"),
+        };
+
+        t2.write_frame_for(&AuthenticationResponse::Challenge(ChallengeResponse {
+            answers: vec![
+                key.public_key().to_string(),
+                hex::encode(key.sign_nonce(&nonce)),
+            ],
+        }))
+        .await
+        .unwrap();
+
+        task.await.unwrap().unwrap();
+    }
+}
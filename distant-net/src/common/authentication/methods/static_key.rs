@@ -22,7 +22,10 @@ impl AuthenticationMethod for StaticKeyAuthenticationMethod {
         "static_key"
     }
 
-    async fn authenticate(&self, authenticator: &mut dyn Authenticator) -> io::Result<()> {
+    async fn authenticate(
+        &self,
+        authenticator: &mut dyn Authenticator,
+    ) -> io::Result<Option<String>> {
         let response = authenticator
             .challenge(Challenge {
                 questions: vec![Question {
@@ -45,7 +48,7 @@ impl AuthenticationMethod for StaticKeyAuthenticationMethod {
             .unwrap()
             .parse::<HeapSecretKey>()
         {
-            Ok(key) if key == self.key => Ok(()),
+            Ok(key) if key == self.key => Ok(None),
             _ => Err(Error::non_fatal("answer does not match key").into_io_permission_denied()),
         }
     }
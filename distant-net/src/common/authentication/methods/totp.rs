@@ -0,0 +1,142 @@
+use super::{AuthenticationMethod, Authenticator, Challenge, Error, Question};
+use crate::common::authentication::TotpSecret;
+use async_trait::async_trait;
+use std::{
+    io,
+    sync::Mutex,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Authentication method for a time-based one-time password (TOTP) produced by an authenticator
+/// app, usually required alongside another method as a second factor
+#[derive(Debug)]
+pub struct TotpAuthenticationMethod {
+    secret: TotpSecret,
+
+    /// Time step of the last accepted code, used to prevent a code from being used twice
+    last_step: Mutex<Option<u64>>,
+}
+
+impl TotpAuthenticationMethod {
+    /// Creates a new method that accepts codes produced using the given `secret`
+    #[inline]
+    pub fn new(secret: TotpSecret) -> Self {
+        Self {
+            secret,
+            last_step: Mutex::new(None),
+        }
+    }
+
+    fn verify_at(&self, code: &str, unix_time: u64) -> bool {
+        match self.secret.find_step(code.trim(), unix_time) {
+            Some(step) => {
+                let mut last_step = self.last_step.lock().unwrap();
+                if matches!(*last_step, Some(last) if step <= last) {
+                    return false;
+                }
+                *last_step = Some(step);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+#[async_trait]
+impl AuthenticationMethod for TotpAuthenticationMethod {
+    fn id(&self) -> &'static str {
+        "totp"
+    }
+
+    async fn authenticate(
+        &self,
+        authenticator: &mut dyn Authenticator,
+    ) -> io::Result<Option<String>> {
+        let response = authenticator
+            .challenge(Challenge {
+                questions: vec![Question {
+                    label: "totp".to_string(),
+                    text: "Authentication code: ".to_string(),
+                    options: Default::default(),
+                }],
+                options: Default::default(),
+            })
+            .await?;
+
+        let code = match response.answers.into_iter().next() {
+            Some(code) => code,
+            None => return Err(Error::non_fatal("missing answer").into_io_permission_denied()),
+        };
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_err(|x| io::Error::new(io::ErrorKind::Other, x))?
+            .as_secs();
+
+        if self.verify_at(&code, now) {
+            Ok(None)
+        } else {
+            Err(Error::non_fatal("invalid code").into_io_permission_denied())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::{
+        authentication::msg::{AuthenticationResponse, ChallengeResponse},
+        FramedTransport,
+    };
+    use test_log::test;
+
+    #[test]
+    fn verify_at_should_reject_code_reused_within_its_time_step() {
+        let secret = TotpSecret::generate();
+        let method = TotpAuthenticationMethod::new(secret.clone());
+
+        let code = secret.code_at(1000);
+        assert!(method.verify_at(&code, 1000));
+        assert!(!method.verify_at(&code, 1000));
+
+        // An older code is also rejected once a newer one has been used
+        assert!(method.verify_at(&secret.code_at(1000 + 30), 1000 + 30));
+        assert!(!method.verify_at(&secret.code_at(1000), 1000 + 30));
+    }
+
+    #[test(tokio::test)]
+    async fn authenticate_should_fail_if_code_is_invalid() {
+        let method = TotpAuthenticationMethod::new(TotpSecret::generate());
+        let (mut t1, mut t2) = FramedTransport::test_pair(100);
+
+        t2.write_frame_for(&AuthenticationResponse::Challenge(ChallengeResponse {
+            answers: vec!["not-a-code".to_string()],
+        }))
+        .await
+        .unwrap();
+
+        assert_eq!(
+            method.authenticate(&mut t1).await.unwrap_err().kind(),
+            io::ErrorKind::PermissionDenied
+        );
+    }
+
+    #[test(tokio::test)]
+    async fn authenticate_should_succeed_if_code_matches_current_time() {
+        let secret = TotpSecret::generate();
+        let method = TotpAuthenticationMethod::new(secret.clone());
+        let (mut t1, mut t2) = FramedTransport::test_pair(100);
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        t2.write_frame_for(&AuthenticationResponse::Challenge(ChallengeResponse {
+            answers: vec![secret.code_at(now)],
+        }))
+        .await
+        .unwrap();
+
+        assert_eq!(method.authenticate(&mut t1).await.unwrap(), None);
+    }
+}
@@ -0,0 +1,209 @@
+use argon2::{
+    password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
+    Argon2,
+};
+use derive_more::{Display, Error};
+use std::{collections::BTreeMap, fmt, fs, io, path::Path, str::FromStr};
+
+/// Argon2 hash of an arbitrary password using the default parameters, verified against when a
+/// user is unknown so that the time taken does not reveal which users exist
+const DUMMY_HASH: &str =
+    "$argon2id$v=19$m=19456,t=2,p=1$2d9cEQO2TDU8jH6A51QuFg$GwY0RpnyPWuj5/Rcv1U7mhQwJCH/CNwyExugIjvi69I";
+
+#[derive(Debug, Display, Error)]
+#[display(fmt = "invalid credentials on line {}", line)]
+pub struct PasswordCredentialsError {
+    pub line: usize,
+}
+
+impl From<PasswordCredentialsError> for io::Error {
+    fn from(x: PasswordCredentialsError) -> Self {
+        io::Error::new(io::ErrorKind::InvalidData, x)
+    }
+}
+
+/// Represents usernames and the Argon2 hashes of their passwords used by a server to authenticate
+/// clients
+///
+/// When parsed, each line contains a username and a hash in the PHC string format separated by a
+/// colon, similar to an htpasswd file. Blank lines and lines starting with `#` are ignored.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PasswordCredentials(BTreeMap<String, String>);
+
+impl PasswordCredentials {
+    /// Creates a new, empty collection of credentials
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loads the credentials from the file at `path`
+    pub fn from_file(path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(fs::read_to_string(path)?.parse()?)
+    }
+
+    /// Writes the credentials to the file at `path`, which is only readable and writable by the
+    /// current user on unix platforms
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+
+        io::Write::write_all(&mut options.open(path)?, self.to_string().as_bytes())
+    }
+
+    /// Hashes `password` and stores it for `username`, replacing any existing password for the
+    /// user. Fails if the username is empty or contains a colon or whitespace.
+    pub fn insert(&mut self, username: impl Into<String>, password: &str) -> io::Result<()> {
+        let username = username.into();
+        if username.is_empty() || username.contains(|c: char| c == ':' || c.is_whitespace()) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Invalid username: {username:?}"),
+            ));
+        }
+
+        let salt = SaltString::generate(&mut OsRng);
+        let hash = Argon2::default()
+            .hash_password(password.as_bytes(), &salt)
+            .map_err(|x| io::Error::new(io::ErrorKind::Other, x.to_string()))?;
+        self.0.insert(username, hash.to_string());
+        Ok(())
+    }
+
+    /// Removes the password for `username`, returning true if the user existed
+    pub fn remove(&mut self, username: &str) -> bool {
+        self.0.remove(username).is_some()
+    }
+
+    /// Returns true if a password is stored for `username`
+    pub fn contains(&self, username: &str) -> bool {
+        self.0.contains_key(username)
+    }
+
+    /// Returns true if `password` matches the one stored for `username`
+    ///
+    /// A password is still checked against a dummy hash when the user is unknown, taking the same
+    /// time as checking the password of a user that exists.
+    pub fn verify(&self, username: &str, password: &str) -> bool {
+        let (hash, known) = match self.0.get(username) {
+            Some(hash) => (hash.as_str(), true),
+            None => (DUMMY_HASH, false),
+        };
+
+        let valid = match PasswordHash::new(hash) {
+            Ok(hash) => Argon2::default()
+                .verify_password(password.as_bytes(), &hash)
+                .is_ok(),
+            Err(_) => false,
+        };
+
+        known && valid
+    }
+
+    /// Returns an iterator over the usernames in sorted order
+    pub fn usernames(&self) -> impl Iterator<Item = &str> {
+        self.0.keys().map(String::as_str)
+    }
+
+    /// Returns the total users with a stored password
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns true if there are no users
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl FromStr for PasswordCredentials {
+    type Err = PasswordCredentialsError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut credentials = BTreeMap::new();
+        for (i, line) in s.lines().map(str::trim).enumerate() {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            match line.split_once(':') {
+                Some((username, hash))
+                    if !username.is_empty() && PasswordHash::new(hash).is_ok() =>
+                {
+                    credentials.insert(username.to_string(), hash.to_string());
+                }
+                _ => return Err(PasswordCredentialsError { line: i + 1 }),
+            }
+        }
+
+        Ok(Self(credentials))
+    }
+}
+
+impl fmt::Display for PasswordCredentials {
+    /// Display the credentials with one `username:hash` per line
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (username, hash) in self.0.iter() {
+            writeln!(f, "{username}:{hash}")?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verify_should_succeed_only_if_password_matches_stored_hash() {
+        let mut credentials = PasswordCredentials::new();
+        credentials.insert("alice", "secret").unwrap();
+
+        assert!(credentials.verify("alice", "secret"));
+        assert!(!credentials.verify("alice", "other"));
+        assert!(!credentials.verify("bob", "secret"));
+    }
+
+    #[test]
+    fn verify_should_fail_for_unknown_user_even_with_password_of_dummy_hash() {
+        // Dummy hash must be valid for unknown users to take as long as known users
+        PasswordHash::new(DUMMY_HASH).unwrap();
+
+        let credentials = PasswordCredentials::new();
+        assert!(!credentials.verify("alice", "distant-dummy-password"));
+    }
+
+    #[test]
+    fn insert_should_fail_if_username_is_invalid() {
+        let mut credentials = PasswordCredentials::new();
+        credentials.insert("", "secret").unwrap_err();
+        credentials.insert("al:ice", "secret").unwrap_err();
+        credentials.insert("al ice", "secret").unwrap_err();
+        assert!(credentials.is_empty());
+    }
+
+    #[test]
+    fn credentials_should_round_trip_through_string() {
+        let mut credentials = PasswordCredentials::new();
+        credentials.insert("alice", "secret").unwrap();
+        credentials.insert("bob", "password").unwrap();
+
+        let parsed: PasswordCredentials = format!("# team\n\n{credentials}").parse().unwrap();
+        assert_eq!(parsed, credentials);
+        assert!(parsed.verify("bob", "password"));
+    }
+
+    #[test]
+    fn credentials_should_fail_to_parse_line_without_valid_hash() {
+        let err = "alice:not-a-hash"
+            .parse::<PasswordCredentials>()
+            .unwrap_err();
+        assert_eq!(err.line, 1);
+    }
+}
@@ -0,0 +1,219 @@
+use derive_more::{Display, Error};
+use hmac::{Hmac, Mac};
+use rand::{rngs::OsRng, RngCore};
+use sha1::Sha1;
+use std::{fmt, fs, io, path::Path, str::FromStr};
+
+/// Alphabet used to encode secrets as expected by authenticator apps
+const ALPHABET: base32::Alphabet = base32::Alphabet::RFC4648 { padding: false };
+
+/// Total seconds that each code is valid
+pub const TOTP_PERIOD: u64 = 30;
+
+/// Total digits within each code
+pub const TOTP_DIGITS: u32 = 6;
+
+#[derive(Debug, Display, Error)]
+pub struct TotpSecretError;
+
+impl From<TotpSecretError> for io::Error {
+    fn from(_: TotpSecretError) -> Self {
+        io::Error::new(io::ErrorKind::InvalidData, "not valid base32 totp secret")
+    }
+}
+
+/// Represents a secret shared with an authenticator app to produce time-based one-time passwords
+/// as described in RFC 6238, using HMAC-SHA1, six digits, and a thirty second period
+#[derive(Clone, PartialEq, Eq)]
+pub struct TotpSecret(Vec<u8>);
+
+impl fmt::Debug for TotpSecret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("TotpSecret")
+            .field(&"**OMITTED**".to_string())
+            .finish()
+    }
+}
+
+impl TotpSecret {
+    /// Generates a new random secret of 160 bits as recommended by RFC 4226
+    pub fn generate() -> Self {
+        let mut bytes = vec![0; 20];
+        OsRng.fill_bytes(&mut bytes);
+        Self(bytes)
+    }
+
+    /// Loads a secret from the file at `path`, which contains the secret encoded as base32
+    pub fn from_file(path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(fs::read_to_string(path)?.trim().parse()?)
+    }
+
+    /// Writes the secret encoded as base32 to the file at `path`, which is only readable and
+    /// writable by the current user on unix platforms
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+
+        io::Write::write_all(&mut options.open(path)?, format!("{self}\n").as_bytes())
+    }
+
+    /// Returns the time step containing `unix_time` (seconds since the epoch)
+    pub fn step_at(unix_time: u64) -> u64 {
+        unix_time / TOTP_PERIOD
+    }
+
+    /// Produces the code for the given time `step`
+    pub fn code_at_step(&self, step: u64) -> String {
+        let mut mac = Hmac::<Sha1>::new_from_slice(&self.0).expect("HMAC accepts any key size");
+        mac.update(&step.to_be_bytes());
+        let hash = mac.finalize().into_bytes();
+
+        // Dynamic truncation as described in RFC 4226 section 5.3
+        let offset = (hash[hash.len() - 1] & 0xf) as usize;
+        let binary = u32::from_be_bytes([
+            hash[offset] & 0x7f,
+            hash[offset + 1],
+            hash[offset + 2],
+            hash[offset + 3],
+        ]);
+
+        format!(
+            "{:0width$}",
+            binary % 10u32.pow(TOTP_DIGITS),
+            width = TOTP_DIGITS as usize
+        )
+    }
+
+    /// Produces the code for the time step containing `unix_time`
+    pub fn code_at(&self, unix_time: u64) -> String {
+        self.code_at_step(Self::step_at(unix_time))
+    }
+
+    /// Returns the time step whose code matches `code`, checking the step containing `unix_time`
+    /// and the steps immediately before and after it to allow for clock drift
+    pub fn find_step(&self, code: &str, unix_time: u64) -> Option<u64> {
+        let step = Self::step_at(unix_time);
+        [step, step.saturating_sub(1), step + 1]
+            .into_iter()
+            .find(|step| constant_time_eq(self.code_at_step(*step).as_bytes(), code.as_bytes()))
+    }
+
+    /// Returns a URI that can be added to an authenticator app, usually by rendering it as a QR
+    /// code, where `account` and `issuer` are labels shown within the app
+    pub fn to_uri(&self, account: &str, issuer: &str) -> String {
+        format!(
+            "otpauth://totp/{issuer_label}:{account}?secret={self}&issuer={issuer}&algorithm=SHA1&digits={TOTP_DIGITS}&period={TOTP_PERIOD}",
+            issuer_label = percent_encode(issuer),
+            account = percent_encode(account),
+            issuer = percent_encode(issuer),
+        )
+    }
+}
+
+impl FromStr for TotpSecret {
+    type Err = TotpSecretError;
+
+    /// Parse a str of base32 as the bytes of a secret, ignoring case and padding
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match base32::decode(ALPHABET, &s.trim_end_matches('=').to_ascii_uppercase()) {
+            Some(bytes) if !bytes.is_empty() => Ok(Self(bytes)),
+            _ => Err(TotpSecretError),
+        }
+    }
+}
+
+impl fmt::Display for TotpSecret {
+    /// Display the secret as base32 without padding
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", base32::encode(ALPHABET, &self.0))
+    }
+}
+
+/// Compares two byte slices without exiting early on the first difference
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// Encodes everything other than unreserved characters as described in RFC 3986
+fn percent_encode(s: &str) -> String {
+    let mut encoded = String::new();
+    for byte in s.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Secret from the test vectors of RFC 6238 appendix B for SHA1
+    fn rfc_secret() -> TotpSecret {
+        TotpSecret(b"12345678901234567890".to_vec())
+    }
+
+    #[test]
+    fn code_at_should_match_rfc_6238_test_vectors() {
+        // NOTE: The RFC uses eight digits, so we compare against the last six
+        let secret = rfc_secret();
+        assert_eq!(secret.code_at(59), "287082");
+        assert_eq!(secret.code_at(1111111109), "081804");
+        assert_eq!(secret.code_at(1234567890), "005924");
+        assert_eq!(secret.code_at(2000000000), "279037");
+    }
+
+    #[test]
+    fn find_step_should_allow_one_step_of_drift() {
+        let secret = rfc_secret();
+        let code = secret.code_at(1111111109);
+        let step = TotpSecret::step_at(1111111109);
+
+        assert_eq!(secret.find_step(&code, 1111111109), Some(step));
+        assert_eq!(
+            secret.find_step(&code, 1111111109 + TOTP_PERIOD),
+            Some(step)
+        );
+        assert_eq!(
+            secret.find_step(&code, 1111111109 - TOTP_PERIOD),
+            Some(step)
+        );
+        assert_eq!(secret.find_step(&code, 1111111109 + 2 * TOTP_PERIOD), None);
+    }
+
+    #[test]
+    fn secret_should_round_trip_through_string() {
+        let secret = TotpSecret::generate();
+        assert_eq!(secret.to_string().parse::<TotpSecret>().unwrap(), secret);
+        assert_eq!(
+            secret
+                .to_string()
+                .to_lowercase()
+                .parse::<TotpSecret>()
+                .unwrap(),
+            secret
+        );
+        "not base32!".parse::<TotpSecret>().unwrap_err();
+    }
+
+    #[test]
+    fn to_uri_should_encode_labels() {
+        let secret = rfc_secret();
+        assert_eq!(
+            secret.to_uri("alice@jump box", "distant"),
+            format!(
+                "otpauth://totp/distant:alice%40jump%20box?secret={secret}&issuer=distant&algorithm=SHA1&digits=6&period=30"
+            )
+        );
+    }
+}
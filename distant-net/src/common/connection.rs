@@ -1,6 +1,6 @@
 use super::{
     authentication::{AuthHandler, Authenticate, Keychain, KeychainResult, Verifier},
-    Backup, FramedTransport, HeapSecretKey, Reconnectable, Transport,
+    Backup, FramedTransport, Handshake, HeapSecretKey, Reconnectable, Transport,
 };
 use async_trait::async_trait;
 use log::*;
@@ -38,6 +38,9 @@ pub enum Connection<T> {
         /// Used to send the backup into storage when the connection is dropped
         tx: oneshot::Sender<Backup>,
 
+        /// Name of the user that authenticated, if the authentication method identified one
+        username: Option<String>,
+
         /// Underlying transport used to communicate
         transport: FramedTransport<T>,
     },
@@ -237,7 +240,8 @@ where
     /// Transforms a raw [`Transport`] into an established [`Connection`] from the server-side by
     /// performing the following:
     ///
-    /// 1. Handshakes to derive the appropriate [`Codec`](crate::Codec) to use
+    /// 1. Handshakes to derive the appropriate [`Codec`](crate::Codec) to use, where the
+    ///    server-side `handshake` limits the compression and encryption the client can choose
     /// 2. Authenticates the established connection to ensure it is valid by either using the
     ///    given `verifier` or, if working with an existing client connection, will validate an OTP
     ///    from our database
@@ -245,15 +249,16 @@ where
     ///    receiving any frames from the other side
     pub async fn server(
         transport: T,
+        handshake: Handshake,
         verifier: &Verifier,
-        keychain: Keychain<oneshot::Receiver<Backup>>,
+        keychain: Keychain<(Option<String>, oneshot::Receiver<Backup>)>,
     ) -> io::Result<Self> {
         let id: ConnectionId = rand::random();
 
         // Perform a handshake to ensure that the connection is properly established and encrypted
         debug!("[Conn {id}] Performing handshake");
-        let mut transport: FramedTransport<T> =
-            FramedTransport::from_server_handshake(transport).await?;
+        let mut transport: FramedTransport<T> = FramedTransport::plain(transport);
+        transport.handshake(handshake).await?;
 
         // Receive a client id, look up to see if the client id exists already
         //
@@ -276,7 +281,7 @@ where
 
         // Based on the connection type, we either try to find and validate an existing connection
         // or we perform normal verification
-        let id = match connection_type {
+        let (id, username) = match connection_type {
             ConnectType::Connect => {
                 // Communicate the connection id
                 debug!("[Conn {id}] Telling other side to change connection id");
@@ -284,7 +289,7 @@ where
 
                 // Perform authentication to ensure the connection is valid
                 debug!("[Conn {id}] Verifying connection");
-                verifier.verify(&mut transport).await?;
+                let username = verifier.verify(&mut transport).await?.username;
 
                 // Derive an OTP for reauthentication
                 debug!("[Conn {id}] Deriving future OTP for reauthentication");
@@ -292,9 +297,11 @@ where
 
                 // Store the id, OTP, and backup retrieval in our database
                 info!("[Conn {id}] Connect completed successfully!");
-                keychain.insert(id.to_string(), reauth_otp, rx).await;
+                keychain
+                    .insert(id.to_string(), reauth_otp, (username.clone(), rx))
+                    .await;
 
-                id
+                (id, username)
             }
             ConnectType::Reconnect { id: other_id, otp } => {
                 let reauth_otp = HeapSecretKey::from(otp);
@@ -304,7 +311,7 @@ where
                     .remove_if_has_key(other_id.to_string(), reauth_otp.clone())
                     .await
                 {
-                    KeychainResult::Ok(x) => {
+                    KeychainResult::Ok((username, x)) => {
                         // Match found, so we want ot update our id to be the pre-existing id
                         debug!("[Conn {id}] Reassigning to {other_id}");
                         let id = other_id;
@@ -329,7 +336,9 @@ where
                                     Err(x) => {
                                         error!("[Conn {id}] Encountered error, restoring with old backup");
                                         let _ = tx.send($backup);
-                                        keychain.insert(id.to_string(), reauth_otp, rx).await;
+                                        keychain
+                                            .insert(id.to_string(), reauth_otp, (username, rx))
+                                            .await;
                                         return Err(x);
                                     }
                                 }
@@ -351,9 +360,11 @@ where
 
                         // Store the id, OTP, and backup retrieval in our database
                         info!("[Conn {id}] Reconnect restoration completed successfully!");
-                        keychain.insert(id.to_string(), new_reauth_otp, rx).await;
+                        keychain
+                            .insert(id.to_string(), new_reauth_otp, (username.clone(), rx))
+                            .await;
 
-                        id
+                        (id, username)
                     }
                     KeychainResult::InvalidPassword => {
                         return Err(io::Error::new(
@@ -371,7 +382,12 @@ where
             }
         };
 
-        Ok(Self::Server { id, tx, transport })
+        Ok(Self::Server {
+            id,
+            tx,
+            username,
+            transport,
+        })
     }
 }
 
@@ -397,6 +413,7 @@ impl Connection<InmemoryTransport> {
         let server = Connection::Server {
             id,
             tx: oneshot::channel().0,
+            username: None,
             transport: t2,
         };
 
@@ -412,6 +429,15 @@ impl<T> Connection<T> {
             Self::Server { id, .. } => *id,
         }
     }
+
+    /// Returns the name of the user that authenticated, or none if no user was identified or the
+    /// connection is client-side.
+    pub fn username(&self) -> Option<&str> {
+        match self {
+            Self::Client { .. } => None,
+            Self::Server { username, .. } => username.as_deref(),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -457,7 +483,7 @@ mod tests {
     use super::*;
     use crate::common::{
         authentication::{msg::Challenge, Authenticator, DummyAuthHandler},
-        Frame,
+        EncryptionType, Frame, KeyExchangeType,
     };
     use std::sync::Arc;
     use test_log::test;
@@ -636,7 +662,7 @@ mod tests {
         // Spawn a task to perform the server connection so we don't deadlock while simulating the
         // client actions on the other side
         let task = tokio::spawn(async move {
-            Connection::server(t2.into_inner(), &verifier, keychain)
+            Connection::server(t2.into_inner(), Handshake::server(), &verifier, keychain)
                 .await
                 .unwrap()
         });
@@ -648,6 +674,55 @@ mod tests {
         task.await.unwrap_err();
     }
 
+    #[test(tokio::test)]
+    async fn server_should_only_offer_the_encryption_types_of_the_handshake() {
+        #[derive(Debug, Deserialize)]
+        struct Options {
+            encryption_types: Vec<EncryptionType>,
+        }
+
+        let (mut t1, t2) = FramedTransport::pair(100);
+        let verifier = Verifier::none();
+        let keychain = Keychain::new();
+
+        let _task = tokio::spawn(async move {
+            let handshake = Handshake::Server {
+                compression_types: Vec::new(),
+                encryption_types: vec![EncryptionType::Aes256Gcm],
+                key_exchange_types: KeyExchangeType::known_variants().to_vec(),
+            };
+            Connection::server(t2.into_inner(), handshake, &verifier, keychain).await
+        });
+
+        let options = t1.read_frame_as::<Options>().await.unwrap().unwrap();
+        assert_eq!(options.encryption_types, vec![EncryptionType::Aes256Gcm]);
+    }
+
+    #[test(tokio::test)]
+    async fn client_should_use_encryption_type_offered_by_server_if_preferred_is_unavailable() {
+        let (t1, t2) = InmemoryTransport::pair(100);
+        let verifier = Verifier::none();
+        let keychain = Keychain::new();
+
+        let task = tokio::spawn(async move {
+            let handshake = Handshake::Server {
+                compression_types: Vec::new(),
+                encryption_types: vec![EncryptionType::Aes256Gcm],
+                key_exchange_types: KeyExchangeType::known_variants().to_vec(),
+            };
+            Connection::server(t2, handshake, &verifier, keychain)
+                .await
+                .unwrap()
+        });
+
+        let mut client = Connection::client(t1, DummyAuthHandler).await.unwrap();
+        let mut server = task.await.unwrap();
+
+        // Verify that the connections are able to communicate using the negotiated codec
+        client.write_frame(Frame::new(b"hello")).await.unwrap();
+        assert_eq!(server.read_frame().await.unwrap().unwrap(), b"hello");
+    }
+
     #[test(tokio::test)]
     async fn server_should_fail_if_unable_to_receive_connect_type() {
         let (mut t1, t2) = FramedTransport::pair(100);
@@ -657,7 +732,7 @@ mod tests {
         // Spawn a task to perform the server connection so we don't deadlock while simulating the
         // client actions on the other side
         let task = tokio::spawn(async move {
-            Connection::server(t2.into_inner(), &verifier, keychain)
+            Connection::server(t2.into_inner(), Handshake::server(), &verifier, keychain)
                 .await
                 .unwrap()
         });
@@ -681,7 +756,7 @@ mod tests {
         // Spawn a task to perform the server connection so we don't deadlock while simulating the
         // client actions on the other side
         let task = tokio::spawn(async move {
-            Connection::server(t2.into_inner(), &verifier, keychain)
+            Connection::server(t2.into_inner(), Handshake::server(), &verifier, keychain)
                 .await
                 .unwrap()
         });
@@ -715,7 +790,7 @@ mod tests {
         // Spawn a task to perform the server connection so we don't deadlock while simulating the
         // client actions on the other side
         let task = tokio::spawn(async move {
-            Connection::server(t2.into_inner(), &verifier, keychain)
+            Connection::server(t2.into_inner(), Handshake::server(), &verifier, keychain)
                 .await
                 .unwrap()
         });
@@ -748,7 +823,7 @@ mod tests {
         // Spawn a task to perform the server connection so we don't deadlock while simulating the
         // client actions on the other side
         let task = tokio::spawn(async move {
-            Connection::server(t2.into_inner(), &verifier, keychain)
+            Connection::server(t2.into_inner(), Handshake::server(), &verifier, keychain)
                 .await
                 .unwrap()
         });
@@ -781,14 +856,14 @@ mod tests {
             .insert(
                 1234.to_string(),
                 HeapSecretKey::generate(32).unwrap(),
-                oneshot::channel().1,
+                (None, oneshot::channel().1),
             )
             .await;
 
         // Spawn a task to perform the server connection so we don't deadlock while simulating the
         // client actions on the other side
         let task = tokio::spawn(async move {
-            Connection::server(t2.into_inner(), &verifier, keychain)
+            Connection::server(t2.into_inner(), Handshake::server(), &verifier, keychain)
                 .await
                 .unwrap()
         });
@@ -820,13 +895,13 @@ mod tests {
         let key = HeapSecretKey::generate(32).unwrap();
 
         keychain
-            .insert(1234.to_string(), key.clone(), oneshot::channel().1)
+            .insert(1234.to_string(), key.clone(), (None, oneshot::channel().1))
             .await;
 
         // Spawn a task to perform the server connection so we don't deadlock while simulating the
         // client actions on the other side
         let task = tokio::spawn(async move {
-            Connection::server(t2.into_inner(), &verifier, keychain)
+            Connection::server(t2.into_inner(), Handshake::server(), &verifier, keychain)
                 .await
                 .unwrap()
         });
@@ -858,13 +933,13 @@ mod tests {
         let key = HeapSecretKey::generate(32).unwrap();
 
         keychain
-            .insert(1234.to_string(), key.clone(), oneshot::channel().1)
+            .insert(1234.to_string(), key.clone(), (None, oneshot::channel().1))
             .await;
 
         // Spawn a task to perform the server connection so we don't deadlock while simulating the
         // client actions on the other side
         let task = tokio::spawn(async move {
-            Connection::server(t2.into_inner(), &verifier, keychain)
+            Connection::server(t2.into_inner(), Handshake::server(), &verifier, keychain)
                 .await
                 .unwrap()
         });
@@ -902,7 +977,7 @@ mod tests {
         let task = tokio::spawn({
             let keychain = keychain.clone();
             async move {
-                Connection::server(t2.into_inner(), &verifier, keychain)
+                Connection::server(t2.into_inner(), Handshake::server(), &verifier, keychain)
                     .await
                     .unwrap()
             }
@@ -958,7 +1033,7 @@ mod tests {
 
                 let (tx, rx) = oneshot::channel();
                 tx.send(backup).unwrap();
-                rx
+                (Some("alice".to_string()), rx)
             })
             .await;
 
@@ -967,7 +1042,7 @@ mod tests {
         let task = tokio::spawn({
             let keychain = keychain.clone();
             async move {
-                Connection::server(t2.into_inner(), &verifier, keychain)
+                Connection::server(t2.into_inner(), Handshake::server(), &verifier, keychain)
                     .await
                     .unwrap()
             }
@@ -1010,6 +1085,9 @@ mod tests {
         // Validate the connection ids match
         assert_eq!(server.id(), id);
 
+        // Validate the user that originally authenticated is restored
+        assert_eq!(server.username(), Some("alice"));
+
         // Validate the OTP was stored in our keychain
         assert!(
             keychain
@@ -1027,7 +1105,7 @@ mod tests {
 
         // Spawn a task to perform the server connection so we don't deadlock
         let task = tokio::spawn(async move {
-            Connection::server(t2, &verifier, keychain)
+            Connection::server(t2, Handshake::server(), &verifier, keychain)
                 .await
                 .expect("Failed to connect from server")
         });
@@ -1050,7 +1128,7 @@ mod tests {
         Connection<InmemoryTransport>,
         InmemoryTransport,
         Arc<Verifier>,
-        Keychain<oneshot::Receiver<Backup>>,
+        Keychain<(Option<String>, oneshot::Receiver<Backup>)>,
     ) {
         let (t1, t2) = InmemoryTransport::pair(100);
         let verifier = Arc::new(Verifier::none());
@@ -1061,7 +1139,7 @@ mod tests {
             let verifier = Arc::clone(&verifier);
             let keychain = keychain.clone();
             tokio::spawn(async move {
-                Connection::server(t2, &verifier, keychain)
+                Connection::server(t2, Handshake::server(), &verifier, keychain)
                     .await
                     .expect("Failed to connect from server")
             })
@@ -1218,6 +1296,7 @@ mod tests {
             .await
             .into_ok()
             .expect("Invalid id or OTP")
+            .1
             .await
             .expect("Failed to retrieve backup");
 
@@ -1258,6 +1337,7 @@ mod tests {
         let mut connection = Connection::Server {
             id: rand::random(),
             tx: oneshot::channel().0,
+            username: None,
             transport: FramedTransport::pair(100).0,
         };
 
@@ -1273,7 +1353,7 @@ mod tests {
 
         // Spawn a task to perform the server reconnection so we don't deadlock
         let task = tokio::spawn(async move {
-            Connection::server(transport, &verifier, keychain)
+            Connection::server(transport, Handshake::server(), &verifier, keychain)
                 .await
                 .expect("Failed to connect from server")
         });
@@ -13,6 +13,9 @@ pub use host::{Host, HostParseError};
 ///
 /// * `distant://hostname:port` - connect to a distant server
 /// * `ssh://[user@]hostname[:port]` - connect to an SSH server
+/// * `ws://hostname[:port]` - connect to a distant server over WebSocket, such as one behind an
+///   HTTP reverse proxy
+/// * `wss://hostname[:port]` - connect to a distant server over WebSocket wrapped in TLS
 ///
 /// **Note:** Due to the limitations of a URI, an IPv6 address is not supported.
 #[derive(Clone, Debug, Hash, PartialEq, Eq)]
@@ -45,6 +48,18 @@ impl Destination {
             None => false,
         }
     }
+
+    /// Returns the port of the destination, falling back to the well-known port of its scheme
+    /// when it has one and no port was specified (80 for `ws` and 443 for `wss`)
+    pub fn port_or_default(&self) -> Option<u16> {
+        self.port.or(if self.scheme_eq("ws") {
+            Some(80)
+        } else if self.scheme_eq("wss") {
+            Some(443)
+        } else {
+            None
+        })
+    }
 }
 
 impl AsRef<Destination> for &Destination {
@@ -173,4 +188,19 @@ mod tests {
         };
         assert_eq!(destination, "[::1]:12345");
     }
+
+    #[test]
+    fn port_or_default_should_fall_back_to_well_known_port_of_websocket_schemes() {
+        let destination: Destination = "ws://example.com".parse().unwrap();
+        assert_eq!(destination.port_or_default(), Some(80));
+
+        let destination: Destination = "WSS://example.com".parse().unwrap();
+        assert_eq!(destination.port_or_default(), Some(443));
+
+        let destination: Destination = "wss://example.com:8443".parse().unwrap();
+        assert_eq!(destination.port_or_default(), Some(8443));
+
+        let destination: Destination = "ssh://example.com".parse().unwrap();
+        assert_eq!(destination.port_or_default(), None);
+    }
 }
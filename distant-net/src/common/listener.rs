@@ -7,12 +7,21 @@ pub use mapped::*;
 mod mpsc;
 pub use mpsc::*;
 
+mod multi;
+pub use multi::*;
+
 mod oneshot;
 pub use oneshot::*;
 
 mod tcp;
 pub use tcp::*;
 
+mod tls;
+pub use tls::*;
+
+mod websocket;
+pub use websocket::*;
+
 #[cfg(unix)]
 mod unix;
 
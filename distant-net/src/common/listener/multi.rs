@@ -0,0 +1,173 @@
+use super::Listener;
+use async_trait::async_trait;
+use log::*;
+use std::{fmt, io};
+use tokio::{sync::mpsc, task::JoinHandle};
+
+/// Represents a [`Listener`] that accepts connections from several other listeners at once, such
+/// as TCP over IPv4 and IPv6 alongside a Unix socket, so that they can be served by one server.
+///
+/// Each listener is accepted from in the background, where a listener that stops accepting is
+/// logged and dropped while the others continue. [`Listener::accept`] only fails once every
+/// listener has stopped.
+pub struct MultiListener<T> {
+    rx: mpsc::Receiver<T>,
+    tasks: Vec<JoinHandle<()>>,
+}
+
+impl<T> MultiListener<T>
+where
+    T: Send + 'static,
+{
+    /// Creates a new listener that accepts connections from all of `listeners`
+    pub fn new<I>(listeners: I) -> Self
+    where
+        I: IntoIterator<Item = Box<dyn Listener<Output = T>>>,
+    {
+        let (tx, rx) = mpsc::channel(1);
+        let tasks = listeners
+            .into_iter()
+            .map(|mut listener| {
+                let tx = tx.clone();
+                tokio::spawn(async move {
+                    loop {
+                        match listener.accept().await {
+                            Ok(x) => {
+                                if tx.send(x).await.is_err() {
+                                    break;
+                                }
+                            }
+                            Err(x) => {
+                                error!("Listener no longer accepting connections: {x}");
+                                break;
+                            }
+                        }
+                    }
+                })
+            })
+            .collect();
+
+        Self { rx, tasks }
+    }
+}
+
+impl<T> Drop for MultiListener<T> {
+    fn drop(&mut self) {
+        for task in self.tasks.iter() {
+            task.abort();
+        }
+    }
+}
+
+impl<T> fmt::Debug for MultiListener<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MultiListener")
+            .field("listeners", &self.tasks.len())
+            .finish()
+    }
+}
+
+#[async_trait]
+impl<T> Listener for MultiListener<T>
+where
+    T: Send + 'static,
+{
+    type Output = T;
+
+    async fn accept(&mut self) -> io::Result<Self::Output> {
+        self.rx.recv().await.ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::BrokenPipe,
+                "No listener is accepting connections",
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::{MpscListener, OneshotListener};
+    use test_log::test;
+
+    #[test(tokio::test)]
+    async fn accept_should_return_connections_from_every_listener() {
+        let (tx, listener) = MpscListener::channel(1);
+        let mut listener = MultiListener::new([
+            Box::new(listener) as Box<dyn Listener<Output = &str>>,
+            Box::new(OneshotListener::from_value("hello")),
+        ]);
+
+        tx.send("world").await.unwrap();
+
+        let mut values = vec![
+            listener.accept().await.unwrap(),
+            listener.accept().await.unwrap(),
+        ];
+        values.sort_unstable();
+        assert_eq!(values, ["hello", "world"]);
+
+        // A listener that stops does not stop the others
+        tx.send("again").await.unwrap();
+        assert_eq!(listener.accept().await.unwrap(), "again");
+    }
+
+    #[test(tokio::test)]
+    async fn accept_should_fail_once_every_listener_has_stopped() {
+        let mut listener = MultiListener::new([
+            Box::new(OneshotListener::from_value("hello")) as Box<dyn Listener<Output = &str>>,
+            Box::new(OneshotListener::from_value("world")),
+        ]);
+
+        listener.accept().await.unwrap();
+        listener.accept().await.unwrap();
+        assert_eq!(
+            listener.accept().await.unwrap_err().kind(),
+            io::ErrorKind::BrokenPipe
+        );
+    }
+
+    #[cfg(unix)]
+    #[test(tokio::test)]
+    async fn accept_should_support_listeners_of_different_transports() {
+        use crate::common::{
+            MappedListener, TcpListener, TcpTransport, Transport, TransportExt, UnixSocketListener,
+            UnixSocketTransport,
+        };
+        use std::net::{IpAddr, Ipv6Addr, SocketAddr};
+
+        fn boxed<L>(listener: L) -> Box<dyn Listener<Output = Box<dyn Transport>>>
+        where
+            L: Listener + 'static,
+            L::Output: Transport + 'static,
+        {
+            Box::new(MappedListener::new(listener, |transport| {
+                Box::new(transport) as Box<dyn Transport>
+            }))
+        }
+
+        let addr = IpAddr::V6(Ipv6Addr::LOCALHOST);
+        let tcp = TcpListener::bind(addr, 0).await.expect("Failed to bind");
+        let address = SocketAddr::from((addr, tcp.port()));
+
+        let path = tempfile::NamedTempFile::new().unwrap().path().to_path_buf();
+        let unix = UnixSocketListener::bind(&path)
+            .await
+            .expect("Failed to bind");
+
+        let mut listener = MultiListener::new([boxed(tcp), boxed(unix)]);
+
+        let _tcp_conn = TcpTransport::connect(&address).await.unwrap();
+        let conn = listener.accept().await.unwrap();
+        assert_eq!(conn.peer_credentials(), None);
+
+        let unix_conn = UnixSocketTransport::connect(&path).await.unwrap();
+        let conn = listener.accept().await.unwrap();
+        assert!(conn.peer_credentials().is_some());
+
+        conn.write_all(b"hello").await.unwrap();
+        let mut buf = [0; 5];
+        unix_conn.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"hello");
+    }
+}
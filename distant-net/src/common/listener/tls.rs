@@ -0,0 +1,161 @@
+use super::{Listener, TcpListener};
+use crate::common::{PortRange, TcpTransport, TlsServerConfig, TlsTransport};
+use async_trait::async_trait;
+use log::*;
+use std::{fmt, io, net::IpAddr, time::Duration};
+use tokio::{sync::mpsc, task::JoinHandle};
+
+/// Maximum time a client has to complete its TLS handshake before it is dropped
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Represents a [`Listener`] for incoming connections over TCP that are wrapped in TLS.
+///
+/// TLS handshakes happen in the background so that a slow or misbehaving client cannot hold up
+/// other clients, and clients that fail their handshake are logged and dropped rather than
+/// reported by [`Listener::accept`].
+pub struct TlsListener {
+    addr: IpAddr,
+    port: u16,
+    rx: mpsc::Receiver<TlsTransport<TcpTransport>>,
+    task: JoinHandle<()>,
+}
+
+impl TlsListener {
+    /// Creates a new listener by binding to the specified IP address and port in the given port
+    /// range, presenting the identity of `config` to clients
+    pub async fn bind(
+        addr: IpAddr,
+        port: impl Into<PortRange>,
+        config: &TlsServerConfig,
+    ) -> io::Result<Self> {
+        let config = config.build()?;
+        let mut listener = TcpListener::bind(addr, port).await?;
+        let port = listener.port();
+
+        let (tx, rx) = mpsc::channel(1);
+        let task = tokio::spawn(async move {
+            loop {
+                let transport = match listener.accept().await {
+                    Ok(x) => x,
+                    Err(x) => {
+                        error!("TLS listener no longer accepting connections: {x}");
+                        break;
+                    }
+                };
+
+                let tx = tx.clone();
+                let config = config.clone();
+                tokio::spawn(async move {
+                    let peer = format!("{}:{}", transport.ip_addr(), transport.port());
+                    let result = tokio::time::timeout(
+                        HANDSHAKE_TIMEOUT,
+                        TlsTransport::accept_with(transport, config),
+                    )
+                    .await;
+
+                    match result {
+                        Ok(Ok(transport)) => {
+                            let _ = tx.send(transport).await;
+                        }
+                        Ok(Err(x)) => warn!("TLS handshake with {peer} failed: {x}"),
+                        Err(_) => warn!("TLS handshake with {peer} timed out"),
+                    }
+                });
+            }
+        });
+
+        Ok(Self {
+            addr,
+            port,
+            rx,
+            task,
+        })
+    }
+
+    /// Returns the IP address that the listener is bound to
+    pub fn ip_addr(&self) -> IpAddr {
+        self.addr
+    }
+
+    /// Returns the port that the listener is bound to
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+}
+
+impl Drop for TlsListener {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+impl fmt::Debug for TlsListener {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TlsListener")
+            .field("addr", &self.addr)
+            .field("port", &self.port)
+            .finish()
+    }
+}
+
+#[async_trait]
+impl Listener for TlsListener {
+    type Output = TlsTransport<TcpTransport>;
+
+    async fn accept(&mut self) -> io::Result<Self::Output> {
+        self.rx.recv().await.ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::BrokenPipe,
+                "TLS listener no longer accepting connections",
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::{TlsClientConfig, TlsIdentity, TlsVerification, TransportExt};
+    use std::net::{Ipv6Addr, SocketAddr};
+    use test_log::test;
+
+    #[test(tokio::test)]
+    async fn should_skip_clients_that_fail_their_handshake() {
+        let identity = TlsIdentity::generate_self_signed();
+        let fingerprint = identity.fingerprint().unwrap();
+        let addr = IpAddr::V6(Ipv6Addr::LOCALHOST);
+        let mut listener = TlsListener::bind(addr, 0, &TlsServerConfig::new(identity))
+            .await
+            .expect("Failed to bind");
+        let address = SocketAddr::from((addr, listener.port()));
+
+        let task = tokio::spawn(async move {
+            let conn = listener.accept().await?;
+            conn.write_all(b"hello conn").await?;
+
+            let mut buf: [u8; 12] = [0; 12];
+            let _ = conn.read_exact(&mut buf).await?;
+            assert_eq!(&buf, b"hello server");
+
+            io::Result::Ok(())
+        });
+
+        // A client that speaks something other than TLS is dropped without being accepted
+        let conn = TcpTransport::connect(&address).await.unwrap();
+        conn.write_all(b"not a tls handshake").await.unwrap();
+
+        let config = TlsClientConfig::new("localhost", TlsVerification::fingerprint(fingerprint));
+        let conn = TlsTransport::connect(TcpTransport::connect(&address).await.unwrap(), &config)
+            .await
+            .expect("Failed to connect");
+        conn.write_all(b"hello server").await.unwrap();
+
+        let mut buf: [u8; 10] = [0; 10];
+        conn.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"hello conn");
+
+        task.await
+            .unwrap()
+            .expect("Listener task failed unexpectedly");
+    }
+}
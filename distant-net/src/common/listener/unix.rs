@@ -1,9 +1,9 @@
 use super::Listener;
-use crate::common::UnixSocketTransport;
+use crate::common::{PeerCredentials, UnixSocketTransport};
 use async_trait::async_trait;
 use std::{
     fmt, io,
-    os::unix::fs::PermissionsExt,
+    os::unix::fs::{MetadataExt, PermissionsExt},
     path::{Path, PathBuf},
 };
 use tokio::net::{UnixListener, UnixStream};
@@ -62,6 +62,20 @@ impl UnixSocketListener {
         &self.path
     }
 
+    /// Returns the credentials of the owner of the socket file, which are those of the current
+    /// process unless the file has been changed since binding. Useful as the baseline of peers
+    /// to allow when authenticating with [`PeerCredentialsAuthenticationMethod`].
+    ///
+    /// [`PeerCredentialsAuthenticationMethod`]: crate::common::authentication::PeerCredentialsAuthenticationMethod
+    pub fn owner_credentials(&self) -> io::Result<PeerCredentials> {
+        let metadata = std::fs::metadata(&self.path)?;
+        Ok(PeerCredentials {
+            uid: metadata.uid(),
+            gid: metadata.gid(),
+            pid: Some(std::process::id() as i32),
+        })
+    }
+
     /// Returns the default unix socket file permissions as an octal (e.g. `0o600`)
     pub const fn default_unix_socket_file_permissions() -> u32 {
         0o600
@@ -95,7 +109,7 @@ impl Listener for UnixSocketListener {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::common::TransportExt;
+    use crate::common::{Transport, TransportExt};
     use tempfile::NamedTempFile;
     use test_log::test;
     use tokio::{sync::oneshot, task::JoinHandle};
@@ -132,6 +146,34 @@ mod tests {
             .expect_err("Unexpectedly succeeded in binding to same socket");
     }
 
+    #[test(tokio::test)]
+    async fn accepted_transport_should_report_credentials_of_connecting_process() {
+        // Generate a socket path and delete the file after so there is nothing there
+        let path = NamedTempFile::new()
+            .expect("Failed to create socket file")
+            .path()
+            .to_path_buf();
+
+        let mut listener = UnixSocketListener::bind(&path)
+            .await
+            .expect("Failed to bind");
+        let owner = listener
+            .owner_credentials()
+            .expect("Failed to get owner credentials");
+
+        let _conn = UnixSocketTransport::connect(&path)
+            .await
+            .expect("Failed to connect");
+        let transport = listener.accept().await.expect("Failed to accept");
+
+        // Both sides of the connection are this process
+        let credentials = transport
+            .peer_credentials()
+            .expect("Missing peer credentials");
+        assert_eq!(credentials.uid, owner.uid);
+        assert_eq!(credentials.pid, owner.pid);
+    }
+
     #[test(tokio::test)]
     async fn should_be_able_to_receive_connections_and_read_and_write_data_with_them() {
         let (tx, rx) = oneshot::channel();
@@ -0,0 +1,146 @@
+use super::Listener;
+use crate::common::{Transport, WebSocketTransport};
+use async_trait::async_trait;
+use log::*;
+use std::{fmt, io, time::Duration};
+use tokio::{sync::mpsc, task::JoinHandle};
+
+/// Maximum time a client has to complete its WebSocket handshake before it is dropped
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Represents a [`Listener`] for incoming WebSocket connections over the transports accepted by
+/// another listener, such as a [`TcpListener`] for `ws://` or a [`TlsListener`] for `wss://`.
+///
+/// WebSocket handshakes happen in the background so that a slow or misbehaving client cannot hold
+/// up other clients, and clients that fail their handshake are logged and dropped rather than
+/// reported by [`Listener::accept`].
+///
+/// [`TcpListener`]: super::TcpListener
+/// [`TlsListener`]: super::TlsListener
+pub struct WebSocketListener<T> {
+    rx: mpsc::Receiver<WebSocketTransport<T>>,
+    task: JoinHandle<()>,
+}
+
+impl<T> WebSocketListener<T>
+where
+    T: Transport + 'static,
+{
+    /// Creates a new listener that performs the server side of a WebSocket handshake with every
+    /// transport accepted by `listener`
+    pub fn new<L>(mut listener: L) -> Self
+    where
+        L: Listener<Output = T> + 'static,
+    {
+        let (tx, rx) = mpsc::channel(1);
+        let task = tokio::spawn(async move {
+            loop {
+                let transport = match listener.accept().await {
+                    Ok(x) => x,
+                    Err(x) => {
+                        error!("WebSocket listener no longer accepting connections: {x}");
+                        break;
+                    }
+                };
+
+                let tx = tx.clone();
+                tokio::spawn(async move {
+                    let peer = format!("{transport:?}");
+                    let result = tokio::time::timeout(
+                        HANDSHAKE_TIMEOUT,
+                        WebSocketTransport::accept(transport),
+                    )
+                    .await;
+
+                    match result {
+                        Ok(Ok(transport)) => {
+                            let _ = tx.send(transport).await;
+                        }
+                        Ok(Err(x)) => warn!("WebSocket handshake with {peer} failed: {x}"),
+                        Err(_) => warn!("WebSocket handshake with {peer} timed out"),
+                    }
+                });
+            }
+        });
+
+        Self { rx, task }
+    }
+}
+
+impl<T> Drop for WebSocketListener<T> {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+impl<T> fmt::Debug for WebSocketListener<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WebSocketListener").finish()
+    }
+}
+
+#[async_trait]
+impl<T> Listener for WebSocketListener<T>
+where
+    T: Transport + 'static,
+{
+    type Output = WebSocketTransport<T>;
+
+    async fn accept(&mut self) -> io::Result<Self::Output> {
+        self.rx.recv().await.ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::BrokenPipe,
+                "WebSocket listener no longer accepting connections",
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::{TcpListener, TcpTransport, TransportExt};
+    use std::net::{IpAddr, Ipv6Addr, SocketAddr};
+    use test_log::test;
+
+    #[test(tokio::test)]
+    async fn should_skip_clients_that_fail_their_handshake() {
+        let addr = IpAddr::V6(Ipv6Addr::LOCALHOST);
+        let listener = TcpListener::bind(addr, 0).await.expect("Failed to bind");
+        let address = SocketAddr::from((addr, listener.port()));
+        let mut listener = WebSocketListener::new(listener);
+
+        let task = tokio::spawn(async move {
+            let conn = listener.accept().await?;
+            conn.write_all(b"hello conn").await?;
+
+            let mut buf: [u8; 12] = [0; 12];
+            let _ = conn.read_exact(&mut buf).await?;
+            assert_eq!(&buf, b"hello server");
+
+            io::Result::Ok(())
+        });
+
+        // A client that speaks something other than WebSocket is dropped without being accepted
+        let conn = TcpTransport::connect(&address).await.unwrap();
+        conn.write_all(b"GET / HTTP/1.1\r\nHost: localhost\r\n\r\n")
+            .await
+            .unwrap();
+
+        let conn = WebSocketTransport::connect(
+            TcpTransport::connect(&address).await.unwrap(),
+            format!("ws://{address}/"),
+        )
+        .await
+        .expect("Failed to connect");
+        conn.write_all(b"hello server").await.unwrap();
+
+        let mut buf: [u8; 10] = [0; 10];
+        conn.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"hello conn");
+
+        task.await
+            .unwrap()
+            .expect("Listener task failed unexpectedly");
+    }
+}
@@ -7,9 +7,18 @@ pub use framed::*;
 mod inmemory;
 pub use inmemory::*;
 
+mod stdio;
+pub use stdio::*;
+
 mod tcp;
 pub use tcp::*;
 
+mod tls;
+pub use tls::*;
+
+mod websocket;
+pub use websocket::*;
+
 #[cfg(test)]
 mod test;
 
@@ -33,6 +42,20 @@ pub use tokio::io::{Interest, Ready};
 /// Duration to wait after WouldBlock received during looping operations like `read_exact`.
 const SLEEP_DURATION: Duration = Duration::from_millis(1);
 
+/// Identity of the process on the other side of a local connection as reported by the operating
+/// system, which cannot be forged by the peer
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct PeerCredentials {
+    /// Effective user id of the peer process
+    pub uid: u32,
+
+    /// Effective group id of the peer process
+    pub gid: u32,
+
+    /// Id of the peer process, if available on the platform
+    pub pid: Option<i32>,
+}
+
 /// Interface representing a connection that is reconnectable.
 #[async_trait]
 pub trait Reconnectable {
@@ -63,6 +86,12 @@ pub trait Transport: Reconnectable + fmt::Debug + Send + Sync {
     /// Waits for the transport to be ready based on the given interest, returning the ready
     /// status.
     async fn ready(&self, interest: Interest) -> io::Result<Ready>;
+
+    /// Returns the credentials of the process on the other side of the transport, if the
+    /// transport is local and the operating system reports them. Defaults to `None`.
+    fn peer_credentials(&self) -> Option<PeerCredentials> {
+        None
+    }
 }
 
 #[async_trait]
@@ -78,6 +107,10 @@ impl Transport for Box<dyn Transport> {
     async fn ready(&self, interest: Interest) -> io::Result<Ready> {
         Transport::ready(AsRef::as_ref(self), interest).await
     }
+
+    fn peer_credentials(&self) -> Option<PeerCredentials> {
+        Transport::peer_credentials(AsRef::as_ref(self))
+    }
 }
 
 #[async_trait]
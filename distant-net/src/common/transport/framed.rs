@@ -1,5 +1,5 @@
 use super::{InmemoryTransport, Interest, Ready, Reconnectable, Transport};
-use crate::common::utils;
+use crate::common::{utils, Version, PROTOCOL_VERSION};
 use async_trait::async_trait;
 use bytes::{Buf, BytesMut};
 use log::*;
@@ -11,12 +11,15 @@ mod codec;
 mod exchange;
 mod frame;
 mod handshake;
+mod rekey;
 
 pub use backup::*;
 pub use codec::*;
 pub use exchange::*;
 pub use frame::*;
 pub use handshake::*;
+pub use rekey::RekeyConfig;
+use rekey::{Rekey, RekeyMessage, RekeyState, REKEY_FEATURE};
 
 /// Size of the read buffer when reading bytes to construct a frame
 const READ_BUF_SIZE: usize = 8 * 1024;
@@ -24,6 +27,22 @@ const READ_BUF_SIZE: usize = 8 * 1024;
 /// Duration to wait after WouldBlock received during looping operations like `read_frame`
 const SLEEP_DURATION: Duration = Duration::from_millis(1);
 
+/// Optional features of the protocol supported by this transport, reported to the other side
+/// during the handshake and used when both sides support them
+const SUPPORTED_FEATURES: &[&str] = &[REKEY_FEATURE];
+
+/// Public key and salt sent to the other side during a key exchange
+#[derive(Debug, Serialize, Deserialize)]
+struct KeyExchangeData {
+    /// Bytes of the public key
+    #[serde(with = "serde_bytes")]
+    public_key: PublicKeyBytes,
+
+    /// Randomly generated salt
+    #[serde(with = "serde_bytes")]
+    salt: Salt,
+}
+
 /// Represents a wrapper around a [`Transport`] that reads and writes using frames defined by a
 /// [`Codec`].
 ///
@@ -44,6 +63,21 @@ pub struct FramedTransport<T> {
 
     /// Stores outgoing frames in case of transmission issues
     pub backup: Backup,
+
+    /// Key exchange algorithm negotiated during the last successful handshake
+    key_exchange_type: Option<KeyExchangeType>,
+
+    /// Thresholds at which this side starts rekeying
+    rekey_config: RekeyConfig,
+
+    /// Rekeying state, present when the last handshake agreed on encryption and rekeying
+    rekey: Option<Rekey>,
+
+    /// Protocol version reported by the other side during the last successful handshake
+    peer_version: Option<Version>,
+
+    /// Features reported by the other side during the last successful handshake
+    peer_features: Vec<String>,
 }
 
 impl<T> FramedTransport<T> {
@@ -54,6 +88,11 @@ impl<T> FramedTransport<T> {
             incoming: BytesMut::with_capacity(READ_BUF_SIZE * 2),
             outgoing: BytesMut::with_capacity(READ_BUF_SIZE * 2),
             backup: Backup::new(),
+            key_exchange_type: None,
+            rekey_config: RekeyConfig::default(),
+            rekey: None,
+            peer_version: None,
+            peer_features: Vec::new(),
         }
     }
 
@@ -93,6 +132,42 @@ impl<T> FramedTransport<T> {
         self.codec.as_mut()
     }
 
+    /// Returns the key exchange algorithm negotiated during the last successful handshake, or
+    /// `None` if no handshake has happened or it did not involve encryption.
+    pub fn key_exchange_type(&self) -> Option<KeyExchangeType> {
+        self.key_exchange_type
+    }
+
+    /// Returns the protocol version reported by the other side during the last successful
+    /// handshake, or `None` if no handshake has happened or the other side is too old to report
+    /// its version.
+    pub fn peer_version(&self) -> Option<&Version> {
+        self.peer_version.as_ref()
+    }
+
+    /// Returns the optional features of the protocol reported by the other side during the last
+    /// successful handshake.
+    pub fn peer_features(&self) -> &[String] {
+        &self.peer_features
+    }
+
+    /// Returns the thresholds at which this transport starts rekeying.
+    pub fn rekey_config(&self) -> RekeyConfig {
+        self.rekey_config
+    }
+
+    /// Sets the thresholds at which this transport starts rekeying, which only happens when the
+    /// last handshake agreed on encryption and both sides support rekeying.
+    pub fn set_rekey_config(&mut self, config: RekeyConfig) {
+        self.rekey_config = config;
+    }
+
+    /// Returns true if the last handshake agreed that either side can replace the key used for
+    /// encryption by performing a new key exchange in-band.
+    pub fn is_rekey_supported(&self) -> bool {
+        self.rekey.is_some()
+    }
+
     /// Clears the internal transport buffers.
     pub fn clear(&mut self) {
         self.incoming.clear();
@@ -134,6 +209,11 @@ impl<T: Transport + 'static> FramedTransport<T> {
             incoming: self.incoming,
             outgoing: self.outgoing,
             backup: self.backup,
+            key_exchange_type: self.key_exchange_type,
+            rekey_config: self.rekey_config,
+            rekey: self.rekey,
+            peer_version: self.peer_version,
+            peer_features: self.peer_features,
         }
     }
 }
@@ -198,6 +278,10 @@ impl<T: Transport> FramedTransport<T> {
     ///
     /// [`ErrorKind::WouldBlock`]: io::ErrorKind::WouldBlock
     pub fn try_flush(&mut self) -> io::Result<usize> {
+        // Queue the start of a rekey ahead of flushing if one is due, which is checked here as
+        // flushing happens regularly even when there is nothing new to write
+        self.start_rekey_if_due()?;
+
         let mut bytes_written = 0;
 
         // Continue to send from the outgoing buffer until we either finish or fail
@@ -251,16 +335,22 @@ impl<T: Transport> FramedTransport<T> {
         // Attempt to read a frame, returning the decoded frame if we get one, returning any error
         // that is encountered from reading frames or failing to decode, or otherwise doing nothing
         // and continuing forward.
+        //
+        // NOTE: Frames used to rekey are handled here and skipped, as they are not meant for the
+        //       caller and are not tracked by the backup.
         macro_rules! read_next_frame {
             () => {{
-                match Frame::read(&mut self.incoming) {
-                    None => (),
-                    Some(frame) => {
-                        if frame.is_nonempty() {
-                            self.backup.increment_received_cnt();
-                        }
-                        return Ok(Some(self.codec.decode(frame)?.into_owned()));
+                while let Some(frame) = Frame::read(&mut self.incoming) {
+                    let is_nonempty = frame.is_nonempty();
+                    let frame = self.decode_frame(frame)?;
+                    if self.try_handle_rekey_frame(&frame)? {
+                        continue;
+                    }
+
+                    if is_nonempty {
+                        self.backup.increment_received_cnt();
                     }
+                    return Ok(Some(frame));
                 }
             }};
         }
@@ -362,9 +452,11 @@ impl<T: Transport> FramedTransport<T> {
             .map_err(|x| io::Error::new(io::ErrorKind::InvalidInput, x))?;
 
         // Encode the frame and store it in our outgoing queue
-        self.codec
-            .encode(frame.as_borrowed())?
-            .write(&mut self.outgoing);
+        let encoded = self.codec.encode(frame.as_borrowed())?;
+        if let Some(rekey) = self.rekey.as_mut() {
+            rekey.bytes += encoded.len() as u64;
+        }
+        encoded.write(&mut self.outgoing);
 
         // Update tracking stats and more of backup if frame is nonempty
         if frame.is_nonempty() {
@@ -431,6 +523,127 @@ impl<T: Transport> FramedTransport<T> {
         self.write_frame(data).await
     }
 
+    /// Decodes an incoming `frame`, using the codec of the previous key if the other side has not
+    /// yet finished rekeying.
+    fn decode_frame(&mut self, frame: OwnedFrame) -> io::Result<OwnedFrame> {
+        let len = frame.len() as u64;
+        let frame = match self.rekey.as_mut() {
+            Some(rekey) => {
+                rekey.bytes += len;
+                match &mut rekey.state {
+                    RekeyState::AwaitingDone(codec) => codec.decode(frame)?,
+                    _ => self.codec.decode(frame)?,
+                }
+            }
+            None => self.codec.decode(frame)?,
+        };
+
+        Ok(frame.into_owned())
+    }
+
+    /// Encodes a rekey `message` with the current codec and queues it to be sent, bypassing the
+    /// backup as rekey messages are never replayed.
+    fn queue_rekey_message(&mut self, message: &RekeyMessage) -> io::Result<()> {
+        let bytes = message.to_frame_bytes()?;
+        self.codec
+            .encode(Frame::new(&bytes))?
+            .write(&mut self.outgoing);
+        Ok(())
+    }
+
+    /// Starts a rekey by queueing our half of a new key exchange if the thresholds have been
+    /// reached and no rekey is in progress.
+    fn start_rekey_if_due(&mut self) -> io::Result<()> {
+        let rekey = match self.rekey.as_mut() {
+            Some(rekey) if rekey.is_due(&self.rekey_config) => rekey,
+            _ => return Ok(()),
+        };
+
+        debug!("Starting {} rekey", rekey.key_exchange_type);
+        let exchange = KeyExchange::new(rekey.key_exchange_type)?;
+        let message = RekeyMessage::Init(KeyExchangeData {
+            public_key: exchange.pk_bytes(),
+            salt: *exchange.salt(),
+        });
+        rekey.state = RekeyState::AwaitingReply(exchange);
+        self.queue_rekey_message(&message)
+    }
+
+    /// Processes a decoded `frame` if it is part of a rekey, returning true if it was. Any
+    /// messages sent in response are queued and flushed alongside the next frame.
+    fn try_handle_rekey_frame(&mut self, frame: &OwnedFrame) -> io::Result<bool> {
+        let rekey = match self.rekey.as_mut() {
+            Some(rekey) => rekey,
+            None => return Ok(false),
+        };
+
+        let message = match RekeyMessage::from_frame_bytes(frame.as_item()) {
+            Some(message) => message?,
+            None => return Ok(false),
+        };
+
+        match (
+            message,
+            std::mem::replace(&mut rekey.state, RekeyState::Idle),
+        ) {
+            // Both sides started a rekey at the same time, so the side with the smaller public
+            // key continues as the initiator and the other side responds instead
+            (RekeyMessage::Init(data), RekeyState::AwaitingReply(exchange))
+                if exchange.pk_bytes().as_bytes() < data.public_key.as_bytes() =>
+            {
+                trace!("Ignoring rekey started by other side in favor of our own");
+                rekey.state = RekeyState::AwaitingReply(exchange);
+            }
+
+            (RekeyMessage::Init(data), RekeyState::Idle | RekeyState::AwaitingReply(_)) => {
+                trace!("Responding to rekey started by other side");
+                let exchange = KeyExchange::new(rekey.key_exchange_type)?;
+                let reply = RekeyMessage::Reply(KeyExchangeData {
+                    public_key: exchange.pk_bytes(),
+                    salt: *exchange.salt(),
+                });
+                let key = exchange.derive_shared_secret(data.public_key, data.salt)?;
+                let codec = rekey.new_codec(key.unprotected_as_bytes())?;
+
+                // Send our reply using the old key, and then use the new key for everything we
+                // send afterwards while still receiving with the old key until the other side
+                // reports that it is done
+                self.queue_rekey_message(&reply)?;
+                let old_codec = std::mem::replace(&mut self.codec, codec);
+                if let Some(rekey) = self.rekey.as_mut() {
+                    rekey.state = RekeyState::AwaitingDone(old_codec);
+                }
+            }
+
+            (RekeyMessage::Reply(data), RekeyState::AwaitingReply(exchange)) => {
+                let key = exchange.derive_shared_secret(data.public_key, data.salt)?;
+                let codec = rekey.new_codec(key.unprotected_as_bytes())?;
+                rekey.finish();
+
+                // Everything the other side sent after its reply uses the new key, so report
+                // that we are done using the old key and then use the new key in both directions
+                self.queue_rekey_message(&RekeyMessage::Done)?;
+                self.codec = codec;
+                debug!("Finished rekey");
+            }
+
+            (RekeyMessage::Done, RekeyState::AwaitingDone(_)) => {
+                rekey.finish();
+                debug!("Finished rekey");
+            }
+
+            (message, state) => {
+                rekey.state = state;
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("Unexpected rekey message: {message:?}"),
+                ));
+            }
+        }
+
+        Ok(true)
+    }
+
     /// Executes the async function while the [`Backup`] of this transport is frozen.
     pub async fn do_frozen<F, X>(&mut self, mut f: F) -> io::Result<()>
     where
@@ -644,6 +857,7 @@ impl<T: Transport> FramedTransport<T> {
         //
         // NOTE: We grab the old codec in case we encounter an error and need to reset it
         let old_codec = std::mem::replace(&mut self.codec, Box::new(PlainCodec::new()));
+        let old_rekey = self.rekey.take();
         self.clear();
 
         // Swap out our backup so we don't mutate it from synchronization efforts
@@ -653,13 +867,18 @@ impl<T: Transport> FramedTransport<T> {
         // reset the codec back to what it was prior to attempting the handshake and clear the
         // internal buffers as they may be corrupt.
         match self.handshake_impl(handshake).await {
-            Ok(codec) => {
-                self.set_codec(codec);
+            Ok(outcome) => {
+                self.set_codec(outcome.codec);
                 self.backup = backup;
+                self.key_exchange_type = outcome.key_exchange_type;
+                self.rekey = outcome.rekey;
+                self.peer_version = outcome.peer_version;
+                self.peer_features = outcome.peer_features;
                 Ok(())
             }
             Err(x) => {
                 self.set_codec(old_codec);
+                self.rekey = old_rekey;
                 self.clear();
                 self.backup = backup;
                 Err(x)
@@ -667,18 +886,48 @@ impl<T: Transport> FramedTransport<T> {
         }
     }
 
-    async fn handshake_impl(&mut self, handshake: Handshake) -> io::Result<BoxedCodec> {
+    async fn handshake_impl(&mut self, handshake: Handshake) -> io::Result<HandshakeOutcome> {
         #[derive(Debug, Serialize, Deserialize)]
         struct Choice {
             compression_level: Option<CompressionLevel>,
             compression_type: Option<CompressionType>,
             encryption_type: Option<EncryptionType>,
+
+            /// Missing when talking to older peers, which only support P-256
+            #[serde(default)]
+            key_exchange_type: Option<KeyExchangeType>,
+
+            /// Protocol version of the client, missing when talking to older peers
+            #[serde(default)]
+            version: Option<Version>,
+
+            /// Optional features supported by the client
+            #[serde(default)]
+            features: Vec<String>,
         }
 
         #[derive(Debug, Serialize, Deserialize)]
         struct Options {
             compression_types: Vec<CompressionType>,
             encryption_types: Vec<EncryptionType>,
+
+            /// Missing when talking to older peers, which only support P-256
+            #[serde(default = "Options::default_key_exchange_types")]
+            key_exchange_types: Vec<KeyExchangeType>,
+
+            /// Protocol version of the server, missing when talking to older peers
+            #[serde(default)]
+            version: Option<Version>,
+
+            /// Optional features supported by the server
+            #[serde(default)]
+            features: Vec<String>,
+        }
+
+        impl Options {
+            fn default_key_exchange_types() -> Vec<KeyExchangeType> {
+                vec![KeyExchangeType::P256]
+            }
         }
 
         // Define a label to distinguish log output for client and server
@@ -688,21 +937,34 @@ impl<T: Transport> FramedTransport<T> {
             "Handshake | Server"
         };
 
-        // Determine compression and encryption to apply to framed transport
-        let choice = match handshake {
+        // Each side uses its own nonces when encrypting, as both share the same key
+        let role = if handshake.is_client() {
+            EncryptionRole::Client
+        } else {
+            EncryptionRole::Server
+        };
+
+        // Determine compression and encryption to apply to framed transport, along with the
+        // version and features reported by the other side
+        let (choice, peer_version, peer_features) = match handshake {
             Handshake::Client {
                 preferred_compression_type,
                 preferred_compression_level,
                 preferred_encryption_type,
+                preferred_key_exchange_type,
             } => {
                 // Receive options from the server and pick one
                 debug!("[{log_label}] Waiting on options");
-                let options = self.read_frame_as::<Options>().await?.ok_or_else(|| {
-                    io::Error::new(
-                        io::ErrorKind::UnexpectedEof,
-                        "Transport closed early while waiting for options",
-                    )
-                })?;
+                let options = self
+                    .read_frame_as::<Options>()
+                    .await
+                    .map_err(|x| invalid_handshake_frame_error("options", "server", x))?
+                    .ok_or_else(|| {
+                        io::Error::new(
+                            io::ErrorKind::UnexpectedEof,
+                            "Transport closed early while waiting for options",
+                        )
+                    })?;
 
                 // Choose a compression and encryption option from the options
                 debug!("[{log_label}] Selecting from options: {options:?}");
@@ -726,21 +988,42 @@ impl<T: Transport> FramedTransport<T> {
                                 .find(|ty| !ty.is_unknown())
                                 .copied()
                         }),
+
+                    // Use preferred key exchange, otherwise pick first non-unknown key exchange
+                    // type that is available instead
+                    key_exchange_type: preferred_key_exchange_type
+                        .filter(|ty| options.key_exchange_types.contains(ty))
+                        .or_else(|| {
+                            options
+                                .key_exchange_types
+                                .iter()
+                                .find(|ty| !ty.is_unknown())
+                                .copied()
+                        }),
+
+                    version: Some(PROTOCOL_VERSION),
+                    features: supported_features(),
                 };
 
-                // Report back to the server the choice
+                // Report back to the server the choice, which also reports our version so that
+                // the server can fail in the same way if our versions are incompatible
                 debug!("[{log_label}] Reporting choice: {choice:?}");
                 self.write_frame_for(&choice).await?;
 
-                choice
+                check_peer_version("server", options.version.as_ref())?;
+                (choice, options.version, options.features)
             }
             Handshake::Server {
                 compression_types,
                 encryption_types,
+                key_exchange_types,
             } => {
                 let options = Options {
                     compression_types: compression_types.to_vec(),
                     encryption_types: encryption_types.to_vec(),
+                    key_exchange_types: key_exchange_types.to_vec(),
+                    version: Some(PROTOCOL_VERSION),
+                    features: supported_features(),
                 };
 
                 // Send options to the client
@@ -749,12 +1032,51 @@ impl<T: Transport> FramedTransport<T> {
 
                 // Get client's response with selected compression and encryption
                 debug!("[{log_label}] Waiting on choice");
-                self.read_frame_as::<Choice>().await?.ok_or_else(|| {
-                    io::Error::new(
-                        io::ErrorKind::UnexpectedEof,
-                        "Transport closed early while waiting for choice",
-                    )
-                })?
+                let mut choice = self
+                    .read_frame_as::<Choice>()
+                    .await
+                    .map_err(|x| invalid_handshake_frame_error("choice", "client", x))?
+                    .ok_or_else(|| {
+                        io::Error::new(
+                            io::ErrorKind::UnexpectedEof,
+                            "Transport closed early while waiting for choice",
+                        )
+                    })?;
+                check_peer_version("client", choice.version.as_ref())?;
+
+                // Ensure the client did not pick an encryption type that we never offered, which
+                // includes skipping encryption when we offered some
+                match choice.encryption_type {
+                    Some(ty) if !options.encryption_types.contains(&ty) => {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidInput,
+                            format!("Encryption type {ty} was not offered"),
+                        ));
+                    }
+                    None if !options.encryption_types.is_empty() => {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidInput,
+                            "Encryption is required, but none was chosen",
+                        ));
+                    }
+                    _ => (),
+                }
+
+                // Ensure the client did not pick a key exchange that we never offered, treating
+                // a missing choice as an older client that only supports P-256
+                if choice.encryption_type.is_some() {
+                    let ty = choice.key_exchange_type.unwrap_or_default();
+                    if !options.key_exchange_types.contains(&ty) {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidInput,
+                            format!("Key exchange type {ty} was not offered"),
+                        ));
+                    }
+                }
+
+                let version = choice.version.take();
+                let features = std::mem::take(&mut choice.features);
+                (choice, version, features)
             }
         };
 
@@ -778,58 +1100,68 @@ impl<T: Transport> FramedTransport<T> {
                 ))
             }
             Some(ty) => {
-                let key = self.exchange_keys_impl(log_label).await?;
-                Some(ty.new_codec(key.unprotected_as_bytes())?)
+                let key_exchange_type = choice.key_exchange_type.unwrap_or_default();
+                let key = self
+                    .exchange_keys_impl(key_exchange_type, log_label)
+                    .await?;
+                Some((
+                    ty.new_codec(key.unprotected_as_bytes(), role)?,
+                    key_exchange_type,
+                ))
             }
             None => None,
         };
+        let key_exchange_type = encryption_codec.as_ref().map(|(_, ty)| *ty);
+        let encryption_codec = encryption_codec.map(|(codec, _)| codec);
+
+        // Rekeying replaces the key used for encryption, so it only applies when encrypting
+        let rekey_supported = peer_features.iter().any(|x| x == REKEY_FEATURE);
+        let rekey = match (rekey_supported, choice.encryption_type, key_exchange_type) {
+            (true, Some(encryption_type), Some(key_exchange_type)) => Some(Rekey::new(
+                encryption_type,
+                choice.compression_type.map(|ty| (ty, compression_level)),
+                key_exchange_type,
+                role,
+            )),
+            _ => None,
+        };
 
         // Bundle our compression and encryption codecs into a single, chained codec
         trace!("[{log_label}] Bundling codecs");
-        let codec: BoxedCodec = match (compression_codec, encryption_codec) {
-            // If we have both encryption and compression, do the encryption first and then
-            // compress in order to get smallest result
-            (Some(c), Some(e)) => Box::new(ChainCodec::new(e, c)),
-
-            // If we just have compression, pass along the compression codec
-            (Some(c), None) => Box::new(c),
-
-            // If we just have encryption, pass along the encryption codec
-            (None, Some(e)) => Box::new(e),
+        let codec = bundle_codecs(compression_codec, encryption_codec);
 
-            // If we have neither compression nor encryption, use a plaintext codec
-            (None, None) => Box::new(PlainCodec::new()),
-        };
-
-        Ok(codec)
+        Ok(HandshakeOutcome {
+            codec,
+            key_exchange_type,
+            rekey,
+            peer_version,
+            peer_features,
+        })
     }
 
     /// Places the transport into key-exchange mode where it attempts to derive a shared secret key
     /// with the other transport.
+    ///
+    /// Uses the key exchange algorithm negotiated during the last handshake, or P-256 if there
+    /// was none, meaning that both sides need to have performed the same handshake beforehand.
     pub async fn exchange_keys(&mut self) -> io::Result<SecretKey32> {
-        self.exchange_keys_impl("").await
+        let ty = self.key_exchange_type.unwrap_or_default();
+        self.exchange_keys_impl(ty, "").await
     }
 
-    async fn exchange_keys_impl(&mut self, label: &str) -> io::Result<SecretKey32> {
+    async fn exchange_keys_impl(
+        &mut self,
+        ty: KeyExchangeType,
+        label: &str,
+    ) -> io::Result<SecretKey32> {
         let log_label = if label.is_empty() {
             String::new()
         } else {
             format!("[{label}] ")
         };
 
-        #[derive(Serialize, Deserialize)]
-        struct KeyExchangeData {
-            /// Bytes of the public key
-            #[serde(with = "serde_bytes")]
-            public_key: PublicKeyBytes,
-
-            /// Randomly generated salt
-            #[serde(with = "serde_bytes")]
-            salt: Salt,
-        }
-
-        debug!("{log_label}Exchanging public key and salt");
-        let exchange = KeyExchange::default();
+        debug!("{log_label}Exchanging {ty} public key and salt");
+        let exchange = KeyExchange::new(ty)?;
         self.write_frame_for(&KeyExchangeData {
             public_key: exchange.pk_bytes(),
             salt: *exchange.salt(),
@@ -857,6 +1189,80 @@ impl<T: Transport> FramedTransport<T> {
     }
 }
 
+/// Result of a successful handshake to apply to a transport
+struct HandshakeOutcome {
+    codec: BoxedCodec,
+    key_exchange_type: Option<KeyExchangeType>,
+    rekey: Option<Rekey>,
+    peer_version: Option<Version>,
+    peer_features: Vec<String>,
+}
+
+/// Returns the features to report to the other side during a handshake
+fn supported_features() -> Vec<String> {
+    SUPPORTED_FEATURES.iter().map(ToString::to_string).collect()
+}
+
+/// Fails if the protocol version reported by the `peer` during a handshake is incompatible with
+/// our own, where a missing version means that the peer predates protocol versions and is
+/// therefore incompatible as well
+fn check_peer_version(peer: &str, version: Option<&Version>) -> io::Result<()> {
+    match version {
+        Some(version) if !PROTOCOL_VERSION.is_compatible_with(version) => Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!(
+                "The {peer} speaks protocol version {version}, which is incompatible with \
+                 protocol version {PROTOCOL_VERSION} spoken here"
+            ),
+        )),
+        Some(_) => Ok(()),
+        None => Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!(
+                "The {peer} did not report a protocol version, meaning that it is older than \
+                 0.20 and incompatible with protocol version {PROTOCOL_VERSION} spoken here"
+            ),
+        )),
+    }
+}
+
+/// Clarifies an error from reading the `frame` sent by the `peer` during a handshake, as failing
+/// to parse it most likely means that the peer is running an incompatible version
+fn invalid_handshake_frame_error(frame: &str, peer: &str, x: io::Error) -> io::Error {
+    if x.kind() == io::ErrorKind::InvalidData {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "Received invalid handshake {frame} from the {peer}, which may be running an \
+                 incompatible version: {x}"
+            ),
+        )
+    } else {
+        x
+    }
+}
+
+/// Bundles compression and encryption codecs into a single, chained codec
+fn bundle_codecs(
+    compression_codec: Option<CompressionCodec>,
+    encryption_codec: Option<EncryptionCodec>,
+) -> BoxedCodec {
+    match (compression_codec, encryption_codec) {
+        // If we have both encryption and compression, do the encryption first and then
+        // compress in order to get smallest result
+        (Some(c), Some(e)) => Box::new(ChainCodec::new(e, c)),
+
+        // If we just have compression, pass along the compression codec
+        (Some(c), None) => Box::new(c),
+
+        // If we just have encryption, pass along the encryption codec
+        (None, Some(e)) => Box::new(e),
+
+        // If we have neither compression nor encryption, use a plaintext codec
+        (None, None) => Box::new(PlainCodec::new()),
+    }
+}
+
 #[async_trait]
 impl<T> Reconnectable for FramedTransport<T>
 where
@@ -1931,6 +2337,414 @@ mod tests {
         task.await.unwrap();
     }
 
+    #[test(tokio::test)]
+    async fn handshake_should_support_client_preferring_zstd_compression() {
+        let (mut t1, mut t2) = FramedTransport::test_pair(100);
+
+        let task = tokio::spawn(async move {
+            t2.server_handshake().await.unwrap();
+
+            // Receive one frame and echo it back
+            let frame = t2.read_frame().await.unwrap().unwrap();
+            t2.write_frame(frame).await.unwrap();
+        });
+
+        t1.handshake(Handshake::Client {
+            preferred_compression_type: Some(CompressionType::Zstd),
+            preferred_compression_level: Some(CompressionLevel::Three),
+            preferred_encryption_type: Some(EncryptionType::XChaCha20Poly1305),
+            preferred_key_exchange_type: Some(KeyExchangeType::X25519),
+        })
+        .await
+        .unwrap();
+
+        // Verify that the transports can communicate using the compressed codec
+        t1.write_frame(b"hello world").await.unwrap();
+        assert_eq!(t1.read_frame().await.unwrap().unwrap(), b"hello world");
+
+        task.await.unwrap();
+    }
+
+    #[test(tokio::test)]
+    async fn handshake_should_negotiate_x25519_key_exchange_by_default() {
+        let (mut t1, mut t2) = FramedTransport::test_pair(100);
+
+        let task = tokio::spawn(async move {
+            t2.server_handshake().await.unwrap();
+            t2
+        });
+
+        t1.client_handshake().await.unwrap();
+        let t2 = task.await.unwrap();
+
+        assert_eq!(t1.key_exchange_type(), Some(KeyExchangeType::X25519));
+        assert_eq!(t2.key_exchange_type(), Some(KeyExchangeType::X25519));
+    }
+
+    #[test(tokio::test)]
+    async fn handshake_should_use_p256_key_exchange_if_only_one_offered_by_server() {
+        let (mut t1, mut t2) = FramedTransport::test_pair(100);
+
+        let task = tokio::spawn(async move {
+            t2.handshake(Handshake::Server {
+                compression_types: Vec::new(),
+                encryption_types: EncryptionType::known_variants().to_vec(),
+                key_exchange_types: vec![KeyExchangeType::P256],
+            })
+            .await
+            .unwrap();
+            t2
+        });
+
+        t1.client_handshake().await.unwrap();
+        let mut t2 = task.await.unwrap();
+
+        assert_eq!(t1.key_exchange_type(), Some(KeyExchangeType::P256));
+        assert_eq!(t2.key_exchange_type(), Some(KeyExchangeType::P256));
+
+        // Subsequent key exchanges use the negotiated algorithm on both sides
+        let task = tokio::spawn(async move { t2.exchange_keys().await.unwrap() });
+        let key = t1.exchange_keys().await.unwrap();
+        assert_eq!(key, task.await.unwrap());
+    }
+
+    #[test(tokio::test)]
+    async fn handshake_for_client_should_use_p256_key_exchange_if_server_does_not_offer_any() {
+        // Mirrors the options sent by servers that do not report key exchange types, which only
+        // support P-256
+        #[derive(Debug, Serialize, Deserialize)]
+        struct Options {
+            compression_types: Vec<CompressionType>,
+            encryption_types: Vec<EncryptionType>,
+            version: Version,
+        }
+
+        #[derive(Debug, Serialize, Deserialize)]
+        struct Choice {
+            compression_level: Option<CompressionLevel>,
+            compression_type: Option<CompressionType>,
+            encryption_type: Option<EncryptionType>,
+            key_exchange_type: Option<KeyExchangeType>,
+        }
+
+        let (mut t1, mut t2) = FramedTransport::test_pair(100);
+
+        let task = tokio::spawn(async move {
+            t2.write_frame_for(&Options {
+                compression_types: Vec::new(),
+                encryption_types: vec![EncryptionType::XChaCha20Poly1305],
+                version: PROTOCOL_VERSION,
+            })
+            .await
+            .unwrap();
+
+            let choice = t2.read_frame_as::<Choice>().await.unwrap().unwrap();
+            assert_eq!(choice.key_exchange_type, Some(KeyExchangeType::P256));
+
+            // Complete the exchange the way an older server would
+            t2.exchange_keys().await.unwrap()
+        });
+
+        t1.client_handshake().await.unwrap();
+        assert_eq!(t1.key_exchange_type(), Some(KeyExchangeType::P256));
+        assert!(!t1.is_rekey_supported());
+        assert_eq!(t1.peer_version(), Some(&PROTOCOL_VERSION));
+        task.await.unwrap();
+    }
+
+    #[test(tokio::test)]
+    async fn handshake_for_server_should_fail_if_selected_key_exchange_type_was_not_offered() {
+        #[derive(Debug, Serialize, Deserialize)]
+        struct Choice {
+            compression_level: Option<CompressionLevel>,
+            compression_type: Option<CompressionType>,
+            encryption_type: Option<EncryptionType>,
+            key_exchange_type: Option<KeyExchangeType>,
+            version: Version,
+        }
+
+        let (mut t1, mut t2) = FramedTransport::test_pair(100);
+
+        t2.write_frame_for(&Choice {
+            compression_level: None,
+            compression_type: None,
+            encryption_type: Some(EncryptionType::XChaCha20Poly1305),
+            key_exchange_type: Some(KeyExchangeType::P256),
+            version: PROTOCOL_VERSION,
+        })
+        .await
+        .unwrap();
+
+        let err = t1
+            .handshake(Handshake::Server {
+                compression_types: Vec::new(),
+                encryption_types: EncryptionType::known_variants().to_vec(),
+                key_exchange_types: vec![KeyExchangeType::X25519],
+            })
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert_eq!(t1.key_exchange_type(), None);
+    }
+
+    #[test(tokio::test)]
+    async fn handshake_for_server_should_fail_if_selected_encryption_type_was_not_offered() {
+        #[derive(Debug, Serialize, Deserialize)]
+        struct Choice {
+            compression_level: Option<CompressionLevel>,
+            compression_type: Option<CompressionType>,
+            encryption_type: Option<EncryptionType>,
+            version: Version,
+        }
+
+        // Choosing encryption that was not offered, or no encryption at all when some was
+        // offered, should both fail
+        for encryption_type in [Some(EncryptionType::XChaCha20Poly1305), None] {
+            let (mut t1, mut t2) = FramedTransport::test_pair(100);
+
+            t2.write_frame_for(&Choice {
+                compression_level: None,
+                compression_type: None,
+                encryption_type,
+                version: PROTOCOL_VERSION,
+            })
+            .await
+            .unwrap();
+
+            let err = t1
+                .handshake(Handshake::Server {
+                    compression_types: Vec::new(),
+                    encryption_types: vec![EncryptionType::Aes256Gcm],
+                    key_exchange_types: KeyExchangeType::known_variants().to_vec(),
+                })
+                .await
+                .unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+            assert_eq!(t1.key_exchange_type(), None);
+        }
+    }
+
+    #[test(tokio::test)]
+    async fn handshake_should_record_protocol_version_and_features_of_other_side() {
+        let (mut t1, mut t2) = FramedTransport::test_pair(100);
+
+        let task = tokio::spawn(async move {
+            t2.server_handshake().await.unwrap();
+            t2
+        });
+
+        t1.client_handshake().await.unwrap();
+        let t2 = task.await.unwrap();
+
+        assert_eq!(t1.peer_version(), Some(&PROTOCOL_VERSION));
+        assert_eq!(t2.peer_version(), Some(&PROTOCOL_VERSION));
+        assert_eq!(t1.peer_features(), supported_features());
+        assert_eq!(t2.peer_features(), supported_features());
+    }
+
+    #[test(tokio::test)]
+    async fn handshake_for_client_should_fail_if_server_protocol_version_is_incompatible() {
+        #[derive(Debug, Serialize, Deserialize)]
+        struct Options {
+            compression_types: Vec<CompressionType>,
+            encryption_types: Vec<EncryptionType>,
+            version: Version,
+        }
+
+        let (mut t1, mut t2) = FramedTransport::test_pair(100);
+
+        t2.write_frame_for(&Options {
+            compression_types: Vec::new(),
+            encryption_types: Vec::new(),
+            version: Version::new(PROTOCOL_VERSION.major() + 1, 0, 0),
+        })
+        .await
+        .unwrap();
+
+        let err = t1.client_handshake().await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+        assert_eq!(t1.peer_version(), None);
+    }
+
+    #[test(tokio::test)]
+    async fn handshake_for_server_should_fail_if_client_protocol_version_is_incompatible() {
+        #[derive(Debug, Serialize, Deserialize)]
+        struct Choice {
+            compression_level: Option<CompressionLevel>,
+            compression_type: Option<CompressionType>,
+            encryption_type: Option<EncryptionType>,
+            version: Version,
+        }
+
+        let (mut t1, mut t2) = FramedTransport::test_pair(100);
+
+        t2.write_frame_for(&Choice {
+            compression_level: None,
+            compression_type: None,
+            encryption_type: None,
+            version: Version::new(PROTOCOL_VERSION.major() + 1, 0, 0),
+        })
+        .await
+        .unwrap();
+
+        let err = t1.server_handshake().await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+    }
+
+    #[test(tokio::test)]
+    async fn handshake_for_server_should_fail_if_client_does_not_report_protocol_version() {
+        // Mirrors the choice sent by clients older than 0.20
+        #[derive(Debug, Serialize, Deserialize)]
+        struct Choice {
+            compression_level: Option<CompressionLevel>,
+            compression_type: Option<CompressionType>,
+            encryption_type: Option<EncryptionType>,
+        }
+
+        let (mut t1, mut t2) = FramedTransport::test_pair(100);
+
+        t2.write_frame_for(&Choice {
+            compression_level: None,
+            compression_type: None,
+            encryption_type: Some(EncryptionType::XChaCha20Poly1305),
+        })
+        .await
+        .unwrap();
+
+        let err = t1.server_handshake().await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+        assert_eq!(t1.peer_version(), None);
+    }
+
+    #[test(tokio::test)]
+    async fn handshake_for_client_should_fail_if_server_does_not_report_protocol_version() {
+        // Mirrors the options sent by servers older than 0.20
+        #[derive(Debug, Serialize, Deserialize)]
+        struct Options {
+            compression_types: Vec<CompressionType>,
+            encryption_types: Vec<EncryptionType>,
+        }
+
+        let (mut t1, mut t2) = FramedTransport::test_pair(100);
+
+        t2.write_frame_for(&Options {
+            compression_types: Vec::new(),
+            encryption_types: EncryptionType::known_variants().to_vec(),
+        })
+        .await
+        .unwrap();
+
+        let err = t1.client_handshake().await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+        assert_eq!(t1.peer_version(), None);
+    }
+
+    #[test(tokio::test)]
+    async fn rekey_should_replace_codec_without_dropping_frames() {
+        let (mut t1, mut t2) = FramedTransport::test_pair(100);
+        t1.set_rekey_config(RekeyConfig {
+            max_bytes: Some(1),
+            max_duration: None,
+        });
+        t2.set_rekey_config(RekeyConfig::never());
+
+        let task = tokio::spawn(async move {
+            t2.server_handshake().await.unwrap();
+
+            // Echo back frames, which interleave with the rekey started by the other side
+            for _ in 0..3 {
+                let frame = t2.read_frame().await.unwrap().unwrap();
+                t2.write_frame(frame).await.unwrap();
+            }
+        });
+
+        t1.client_handshake().await.unwrap();
+        assert!(t1.is_rekey_supported());
+        let mut old_codec = t1.codec.clone();
+
+        for i in 0..3 {
+            let data = format!("frame {i}");
+            t1.write_frame(data.as_bytes()).await.unwrap();
+            assert_eq!(t1.read_frame().await.unwrap().unwrap(), data.as_bytes());
+        }
+
+        task.await.unwrap();
+
+        // Verify that we are no longer using the key from the handshake
+        let frame = old_codec.encode(Frame::new(b"test")).unwrap();
+        t1.codec.decode(frame).unwrap_err();
+
+        // Rekey frames are not tracked as part of the backup
+        assert_eq!(t1.backup.sent_cnt(), 3);
+        assert_eq!(t1.backup.received_cnt(), 3);
+    }
+
+    #[test(tokio::test)]
+    async fn rekey_should_succeed_if_both_sides_start_at_the_same_time() {
+        let (mut t1, mut t2) = FramedTransport::test_pair(100);
+        let config = RekeyConfig {
+            max_bytes: Some(1),
+            max_duration: None,
+        };
+        t1.set_rekey_config(config);
+        t2.set_rekey_config(config);
+
+        let task = tokio::spawn(async move {
+            t2.server_handshake().await.unwrap();
+            let mut old_codec = t2.codec.clone();
+
+            for _ in 0..3 {
+                t2.write_frame(b"from server").await.unwrap();
+                assert_eq!(t2.read_frame().await.unwrap().unwrap(), b"from client");
+            }
+
+            let frame = old_codec.encode(Frame::new(b"test")).unwrap();
+            t2.codec.decode(frame).unwrap_err();
+        });
+
+        t1.client_handshake().await.unwrap();
+        let mut old_codec = t1.codec.clone();
+
+        for _ in 0..3 {
+            t1.write_frame(b"from client").await.unwrap();
+            assert_eq!(t1.read_frame().await.unwrap().unwrap(), b"from server");
+        }
+
+        task.await.unwrap();
+
+        let frame = old_codec.encode(Frame::new(b"test")).unwrap();
+        t1.codec.decode(frame).unwrap_err();
+    }
+
+    #[test(tokio::test)]
+    async fn rekey_should_not_happen_if_handshake_did_not_use_encryption() {
+        let (mut t1, mut t2) = FramedTransport::test_pair(100);
+        t1.set_rekey_config(RekeyConfig {
+            max_bytes: Some(1),
+            max_duration: None,
+        });
+
+        let task = tokio::spawn(async move {
+            t2.handshake(Handshake::Server {
+                compression_types: Vec::new(),
+                encryption_types: Vec::new(),
+                key_exchange_types: KeyExchangeType::known_variants().to_vec(),
+            })
+            .await
+            .unwrap();
+            t2
+        });
+
+        t1.client_handshake().await.unwrap();
+        assert!(!t1.is_rekey_supported());
+
+        // Writing well past the threshold only sends our frame
+        let mut t2 = task.await.unwrap();
+        t1.write_frame(b"hello").await.unwrap();
+        assert_eq!(t2.read_frame().await.unwrap().unwrap(), b"hello");
+        assert!(t2.incoming.is_empty());
+    }
+
     #[test(tokio::test)]
     async fn handshake_failing_should_ensure_existing_codec_remains() {
         let (mut t1, t2) = FramedTransport::test_pair(100);
@@ -2062,6 +2876,7 @@ mod tests {
         struct Options {
             compression_types: Vec<CompressionType>,
             encryption_types: Vec<EncryptionType>,
+            version: Version,
         }
 
         let (mut t1, mut t2) = FramedTransport::test_pair(100);
@@ -2070,6 +2885,7 @@ mod tests {
         t2.write_frame_for(&Options {
             compression_types: CompressionType::known_variants().to_vec(),
             encryption_types: EncryptionType::known_variants().to_vec(),
+            version: PROTOCOL_VERSION,
         })
         .await
         .unwrap();
@@ -2123,6 +2939,7 @@ mod tests {
             compression_level: Option<CompressionLevel>,
             compression_type: Option<CompressionType>,
             encryption_type: Option<EncryptionType>,
+            version: Version,
         }
 
         let (mut t1, mut t2) = FramedTransport::test_pair(100);
@@ -2132,6 +2949,7 @@ mod tests {
             compression_level: None,
             compression_type: Some(CompressionType::Unknown),
             encryption_type: None,
+            version: PROTOCOL_VERSION,
         })
         .await
         .unwrap();
@@ -2149,6 +2967,7 @@ mod tests {
             compression_level: Option<CompressionLevel>,
             compression_type: Option<CompressionType>,
             encryption_type: Option<EncryptionType>,
+            version: Version,
         }
 
         let (mut t1, mut t2) = FramedTransport::test_pair(100);
@@ -2158,6 +2977,7 @@ mod tests {
             compression_level: None,
             compression_type: None,
             encryption_type: Some(EncryptionType::Unknown),
+            version: PROTOCOL_VERSION,
         })
         .await
         .unwrap();
@@ -2175,6 +2995,7 @@ mod tests {
             compression_level: Option<CompressionLevel>,
             compression_type: Option<CompressionType>,
             encryption_type: Option<EncryptionType>,
+            version: Version,
         }
 
         let (mut t1, mut t2) = FramedTransport::test_pair(100);
@@ -2184,6 +3005,7 @@ mod tests {
             compression_level: None,
             compression_type: None,
             encryption_type: Some(EncryptionType::XChaCha20Poly1305),
+            version: PROTOCOL_VERSION,
         })
         .await
         .unwrap();
@@ -4,7 +4,7 @@ use flate2::{
     Compression,
 };
 use serde::{Deserialize, Serialize};
-use std::io::{self, Read};
+use std::io::{self, Read, Write};
 
 /// Represents the level of compression to apply to data
 #[derive(Copy, Clone, Debug, Hash, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
@@ -51,6 +51,8 @@ pub enum CompressionType {
     Deflate,
     Gzip,
     Zlib,
+    Zstd,
+    Lz4,
 
     /// Indicates an unknown compression type for use in handshakes
     #[serde(other)]
@@ -64,6 +66,8 @@ impl CompressionType {
             CompressionType::Deflate,
             CompressionType::Gzip,
             CompressionType::Zlib,
+            CompressionType::Zstd,
+            CompressionType::Lz4,
         ]
     }
 
@@ -90,6 +94,14 @@ pub enum CompressionCodec {
 
     /// Apply zlib compression/decompression using compression `level`
     Zlib { level: CompressionLevel },
+
+    /// Apply Zstandard compression/decompression using compression `level`, where low levels such
+    /// as one through three are both fast and effective
+    Zstd { level: CompressionLevel },
+
+    /// Apply LZ4 (frame format) compression/decompression using compression `level`, where levels
+    /// three and above switch to the slower high compression mode
+    Lz4 { level: CompressionLevel },
 }
 
 impl CompressionCodec {
@@ -103,6 +115,8 @@ impl CompressionCodec {
             CompressionType::Deflate => Ok(Self::Deflate { level }),
             CompressionType::Gzip => Ok(Self::Gzip { level }),
             CompressionType::Zlib => Ok(Self::Zlib { level }),
+            CompressionType::Zstd => Ok(Self::Zstd { level }),
+            CompressionType::Lz4 => Ok(Self::Lz4 { level }),
             CompressionType::Unknown => Err(io::Error::new(
                 io::ErrorKind::InvalidInput,
                 "Unknown compression type",
@@ -131,12 +145,28 @@ impl CompressionCodec {
         }
     }
 
+    /// Create a new Zstandard compression codec with the specified `level`
+    pub fn zstd(level: impl Into<CompressionLevel>) -> Self {
+        Self::Zstd {
+            level: level.into(),
+        }
+    }
+
+    /// Create a new LZ4 compression codec with the specified `level`
+    pub fn lz4(level: impl Into<CompressionLevel>) -> Self {
+        Self::Lz4 {
+            level: level.into(),
+        }
+    }
+
     /// Returns the compression level associated with the codec
     pub fn level(&self) -> CompressionLevel {
         match self {
             Self::Deflate { level } => *level,
             Self::Gzip { level } => *level,
             Self::Zlib { level } => *level,
+            Self::Zstd { level } => *level,
+            Self::Lz4 { level } => *level,
         }
     }
 
@@ -146,6 +176,8 @@ impl CompressionCodec {
             Self::Deflate { .. } => CompressionType::Deflate,
             Self::Gzip { .. } => CompressionType::Gzip,
             Self::Zlib { .. } => CompressionType::Zlib,
+            Self::Zstd { .. } => CompressionType::Zstd,
+            Self::Lz4 { .. } => CompressionType::Lz4,
         }
     }
 }
@@ -165,6 +197,18 @@ impl Codec for CompressionCodec {
             Self::Zlib { level } => {
                 ZlibEncoder::new(item, Compression::new(level as u32)).read_to_end(&mut buf)?
             }
+            Self::Zstd { level } => {
+                zstd::stream::read::Encoder::new(item, level as i32)?.read_to_end(&mut buf)?
+            }
+            Self::Lz4 { level } => {
+                let mut encoder = lz4::EncoderBuilder::new()
+                    .level(level as u32)
+                    .build(&mut buf)?;
+                encoder.write_all(item)?;
+
+                let (buf, result) = encoder.finish();
+                result.map(|_| buf.len())?
+            }
         };
 
         Ok(Frame::from(buf))
@@ -178,6 +222,8 @@ impl Codec for CompressionCodec {
             Self::Deflate { .. } => DeflateDecoder::new(item).read_to_end(&mut buf)?,
             Self::Gzip { .. } => GzDecoder::new(item).read_to_end(&mut buf)?,
             Self::Zlib { .. } => ZlibDecoder::new(item).read_to_end(&mut buf)?,
+            Self::Zstd { .. } => zstd::stream::read::Decoder::new(item)?.read_to_end(&mut buf)?,
+            Self::Lz4 { .. } => lz4::Decoder::new(item)?.read_to_end(&mut buf)?,
         };
 
         Ok(Frame::from(buf))
@@ -220,6 +266,24 @@ mod tests {
             .read_to_end(&mut item)
             .unwrap();
         assert_eq!(item, b"some bytes");
+
+        // Encode using zstd and verify that the compression was as expected by decompressing
+        let mut codec = CompressionCodec::zstd(CompressionLevel::BEST);
+        let frame = codec.encode(Frame::new(b"some bytes")).unwrap();
+
+        let item = zstd::stream::decode_all(frame.as_item()).unwrap();
+        assert_eq!(item, b"some bytes");
+
+        // Encode using lz4 and verify that the compression was as expected by decompressing
+        let mut codec = CompressionCodec::lz4(CompressionLevel::BEST);
+        let frame = codec.encode(Frame::new(b"some bytes")).unwrap();
+
+        let mut item = Vec::new();
+        lz4::Decoder::new(frame.as_item())
+            .unwrap()
+            .read_to_end(&mut item)
+            .unwrap();
+        assert_eq!(item, b"some bytes");
     }
 
     #[test]
@@ -259,5 +323,51 @@ mod tests {
         let mut codec = CompressionCodec::zlib(CompressionLevel::BEST);
         let frame = codec.decode(frame).unwrap();
         assert_eq!(frame, b"some bytes");
+
+        // Decode using zstd
+        let frame = Frame::from(zstd::stream::encode_all(b"some bytes".as_slice(), 19).unwrap());
+        let mut codec = CompressionCodec::zstd(CompressionLevel::BEST);
+        let frame = codec.decode(frame).unwrap();
+        assert_eq!(frame, b"some bytes");
+
+        // Decode using lz4
+        let frame = {
+            let mut encoder = lz4::EncoderBuilder::new().build(Vec::new()).unwrap();
+            encoder.write_all(b"some bytes").unwrap();
+            let (item, result) = encoder.finish();
+            result.unwrap();
+            Frame::from(item)
+        };
+        let mut codec = CompressionCodec::lz4(CompressionLevel::BEST);
+        let frame = codec.decode(frame).unwrap();
+        assert_eq!(frame, b"some bytes");
+    }
+
+    #[test]
+    fn zstd_and_lz4_should_be_unknown_to_peers_that_do_not_support_them() {
+        // Mirrors the compression types known before zstd and lz4 were added
+        #[derive(Debug, PartialEq, Eq, Deserialize)]
+        enum OlderCompressionType {
+            Deflate,
+            Gzip,
+            Zlib,
+            #[serde(other)]
+            Unknown,
+        }
+
+        let data =
+            crate::common::utils::serialize_to_vec(&CompressionType::known_variants()).unwrap();
+        let types: Vec<OlderCompressionType> =
+            crate::common::utils::deserialize_from_slice(&data).unwrap();
+        assert_eq!(
+            types,
+            vec![
+                OlderCompressionType::Deflate,
+                OlderCompressionType::Gzip,
+                OlderCompressionType::Zlib,
+                OlderCompressionType::Unknown,
+                OlderCompressionType::Unknown,
+            ]
+        );
     }
 }
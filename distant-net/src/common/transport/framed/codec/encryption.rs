@@ -1,6 +1,13 @@
 use super::{Codec, Frame};
-use derive_more::Display;
-use std::{fmt, io};
+use derive_more::{Display, Error};
+use std::{
+    fmt, io,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
 
 mod key;
 pub use key::*;
@@ -14,6 +21,10 @@ pub enum EncryptionType {
     #[display(fmt = "xchacha20poly1305")]
     XChaCha20Poly1305,
 
+    /// AES-256 in Galois/Counter Mode with a 96-bit (12-byte) nonce
+    #[display(fmt = "aes256gcm")]
+    Aes256Gcm,
+
     /// Indicates an unknown encryption type for use in handshakes
     #[display(fmt = "unknown")]
     #[serde(other)]
@@ -24,7 +35,7 @@ impl EncryptionType {
     /// Generates bytes for a secret key based on the encryption type
     pub fn generate_secret_key_bytes(&self) -> io::Result<Vec<u8>> {
         match self {
-            Self::XChaCha20Poly1305 => Ok(SecretKey::<32>::generate()
+            Self::XChaCha20Poly1305 | Self::Aes256Gcm => Ok(SecretKey::<32>::generate()
                 .unwrap()
                 .into_heap_secret_key()
                 .unprotected_into_bytes()),
@@ -37,7 +48,7 @@ impl EncryptionType {
 
     /// Returns a list of all variants of the type *except* unknown.
     pub const fn known_variants() -> &'static [EncryptionType] {
-        &[EncryptionType::XChaCha20Poly1305]
+        &[EncryptionType::XChaCha20Poly1305, EncryptionType::Aes256Gcm]
     }
 
     /// Returns true if type is unknown
@@ -45,10 +56,45 @@ impl EncryptionType {
         matches!(self, Self::Unknown)
     }
 
-    /// Creates a new [`EncryptionCodec`] for this type, failing if this type is unknown or the key
-    /// is an invalid length
-    pub fn new_codec(&self, key: &[u8]) -> io::Result<EncryptionCodec> {
-        EncryptionCodec::from_type_and_key(*self, key)
+    /// Creates a new [`EncryptionCodec`] for this type used by the `role` side of a connection,
+    /// failing if this type is unknown or the key is an invalid length
+    pub fn new_codec(&self, key: &[u8], role: EncryptionRole) -> io::Result<EncryptionCodec> {
+        EncryptionCodec::from_type_and_key(*self, key, role)
+    }
+}
+
+#[derive(Debug, Display, Error)]
+#[display(fmt = "unknown encryption type: {}", _0)]
+pub struct EncryptionTypeParseError(#[error(not(source))] String);
+
+impl FromStr for EncryptionType {
+    type Err = EncryptionTypeParseError;
+
+    /// Parses the name of a known encryption type as displayed, ignoring case
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::known_variants()
+            .iter()
+            .find(|ty| ty.to_string().eq_ignore_ascii_case(s.trim()))
+            .copied()
+            .ok_or_else(|| EncryptionTypeParseError(s.to_string()))
+    }
+}
+
+/// Side of a connection that an [`EncryptionCodec`] encrypts frames for, which keeps the nonces
+/// used in each direction apart as both sides share the same key
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum EncryptionRole {
+    Client,
+    Server,
+}
+
+impl EncryptionRole {
+    /// Returns the fixed prefix of nonces generated in sequence for this side of a connection
+    const fn nonce_prefix(self) -> [u8; 4] {
+        match self {
+            Self::Client => [0, 0, 0, 0],
+            Self::Server => [0, 0, 0, 1],
+        }
     }
 }
 
@@ -60,12 +106,26 @@ pub enum EncryptionCodec {
     XChaCha20Poly1305 {
         cipher: chacha20poly1305::XChaCha20Poly1305,
     },
+
+    /// AES-256 in Galois/Counter Mode with a 96-bit (12-byte) nonce, using [`Aes256Gcm`]
+    /// underneath
+    ///
+    /// [`Aes256Gcm`]: aes_gcm::Aes256Gcm
+    Aes256Gcm {
+        cipher: Box<aes_gcm::Aes256Gcm>,
+        nonces: NonceSequence,
+    },
 }
 
 impl EncryptionCodec {
-    /// Makes a new [`EncryptionCodec`] based on the [`EncryptionType`] and `key`, returning an
-    /// error if the key is invalid for the encryption type or the type is unknown
-    pub fn from_type_and_key(ty: EncryptionType, key: &[u8]) -> io::Result<EncryptionCodec> {
+    /// Makes a new [`EncryptionCodec`] based on the [`EncryptionType`] and `key` for the `role`
+    /// side of a connection, returning an error if the key is invalid for the encryption type or
+    /// the type is unknown
+    pub fn from_type_and_key(
+        ty: EncryptionType,
+        key: &[u8],
+        role: EncryptionRole,
+    ) -> io::Result<EncryptionCodec> {
         match ty {
             EncryptionType::XChaCha20Poly1305 => {
                 use chacha20poly1305::{KeyInit, XChaCha20Poly1305};
@@ -73,6 +133,15 @@ impl EncryptionCodec {
                     .map_err(|x| io::Error::new(io::ErrorKind::InvalidInput, x))?;
                 Ok(Self::XChaCha20Poly1305 { cipher })
             }
+            EncryptionType::Aes256Gcm => {
+                use aes_gcm::{Aes256Gcm, KeyInit};
+                let cipher = Aes256Gcm::new_from_slice(key)
+                    .map_err(|x| io::Error::new(io::ErrorKind::InvalidInput, x))?;
+                Ok(Self::Aes256Gcm {
+                    cipher: Box::new(cipher),
+                    nonces: NonceSequence::new(role),
+                })
+            }
             EncryptionType::Unknown => Err(io::Error::new(
                 io::ErrorKind::InvalidInput,
                 "Encryption type is unknown",
@@ -82,9 +151,21 @@ impl EncryptionCodec {
 
     pub fn new_xchacha20poly1305(secret_key: SecretKey32) -> EncryptionCodec {
         // NOTE: This should never fail as we are enforcing the key size at compile time
+        // NOTE: Nonces are random for this type, so the role does not matter
         Self::from_type_and_key(
             EncryptionType::XChaCha20Poly1305,
             secret_key.unprotected_as_bytes(),
+            EncryptionRole::Client,
+        )
+        .unwrap()
+    }
+
+    pub fn new_aes256gcm(secret_key: SecretKey32, role: EncryptionRole) -> EncryptionCodec {
+        // NOTE: This should never fail as we are enforcing the key size at compile time
+        Self::from_type_and_key(
+            EncryptionType::Aes256Gcm,
+            secret_key.unprotected_as_bytes(),
+            role,
         )
         .unwrap()
     }
@@ -93,6 +174,7 @@ impl EncryptionCodec {
     pub fn ty(&self) -> EncryptionType {
         match self {
             Self::XChaCha20Poly1305 { .. } => EncryptionType::XChaCha20Poly1305,
+            Self::Aes256Gcm { .. } => EncryptionType::Aes256Gcm,
         }
     }
 
@@ -101,19 +183,26 @@ impl EncryptionCodec {
         match self {
             // XChaCha20Poly1305 uses a 192-bit (24-byte) key
             Self::XChaCha20Poly1305 { .. } => 24,
+
+            // AES-GCM uses a 96-bit (12-byte) nonce
+            Self::Aes256Gcm { .. } => 12,
         }
     }
 
     /// Generates a new nonce for the encryption algorithm
-    fn generate_nonce_bytes(&self) -> Vec<u8> {
-        // NOTE: As seen in orion, with a 24-bit nonce, it's safe to generate instead of
-        //       maintaining a stateful counter due to its size (24-byte secret key generation
-        //       will never panic)
+    fn generate_nonce_bytes(&mut self) -> io::Result<Vec<u8>> {
         match self {
-            Self::XChaCha20Poly1305 { .. } => SecretKey::<24>::generate()
+            // NOTE: As seen in orion, with a 24-bit nonce, it's safe to generate instead of
+            //       maintaining a stateful counter due to its size (24-byte secret key generation
+            //       will never panic)
+            Self::XChaCha20Poly1305 { .. } => Ok(SecretKey::<24>::generate()
                 .unwrap()
                 .into_heap_secret_key()
-                .unprotected_into_bytes(),
+                .unprotected_into_bytes()),
+
+            // NOTE: A 12-byte nonce is too small to generate randomly for every frame, so we
+            //       use a sequence that is guaranteed not to repeat for this codec
+            Self::Aes256Gcm { nonces, .. } => nonces.next_nonce().map(|x| x.to_vec()),
         }
     }
 }
@@ -130,7 +219,7 @@ impl fmt::Debug for EncryptionCodec {
 
 impl Codec for EncryptionCodec {
     fn encode<'a>(&mut self, frame: Frame<'a>) -> io::Result<Frame<'a>> {
-        let nonce_bytes = self.generate_nonce_bytes();
+        let nonce_bytes = self.generate_nonce_bytes()?;
 
         Ok(match self {
             Self::XChaCha20Poly1305 { cipher } => {
@@ -147,6 +236,22 @@ impl Codec for EncryptionCodec {
                 let mut frame = Frame::from(nonce_bytes);
                 frame.extend(ciphertext);
 
+                frame
+            }
+            Self::Aes256Gcm { cipher, .. } => {
+                use aes_gcm::{aead::Aead, Nonce};
+                let item = frame.into_item();
+                let nonce = Nonce::from_slice(&nonce_bytes);
+
+                // Encrypt the frame's item as our ciphertext
+                let ciphertext = cipher
+                    .encrypt(nonce, item.as_ref())
+                    .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "Encryption failed"))?;
+
+                // Start our frame with the nonce at the beginning
+                let mut frame = Frame::from(nonce_bytes);
+                frame.extend(ciphertext);
+
                 frame
             }
         })
@@ -171,12 +276,55 @@ impl Codec for EncryptionCodec {
                     .decrypt(nonce, &frame.as_item()[nonce_size..])
                     .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "Decryption failed"))?
             }
+            Self::Aes256Gcm { cipher, .. } => {
+                use aes_gcm::{aead::Aead, Nonce};
+                let nonce = Nonce::from_slice(&frame.as_item()[..nonce_size]);
+                cipher
+                    .decrypt(nonce, &frame.as_item()[nonce_size..])
+                    .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "Decryption failed"))?
+            }
         };
 
         Ok(Frame::from(item))
     }
 }
 
+/// Sequence of unique 96-bit nonces formed from a fixed 32-bit prefix for each side of a
+/// connection followed by a 64-bit counter, as recommended for AES-GCM by NIST SP 800-38D
+///
+/// The prefix keeps the sequences of the two sides of a connection, which share a key, from
+/// overlapping. Clones share the same counter so that a nonce is never used twice.
+#[derive(Clone)]
+pub struct NonceSequence {
+    prefix: [u8; 4],
+    counter: Arc<AtomicU64>,
+}
+
+impl NonceSequence {
+    /// Creates a new sequence for the `role` side of a connection
+    pub fn new(role: EncryptionRole) -> Self {
+        Self {
+            prefix: role.nonce_prefix(),
+            counter: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// Returns the next nonce in the sequence, failing once the counter is exhausted
+    pub fn next_nonce(&mut self) -> io::Result<[u8; 12]> {
+        let counter = self
+            .counter
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |x| x.checked_add(1))
+            .map_err(|_| {
+                io::Error::new(io::ErrorKind::Other, "Exhausted nonces for encryption key")
+            })?;
+
+        let mut nonce = [0; 12];
+        nonce[..4].copy_from_slice(&self.prefix);
+        nonce[4..].copy_from_slice(&counter.to_be_bytes());
+        Ok(nonce)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -186,7 +334,8 @@ mod tests {
     fn encode_should_build_a_frame_containing_a_length_nonce_and_ciphertext() {
         let ty = EncryptionType::XChaCha20Poly1305;
         let key = ty.generate_secret_key_bytes().unwrap();
-        let mut codec = EncryptionCodec::from_type_and_key(ty, &key).unwrap();
+        let mut codec =
+            EncryptionCodec::from_type_and_key(ty, &key, EncryptionRole::Client).unwrap();
 
         let frame = codec
             .encode(Frame::new(b"hello world"))
@@ -211,7 +360,8 @@ mod tests {
     fn decode_should_fail_if_frame_length_is_smaller_than_nonce_plus_data() {
         let ty = EncryptionType::XChaCha20Poly1305;
         let key = ty.generate_secret_key_bytes().unwrap();
-        let mut codec = EncryptionCodec::from_type_and_key(ty, &key).unwrap();
+        let mut codec =
+            EncryptionCodec::from_type_and_key(ty, &key, EncryptionRole::Client).unwrap();
 
         // NONCE_SIZE + 1 is minimum for frame length
         let frame = Frame::from(b"a".repeat(codec.nonce_size()));
@@ -227,7 +377,8 @@ mod tests {
     fn decode_should_fail_if_unable_to_decrypt_frame_item() {
         let ty = EncryptionType::XChaCha20Poly1305;
         let key = ty.generate_secret_key_bytes().unwrap();
-        let mut codec = EncryptionCodec::from_type_and_key(ty, &key).unwrap();
+        let mut codec =
+            EncryptionCodec::from_type_and_key(ty, &key, EncryptionRole::Client).unwrap();
 
         // NONCE_SIZE + 1 is minimum for frame length
         let frame = Frame::from(b"a".repeat(codec.nonce_size() + 1));
@@ -243,7 +394,8 @@ mod tests {
     fn decode_should_return_decrypted_frame_when_successful() {
         let ty = EncryptionType::XChaCha20Poly1305;
         let key = ty.generate_secret_key_bytes().unwrap();
-        let mut codec = EncryptionCodec::from_type_and_key(ty, &key).unwrap();
+        let mut codec =
+            EncryptionCodec::from_type_and_key(ty, &key, EncryptionRole::Client).unwrap();
 
         let frame = codec
             .encode(Frame::new(b"hello, world"))
@@ -252,4 +404,77 @@ mod tests {
         let frame = codec.decode(frame).expect("Failed to decode");
         assert_eq!(frame, b"hello, world");
     }
+
+    #[test]
+    fn aes256gcm_should_round_trip_and_never_reuse_a_nonce() {
+        let ty = EncryptionType::Aes256Gcm;
+        let key = ty.generate_secret_key_bytes().unwrap();
+        let mut codec =
+            EncryptionCodec::from_type_and_key(ty, &key, EncryptionRole::Client).unwrap();
+        let mut clone = codec.clone();
+        let mut server =
+            EncryptionCodec::from_type_and_key(ty, &key, EncryptionRole::Server).unwrap();
+
+        let f1 = codec.encode(Frame::new(b"hello world")).unwrap();
+        let f2 = codec.encode(Frame::new(b"hello world")).unwrap();
+        let f3 = clone.encode(Frame::new(b"hello world")).unwrap();
+        let f4 = server.encode(Frame::new(b"hello world")).unwrap();
+
+        // Every nonce is unique, even across clones and the other side of the connection
+        let nonce = |frame: &Frame| frame.as_item()[..12].to_vec();
+        let nonces = [nonce(&f1), nonce(&f2), nonce(&f3), nonce(&f4)];
+        for (i, n1) in nonces.iter().enumerate() {
+            for n2 in nonces.iter().skip(i + 1) {
+                assert_ne!(n1, n2);
+            }
+        }
+
+        // Any codec can decrypt what the others produced as they share a key
+        assert_eq!(server.decode(f1).unwrap(), b"hello world");
+        assert_eq!(codec.decode(f3).unwrap(), b"hello world");
+        assert_eq!(clone.decode(f4).unwrap(), b"hello world");
+
+        // Manually decrypt to ensure the frame contains the nonce followed by the ciphertext
+        let item = {
+            use aes_gcm::{aead::Aead, Aes256Gcm, KeyInit, Nonce};
+            let cipher = Aes256Gcm::new_from_slice(&key).unwrap();
+            cipher
+                .decrypt(Nonce::from_slice(&f2.as_item()[..12]), &f2.as_item()[12..])
+                .expect("Failed to decrypt")
+        };
+        assert_eq!(item, b"hello world");
+    }
+
+    #[test]
+    fn nonce_sequence_should_keep_prefix_and_increment_counter() {
+        let mut nonces = NonceSequence::new(EncryptionRole::Client);
+        let n1 = nonces.next_nonce().unwrap();
+        let n2 = nonces.next_nonce().unwrap();
+
+        assert_eq!(n1[..4], n2[..4]);
+        assert_eq!(n1[4..], 0u64.to_be_bytes());
+        assert_eq!(n2[4..], 1u64.to_be_bytes());
+
+        // The other side of a connection starts from the same counter with another prefix
+        let n3 = NonceSequence::new(EncryptionRole::Server)
+            .next_nonce()
+            .unwrap();
+        assert_ne!(n1[..4], n3[..4]);
+        assert_eq!(n1[4..], n3[4..]);
+
+        nonces.counter.store(u64::MAX, Ordering::Relaxed);
+        nonces.next_nonce().unwrap_err();
+    }
+
+    #[test]
+    fn encryption_type_should_parse_from_displayed_name() {
+        for ty in EncryptionType::known_variants() {
+            assert_eq!(ty.to_string().parse::<EncryptionType>().unwrap(), *ty);
+        }
+        assert_eq!(
+            "AES256GCM".parse::<EncryptionType>().unwrap(),
+            EncryptionType::Aes256Gcm
+        );
+        "unknown".parse::<EncryptionType>().unwrap_err();
+    }
 }
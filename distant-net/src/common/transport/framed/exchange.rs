@@ -1,8 +1,11 @@
 use super::SecretKey32;
+use derive_more::{Display, Error};
+use hkdf::SimpleHkdf;
 use p256::{ecdh::EphemeralSecret, PublicKey};
 use rand::rngs::OsRng;
+use serde::{Deserialize, Serialize};
 use sha2::Sha256;
-use std::{convert::TryFrom, io};
+use std::{convert::TryFrom, io, str::FromStr};
 
 mod pkb;
 pub use pkb::PublicKeyBytes;
@@ -10,27 +13,118 @@ pub use pkb::PublicKeyBytes;
 mod salt;
 pub use salt::Salt;
 
+/// Represents the algorithm used by a [`KeyExchange`] to agree on a shared secret
+#[derive(Copy, Clone, Debug, Display, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum KeyExchangeType {
+    /// Elliptic-curve Diffie-Hellman using the NIST P-256 curve
+    #[display(fmt = "p256")]
+    P256,
+
+    /// Elliptic-curve Diffie-Hellman using Curve25519
+    #[display(fmt = "x25519")]
+    X25519,
+
+    /// Indicates an unknown key exchange type for use in handshakes
+    #[display(fmt = "unknown")]
+    #[serde(other)]
+    Unknown,
+}
+
+impl KeyExchangeType {
+    /// Returns a list of all variants of the type *except* unknown.
+    pub const fn known_variants() -> &'static [KeyExchangeType] {
+        &[KeyExchangeType::X25519, KeyExchangeType::P256]
+    }
+
+    /// Returns true if type is unknown
+    pub fn is_unknown(&self) -> bool {
+        matches!(self, Self::Unknown)
+    }
+}
+
+impl Default for KeyExchangeType {
+    /// Defaults to P-256, which is the only key exchange supported by older peers
+    fn default() -> Self {
+        Self::P256
+    }
+}
+
+#[derive(Debug, Display, Error)]
+#[display(fmt = "unknown key exchange type: {}", _0)]
+pub struct KeyExchangeTypeParseError(#[error(not(source))] String);
+
+impl FromStr for KeyExchangeType {
+    type Err = KeyExchangeTypeParseError;
+
+    /// Parses the name of a known key exchange type as displayed, ignoring case
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::known_variants()
+            .iter()
+            .find(|ty| ty.to_string().eq_ignore_ascii_case(s.trim()))
+            .copied()
+            .ok_or_else(|| KeyExchangeTypeParseError(s.to_string()))
+    }
+}
+
+/// Ephemeral secret of one side of a key exchange
+enum EphemeralKeyExchangeSecret {
+    P256(EphemeralSecret),
+    X25519(x25519_dalek::EphemeralSecret),
+}
+
 /// Utility to support performing an exchange of public keys and salts in order to derive a shared
 /// key between two separate entities
 pub struct KeyExchange {
-    secret: EphemeralSecret,
+    secret: EphemeralKeyExchangeSecret,
+    public_key: PublicKeyBytes,
     salt: Salt,
 }
 
 impl Default for KeyExchange {
-    // Create a new handshake instance with a secret and salt
+    // Create a new handshake instance with a P-256 secret and salt
     fn default() -> Self {
-        let secret = EphemeralSecret::random(&mut OsRng);
-        let salt = Salt::random();
-
-        Self { secret, salt }
+        Self::new(KeyExchangeType::P256).unwrap()
     }
 }
 
 impl KeyExchange {
+    /// Create a new key exchange using the algorithm `ty` with a random secret and salt, failing
+    /// if the type is unknown
+    pub fn new(ty: KeyExchangeType) -> io::Result<Self> {
+        let (secret, public_key) = match ty {
+            KeyExchangeType::P256 => {
+                let secret = EphemeralSecret::random(&mut OsRng);
+                let public_key = PublicKeyBytes::from(secret.public_key());
+                (EphemeralKeyExchangeSecret::P256(secret), public_key)
+            }
+            KeyExchangeType::X25519 => {
+                let secret = x25519_dalek::EphemeralSecret::random_from_rng(OsRng);
+                let public_key = PublicKeyBytes::from(x25519_dalek::PublicKey::from(&secret));
+                (EphemeralKeyExchangeSecret::X25519(secret), public_key)
+            }
+            KeyExchangeType::Unknown => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "Unknown key exchange type",
+                ))
+            }
+        };
+
+        Ok(Self {
+            secret,
+            public_key,
+            salt: Salt::random(),
+        })
+    }
+
+    /// Returns the algorithm used by this key exchange
+    pub fn ty(&self) -> KeyExchangeType {
+        self.public_key.ty()
+    }
+
     // Return encoded bytes of public key
     pub fn pk_bytes(&self) -> PublicKeyBytes {
-        PublicKeyBytes::from(self.secret.public_key())
+        self.public_key.clone()
     }
 
     // Return the salt contained by this handshake
@@ -38,23 +132,41 @@ impl KeyExchange {
         &self.salt
     }
 
-    /// Derives a shared secret using another key exchange's public key and salt
+    /// Derives a shared secret using another key exchange's public key and salt, failing if the
+    /// other key exchange used a different algorithm
     pub fn derive_shared_secret(
-        &self,
+        self,
         public_key: PublicKeyBytes,
         salt: Salt,
     ) -> io::Result<SecretKey32> {
-        // Decode the public key of the other side
-        let decoded_public_key = PublicKey::try_from(public_key)?;
-
         // Produce a salt that is consistent with what the other side will do
         let shared_salt = self.salt ^ salt;
 
-        // Acquire the shared secret
-        let shared_secret = self.secret.diffie_hellman(&decoded_public_key);
+        // Acquire the shared secret and extract entropy from it for use in producing a key
+        let hkdf = match self.secret {
+            EphemeralKeyExchangeSecret::P256(secret) => {
+                // Decode the public key of the other side
+                let decoded_public_key = PublicKey::try_from(public_key)?;
+
+                let shared_secret = secret.diffie_hellman(&decoded_public_key);
+                shared_secret.extract::<Sha256>(Some(shared_salt.as_ref()))
+            }
+            EphemeralKeyExchangeSecret::X25519(secret) => {
+                // Decode the public key of the other side
+                let decoded_public_key = x25519_dalek::PublicKey::try_from(public_key)?;
 
-        // Extract entropy from the shared secret for use in producing a key
-        let hkdf = shared_secret.extract::<Sha256>(Some(shared_salt.as_ref()));
+                // Reject public keys of low order, which would force a predictable secret
+                let shared_secret = secret.diffie_hellman(&decoded_public_key);
+                if !shared_secret.was_contributory() {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "Public key of other side is not contributory",
+                    ));
+                }
+
+                SimpleHkdf::<Sha256>::new(Some(shared_salt.as_ref()), shared_secret.as_bytes())
+            }
+        };
 
         // Derive a shared key (32 bytes)
         let mut shared_key = [0u8; 32];
@@ -64,3 +176,54 @@ impl KeyExchange {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn derive_shared_secret_should_produce_same_key_on_both_sides() {
+        for ty in KeyExchangeType::known_variants() {
+            let a = KeyExchange::new(*ty).unwrap();
+            let b = KeyExchange::new(*ty).unwrap();
+            let (a_pk, a_salt) = (a.pk_bytes(), *a.salt());
+            let (b_pk, b_salt) = (b.pk_bytes(), *b.salt());
+
+            let a_key = a.derive_shared_secret(b_pk, b_salt).unwrap();
+            let b_key = b.derive_shared_secret(a_pk, a_salt).unwrap();
+            assert_eq!(a_key, b_key, "Keys differ for {ty}");
+        }
+    }
+
+    #[test]
+    fn derive_shared_secret_should_fail_if_other_side_used_different_algorithm() {
+        let a = KeyExchange::new(KeyExchangeType::X25519).unwrap();
+        let b = KeyExchange::new(KeyExchangeType::P256).unwrap();
+        let (b_pk, b_salt) = (b.pk_bytes(), *b.salt());
+
+        let err = a.derive_shared_secret(b_pk, b_salt).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn public_key_bytes_should_identify_algorithm_when_deserialized() {
+        for ty in KeyExchangeType::known_variants() {
+            let pk = KeyExchange::new(*ty).unwrap().pk_bytes();
+            let bytes = Vec::from(pk.clone());
+            let decoded = PublicKeyBytes::try_from(bytes).unwrap();
+            assert_eq!(decoded.ty(), *ty);
+            assert_eq!(decoded, pk);
+        }
+    }
+
+    #[test]
+    fn derive_shared_secret_should_reject_low_order_x25519_public_key() {
+        let a = KeyExchange::new(KeyExchangeType::X25519).unwrap();
+
+        // All zeros is a point of small order that would yield an all zero shared secret
+        let err = a
+            .derive_shared_secret(PublicKeyBytes::X25519([0; 32]), Salt::random())
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
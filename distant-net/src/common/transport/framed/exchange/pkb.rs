@@ -1,17 +1,52 @@
+use super::KeyExchangeType;
 use p256::{EncodedPoint, PublicKey};
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::{convert::TryFrom, io};
 
-/// Represents a wrapper around [`EncodedPoint`], and exists to
-/// fix an issue with [`serde`] deserialization failing when
-/// directly serializing the [`EncodedPoint`] type
+/// Total bytes of an X25519 public key, which differs from the 33 or 65 bytes of a SEC1-encoded
+/// P-256 public key so that the two can be told apart when decoding
+const X25519_PUBLIC_KEY_LEN: usize = 32;
+
+/// Represents the public key of one side of a key exchange, tagged with the algorithm of the
+/// exchange that produced it
+///
+/// Serializes as the raw bytes of the key, where a P-256 key is a SEC1 encoded point (also fixing
+/// an issue with [`serde`] deserialization failing when directly serializing the
+/// [`EncodedPoint`] type) and an X25519 key is its 32 bytes.
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(into = "Vec<u8>", try_from = "Vec<u8>")]
-pub struct PublicKeyBytes(EncodedPoint);
+pub enum PublicKeyBytes {
+    P256(EncodedPoint),
+    X25519([u8; X25519_PUBLIC_KEY_LEN]),
+}
+
+impl PublicKeyBytes {
+    /// Returns the algorithm of the key exchange that produced this key
+    pub fn ty(&self) -> KeyExchangeType {
+        match self {
+            Self::P256(_) => KeyExchangeType::P256,
+            Self::X25519(_) => KeyExchangeType::X25519,
+        }
+    }
+
+    /// Returns the raw bytes of the key
+    pub fn as_bytes(&self) -> &[u8] {
+        match self {
+            Self::P256(x) => x.as_bytes(),
+            Self::X25519(x) => x,
+        }
+    }
+}
 
 impl From<PublicKey> for PublicKeyBytes {
     fn from(pk: PublicKey) -> Self {
-        Self(EncodedPoint::from(pk))
+        Self::P256(EncodedPoint::from(pk))
+    }
+}
+
+impl From<x25519_dalek::PublicKey> for PublicKeyBytes {
+    fn from(pk: x25519_dalek::PublicKey) -> Self {
+        Self::X25519(pk.to_bytes())
     }
 }
 
@@ -19,14 +54,34 @@ impl TryFrom<PublicKeyBytes> for PublicKey {
     type Error = io::Error;
 
     fn try_from(pkb: PublicKeyBytes) -> Result<Self, Self::Error> {
-        PublicKey::from_sec1_bytes(pkb.0.as_ref())
-            .map_err(|x| io::Error::new(io::ErrorKind::InvalidData, x))
+        match pkb {
+            PublicKeyBytes::P256(x) => PublicKey::from_sec1_bytes(x.as_ref())
+                .map_err(|x| io::Error::new(io::ErrorKind::InvalidData, x)),
+            x => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Expected p256 public key, but got {}", x.ty()),
+            )),
+        }
+    }
+}
+
+impl TryFrom<PublicKeyBytes> for x25519_dalek::PublicKey {
+    type Error = io::Error;
+
+    fn try_from(pkb: PublicKeyBytes) -> Result<Self, Self::Error> {
+        match pkb {
+            PublicKeyBytes::X25519(x) => Ok(x25519_dalek::PublicKey::from(x)),
+            x => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Expected x25519 public key, but got {}", x.ty()),
+            )),
+        }
     }
 }
 
 impl From<PublicKeyBytes> for Vec<u8> {
     fn from(pkb: PublicKeyBytes) -> Self {
-        pkb.0.as_bytes().to_vec()
+        pkb.as_bytes().to_vec()
     }
 }
 
@@ -34,9 +89,12 @@ impl TryFrom<Vec<u8>> for PublicKeyBytes {
     type Error = io::Error;
 
     fn try_from(bytes: Vec<u8>) -> Result<Self, Self::Error> {
-        Ok(Self(EncodedPoint::from_bytes(bytes).map_err(|x| {
-            io::Error::new(io::ErrorKind::InvalidData, x.to_string())
-        })?))
+        match <[u8; X25519_PUBLIC_KEY_LEN]>::try_from(bytes.as_slice()) {
+            Ok(x) => Ok(Self::X25519(x)),
+            Err(_) => Ok(Self::P256(EncodedPoint::from_bytes(bytes).map_err(
+                |x| io::Error::new(io::ErrorKind::InvalidData, x.to_string()),
+            )?)),
+        }
     }
 }
 
@@ -45,7 +103,7 @@ impl serde_bytes::Serialize for PublicKeyBytes {
     where
         S: Serializer,
     {
-        serializer.serialize_bytes(self.0.as_ref())
+        serializer.serialize_bytes(self.as_bytes())
     }
 }
 
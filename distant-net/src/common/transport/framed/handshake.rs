@@ -1,4 +1,4 @@
-use super::{CompressionLevel, CompressionType, EncryptionType};
+use super::{CompressionLevel, CompressionType, EncryptionType, KeyExchangeType};
 
 /// Definition of the handshake to perform for a transport
 #[derive(Clone, Debug)]
@@ -13,6 +13,9 @@ pub enum Handshake {
 
         /// Preferred encryption algorithm when presented options by server
         preferred_encryption_type: Option<EncryptionType>,
+
+        /// Preferred key exchange algorithm when presented options by server
+        preferred_key_exchange_type: Option<KeyExchangeType>,
     },
 
     /// Indicates that the handshake is being performed from the server-side
@@ -22,26 +25,31 @@ pub enum Handshake {
 
         /// List of available encryption algorithms for use between client and server
         encryption_types: Vec<EncryptionType>,
+
+        /// List of available key exchange algorithms for use between client and server
+        key_exchange_types: Vec<KeyExchangeType>,
     },
 }
 
 impl Handshake {
     /// Creates a new client handshake definition, providing defaults for the preferred compression
-    /// type, compression level, and encryption type
+    /// type, compression level, encryption type, and key exchange type
     pub fn client() -> Self {
         Self::Client {
             preferred_compression_type: None,
             preferred_compression_level: None,
             preferred_encryption_type: Some(EncryptionType::XChaCha20Poly1305),
+            preferred_key_exchange_type: Some(KeyExchangeType::X25519),
         }
     }
 
-    /// Creates a new server handshake definition, providing defaults for the compression types and
-    /// encryption types by including all known variants
+    /// Creates a new server handshake definition, providing defaults for the compression types,
+    /// encryption types, and key exchange types by including all known variants
     pub fn server() -> Self {
         Self::Server {
             compression_types: CompressionType::known_variants().to_vec(),
             encryption_types: EncryptionType::known_variants().to_vec(),
+            key_exchange_types: KeyExchangeType::known_variants().to_vec(),
         }
     }
 
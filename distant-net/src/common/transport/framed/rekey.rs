@@ -0,0 +1,228 @@
+use super::{
+    BoxedCodec, CompressionLevel, CompressionType, EncryptionRole, EncryptionType, KeyExchange,
+    KeyExchangeData, KeyExchangeType,
+};
+use crate::common::utils;
+use serde::{Deserialize, Serialize};
+use std::{
+    io,
+    time::{Duration, Instant},
+};
+
+/// Total bytes sent and received after which a transport rekeys by default (1 GiB)
+const DEFAULT_MAX_BYTES: u64 = 1024 * 1024 * 1024;
+
+/// Time after which a transport rekeys by default (1 hour)
+const DEFAULT_MAX_DURATION: Duration = Duration::from_secs(60 * 60);
+
+/// Name of the feature reported during the handshake by transports that support rekeying
+pub(super) const REKEY_FEATURE: &str = "rekey";
+
+/// Prefix of the decoded bytes of a frame that carries a [`RekeyMessage`]. Regular frames are
+/// either empty (heartbeats) or serialized msgpack maps, which never begin with a null byte.
+const REKEY_FRAME_PREFIX: &[u8] = b"\0distant:rekey\0";
+
+/// Thresholds at which a transport replaces the key of its encryption by performing a fresh key
+/// exchange in-band with the other side.
+///
+/// Rekeying only happens when both sides agreed to support it during the handshake, and a side
+/// always honors a rekey started by the other side regardless of its own thresholds.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RekeyConfig {
+    /// Total bytes sent and received using a key before rekeying, or `None` to never rekey
+    /// based on bytes
+    pub max_bytes: Option<u64>,
+
+    /// Time spent using a key before rekeying, or `None` to never rekey based on time
+    pub max_duration: Option<Duration>,
+}
+
+impl RekeyConfig {
+    /// Creates a config that never starts a rekey
+    pub const fn never() -> Self {
+        Self {
+            max_bytes: None,
+            max_duration: None,
+        }
+    }
+
+    /// Returns true if a rekey is due after `bytes` have been sent and received using a key that
+    /// has been in use for `elapsed` time
+    pub fn is_due(&self, bytes: u64, elapsed: Duration) -> bool {
+        matches!(self.max_bytes, Some(max) if bytes >= max)
+            || matches!(self.max_duration, Some(max) if elapsed >= max)
+    }
+}
+
+impl Default for RekeyConfig {
+    /// Rekeys after 1 GiB or an hour, whichever comes first
+    fn default() -> Self {
+        Self {
+            max_bytes: Some(DEFAULT_MAX_BYTES),
+            max_duration: Some(DEFAULT_MAX_DURATION),
+        }
+    }
+}
+
+/// Message sent in-band between transports to establish a new key.
+///
+/// 1. Initiator sends `Init` and keeps using the old key in both directions
+/// 2. Responder sends `Reply` and switches to the new key for everything it sends afterwards
+/// 3. Initiator switches to the new key for everything it receives, sends `Done`, and then
+///    switches to the new key for everything it sends afterwards
+/// 4. Responder switches to the new key for everything it receives
+#[derive(Debug, Serialize, Deserialize)]
+pub(super) enum RekeyMessage {
+    Init(KeyExchangeData),
+    Reply(KeyExchangeData),
+    Done,
+}
+
+impl RekeyMessage {
+    /// Serializes the message into the bytes of a frame
+    pub fn to_frame_bytes(&self) -> io::Result<Vec<u8>> {
+        let mut bytes = REKEY_FRAME_PREFIX.to_vec();
+        bytes.extend(utils::serialize_to_vec(self)?);
+        Ok(bytes)
+    }
+
+    /// Deserializes the message from the bytes of a frame, returning `None` if the frame does not
+    /// contain a rekey message
+    pub fn from_frame_bytes(bytes: &[u8]) -> Option<io::Result<Self>> {
+        bytes
+            .strip_prefix(REKEY_FRAME_PREFIX)
+            .map(utils::deserialize_from_slice)
+    }
+}
+
+/// Progress of a rekey from the perspective of one side
+pub(super) enum RekeyState {
+    /// No rekey is in progress
+    Idle,
+
+    /// Sent `Init` and waiting on `Reply` from the other side
+    AwaitingReply(KeyExchange),
+
+    /// Sent `Reply` and waiting on `Done` from the other side, decoding incoming frames with the
+    /// old codec until then
+    AwaitingDone(BoxedCodec),
+}
+
+impl Clone for RekeyState {
+    /// Clones the state, where the secret of a pending key exchange cannot be cloned and is
+    /// therefore abandoned by the clone
+    fn clone(&self) -> Self {
+        match self {
+            Self::Idle | Self::AwaitingReply(_) => Self::Idle,
+            Self::AwaitingDone(codec) => Self::AwaitingDone(codec.clone()),
+        }
+    }
+}
+
+/// Tracks rekeying for a transport whose handshake established encryption with support for
+/// rekeying on both sides
+#[derive(Clone)]
+pub(super) struct Rekey {
+    /// Encryption negotiated during the handshake, used with each new key
+    pub encryption_type: EncryptionType,
+
+    /// Compression negotiated during the handshake, reapplied with each new key
+    pub compression: Option<(CompressionType, CompressionLevel)>,
+
+    /// Key exchange negotiated during the handshake, used to derive each new key
+    pub key_exchange_type: KeyExchangeType,
+
+    /// Side of the connection that this transport is on, used to build each new codec
+    pub role: EncryptionRole,
+
+    /// Total encoded bytes sent and received using the current key
+    pub bytes: u64,
+
+    /// When the current key was established
+    pub since: Instant,
+
+    pub state: RekeyState,
+}
+
+impl Rekey {
+    pub fn new(
+        encryption_type: EncryptionType,
+        compression: Option<(CompressionType, CompressionLevel)>,
+        key_exchange_type: KeyExchangeType,
+        role: EncryptionRole,
+    ) -> Self {
+        Self {
+            encryption_type,
+            compression,
+            key_exchange_type,
+            role,
+            bytes: 0,
+            since: Instant::now(),
+            state: RekeyState::Idle,
+        }
+    }
+
+    /// Returns true if no rekey is in progress and the thresholds of `config` have been reached
+    pub fn is_due(&self, config: &RekeyConfig) -> bool {
+        matches!(self.state, RekeyState::Idle) && config.is_due(self.bytes, self.since.elapsed())
+    }
+
+    /// Builds a codec using the negotiated compression and encryption with a new `key`
+    pub fn new_codec(&self, key: &[u8]) -> io::Result<BoxedCodec> {
+        let compression = self
+            .compression
+            .map(|(ty, level)| ty.new_codec(level))
+            .transpose()?;
+        let encryption = self.encryption_type.new_codec(key, self.role)?;
+        Ok(super::bundle_codecs(compression, Some(encryption)))
+    }
+
+    /// Marks a new key as established, restarting the thresholds
+    pub fn finish(&mut self) {
+        self.bytes = 0;
+        self.since = Instant::now();
+        self.state = RekeyState::Idle;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn is_due_should_be_true_once_any_threshold_reached() {
+        let config = RekeyConfig {
+            max_bytes: Some(100),
+            max_duration: Some(Duration::from_secs(10)),
+        };
+
+        assert!(!config.is_due(99, Duration::from_secs(9)));
+        assert!(config.is_due(100, Duration::from_secs(0)));
+        assert!(config.is_due(0, Duration::from_secs(10)));
+        assert!(!RekeyConfig::never().is_due(u64::MAX, Duration::MAX));
+    }
+
+    #[test]
+    fn message_should_only_be_parsed_from_frames_with_prefix() {
+        let exchange = KeyExchange::default();
+        let message = RekeyMessage::Init(KeyExchangeData {
+            public_key: exchange.pk_bytes(),
+            salt: *exchange.salt(),
+        });
+
+        let bytes = message.to_frame_bytes().unwrap();
+        match RekeyMessage::from_frame_bytes(&bytes) {
+            Some(Ok(RekeyMessage::Init(data))) => {
+                assert_eq!(data.public_key, exchange.pk_bytes());
+                assert_eq!(data.salt, *exchange.salt());
+            }
+            x => panic!("Unexpected message: {x:?}"),
+        }
+
+        // Regular frames, including a msgpack map and empty heartbeats, are not rekey messages
+        let data = utils::serialize_to_vec(&HashMap::from([("key", "value")])).unwrap();
+        assert!(RekeyMessage::from_frame_bytes(&data).is_none());
+        assert!(RekeyMessage::from_frame_bytes(&[]).is_none());
+    }
+}
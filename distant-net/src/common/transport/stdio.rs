@@ -0,0 +1,379 @@
+use super::{Interest, Ready, Reconnectable, Transport};
+use async_trait::async_trait;
+use bytes::{Buf, BytesMut};
+use log::*;
+use std::{
+    ffi::{OsStr, OsString},
+    fmt, io,
+    process::Stdio,
+    sync::Mutex,
+};
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt},
+    process::{Child, Command},
+    sync::mpsc::{
+        self,
+        error::{TryRecvError, TrySendError},
+    },
+    task::JoinHandle,
+};
+
+/// Maximum size of a single read from the underlying pipe
+const READ_BUF_SIZE: usize = 8 * 1024;
+
+/// Number of reads or writes that can be queued between the transport and the underlying pipes
+const CHANNEL_CAPACITY: usize = 16;
+
+/// Represents a [`Transport`] over a pair of pipes, such as the stdin and stdout of this process
+/// or of a spawned command, letting a server be reached through anything that can run a command
+/// (ssh, `kubectl exec`, `docker exec`, etc.) without binding a port.
+///
+/// The pipes are driven by background tasks, which are stopped when the transport is dropped.
+pub struct StdioTransport {
+    tx: mpsc::Sender<Vec<u8>>,
+    rx: tokio::sync::Mutex<mpsc::Receiver<Vec<u8>>>,
+
+    /// Data received from the pipe that has yet to be read
+    buf: Mutex<BytesMut>,
+
+    reader: JoinHandle<()>,
+
+    /// Command whose stdin and stdout are used, spawned again when reconnecting
+    command: Option<StdioCommand>,
+}
+
+struct StdioCommand {
+    program: OsString,
+    args: Vec<OsString>,
+
+    /// Killed when dropped
+    _child: Child,
+}
+
+impl StdioTransport {
+    /// Creates a transport that reads from `reader` and writes to `writer`
+    pub fn new<R, W>(reader: R, writer: W) -> Self
+    where
+        R: AsyncRead + Send + Unpin + 'static,
+        W: AsyncWrite + Send + Unpin + 'static,
+    {
+        let (incoming_tx, incoming_rx) = mpsc::channel(CHANNEL_CAPACITY);
+        let (outgoing_tx, outgoing_rx) = mpsc::channel(CHANNEL_CAPACITY);
+        let reader = tokio::spawn(read_task(reader, incoming_tx));
+
+        // NOTE: The writer is not stopped when dropped so that it can finish writing what was
+        //       queued, ending on its own once the queue is empty
+        tokio::spawn(write_task(writer, outgoing_rx));
+
+        Self {
+            tx: outgoing_tx,
+            rx: tokio::sync::Mutex::new(incoming_rx),
+            buf: Mutex::new(BytesMut::new()),
+            reader,
+            command: None,
+        }
+    }
+
+    /// Creates a transport over the stdin and stdout of this process
+    pub fn stdio() -> Self {
+        Self::new(tokio::io::stdin(), tokio::io::stdout())
+    }
+
+    /// Spawns `program` with `args`, creating a transport over its stdin and stdout while its
+    /// stderr is inherited from this process. The command is killed when the transport is dropped
+    /// and spawned again when reconnecting.
+    pub fn spawn<I, S>(program: impl AsRef<OsStr>, args: I) -> io::Result<Self>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        Self::spawn_command(
+            program.as_ref().to_os_string(),
+            args.into_iter()
+                .map(|x| x.as_ref().to_os_string())
+                .collect(),
+        )
+    }
+
+    fn spawn_command(program: OsString, args: Vec<OsString>) -> io::Result<Self> {
+        let mut child = Command::new(&program)
+            .args(&args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .kill_on_drop(true)
+            .spawn()?;
+
+        let stdin = child.stdin.take().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::BrokenPipe, "Command stdin unavailable")
+        })?;
+        let stdout = child.stdout.take().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::BrokenPipe, "Command stdout unavailable")
+        })?;
+
+        let mut transport = Self::new(stdout, stdin);
+        transport.command = Some(StdioCommand {
+            program,
+            args,
+            _child: child,
+        });
+        Ok(transport)
+    }
+
+    /// Returns what is ready for `interest` without waiting
+    fn ready_now(&self, interest: Interest) -> Ready {
+        let mut ready = Ready::EMPTY;
+
+        if interest.is_readable() {
+            let mut buf = self.buf.lock().unwrap();
+            if buf.is_empty() {
+                if let Ok(mut rx) = self.rx.try_lock() {
+                    match rx.try_recv() {
+                        Ok(data) => buf.extend_from_slice(&data),
+                        Err(TryRecvError::Empty) => {}
+                        Err(TryRecvError::Disconnected) => ready |= Ready::READ_CLOSED,
+                    }
+                }
+            }
+
+            if !buf.is_empty() {
+                ready |= Ready::READABLE;
+            }
+        }
+
+        if interest.is_writable() {
+            if self.tx.is_closed() {
+                ready |= Ready::WRITE_CLOSED;
+            } else if self.tx.capacity() > 0 {
+                ready |= Ready::WRITABLE;
+            }
+        }
+
+        ready
+    }
+
+    /// Waits for data to read, returning [`Ready::READ_CLOSED`] once the pipe has closed and all
+    /// of its data has been read
+    async fn readable(&self) -> Ready {
+        if !self.buf.lock().unwrap().is_empty() {
+            return Ready::READABLE;
+        }
+
+        match self.rx.lock().await.recv().await {
+            Some(data) => {
+                self.buf.lock().unwrap().extend_from_slice(&data);
+                Ready::READABLE
+            }
+            None => Ready::READ_CLOSED,
+        }
+    }
+
+    /// Waits for room to queue a write, returning [`Ready::WRITE_CLOSED`] once the pipe has closed
+    async fn writable(&self) -> Ready {
+        match self.tx.reserve().await {
+            Ok(_) => Ready::WRITABLE,
+            Err(_) => Ready::WRITE_CLOSED,
+        }
+    }
+}
+
+impl Drop for StdioTransport {
+    fn drop(&mut self) {
+        self.reader.abort();
+    }
+}
+
+impl fmt::Debug for StdioTransport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StdioTransport")
+            .field(
+                "program",
+                &self.command.as_ref().map(|command| &command.program),
+            )
+            .finish()
+    }
+}
+
+#[async_trait]
+impl Reconnectable for StdioTransport {
+    async fn reconnect(&mut self) -> io::Result<()> {
+        match self.command.as_ref() {
+            Some(command) => {
+                *self = Self::spawn_command(command.program.clone(), command.args.clone())?;
+                Ok(())
+            }
+            None => Err(io::Error::new(
+                io::ErrorKind::ConnectionRefused,
+                "Pipes cannot be reopened",
+            )),
+        }
+    }
+}
+
+#[async_trait]
+impl Transport for StdioTransport {
+    fn try_read(&self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut data = self.buf.lock().unwrap();
+        if data.is_empty() {
+            // Another read is waiting on the pipe, which will store what it receives
+            let mut rx = self
+                .rx
+                .try_lock()
+                .map_err(|_| io::Error::from(io::ErrorKind::WouldBlock))?;
+            match rx.try_recv() {
+                Ok(x) => data.extend_from_slice(&x),
+                Err(TryRecvError::Empty) => return Err(io::Error::from(io::ErrorKind::WouldBlock)),
+                Err(TryRecvError::Disconnected) => return Ok(0),
+            }
+        }
+
+        let n = buf.len().min(data.len());
+        buf[..n].copy_from_slice(&data[..n]);
+        data.advance(n);
+        Ok(n)
+    }
+
+    fn try_write(&self, buf: &[u8]) -> io::Result<usize> {
+        match self.tx.try_send(buf.to_vec()) {
+            Ok(()) => Ok(buf.len()),
+            Err(TrySendError::Full(_)) => Err(io::Error::from(io::ErrorKind::WouldBlock)),
+            Err(TrySendError::Closed(_)) => Ok(0),
+        }
+    }
+
+    async fn ready(&self, interest: Interest) -> io::Result<Ready> {
+        // Report everything that is ready without waiting, like a socket would
+        let ready = self.ready_now(interest);
+        if !ready.is_empty() {
+            return Ok(ready);
+        }
+
+        let readable = async {
+            if interest.is_readable() {
+                self.readable().await
+            } else {
+                std::future::pending().await
+            }
+        };
+
+        let writable = async {
+            if interest.is_writable() {
+                self.writable().await
+            } else {
+                std::future::pending().await
+            }
+        };
+
+        Ok(tokio::select! {
+            ready = readable => ready,
+            ready = writable => ready,
+        })
+    }
+}
+
+/// Forwards everything read from `reader` until it closes
+async fn read_task<R>(mut reader: R, tx: mpsc::Sender<Vec<u8>>)
+where
+    R: AsyncRead + Unpin,
+{
+    let mut buf = vec![0; READ_BUF_SIZE];
+    loop {
+        match reader.read(&mut buf).await {
+            Ok(0) => break,
+            Ok(n) => {
+                if tx.send(buf[..n].to_vec()).await.is_err() {
+                    break;
+                }
+            }
+            Err(x) => {
+                error!("Failed to read from stdio: {x}");
+                break;
+            }
+        }
+    }
+}
+
+/// Writes everything queued to `writer`, closing it once nothing more can be queued
+async fn write_task<W>(mut writer: W, mut rx: mpsc::Receiver<Vec<u8>>)
+where
+    W: AsyncWrite + Unpin,
+{
+    while let Some(data) = rx.recv().await {
+        if let Err(x) = writer.write_all(&data).await {
+            error!("Failed to write to stdio: {x}");
+            return;
+        }
+
+        if let Err(x) = writer.flush().await {
+            error!("Failed to flush stdio: {x}");
+            return;
+        }
+    }
+
+    let _ = writer.shutdown().await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::TransportExt;
+    use test_log::test;
+
+    #[test(tokio::test)]
+    async fn should_be_able_to_send_and_receive_data_over_pipes() {
+        let (transport_out, mut peer_in) = tokio::io::duplex(64);
+        let (mut peer_out, transport_in) = tokio::io::duplex(64);
+        let transport = StdioTransport::new(transport_in, transport_out);
+
+        transport.write_all(b"hello peer").await.unwrap();
+        let mut buf = [0; 10];
+        peer_in.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"hello peer");
+
+        // Reading less than was received keeps the rest for later reads
+        peer_out.write_all(b"hello transport").await.unwrap();
+        let mut buf = [0; 5];
+        transport.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"hello");
+        let mut buf = [0; 10];
+        transport.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b" transport");
+
+        // Closing the pipe ends the transport
+        drop(peer_out);
+        assert_eq!(
+            transport.ready(Interest::READABLE).await.unwrap(),
+            Ready::READ_CLOSED
+        );
+        assert_eq!(transport.try_read(&mut buf).unwrap(), 0);
+    }
+
+    #[test(tokio::test)]
+    async fn reconnect_should_fail_for_pipes() {
+        let (transport_out, _peer_in) = tokio::io::duplex(64);
+        let (_peer_out, transport_in) = tokio::io::duplex(64);
+        let mut transport = StdioTransport::new(transport_in, transport_out);
+
+        assert_eq!(
+            transport.reconnect().await.unwrap_err().kind(),
+            io::ErrorKind::ConnectionRefused
+        );
+    }
+
+    #[cfg(unix)]
+    #[test(tokio::test)]
+    async fn should_be_able_to_talk_to_spawned_command_and_spawn_it_again_when_reconnecting() {
+        let mut transport = StdioTransport::spawn("cat", Vec::<String>::new()).unwrap();
+
+        transport.write_all(b"hello cat").await.unwrap();
+        let mut buf = [0; 9];
+        transport.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"hello cat");
+
+        transport.reconnect().await.unwrap();
+        transport.write_all(b"hello again").await.unwrap();
+        let mut buf = [0; 11];
+        transport.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"hello again");
+    }
+}
@@ -0,0 +1,425 @@
+use super::{Interest, Ready, Reconnectable, Transport, SLEEP_DURATION};
+use async_trait::async_trait;
+use rustls::{
+    ClientConfig, ClientConnection, Connection, ServerConfig, ServerConnection, ServerName,
+};
+use std::{
+    fmt,
+    io::{self, Read, Write},
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+mod config;
+pub use config::*;
+
+/// Represents a [`Transport`] that wraps another transport, such as a [`TcpTransport`], in a TLS
+/// session so that its traffic is standard TLS.
+///
+/// The TLS handshake completes when the transport is created, verifying the certificate of the
+/// other side. As the session already encrypts everything, a server can stop offering encryption
+/// during the handshake of the [`FramedTransport`] by clearing
+/// [`ServerConfig::encryption_types`](crate::server::ServerConfig::encryption_types).
+///
+/// [`TcpTransport`]: super::TcpTransport
+/// [`FramedTransport`]: super::FramedTransport
+pub struct TlsTransport<T> {
+    inner: T,
+    connection: Mutex<Connection>,
+    role: TlsRole,
+}
+
+/// Side of the TLS session, used to start a new session when reconnecting
+#[derive(Clone)]
+enum TlsRole {
+    Client {
+        config: Arc<ClientConfig>,
+        server_name: ServerName,
+    },
+    Server {
+        config: Arc<ServerConfig>,
+    },
+}
+
+impl TlsRole {
+    fn new_connection(&self) -> io::Result<Connection> {
+        let connection = match self {
+            Self::Client {
+                config,
+                server_name,
+            } => {
+                ClientConnection::new(Arc::clone(config), server_name.clone()).map(Connection::from)
+            }
+            Self::Server { config } => {
+                ServerConnection::new(Arc::clone(config)).map(Connection::from)
+            }
+        };
+
+        connection.map_err(|x| io::Error::new(io::ErrorKind::InvalidInput, x))
+    }
+}
+
+impl<T: Transport> TlsTransport<T> {
+    /// Performs the client side of a TLS handshake over `inner`, verifying the server using
+    /// `config`
+    pub async fn connect(inner: T, config: &TlsClientConfig) -> io::Result<Self> {
+        let (config, server_name) = config.build()?;
+        Self::new(
+            inner,
+            TlsRole::Client {
+                config,
+                server_name,
+            },
+        )
+        .await
+    }
+
+    /// Performs the server side of a TLS handshake over `inner`, verifying the client using
+    /// `config` if it requires client certificates
+    pub async fn accept(inner: T, config: &TlsServerConfig) -> io::Result<Self> {
+        Self::accept_with(inner, config.build()?).await
+    }
+
+    /// Performs the server side of a TLS handshake over `inner` using an already-built `config`,
+    /// which avoids rebuilding it for every connection accepted by a listener
+    pub(crate) async fn accept_with(inner: T, config: Arc<ServerConfig>) -> io::Result<Self> {
+        Self::new(inner, TlsRole::Server { config }).await
+    }
+
+    async fn new(inner: T, role: TlsRole) -> io::Result<Self> {
+        let transport = Self {
+            inner,
+            connection: Mutex::new(role.new_connection()?),
+            role,
+        };
+        transport.handshake().await?;
+        Ok(transport)
+    }
+
+    /// Returns a reference to the transport carrying the TLS session
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    /// Returns the fingerprint of the certificate presented by the other side, or `None` if it
+    /// did not present one
+    pub fn peer_fingerprint(&self) -> Option<TlsFingerprint> {
+        self.lock()
+            .peer_certificates()
+            .and_then(|certs| certs.first())
+            .map(|cert| TlsFingerprint::of(&cert.0))
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Connection> {
+        self.connection.lock().unwrap()
+    }
+
+    /// Drives the TLS handshake to completion, waiting on the inner transport as needed
+    async fn handshake(&self) -> io::Result<()> {
+        loop {
+            let interest = {
+                let mut connection = self.lock();
+                match self.flush_tls(&mut connection) {
+                    Ok(()) if !connection.is_handshaking() => return Ok(()),
+                    Ok(()) => match self.read_tls(&mut connection) {
+                        Ok(0) => {
+                            return Err(io::Error::new(
+                                io::ErrorKind::UnexpectedEof,
+                                "Transport closed during TLS handshake",
+                            ))
+                        }
+                        Ok(_) => continue,
+                        Err(x) if x.kind() == io::ErrorKind::WouldBlock => Interest::READABLE,
+                        Err(x) => return Err(x),
+                    },
+                    Err(x) if x.kind() == io::ErrorKind::WouldBlock => Interest::WRITABLE,
+                    Err(x) => return Err(x),
+                }
+            };
+
+            // NOTE: We sleep for a little bit after waiting as some transports report being ready
+            //       before they can make progress, which would otherwise peg the CPU
+            self.inner.ready(interest).await?;
+            tokio::time::sleep(SLEEP_DURATION).await;
+        }
+    }
+
+    /// Writes all pending TLS records to the inner transport, failing with
+    /// [`io::ErrorKind::WouldBlock`] if it cannot take all of them yet
+    fn flush_tls(&self, connection: &mut Connection) -> io::Result<()> {
+        while connection.wants_write() {
+            if connection.write_tls(&mut TransportIo(&self.inner))? == 0 {
+                return Err(io::Error::from(io::ErrorKind::WriteZero));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Reads TLS records from the inner transport and processes them, returning the number of
+    /// bytes read where zero means the inner transport has closed
+    fn read_tls(&self, connection: &mut Connection) -> io::Result<usize> {
+        let n = connection.read_tls(&mut TransportIo(&self.inner))?;
+        if let Err(x) = connection.process_new_packets() {
+            // Try to let the other side know why we are giving up on the session
+            let _ = self.flush_tls(connection);
+            return Err(io::Error::new(io::ErrorKind::InvalidData, x));
+        }
+
+        Ok(n)
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for TlsTransport<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TlsTransport")
+            .field("inner", &self.inner)
+            .field(
+                "role",
+                &match self.role {
+                    TlsRole::Client { .. } => "client",
+                    TlsRole::Server { .. } => "server",
+                },
+            )
+            .finish()
+    }
+}
+
+#[async_trait]
+impl<T: Transport> Reconnectable for TlsTransport<T> {
+    async fn reconnect(&mut self) -> io::Result<()> {
+        self.inner.reconnect().await?;
+        *self.connection.get_mut().unwrap() = self.role.new_connection()?;
+        self.handshake().await
+    }
+}
+
+#[async_trait]
+impl<T: Transport> Transport for TlsTransport<T> {
+    fn try_read(&self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut connection = self.lock();
+        loop {
+            match connection.reader().read(buf) {
+                // Closing without notifying is treated like any other closed transport, as frames
+                // cut short are caught by the framing on top
+                Err(x) if x.kind() == io::ErrorKind::UnexpectedEof => return Ok(0),
+                Err(x) if x.kind() == io::ErrorKind::WouldBlock => {}
+                result => return result,
+            }
+
+            // No decrypted data is left, so decrypt more, where the reader reports the end of
+            // the session on the next pass if the inner transport has closed
+            self.read_tls(&mut connection)?;
+
+            // Processing records can queue records of our own, such as key updates
+            match self.flush_tls(&mut connection) {
+                Err(x) if x.kind() != io::ErrorKind::WouldBlock => return Err(x),
+                _ => {}
+            }
+        }
+    }
+
+    fn try_write(&self, buf: &[u8]) -> io::Result<usize> {
+        let mut connection = self.lock();
+
+        // Push out records left over from earlier writes before taking more, so that a slow
+        // reader on the other side holds back writes instead of records piling up in memory
+        self.flush_tls(&mut connection)?;
+
+        let n = connection.writer().write(buf)?;
+        match self.flush_tls(&mut connection) {
+            Err(x) if x.kind() != io::ErrorKind::WouldBlock => Err(x),
+            _ => Ok(n),
+        }
+    }
+
+    async fn ready(&self, interest: Interest) -> io::Result<Ready> {
+        loop {
+            let flushed = {
+                let mut connection = self.lock();
+
+                // Decrypted data that has yet to be read is ready without waiting
+                let state = connection
+                    .process_new_packets()
+                    .map_err(|x| io::Error::new(io::ErrorKind::InvalidData, x))?;
+                if interest.is_readable()
+                    && (state.plaintext_bytes_to_read() > 0 || state.peer_has_closed())
+                {
+                    return Ok(Ready::READABLE);
+                }
+
+                // Records buffered by an earlier write are only sent by a later write or here, so
+                // keep pushing them out while the caller waits on something else
+                match self.flush_tls(&mut connection) {
+                    Ok(()) => true,
+                    Err(x) if x.kind() == io::ErrorKind::WouldBlock => false,
+                    Err(x) => return Err(x),
+                }
+            };
+
+            if flushed {
+                return self.inner.ready(interest).await;
+            }
+
+            let ready = self.inner.ready(interest | Interest::WRITABLE).await?;
+            if interest.is_readable() && ready.is_readable() {
+                return Ok(Ready::READABLE);
+            }
+
+            tokio::time::sleep(SLEEP_DURATION).await;
+        }
+    }
+}
+
+/// Adapts a [`Transport`] to the blocking I/O traits used by rustls, where the non-blocking reads
+/// and writes of the transport surface as [`io::ErrorKind::WouldBlock`]
+struct TransportIo<'a, T>(&'a T);
+
+impl<T: Transport> Read for TransportIo<'_, T> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.try_read(buf)
+    }
+}
+
+impl<T: Transport> Write for TransportIo<'_, T> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.try_write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::{InmemoryTransport, TransportExt};
+    use test_log::test;
+
+    async fn pair(
+        client_config: TlsClientConfig,
+        server_config: TlsServerConfig,
+    ) -> (
+        io::Result<TlsTransport<InmemoryTransport>>,
+        io::Result<TlsTransport<InmemoryTransport>>,
+    ) {
+        let (t1, t2) = InmemoryTransport::pair(100);
+        let task = tokio::spawn(async move { TlsTransport::accept(t2, &server_config).await });
+        let client = TlsTransport::connect(t1, &client_config).await;
+        (client, task.await.unwrap())
+    }
+
+    #[test(tokio::test)]
+    async fn should_be_able_to_send_and_receive_data_with_pinned_fingerprint() {
+        let identity = TlsIdentity::generate_self_signed();
+        let fingerprint = identity.fingerprint().unwrap();
+
+        let (client, server) = pair(
+            TlsClientConfig::new("localhost", TlsVerification::fingerprint(fingerprint)),
+            TlsServerConfig::new(identity),
+        )
+        .await;
+        let (client, server) = (client.unwrap(), server.unwrap());
+        assert_eq!(client.peer_fingerprint(), Some(fingerprint));
+        assert_eq!(server.peer_fingerprint(), None);
+
+        client.write_all(b"hello server").await.unwrap();
+        let mut buf = [0; 12];
+        server.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"hello server");
+
+        // Larger than a single TLS record to exercise buffering on both sides
+        let data = vec![7u8; 64 * 1024];
+        let task = tokio::spawn(async move {
+            server.write_all(&data).await.unwrap();
+            server
+        });
+        let mut buf = vec![0; 64 * 1024];
+        client.read_exact(&mut buf).await.unwrap();
+        assert!(buf.iter().all(|x| *x == 7));
+        task.await.unwrap();
+    }
+
+    #[test(tokio::test)]
+    async fn connect_should_fail_if_server_fingerprint_is_not_pinned() {
+        let identity = TlsIdentity::generate_self_signed();
+        let other = TlsIdentity::generate_self_signed().fingerprint().unwrap();
+
+        let (client, server) = pair(
+            TlsClientConfig::new("localhost", TlsVerification::fingerprint(other)),
+            TlsServerConfig::new(identity),
+        )
+        .await;
+        assert_eq!(client.unwrap_err().kind(), io::ErrorKind::InvalidData);
+        assert!(server.is_err());
+    }
+
+    #[test(tokio::test)]
+    async fn connect_should_verify_server_using_ca() {
+        let (identity, ca) = TlsIdentity::generate_with_ca();
+        let (client, server) = pair(
+            TlsClientConfig::new("localhost", TlsVerification::Ca(vec![ca.clone()])),
+            TlsServerConfig::new(identity.clone()),
+        )
+        .await;
+        assert!(client.is_ok());
+        assert!(server.is_ok());
+
+        // Name of the server must match the certificate
+        let (client, _) = pair(
+            TlsClientConfig::new("example.com", TlsVerification::Ca(vec![ca])),
+            TlsServerConfig::new(identity.clone()),
+        )
+        .await;
+        assert_eq!(client.unwrap_err().kind(), io::ErrorKind::InvalidData);
+
+        // Certificate must chain to the CA
+        let (_, other_ca) = TlsIdentity::generate_with_ca();
+        let (client, _) = pair(
+            TlsClientConfig::new("localhost", TlsVerification::Ca(vec![other_ca])),
+            TlsServerConfig::new(identity),
+        )
+        .await;
+        assert_eq!(client.unwrap_err().kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test(tokio::test)]
+    async fn accept_should_require_client_certificate_for_mutual_tls() {
+        let server_identity = TlsIdentity::generate_self_signed();
+        let server_fingerprint = server_identity.fingerprint().unwrap();
+        let (client_identity, ca) = TlsIdentity::generate_with_ca();
+        let server_config = TlsServerConfig::new(server_identity)
+            .client_verification(TlsVerification::Ca(vec![ca]));
+
+        let (client, server) = pair(
+            TlsClientConfig::new(
+                "localhost",
+                TlsVerification::fingerprint(server_fingerprint),
+            )
+            .identity(client_identity.clone()),
+            server_config.clone(),
+        )
+        .await;
+        client.unwrap();
+        assert_eq!(
+            server.unwrap().peer_fingerprint(),
+            client_identity.fingerprint()
+        );
+
+        // Without a certificate, the server rejects the client once it finishes its side of the
+        // handshake, which the client learns about when it next reads
+        let (client, server) = pair(
+            TlsClientConfig::new(
+                "localhost",
+                TlsVerification::fingerprint(server_fingerprint),
+            ),
+            server_config,
+        )
+        .await;
+        assert_eq!(server.unwrap_err().kind(), io::ErrorKind::InvalidData);
+        if let Ok(client) = client {
+            let mut buf = [0; 1];
+            assert!(client.read_exact(&mut buf).await.is_err());
+        }
+    }
+}
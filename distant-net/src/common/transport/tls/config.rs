@@ -0,0 +1,434 @@
+use derive_more::{Display, Error};
+use rustls::{
+    client::{ServerCertVerified, ServerCertVerifier, WebPkiVerifier},
+    server::{AllowAnyAuthenticatedClient, ClientCertVerified, ClientCertVerifier},
+    Certificate, ClientConfig, DistinguishedName, PrivateKey, RootCertStore, ServerConfig,
+    ServerName,
+};
+use sha2::{Digest, Sha256};
+use std::{fmt, io, path::Path, str::FromStr, sync::Arc, time::SystemTime};
+
+/// Certificate chain and private key presented by one side of a TLS connection
+#[derive(Clone)]
+pub struct TlsIdentity {
+    cert_chain: Vec<Certificate>,
+    key: PrivateKey,
+}
+
+impl fmt::Debug for TlsIdentity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TlsIdentity")
+            .field("fingerprint", &self.fingerprint())
+            .field("key", &"***")
+            .finish()
+    }
+}
+
+impl TlsIdentity {
+    /// Creates an identity from a DER-encoded certificate chain, starting with the end-entity
+    /// certificate, and the DER-encoded private key of the end-entity certificate
+    pub fn new(cert_chain: Vec<Vec<u8>>, key: Vec<u8>) -> Self {
+        Self {
+            cert_chain: cert_chain.into_iter().map(Certificate).collect(),
+            key: PrivateKey(key),
+        }
+    }
+
+    /// Creates an identity from PEM-encoded certificates, starting with the end-entity
+    /// certificate, and a PEM-encoded private key in PKCS#8, PKCS#1 (RSA), or SEC1 (EC) format
+    pub fn from_pem(certs: &[u8], key: &[u8]) -> io::Result<Self> {
+        let cert_chain = rustls_pemfile::certs(&mut &*certs)?;
+        if cert_chain.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "No certificates found in PEM",
+            ));
+        }
+
+        let mut reader = key;
+        let key = loop {
+            match rustls_pemfile::read_one(&mut reader)? {
+                Some(rustls_pemfile::Item::PKCS8Key(key))
+                | Some(rustls_pemfile::Item::RSAKey(key))
+                | Some(rustls_pemfile::Item::ECKey(key)) => break key,
+                Some(_) => continue,
+                None => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "No private key found in PEM",
+                    ))
+                }
+            }
+        };
+
+        Ok(Self::new(cert_chain, key))
+    }
+
+    /// Loads an identity from a file of PEM-encoded certificates and a file containing the
+    /// PEM-encoded private key, following the rules of [`TlsIdentity::from_pem`]
+    pub async fn from_pem_files(
+        certs: impl AsRef<Path>,
+        key: impl AsRef<Path>,
+    ) -> io::Result<Self> {
+        let certs = tokio::fs::read(certs).await?;
+        let key = tokio::fs::read(key).await?;
+        Self::from_pem(&certs, &key)
+    }
+
+    /// Returns the fingerprint of the end-entity certificate, or `None` if the chain is empty
+    pub fn fingerprint(&self) -> Option<TlsFingerprint> {
+        self.cert_chain
+            .first()
+            .map(|cert| TlsFingerprint::of(&cert.0))
+    }
+}
+
+/// SHA-256 fingerprint of a DER-encoded certificate, displayed as colon-separated uppercase hex
+/// like the output of `openssl x509 -fingerprint -sha256`
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub struct TlsFingerprint([u8; 32]);
+
+impl TlsFingerprint {
+    /// Computes the fingerprint of a DER-encoded certificate
+    pub fn of(cert: &[u8]) -> Self {
+        Self(Sha256::digest(cert).into())
+    }
+
+    /// Returns the raw bytes of the fingerprint
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+}
+
+impl From<[u8; 32]> for TlsFingerprint {
+    fn from(bytes: [u8; 32]) -> Self {
+        Self(bytes)
+    }
+}
+
+impl fmt::Debug for TlsFingerprint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+impl fmt::Display for TlsFingerprint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, byte) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, ":")?;
+            }
+            write!(f, "{byte:02X}")?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Display, Error)]
+#[display(fmt = "invalid sha256 fingerprint: {}", _0)]
+pub struct TlsFingerprintParseError(#[error(not(source))] String);
+
+impl FromStr for TlsFingerprint {
+    type Err = TlsFingerprintParseError;
+
+    /// Parses 32 bytes of hex, ignoring case and optionally separated by colons
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let digits: String = s.trim().chars().filter(|c| *c != ':').collect();
+        hex::decode(digits)
+            .ok()
+            .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+            .map(Self)
+            .ok_or_else(|| TlsFingerprintParseError(s.to_string()))
+    }
+}
+
+/// How one side of a TLS connection verifies the certificate presented by the other side
+#[derive(Clone, Debug)]
+pub enum TlsVerification {
+    /// Certificate must chain to one of these DER-encoded CA certificates and, when verifying a
+    /// server, be valid for the name of the server
+    Ca(Vec<Vec<u8>>),
+
+    /// Certificate must have one of these fingerprints, which pins the exact certificate and
+    /// ignores its issuer, names, and expiration
+    Fingerprint(Vec<TlsFingerprint>),
+
+    /// Certificate must chain to one of the CA certificates trusted by the operating system and,
+    /// when verifying a server, be valid for the name of the server
+    Native,
+}
+
+impl TlsVerification {
+    /// Creates a verification against the CA certificates found in PEM
+    pub fn ca_from_pem(certs: &[u8]) -> io::Result<Self> {
+        let certs = rustls_pemfile::certs(&mut &*certs)?;
+        if certs.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "No certificates found in PEM",
+            ));
+        }
+
+        Ok(Self::Ca(certs))
+    }
+
+    /// Creates a verification that pins a single certificate by its fingerprint
+    pub fn fingerprint(fingerprint: TlsFingerprint) -> Self {
+        Self::Fingerprint(vec![fingerprint])
+    }
+
+    /// Builds the store of CA certificates to verify against, which is empty when pinning
+    /// fingerprints
+    fn root_cert_store(&self) -> io::Result<RootCertStore> {
+        let mut roots = RootCertStore::empty();
+        match self {
+            Self::Ca(certs) => {
+                for cert in certs {
+                    roots
+                        .add(&Certificate(cert.clone()))
+                        .map_err(|x| io::Error::new(io::ErrorKind::InvalidData, x))?;
+                }
+            }
+            Self::Fingerprint(_) => {}
+
+            // Operating systems commonly carry a few certificates that fail to parse, which are
+            // skipped rather than failing every connection
+            Self::Native => {
+                roots.add_parsable_certificates(&rustls_native_certs::load_native_certs()?);
+            }
+        }
+        Ok(roots)
+    }
+}
+
+/// Settings for the client side of a TLS connection
+#[derive(Clone, Debug)]
+pub struct TlsClientConfig {
+    /// Name of the server, which is sent to the server and checked against its certificate when
+    /// verifying using a CA
+    pub server_name: String,
+
+    /// How to verify the certificate of the server
+    pub verification: TlsVerification,
+
+    /// Identity to present when the server requires clients to authenticate with a certificate
+    pub identity: Option<TlsIdentity>,
+}
+
+impl TlsClientConfig {
+    /// Creates a config for connecting to the server with `server_name`, verifying its certificate
+    /// using `verification`, and presenting no client certificate
+    pub fn new(server_name: impl Into<String>, verification: TlsVerification) -> Self {
+        Self {
+            server_name: server_name.into(),
+            verification,
+            identity: None,
+        }
+    }
+
+    /// Presents `identity` to servers that require clients to authenticate with a certificate
+    pub fn identity(self, identity: TlsIdentity) -> Self {
+        Self {
+            identity: Some(identity),
+            ..self
+        }
+    }
+
+    /// Builds the rustls config and name of the server
+    pub(crate) fn build(&self) -> io::Result<(Arc<ClientConfig>, ServerName)> {
+        let server_name = ServerName::try_from(self.server_name.as_str())
+            .map_err(|x| io::Error::new(io::ErrorKind::InvalidInput, x))?;
+
+        let verifier: Arc<dyn ServerCertVerifier> = match &self.verification {
+            TlsVerification::Fingerprint(fingerprints) => {
+                Arc::new(FingerprintVerifier(fingerprints.clone()))
+            }
+            verification => Arc::new(WebPkiVerifier::new(verification.root_cert_store()?, None)),
+        };
+        let builder = ClientConfig::builder()
+            .with_safe_defaults()
+            .with_custom_certificate_verifier(verifier);
+
+        let config = match &self.identity {
+            Some(identity) => builder
+                .with_client_auth_cert(identity.cert_chain.clone(), identity.key.clone())
+                .map_err(|x| io::Error::new(io::ErrorKind::InvalidInput, x))?,
+            None => builder.with_no_client_auth(),
+        };
+
+        Ok((Arc::new(config), server_name))
+    }
+}
+
+/// Settings for the server side of a TLS connection
+#[derive(Clone, Debug)]
+pub struct TlsServerConfig {
+    /// Identity presented to clients
+    pub identity: TlsIdentity,
+
+    /// How to verify the certificates of clients, or `None` to not request certificates from
+    /// clients
+    pub client_verification: Option<TlsVerification>,
+}
+
+impl TlsServerConfig {
+    /// Creates a config presenting `identity` to clients without requesting their certificates
+    pub fn new(identity: TlsIdentity) -> Self {
+        Self {
+            identity,
+            client_verification: None,
+        }
+    }
+
+    /// Requires clients to present a certificate that passes `verification` (mutual TLS)
+    pub fn client_verification(self, verification: TlsVerification) -> Self {
+        Self {
+            client_verification: Some(verification),
+            ..self
+        }
+    }
+
+    /// Builds the rustls config
+    pub(crate) fn build(&self) -> io::Result<Arc<ServerConfig>> {
+        let builder = ServerConfig::builder().with_safe_defaults();
+        let builder = match &self.client_verification {
+            Some(TlsVerification::Fingerprint(fingerprints)) => builder
+                .with_client_cert_verifier(Arc::new(FingerprintVerifier(fingerprints.clone()))),
+            Some(verification) => builder.with_client_cert_verifier(
+                AllowAnyAuthenticatedClient::new(verification.root_cert_store()?).boxed(),
+            ),
+            None => builder.with_no_client_auth(),
+        };
+
+        let config = builder
+            .with_single_cert(self.identity.cert_chain.clone(), self.identity.key.clone())
+            .map_err(|x| io::Error::new(io::ErrorKind::InvalidInput, x))?;
+
+        Ok(Arc::new(config))
+    }
+}
+
+/// Verifies the certificate of the other side by comparing its fingerprint against a list of
+/// pinned fingerprints. The other side still proves that it holds the key of the certificate
+/// through the signature checks of the handshake.
+struct FingerprintVerifier(Vec<TlsFingerprint>);
+
+impl FingerprintVerifier {
+    fn verify(&self, end_entity: &Certificate) -> Result<(), rustls::Error> {
+        let fingerprint = TlsFingerprint::of(&end_entity.0);
+        if self.0.contains(&fingerprint) {
+            Ok(())
+        } else {
+            Err(rustls::Error::General(format!(
+                "Certificate fingerprint {fingerprint} is not pinned"
+            )))
+        }
+    }
+}
+
+impl ServerCertVerifier for FingerprintVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &Certificate,
+        _intermediates: &[Certificate],
+        _server_name: &ServerName,
+        _scts: &mut dyn Iterator<Item = &[u8]>,
+        _ocsp_response: &[u8],
+        _now: SystemTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        self.verify(end_entity)?;
+        Ok(ServerCertVerified::assertion())
+    }
+}
+
+impl ClientCertVerifier for FingerprintVerifier {
+    fn client_auth_root_subjects(&self) -> &[DistinguishedName] {
+        &[]
+    }
+
+    fn verify_client_cert(
+        &self,
+        end_entity: &Certificate,
+        _intermediates: &[Certificate],
+        _now: SystemTime,
+    ) -> Result<ClientCertVerified, rustls::Error> {
+        self.verify(end_entity)?;
+        Ok(ClientCertVerified::assertion())
+    }
+}
+
+#[cfg(test)]
+impl TlsIdentity {
+    /// Generates a self-signed identity for `localhost`
+    pub(crate) fn generate_self_signed() -> Self {
+        let cert = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+        Self::new(
+            vec![cert.serialize_der().unwrap()],
+            cert.serialize_private_key_der(),
+        )
+    }
+
+    /// Generates an identity for `localhost` signed by a new CA, returning the identity and the
+    /// DER-encoded certificate of the CA
+    pub(crate) fn generate_with_ca() -> (Self, Vec<u8>) {
+        let mut params = rcgen::CertificateParams::new(Vec::new());
+        params.is_ca = rcgen::IsCa::Ca(rcgen::BasicConstraints::Unconstrained);
+        let ca = rcgen::Certificate::from_params(params).unwrap();
+
+        let cert = rcgen::Certificate::from_params(rcgen::CertificateParams::new(vec![
+            "localhost".to_string(),
+        ]))
+        .unwrap();
+
+        let identity = Self::new(
+            vec![cert.serialize_der_with_signer(&ca).unwrap()],
+            cert.serialize_private_key_der(),
+        );
+        (identity, ca.serialize_der().unwrap())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fingerprint_should_parse_from_display_with_or_without_colons() {
+        let fingerprint = TlsFingerprint::of(b"certificate");
+        let displayed = fingerprint.to_string();
+        assert_eq!(displayed.len(), 32 * 3 - 1);
+
+        assert_eq!(displayed.parse::<TlsFingerprint>().unwrap(), fingerprint);
+        assert_eq!(
+            displayed
+                .replace(':', "")
+                .to_lowercase()
+                .parse::<TlsFingerprint>()
+                .unwrap(),
+            fingerprint
+        );
+        assert!("AB:CD".parse::<TlsFingerprint>().is_err());
+    }
+
+    #[test]
+    fn identity_should_load_from_pem() {
+        let cert = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+        let pem = cert.serialize_pem().unwrap();
+        let identity =
+            TlsIdentity::from_pem(pem.as_bytes(), cert.serialize_private_key_pem().as_bytes())
+                .unwrap();
+
+        // Each serialization signs anew, so compare against the DER within the same PEM
+        let der = rustls_pemfile::certs(&mut pem.as_bytes())
+            .unwrap()
+            .remove(0);
+        assert_eq!(identity.fingerprint(), Some(TlsFingerprint::of(&der)));
+        assert_eq!(
+            TlsIdentity::from_pem(b"", cert.serialize_private_key_pem().as_bytes())
+                .unwrap_err()
+                .kind(),
+            io::ErrorKind::InvalidData
+        );
+    }
+}
@@ -1,4 +1,4 @@
-use super::{Interest, Ready, Reconnectable, Transport};
+use super::{Interest, PeerCredentials, Ready, Reconnectable, Transport};
 use async_trait::async_trait;
 use std::{
     fmt, io,
@@ -57,6 +57,17 @@ impl Transport for UnixSocketTransport {
     async fn ready(&self, interest: Interest) -> io::Result<Ready> {
         self.inner.ready(interest).await
     }
+
+    /// Returns the credentials of the connected process as reported by the kernel (e.g.
+    /// `SO_PEERCRED` on Linux), or `None` if they could not be retrieved
+    fn peer_credentials(&self) -> Option<PeerCredentials> {
+        let cred = self.inner.peer_cred().ok()?;
+        Some(PeerCredentials {
+            uid: cred.uid(),
+            gid: cred.gid(),
+            pid: cred.pid(),
+        })
+    }
 }
 
 #[cfg(test)]
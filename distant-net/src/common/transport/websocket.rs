@@ -0,0 +1,389 @@
+use super::{Interest, Ready, Reconnectable, Transport, SLEEP_DURATION};
+use async_trait::async_trait;
+use bytes::{Buf, BytesMut};
+use std::{
+    fmt,
+    io::{self, Read, Write},
+    sync::{Arc, Mutex, MutexGuard},
+};
+use tungstenite::{
+    error::ProtocolError,
+    handshake::{HandshakeError, HandshakeRole},
+    Message, WebSocket,
+};
+
+/// Represents a [`Transport`] that carries its data as binary messages of a WebSocket connection
+/// over another transport, such as a [`TcpTransport`] or a [`TlsTransport`], so that it can pass
+/// through HTTP reverse proxies and ingresses.
+///
+/// The WebSocket handshake completes when the transport is created. Each write is sent as a
+/// single binary message, and messages are read back as one continuous stream of bytes, leaving
+/// the boundaries of frames to the [`FramedTransport`] on top.
+///
+/// [`TcpTransport`]: super::TcpTransport
+/// [`TlsTransport`]: super::TlsTransport
+/// [`FramedTransport`]: super::FramedTransport
+pub struct WebSocketTransport<T> {
+    inner: Arc<T>,
+    state: Mutex<WebSocketState<T>>,
+    role: WebSocketRole,
+}
+
+/// Side of the WebSocket connection, used to perform a new handshake when reconnecting
+#[derive(Clone, Debug)]
+enum WebSocketRole {
+    Client { url: String },
+    Server,
+}
+
+struct WebSocketState<T> {
+    socket: Option<WebSocket<TransportStream<T>>>,
+
+    /// Data of messages that has yet to be read
+    buf: BytesMut,
+
+    /// Whether the other side has closed the connection
+    closed: bool,
+}
+
+impl<T: Transport> WebSocketTransport<T> {
+    /// Performs the client side of a WebSocket handshake over `inner`, requesting `url` such as
+    /// `ws://example.com/distant`
+    pub async fn connect(inner: T, url: impl Into<String>) -> io::Result<Self> {
+        Self::new(inner, WebSocketRole::Client { url: url.into() }).await
+    }
+
+    /// Performs the server side of a WebSocket handshake over `inner`, accepting any requested
+    /// path
+    pub async fn accept(inner: T) -> io::Result<Self> {
+        Self::new(inner, WebSocketRole::Server).await
+    }
+
+    async fn new(inner: T, role: WebSocketRole) -> io::Result<Self> {
+        let inner = Arc::new(inner);
+        let socket = Self::handshake(&inner, &role).await?;
+        Ok(Self {
+            inner,
+            state: Mutex::new(WebSocketState {
+                socket: Some(socket),
+                buf: BytesMut::new(),
+                closed: false,
+            }),
+            role,
+        })
+    }
+
+    /// Returns a reference to the transport carrying the WebSocket connection
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    fn lock(&self) -> MutexGuard<'_, WebSocketState<T>> {
+        self.state.lock().unwrap()
+    }
+
+    async fn handshake(
+        inner: &Arc<T>,
+        role: &WebSocketRole,
+    ) -> io::Result<WebSocket<TransportStream<T>>> {
+        let stream = TransportStream(Arc::clone(inner));
+        match role {
+            WebSocketRole::Client { url } => {
+                let result = tungstenite::client::client_with_config(url.as_str(), stream, None);
+                let (socket, _) = Self::complete_handshake(inner, result).await?;
+                Ok(socket)
+            }
+            WebSocketRole::Server => {
+                let result = tungstenite::accept_with_config(stream, None);
+                Self::complete_handshake(inner, result).await
+            }
+        }
+    }
+
+    /// Drives a WebSocket handshake to completion, waiting on the inner transport whenever it
+    /// cannot make progress
+    async fn complete_handshake<R: HandshakeRole>(
+        inner: &T,
+        mut result: Result<R::FinalResult, HandshakeError<R>>,
+    ) -> io::Result<R::FinalResult> {
+        loop {
+            match result {
+                Ok(x) => return Ok(x),
+                Err(HandshakeError::Interrupted(handshake)) => {
+                    // NOTE: We sleep for a little bit after waiting as some transports report
+                    //       being ready before they can make progress, which would otherwise
+                    //       peg the CPU
+                    inner.ready(Interest::READABLE | Interest::WRITABLE).await?;
+                    tokio::time::sleep(SLEEP_DURATION).await;
+                    result = handshake.handshake();
+                }
+                Err(HandshakeError::Failure(x)) => return Err(into_io_error(x)),
+            }
+        }
+    }
+}
+
+impl<T> WebSocketState<T>
+where
+    T: Transport,
+{
+    fn socket(&mut self) -> io::Result<&mut WebSocket<TransportStream<T>>> {
+        self.socket
+            .as_mut()
+            .ok_or_else(|| io::Error::from(io::ErrorKind::NotConnected))
+    }
+
+    /// Writes all pending messages to the inner transport, failing with
+    /// [`io::ErrorKind::WouldBlock`] if it cannot take all of them yet
+    fn flush(&mut self) -> io::Result<()> {
+        self.socket()?.flush().map_err(into_io_error)
+    }
+
+    /// Reads every message available without waiting, buffering the data of binary messages and
+    /// failing with [`io::ErrorKind::WouldBlock`] if no data was available
+    fn read_messages(&mut self) -> io::Result<()> {
+        let mut read_any = false;
+        loop {
+            match self.socket()?.read() {
+                Ok(Message::Binary(data)) => {
+                    self.buf.extend_from_slice(&data);
+                    read_any = true;
+                }
+
+                // Text is not something we send, but its bytes are passed along all the same
+                Ok(Message::Text(text)) => {
+                    self.buf.extend_from_slice(text.as_bytes());
+                    read_any = true;
+                }
+
+                // Pings are answered by pongs queued within the socket, which are written out
+                // by the next flush
+                Ok(Message::Ping(_) | Message::Pong(_) | Message::Frame(_)) => {}
+
+                // Closing without notifying is treated like any other closed transport, as frames
+                // cut short are caught by the framing on top
+                Ok(Message::Close(_))
+                | Err(tungstenite::Error::ConnectionClosed | tungstenite::Error::AlreadyClosed)
+                | Err(tungstenite::Error::Protocol(ProtocolError::ResetWithoutClosingHandshake)) => {
+                    let _ = self.flush();
+                    self.closed = true;
+                    return Ok(());
+                }
+
+                Err(tungstenite::Error::Io(x)) if x.kind() == io::ErrorKind::WouldBlock => {
+                    match self.flush() {
+                        Err(x) if x.kind() != io::ErrorKind::WouldBlock => return Err(x),
+                        _ => {}
+                    }
+
+                    return if read_any { Ok(()) } else { Err(x) };
+                }
+
+                Err(x) => return Err(into_io_error(x)),
+            }
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for WebSocketTransport<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WebSocketTransport")
+            .field("inner", &self.inner)
+            .field("role", &self.role)
+            .finish()
+    }
+}
+
+#[async_trait]
+impl<T: Transport> Reconnectable for WebSocketTransport<T> {
+    async fn reconnect(&mut self) -> io::Result<()> {
+        // Drop the old connection first so that nothing else holds onto the inner transport
+        let state = self.state.get_mut().unwrap();
+        state.socket = None;
+        state.buf.clear();
+        state.closed = false;
+
+        Arc::get_mut(&mut self.inner)
+            .expect("Inner transport only shared with the dropped connection")
+            .reconnect()
+            .await?;
+
+        let socket = Self::handshake(&self.inner, &self.role).await?;
+        self.state.get_mut().unwrap().socket = Some(socket);
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<T: Transport> Transport for WebSocketTransport<T> {
+    fn try_read(&self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut state = self.lock();
+        if state.buf.is_empty() && !state.closed {
+            state.read_messages()?;
+        }
+
+        // Nothing left to read once closed signals the end of the transport
+        let n = buf.len().min(state.buf.len());
+        buf[..n].copy_from_slice(&state.buf[..n]);
+        state.buf.advance(n);
+        Ok(n)
+    }
+
+    fn try_write(&self, buf: &[u8]) -> io::Result<usize> {
+        let mut state = self.lock();
+
+        // Push out messages left over from earlier writes before taking more, so that a slow
+        // reader on the other side holds back writes instead of messages piling up in memory
+        state.flush()?;
+
+        // The message is buffered within the socket even if the inner transport cannot take all
+        // of it yet, so it counts as written
+        state
+            .socket()?
+            .write(Message::Binary(buf.to_vec()))
+            .map_err(into_io_error)?;
+        match state.flush() {
+            Err(x) if x.kind() != io::ErrorKind::WouldBlock => Err(x),
+            _ => Ok(buf.len()),
+        }
+    }
+
+    async fn ready(&self, interest: Interest) -> io::Result<Ready> {
+        loop {
+            let flushed = {
+                let mut state = self.lock();
+
+                // Data of messages that has yet to be read is ready without waiting
+                if interest.is_readable() && (!state.buf.is_empty() || state.closed) {
+                    return Ok(Ready::READABLE);
+                }
+
+                // Messages buffered by an earlier write are only sent by a later write or here,
+                // so keep pushing them out while the caller waits on something else
+                match state.flush() {
+                    Ok(()) => true,
+                    Err(x) if x.kind() == io::ErrorKind::WouldBlock => false,
+                    Err(x) => return Err(x),
+                }
+            };
+
+            if flushed {
+                return self.inner.ready(interest).await;
+            }
+
+            let ready = self.inner.ready(interest | Interest::WRITABLE).await?;
+            if interest.is_readable() && ready.is_readable() {
+                return Ok(Ready::READABLE);
+            }
+
+            tokio::time::sleep(SLEEP_DURATION).await;
+        }
+    }
+}
+
+/// Converts an error from the WebSocket connection into an I/O error, keeping errors of the
+/// inner transport as they are
+fn into_io_error(x: tungstenite::Error) -> io::Error {
+    match x {
+        tungstenite::Error::Io(x) => x,
+        tungstenite::Error::ConnectionClosed
+        | tungstenite::Error::AlreadyClosed
+        | tungstenite::Error::Protocol(ProtocolError::SendAfterClosing) => {
+            io::Error::new(io::ErrorKind::BrokenPipe, x)
+        }
+        x => io::Error::new(io::ErrorKind::InvalidData, x),
+    }
+}
+
+/// Adapts a shared [`Transport`] to the blocking I/O traits used by tungstenite, where the
+/// non-blocking reads and writes of the transport surface as [`io::ErrorKind::WouldBlock`]
+struct TransportStream<T>(Arc<T>);
+
+impl<T: Transport> Read for TransportStream<T> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.try_read(buf)
+    }
+}
+
+impl<T: Transport> Write for TransportStream<T> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.try_write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::{InmemoryTransport, TransportExt};
+    use test_log::test;
+
+    async fn pair() -> (
+        WebSocketTransport<InmemoryTransport>,
+        WebSocketTransport<InmemoryTransport>,
+    ) {
+        let (t1, t2) = InmemoryTransport::pair(100);
+        let task = tokio::spawn(async move { WebSocketTransport::accept(t2).await });
+        let client = WebSocketTransport::connect(t1, "ws://localhost/distant")
+            .await
+            .expect("Failed to connect");
+        (client, task.await.unwrap().expect("Failed to accept"))
+    }
+
+    #[test(tokio::test)]
+    async fn should_be_able_to_send_and_receive_data() {
+        let (client, server) = pair().await;
+
+        client.write_all(b"hello server").await.unwrap();
+        let mut buf = [0; 12];
+        server.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"hello server");
+
+        // Reading less than a message at a time keeps the rest for later reads
+        server.write_all(b"hello client").await.unwrap();
+        let mut buf = [0; 5];
+        client.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"hello");
+        let mut buf = [0; 7];
+        client.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b" client");
+
+        // Larger than the buffer of the inner transport to exercise buffering on both sides
+        let data = vec![7u8; 64 * 1024];
+        let task = tokio::spawn(async move {
+            server.write_all(&data).await.unwrap();
+            server
+        });
+        let mut buf = vec![0; 64 * 1024];
+        client.read_exact(&mut buf).await.unwrap();
+        assert!(buf.iter().all(|x| *x == 7));
+        task.await.unwrap();
+    }
+
+    #[test(tokio::test)]
+    async fn try_read_should_report_end_of_transport_once_other_side_closes() {
+        let (client, server) = pair().await;
+        client.write_all(b"bye").await.unwrap();
+        drop(client);
+
+        let mut buf = Vec::new();
+        server.read_to_end(&mut buf).await.unwrap();
+        assert_eq!(buf, b"bye");
+    }
+
+    #[test(tokio::test)]
+    async fn accept_should_fail_if_client_does_not_speak_websocket() {
+        let (t1, t2) = InmemoryTransport::pair(100);
+        let task = tokio::spawn(async move { WebSocketTransport::accept(t2).await });
+        t1.write_all(b"GET / HTTP/1.1\r\nHost: localhost\r\n\r\n")
+            .await
+            .unwrap();
+        assert_eq!(
+            task.await.unwrap().unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+    }
+}
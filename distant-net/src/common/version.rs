@@ -0,0 +1,101 @@
+use derive_more::{Display, From, Into};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+/// Version of the protocol spoken between clients and servers, which is exchanged during the
+/// handshake so that peers speaking incompatible versions fail with a clear error.
+///
+/// This is bumped following [`Version::is_compatible_with`] whenever the frames exchanged between
+/// a client and server change in a way that older peers cannot understand.
+pub const PROTOCOL_VERSION: Version = Version::new(0, 20, 0);
+
+/// Represents a semantic version, such as the version of the protocol spoken by a peer
+#[derive(
+    Clone, Debug, Display, From, Into, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize,
+)]
+#[serde(transparent)]
+pub struct Version(semver::Version);
+
+impl Version {
+    /// Creates a new version without any pre-release or build metadata
+    pub const fn new(major: u64, minor: u64, patch: u64) -> Self {
+        Self(semver::Version::new(major, minor, patch))
+    }
+
+    /// Returns the major component of the version
+    pub fn major(&self) -> u64 {
+        self.0.major
+    }
+
+    /// Returns the minor component of the version
+    pub fn minor(&self) -> u64 {
+        self.0.minor
+    }
+
+    /// Returns the patch component of the version
+    pub fn patch(&self) -> u64 {
+        self.0.patch
+    }
+
+    /// Returns true if peers speaking this version and `other` can understand each other, which
+    /// is the case when they share a major version, or when they share both a major and minor
+    /// version if the major version is zero
+    pub fn is_compatible_with(&self, other: &Version) -> bool {
+        if self.major() == 0 || other.major() == 0 {
+            self.major() == other.major() && self.minor() == other.minor()
+        } else {
+            self.major() == other.major()
+        }
+    }
+}
+
+impl FromStr for Version {
+    type Err = semver::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        semver::Version::from_str(s).map(Self)
+    }
+}
+
+#[cfg(feature = "schemars")]
+impl schemars::JsonSchema for Version {
+    fn schema_name() -> String {
+        "Version".to_string()
+    }
+
+    fn json_schema(gen: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+        String::json_schema(gen)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::utils;
+
+    #[test]
+    fn is_compatible_with_should_require_same_major_version() {
+        let version = Version::new(1, 2, 3);
+        assert!(version.is_compatible_with(&Version::new(1, 0, 0)));
+        assert!(version.is_compatible_with(&Version::new(1, 5, 0)));
+        assert!(!version.is_compatible_with(&Version::new(2, 2, 3)));
+    }
+
+    #[test]
+    fn is_compatible_with_should_require_same_minor_version_if_major_version_is_zero() {
+        let version = Version::new(0, 20, 0);
+        assert!(version.is_compatible_with(&Version::new(0, 20, 5)));
+        assert!(!version.is_compatible_with(&Version::new(0, 19, 0)));
+        assert!(!version.is_compatible_with(&Version::new(1, 20, 0)));
+    }
+
+    #[test]
+    fn version_should_serialize_as_a_string() {
+        let version: Version = "0.20.1".parse().unwrap();
+        assert_eq!(version, Version::new(0, 20, 1));
+        assert_eq!(
+            utils::serialize_to_vec(&version).unwrap(),
+            utils::serialize_to_vec(&"0.20.1").unwrap()
+        );
+    }
+}
@@ -305,7 +305,9 @@ mod tests {
     use super::*;
     use crate::client::UntypedClient;
     use crate::common::authentication::DummyAuthHandler;
-    use crate::common::{Connection, InmemoryTransport, Request, Response};
+    use crate::common::{
+        Connection, InmemoryTransport, KeyExchangeType, Request, Response, Version,
+    };
 
     fn setup() -> (ManagerClient, Connection<InmemoryTransport>) {
         let (client, server) = Connection::pair(100);
@@ -470,6 +472,8 @@ mod tests {
                 id: 123,
                 destination: "scheme://host".parse::<Destination>().unwrap(),
                 options: "key=value".parse::<Map>().unwrap(),
+                key_exchange_type: Some(KeyExchangeType::X25519),
+                peer_version: Some(Version::new(0, 20, 0)),
             };
 
             transport
@@ -1,4 +1,4 @@
-use crate::common::{ConnectionId, Destination, Map};
+use crate::common::{ConnectionId, Destination, KeyExchangeType, Map, Version};
 use serde::{Deserialize, Serialize};
 
 /// Information about a specific connection
@@ -12,4 +12,12 @@ pub struct ConnectionInfo {
 
     /// Additional options associated with this connection
     pub options: Map,
+
+    /// Key exchange algorithm negotiated with the server, missing if unknown
+    #[serde(default)]
+    pub key_exchange_type: Option<KeyExchangeType>,
+
+    /// Protocol version reported by the server, missing if unknown
+    #[serde(default)]
+    pub peer_version: Option<Version>,
 }
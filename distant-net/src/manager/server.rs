@@ -142,6 +142,8 @@ impl ManagerServer {
                 id: connection.id,
                 destination: connection.destination.clone(),
                 options: connection.options.clone(),
+                key_exchange_type: connection.key_exchange_type,
+                peer_version: connection.peer_version.clone(),
             }),
             None => Err(io::Error::new(
                 io::ErrorKind::NotConnected,
@@ -507,6 +509,8 @@ mod tests {
                 id,
                 destination: "scheme://host".parse().unwrap(),
                 options: "key=value".parse().unwrap(),
+                key_exchange_type: None,
+                peer_version: None,
             }
         );
     }
@@ -1,6 +1,8 @@
 use crate::{
     client::{Mailbox, UntypedClient},
-    common::{ConnectionId, Destination, Map, UntypedRequest, UntypedResponse},
+    common::{
+        ConnectionId, Destination, KeyExchangeType, Map, UntypedRequest, UntypedResponse, Version,
+    },
     manager::data::{ManagerChannelId, ManagerResponse},
     server::ServerReply,
 };
@@ -13,6 +15,8 @@ pub struct ManagerConnection {
     pub id: ConnectionId,
     pub destination: Destination,
     pub options: Map,
+    pub key_exchange_type: Option<KeyExchangeType>,
+    pub peer_version: Option<Version>,
     tx: mpsc::UnboundedSender<Action>,
 
     action_task: JoinHandle<()>,
@@ -63,6 +67,8 @@ impl ManagerConnection {
         client: UntypedClient,
     ) -> io::Result<Self> {
         let connection_id = rand::random();
+        let key_exchange_type = client.key_exchange_type();
+        let peer_version = client.peer_version().cloned();
         let (tx, rx) = mpsc::unbounded_channel();
 
         let (request_tx, request_rx) = mpsc::unbounded_channel();
@@ -78,6 +84,8 @@ impl ManagerConnection {
             id: connection_id,
             destination: spawn,
             options,
+            key_exchange_type,
+            peer_version,
             tx,
             action_task,
             request_task,
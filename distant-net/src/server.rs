@@ -1,4 +1,6 @@
-use crate::common::{authentication::Verifier, Listener, Response, Transport};
+use crate::common::{
+    authentication::Verifier, CompressionType, Handshake, Listener, Response, Transport,
+};
 use async_trait::async_trait;
 use log::*;
 use serde::{de::DeserializeOwned, Serialize};
@@ -85,6 +87,11 @@ impl Server<()> {
         TcpServerBuilder::default()
     }
 
+    /// Creates a new [`WebSocketServerBuilder`] that is used to construct a [`Server`].
+    pub fn websocket() -> WebSocketServerBuilder<()> {
+        WebSocketServerBuilder::default()
+    }
+
     /// Creates a new [`UnixSocketServerBuilder`] that is used to construct a [`Server`].
     #[cfg(unix)]
     pub fn unix_socket() -> UnixSocketServerBuilder<()> {
@@ -218,6 +225,12 @@ where
                     .sleep_duration(config.connection_sleep)
                     .heartbeat_duration(config.connection_heartbeat)
                     .verifier(Arc::downgrade(&verifier))
+                    .handshake(Handshake::Server {
+                        compression_types: CompressionType::known_variants().to_vec(),
+                        encryption_types: config.encryption_types.clone(),
+                        key_exchange_types: config.key_exchange_types.clone(),
+                    })
+                    .rekey_config(config.rekey)
                     .spawn(),
             );
         }
@@ -1,4 +1,6 @@
 mod tcp;
+mod tls;
+mod websocket;
 
 #[cfg(unix)]
 mod unix;
@@ -7,6 +9,8 @@ mod unix;
 mod windows;
 
 pub use tcp::*;
+pub use tls::*;
+pub use websocket::*;
 
 #[cfg(unix)]
 pub use unix::*;
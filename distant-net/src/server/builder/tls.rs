@@ -0,0 +1,144 @@
+use crate::common::{authentication::Verifier, PortRange, TlsListener, TlsServerConfig};
+use crate::server::{Server, ServerConfig, ServerHandler, TcpServerRef};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{io, net::IpAddr};
+
+pub struct TlsServerBuilder<T> {
+    server: Server<T>,
+    tls: TlsServerConfig,
+    codec_encryption: bool,
+}
+
+impl<T> Server<T> {
+    /// Consume [`Server`] and produce a builder for a variant over TCP wrapped in TLS, presenting
+    /// the identity within `tls` to clients.
+    pub fn into_tls_builder(self, tls: TlsServerConfig) -> TlsServerBuilder<T> {
+        TlsServerBuilder {
+            server: self,
+            tls,
+            codec_encryption: true,
+        }
+    }
+}
+
+impl TlsServerBuilder<()> {
+    pub fn new(tls: TlsServerConfig) -> Self {
+        Server::new().into_tls_builder(tls)
+    }
+}
+
+impl<T> TlsServerBuilder<T> {
+    pub fn config(self, config: ServerConfig) -> Self {
+        Self {
+            server: self.server.config(config),
+            ..self
+        }
+    }
+
+    pub fn handler<U>(self, handler: U) -> TlsServerBuilder<U> {
+        TlsServerBuilder {
+            server: self.server.handler(handler),
+            tls: self.tls,
+            codec_encryption: self.codec_encryption,
+        }
+    }
+
+    pub fn verifier(self, verifier: Verifier) -> Self {
+        Self {
+            server: self.server.verifier(verifier),
+            ..self
+        }
+    }
+
+    /// Configure whether to also offer encryption during the handshake of each connection, on top
+    /// of the encryption of TLS. Defaults to true, and disabling it leaves TLS as the only layer
+    /// of encryption, overriding [`ServerConfig::encryption_types`].
+    pub fn codec_encryption(self, codec_encryption: bool) -> Self {
+        Self {
+            codec_encryption,
+            ..self
+        }
+    }
+}
+
+impl<T> TlsServerBuilder<T>
+where
+    T: ServerHandler + Sync + 'static,
+    T::Request: DeserializeOwned + Send + Sync + 'static,
+    T::Response: Serialize + Send + 'static,
+    T::LocalData: Default + Send + Sync + 'static,
+{
+    pub async fn start<P>(self, addr: IpAddr, port: P) -> io::Result<TcpServerRef>
+    where
+        P: Into<PortRange> + Send,
+    {
+        let mut server = self.server;
+        if !self.codec_encryption {
+            server.config.encryption_types.clear();
+        }
+
+        let listener = TlsListener::bind(addr, port, &self.tls).await?;
+        let port = listener.port();
+        let inner = server.start(listener)?;
+        Ok(TcpServerRef { addr, port, inner })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::Client;
+    use crate::common::{
+        authentication::DummyAuthHandler, Request, TlsClientConfig, TlsIdentity, TlsVerification,
+    };
+    use crate::server::ServerCtx;
+    use async_trait::async_trait;
+    use std::net::{Ipv6Addr, SocketAddr};
+    use test_log::test;
+
+    pub struct TestServerHandler;
+
+    #[async_trait]
+    impl ServerHandler for TestServerHandler {
+        type Request = String;
+        type Response = String;
+        type LocalData = ();
+
+        async fn on_request(&self, ctx: ServerCtx<Self::Request, Self::Response, Self::LocalData>) {
+            // Echo back what we received
+            ctx.reply
+                .send(ctx.request.payload.to_string())
+                .await
+                .unwrap();
+        }
+    }
+
+    #[test(tokio::test)]
+    async fn should_invoke_handler_upon_receiving_a_request() {
+        let identity = TlsIdentity::generate_self_signed();
+        let fingerprint = identity.fingerprint().unwrap();
+
+        let server = TlsServerBuilder::new(TlsServerConfig::new(identity))
+            .handler(TestServerHandler)
+            .verifier(Verifier::none())
+            .codec_encryption(false)
+            .start(IpAddr::V6(Ipv6Addr::LOCALHOST), 0)
+            .await
+            .expect("Failed to start TLS server");
+
+        let mut client: Client<String, String> = Client::tls(
+            SocketAddr::from((server.ip_addr(), server.port())),
+            TlsClientConfig::new("localhost", TlsVerification::fingerprint(fingerprint)),
+        )
+        .auth_handler(DummyAuthHandler)
+        .connect()
+        .await
+        .expect("Client failed to connect");
+
+        let response = client
+            .send(Request::new("hello".to_string()))
+            .await
+            .expect("Failed to send message");
+        assert_eq!(response.payload, "hello");
+    }
+}
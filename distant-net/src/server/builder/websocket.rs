@@ -0,0 +1,173 @@
+use crate::common::{
+    authentication::Verifier, PortRange, TcpListener, TlsListener, TlsServerConfig,
+    WebSocketListener,
+};
+use crate::server::{Server, ServerConfig, ServerHandler, TcpServerRef};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{io, net::IpAddr};
+
+pub struct WebSocketServerBuilder<T> {
+    server: Server<T>,
+    tls: Option<TlsServerConfig>,
+}
+
+impl<T> Server<T> {
+    /// Consume [`Server`] and produce a builder for a variant accepting WebSocket connections
+    /// over TCP, such as from behind an HTTP reverse proxy.
+    pub fn into_websocket_builder(self) -> WebSocketServerBuilder<T> {
+        WebSocketServerBuilder {
+            server: self,
+            tls: None,
+        }
+    }
+}
+
+impl Default for WebSocketServerBuilder<()> {
+    fn default() -> Self {
+        Server::new().into_websocket_builder()
+    }
+}
+
+impl<T> WebSocketServerBuilder<T> {
+    pub fn config(self, config: ServerConfig) -> Self {
+        Self {
+            server: self.server.config(config),
+            ..self
+        }
+    }
+
+    pub fn handler<U>(self, handler: U) -> WebSocketServerBuilder<U> {
+        WebSocketServerBuilder {
+            server: self.server.handler(handler),
+            tls: self.tls,
+        }
+    }
+
+    pub fn verifier(self, verifier: Verifier) -> Self {
+        Self {
+            server: self.server.verifier(verifier),
+            ..self
+        }
+    }
+
+    /// Accept WebSocket connections wrapped in TLS (`wss://`), presenting the identity within
+    /// `tls` to clients
+    pub fn tls(self, tls: TlsServerConfig) -> Self {
+        Self {
+            tls: Some(tls),
+            ..self
+        }
+    }
+}
+
+impl<T> WebSocketServerBuilder<T>
+where
+    T: ServerHandler + Sync + 'static,
+    T::Request: DeserializeOwned + Send + Sync + 'static,
+    T::Response: Serialize + Send + 'static,
+    T::LocalData: Default + Send + Sync + 'static,
+{
+    pub async fn start<P>(self, addr: IpAddr, port: P) -> io::Result<TcpServerRef>
+    where
+        P: Into<PortRange> + Send,
+    {
+        let (port, inner) = match self.tls {
+            Some(tls) => {
+                let listener = TlsListener::bind(addr, port, &tls).await?;
+                let port = listener.port();
+                (port, self.server.start(WebSocketListener::new(listener))?)
+            }
+            None => {
+                let listener = TcpListener::bind(addr, port).await?;
+                let port = listener.port();
+                (port, self.server.start(WebSocketListener::new(listener))?)
+            }
+        };
+
+        Ok(TcpServerRef { addr, port, inner })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::{Client, WebSocketConnector};
+    use crate::common::{
+        authentication::DummyAuthHandler, Request, TlsClientConfig, TlsIdentity, TlsVerification,
+    };
+    use crate::server::ServerCtx;
+    use async_trait::async_trait;
+    use std::net::{Ipv6Addr, SocketAddr};
+    use test_log::test;
+
+    pub struct TestServerHandler;
+
+    #[async_trait]
+    impl ServerHandler for TestServerHandler {
+        type Request = String;
+        type Response = String;
+        type LocalData = ();
+
+        async fn on_request(&self, ctx: ServerCtx<Self::Request, Self::Response, Self::LocalData>) {
+            // Echo back what we received
+            ctx.reply
+                .send(ctx.request.payload.to_string())
+                .await
+                .unwrap();
+        }
+    }
+
+    #[test(tokio::test)]
+    async fn should_invoke_handler_upon_receiving_a_request() {
+        let server = WebSocketServerBuilder::default()
+            .handler(TestServerHandler)
+            .verifier(Verifier::none())
+            .start(IpAddr::V6(Ipv6Addr::LOCALHOST), 0)
+            .await
+            .expect("Failed to start WebSocket server");
+        let addr = SocketAddr::from((server.ip_addr(), server.port()));
+
+        let mut client: Client<String, String> = Client::websocket(format!("ws://{addr}/"))
+            .auth_handler(DummyAuthHandler)
+            .connect()
+            .await
+            .expect("Client failed to connect");
+
+        let response = client
+            .send(Request::new("hello".to_string()))
+            .await
+            .expect("Failed to send message");
+        assert_eq!(response.payload, "hello");
+    }
+
+    #[test(tokio::test)]
+    async fn should_support_websocket_over_tls() {
+        let identity = TlsIdentity::generate_self_signed();
+        let fingerprint = identity.fingerprint().unwrap();
+
+        let server = WebSocketServerBuilder::default()
+            .handler(TestServerHandler)
+            .verifier(Verifier::none())
+            .tls(TlsServerConfig::new(identity))
+            .start(IpAddr::V6(Ipv6Addr::LOCALHOST), 0)
+            .await
+            .expect("Failed to start WebSocket server");
+
+        let addr = SocketAddr::from((server.ip_addr(), server.port()));
+
+        let mut client: Client<String, String> =
+            Client::websocket(WebSocketConnector::new(format!("wss://{addr}/")).tls(
+                TlsClientConfig::new("localhost", TlsVerification::fingerprint(fingerprint)),
+            ))
+            .auth_handler(DummyAuthHandler)
+            .connect()
+            .await
+            .expect("Client failed to connect");
+
+        let response = client
+            .send(Request::new("hello".to_string()))
+            .await
+            .expect("Failed to send message");
+        assert_eq!(response.payload, "hello");
+    }
+}
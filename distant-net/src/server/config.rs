@@ -1,3 +1,4 @@
+use crate::common::{EncryptionType, KeyExchangeType, RekeyConfig};
 use derive_more::{Display, Error};
 use serde::{Deserialize, Serialize};
 use std::{num::ParseFloatError, str::FromStr, time::Duration};
@@ -16,6 +17,29 @@ pub struct ServerConfig {
 
     /// Rules for how a server will shutdown automatically
     pub shutdown: Shutdown,
+
+    /// Encryption algorithms offered to clients during the handshake, where a client picks its
+    /// preferred algorithm if offered and otherwise the first one listed
+    #[serde(default = "default_encryption_types")]
+    pub encryption_types: Vec<EncryptionType>,
+
+    /// Key exchange algorithms offered to clients during the handshake, where a client picks its
+    /// preferred algorithm if offered and otherwise the first one listed
+    #[serde(default = "default_key_exchange_types")]
+    pub key_exchange_types: Vec<KeyExchangeType>,
+
+    /// Thresholds at which a connection replaces its encryption key with a new one derived from
+    /// a fresh key exchange with the client
+    #[serde(default)]
+    pub rekey: RekeyConfig,
+}
+
+fn default_encryption_types() -> Vec<EncryptionType> {
+    EncryptionType::known_variants().to_vec()
+}
+
+fn default_key_exchange_types() -> Vec<KeyExchangeType> {
+    KeyExchangeType::known_variants().to_vec()
 }
 
 impl Default for ServerConfig {
@@ -24,6 +48,9 @@ impl Default for ServerConfig {
             connection_sleep: DEFAULT_CONNECTION_SLEEP,
             connection_heartbeat: DEFAULT_HEARTBEAT_DURATION,
             shutdown: Default::default(),
+            encryption_types: default_encryption_types(),
+            key_exchange_types: default_key_exchange_types(),
+            rekey: RekeyConfig::default(),
         }
     }
 }
@@ -4,7 +4,8 @@ use super::{
 };
 use crate::common::{
     authentication::{Keychain, Verifier},
-    Backup, Connection, Frame, Interest, Response, Transport, UntypedRequest,
+    Backup, Connection, Frame, Handshake, Interest, RekeyConfig, Response, Transport,
+    UntypedRequest,
 };
 use log::*;
 use serde::{de::DeserializeOwned, Serialize};
@@ -21,7 +22,7 @@ use tokio::{
     task::JoinHandle,
 };
 
-pub type ServerKeychain = Keychain<oneshot::Receiver<Backup>>;
+pub type ServerKeychain = Keychain<(Option<String>, oneshot::Receiver<Backup>)>;
 
 /// Time to wait inbetween connection read/write when nothing was read or written on last pass.
 const SLEEP_DURATION: Duration = Duration::from_millis(1);
@@ -62,13 +63,15 @@ impl Future for ConnectionTask {
 pub(super) struct ConnectionTaskBuilder<H, S, T> {
     handler: Weak<H>,
     state: Weak<ServerState<S>>,
-    keychain: Keychain<oneshot::Receiver<Backup>>,
+    keychain: ServerKeychain,
     transport: T,
     shutdown: broadcast::Receiver<()>,
     shutdown_timer: Weak<RwLock<ShutdownTimer>>,
     sleep_duration: Duration,
     heartbeat_duration: Duration,
     verifier: Weak<Verifier>,
+    handshake: Handshake,
+    rekey_config: RekeyConfig,
 }
 
 impl ConnectionTaskBuilder<(), (), ()> {
@@ -84,6 +87,8 @@ impl ConnectionTaskBuilder<(), (), ()> {
             sleep_duration: SLEEP_DURATION,
             heartbeat_duration: MINIMUM_HEARTBEAT_DURATION,
             verifier: Weak::new(),
+            handshake: Handshake::server(),
+            rekey_config: RekeyConfig::default(),
         }
     }
 }
@@ -100,6 +105,8 @@ impl<H, S, T> ConnectionTaskBuilder<H, S, T> {
             sleep_duration: self.sleep_duration,
             heartbeat_duration: self.heartbeat_duration,
             verifier: self.verifier,
+            handshake: self.handshake,
+            rekey_config: self.rekey_config,
         }
     }
 
@@ -114,6 +121,8 @@ impl<H, S, T> ConnectionTaskBuilder<H, S, T> {
             sleep_duration: self.sleep_duration,
             heartbeat_duration: self.heartbeat_duration,
             verifier: self.verifier,
+            handshake: self.handshake,
+            rekey_config: self.rekey_config,
         }
     }
 
@@ -128,6 +137,8 @@ impl<H, S, T> ConnectionTaskBuilder<H, S, T> {
             sleep_duration: self.sleep_duration,
             heartbeat_duration: self.heartbeat_duration,
             verifier: self.verifier,
+            handshake: self.handshake,
+            rekey_config: self.rekey_config,
         }
     }
 
@@ -142,6 +153,8 @@ impl<H, S, T> ConnectionTaskBuilder<H, S, T> {
             sleep_duration: self.sleep_duration,
             heartbeat_duration: self.heartbeat_duration,
             verifier: self.verifier,
+            handshake: self.handshake,
+            rekey_config: self.rekey_config,
         }
     }
 
@@ -156,6 +169,8 @@ impl<H, S, T> ConnectionTaskBuilder<H, S, T> {
             sleep_duration: self.sleep_duration,
             heartbeat_duration: self.heartbeat_duration,
             verifier: self.verifier,
+            handshake: self.handshake,
+            rekey_config: self.rekey_config,
         }
     }
 
@@ -173,6 +188,8 @@ impl<H, S, T> ConnectionTaskBuilder<H, S, T> {
             sleep_duration: self.sleep_duration,
             heartbeat_duration: self.heartbeat_duration,
             verifier: self.verifier,
+            handshake: self.handshake,
+            rekey_config: self.rekey_config,
         }
     }
 
@@ -187,6 +204,8 @@ impl<H, S, T> ConnectionTaskBuilder<H, S, T> {
             sleep_duration,
             heartbeat_duration: self.heartbeat_duration,
             verifier: self.verifier,
+            handshake: self.handshake,
+            rekey_config: self.rekey_config,
         }
     }
 
@@ -204,6 +223,8 @@ impl<H, S, T> ConnectionTaskBuilder<H, S, T> {
             sleep_duration: self.sleep_duration,
             heartbeat_duration,
             verifier: self.verifier,
+            handshake: self.handshake,
+            rekey_config: self.rekey_config,
         }
     }
 
@@ -218,6 +239,40 @@ impl<H, S, T> ConnectionTaskBuilder<H, S, T> {
             sleep_duration: self.sleep_duration,
             heartbeat_duration: self.heartbeat_duration,
             verifier,
+            handshake: self.handshake,
+            rekey_config: self.rekey_config,
+        }
+    }
+
+    pub fn handshake(self, handshake: Handshake) -> ConnectionTaskBuilder<H, S, T> {
+        ConnectionTaskBuilder {
+            handler: self.handler,
+            state: self.state,
+            keychain: self.keychain,
+            transport: self.transport,
+            shutdown: self.shutdown,
+            shutdown_timer: self.shutdown_timer,
+            sleep_duration: self.sleep_duration,
+            heartbeat_duration: self.heartbeat_duration,
+            verifier: self.verifier,
+            handshake,
+            rekey_config: self.rekey_config,
+        }
+    }
+
+    pub fn rekey_config(self, rekey_config: RekeyConfig) -> ConnectionTaskBuilder<H, S, T> {
+        ConnectionTaskBuilder {
+            handler: self.handler,
+            state: self.state,
+            keychain: self.keychain,
+            transport: self.transport,
+            shutdown: self.shutdown,
+            shutdown_timer: self.shutdown_timer,
+            sleep_duration: self.sleep_duration,
+            heartbeat_duration: self.heartbeat_duration,
+            verifier: self.verifier,
+            handshake: self.handshake,
+            rekey_config,
         }
     }
 }
@@ -245,6 +300,8 @@ where
             sleep_duration,
             heartbeat_duration,
             verifier,
+            handshake,
+            rekey_config,
         } = self;
 
         // NOTE: This exists purely to make the compiler happy for macro_rules declaration order.
@@ -412,6 +469,7 @@ where
             Some(verifier) => {
                 match await_or_shutdown!(Box::pin(Connection::server(
                     transport,
+                    handshake,
                     verifier.as_ref(),
                     keychain
                 ))) {
@@ -428,12 +486,14 @@ where
 
         // Update our id to be the connection id
         let id = connection.id();
+        connection.set_rekey_config(rekey_config);
 
         // Create local data for the connection and then process it
         debug!("[Conn {id}] Officially accepting connection");
         let mut local_data = H::LocalData::default();
         if let Err(x) = await_or_shutdown!(handler.on_accept(ConnectionCtx {
             connection_id: id,
+            username: connection.username().map(ToString::to_string),
             local_data: &mut local_data
         })) {
             terminate_connection!(@fatal "[Conn {id}] Accepting connection failed: {x}");
@@ -22,6 +22,10 @@ pub struct ConnectionCtx<'a, D> {
     /// Unique identifer associated with the connection
     pub connection_id: ConnectionId,
 
+    /// Name of the user that authenticated the connection, if the authentication method
+    /// identified one
+    pub username: Option<String>,
+
     /// Reference to the connection's local data
     pub local_data: &'a mut D,
 }
@@ -14,6 +14,9 @@ pub trait Reply: Send + Sync {
 
     /// Clones this reply
     fn clone_reply(&self) -> Box<dyn Reply<Data = Self::Data>>;
+
+    /// Returns true if replies can no longer be sent, such as when the connection has closed
+    fn is_closed(&self) -> bool;
 }
 
 impl<T: Send + 'static> Reply for mpsc::Sender<T> {
@@ -35,6 +38,10 @@ impl<T: Send + 'static> Reply for mpsc::Sender<T> {
     fn clone_reply(&self) -> Box<dyn Reply<Data = Self::Data>> {
         Box::new(self.clone())
     }
+
+    fn is_closed(&self) -> bool {
+        mpsc::Sender::is_closed(self)
+    }
 }
 
 /// Utility to send ad-hoc replies from the server back through the connection
@@ -93,6 +100,10 @@ impl<T: Send + 'static> Reply for ServerReply<T> {
     fn clone_reply(&self) -> Box<dyn Reply<Data = Self::Data>> {
         Box::new(self.clone())
     }
+
+    fn is_closed(&self) -> bool {
+        ServerReply::is_closed(self)
+    }
 }
 
 /// Represents a reply where all sends are queued up but not sent until
@@ -195,4 +206,8 @@ impl<T: Send + 'static> Reply for QueuedServerReply<T> {
     fn clone_reply(&self) -> Box<dyn Reply<Data = Self::Data>> {
         Box::new(self.clone())
     }
+
+    fn is_closed(&self) -> bool {
+        QueuedServerReply::is_closed(self)
+    }
 }
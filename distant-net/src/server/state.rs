@@ -8,8 +8,8 @@ pub struct ServerState<T> {
     /// Mapping of connection ids to their tasks.
     pub connections: RwLock<HashMap<ConnectionId, ConnectionState<T>>>,
 
-    /// Mapping of connection ids to (OTP, backup)
-    pub keychain: Keychain<oneshot::Receiver<Backup>>,
+    /// Mapping of connection ids to (OTP, (username, backup))
+    pub keychain: Keychain<(Option<String>, oneshot::Receiver<Backup>)>,
 }
 
 impl<T> ServerState<T> {
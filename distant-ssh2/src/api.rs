@@ -1,16 +1,18 @@
 use crate::{
     process::{spawn_pty, spawn_simple, SpawnResult},
+    search::{spawn_search, SearchPrograms},
     utils::{self, to_other_error},
+    watcher::{PollWatcher, Watchers},
 };
 use async_compat::CompatExt;
 use async_once_cell::OnceCell;
 use async_trait::async_trait;
 use distant_core::{
     data::{
-        Capabilities, CapabilityKind, DirEntry, Environment, FileType, Metadata, ProcessId,
-        PtySize, SystemInfo, UnixMetadata,
+        Capabilities, CapabilityKind, ChangeKind, ChangeKindSet, DirEntry, Environment, FileType,
+        Metadata, ProcessId, PtySize, SearchId, SearchQuery, SystemInfo, WatchOptions,
     },
-    net::server::ConnectionCtx,
+    net::{common::PROTOCOL_VERSION, server::ConnectionCtx},
     DistantApi, DistantCtx,
 };
 use log::*;
@@ -18,11 +20,11 @@ use std::{
     collections::{HashMap, HashSet},
     io,
     path::PathBuf,
-    sync::{Arc, Weak},
+    sync::{atomic::AtomicU64, Arc, Weak},
     time::Duration,
 };
-use tokio::sync::{mpsc, RwLock};
-use wezterm_ssh::{FilePermissions, OpenFileType, OpenOptions, Session as WezSession, WriteMode};
+use tokio::sync::{mpsc, oneshot, RwLock};
+use wezterm_ssh::{OpenFileType, OpenOptions, Session as WezSession, WriteMode};
 
 /// Time after copy completes to wait for stdout/stderr to close
 const COPY_COMPLETE_TIMEOUT: Duration = Duration::from_secs(1);
@@ -35,6 +37,9 @@ pub struct ConnectionState {
     /// Internal reference to global process list for removals
     /// NOTE: Initialized during `on_accept` of [`DistantApi`]
     global_processes: Weak<RwLock<HashMap<ProcessId, Process>>>,
+
+    /// Paths being watched, which stop being watched when the connection terminates
+    watchers: RwLock<Watchers>,
 }
 
 struct Process {
@@ -50,6 +55,19 @@ pub struct SshDistantApi {
 
     /// Global tracking of running processes by id
     processes: Arc<RwLock<HashMap<ProcessId, Process>>>,
+
+    /// Sequence number of the last change observed by any watcher
+    change_seq: Arc<AtomicU64>,
+
+    /// Global tracking of active searches by id, holding the means to cancel each search
+    searches: Arc<RwLock<HashMap<SearchId, oneshot::Sender<()>>>>,
+
+    /// Whether ripgrep is available on the remote machine to perform searches
+    has_ripgrep: OnceCell<bool>,
+
+    /// Whether grep on the remote machine supports perl-compatible regular expressions, used to
+    /// search contents when ripgrep is not available
+    has_grep_perl_regex: OnceCell<bool>,
 }
 
 impl SshDistantApi {
@@ -57,6 +75,10 @@ impl SshDistantApi {
         Self {
             session,
             processes: Arc::new(RwLock::new(HashMap::new())),
+            change_seq: Arc::new(AtomicU64::new(0)),
+            searches: Arc::new(RwLock::new(HashMap::new())),
+            has_ripgrep: OnceCell::new(),
+            has_grep_perl_regex: OnceCell::new(),
         }
     }
 
@@ -86,10 +108,19 @@ impl DistantApi for SshDistantApi {
 
         let mut capabilities = Capabilities::all();
 
-        // Searching is not supported by ssh implementation
-        // TODO: Could we have external search using ripgrep's JSON lines API?
-        capabilities.take(CapabilityKind::Search);
-        capabilities.take(CapabilityKind::CancelSearch);
+        // Searching is performed by running ripgrep or find and grep on the remote machine, which
+        // has no index to build or fuzzy matching of files
+        capabilities.take(CapabilityKind::FindFiles);
+        capabilities.take(CapabilityKind::SearchIndex);
+        capabilities.take(CapabilityKind::SearchUnindex);
+
+        // Watching is implemented by polling over sftp, which does not remember changes that
+        // can be replayed when resuming a watch
+        capabilities.take(CapabilityKind::WatchResume);
+
+        // Tailing files is not supported by ssh implementation
+        capabilities.take(CapabilityKind::FileTail);
+        capabilities.take(CapabilityKind::CancelTail);
 
         Ok(capabilities)
     }
@@ -609,6 +640,73 @@ impl DistantApi for SshDistantApi {
         Ok(())
     }
 
+    async fn watch(
+        &self,
+        ctx: DistantCtx<Self::LocalData>,
+        path: PathBuf,
+        recursive: bool,
+        only: Vec<ChangeKind>,
+        except: Vec<ChangeKind>,
+        options: WatchOptions,
+    ) -> io::Result<()> {
+        debug!(
+            "[Conn {}] Watching {:?} {{recursive: {}, only: {:?}, except: {:?}, options: {:?}}}",
+            ctx.connection_id, path, recursive, only, except, options
+        );
+
+        let sftp = self.session.sftp();
+        let path = utils::canonicalize(&sftp, path).await?;
+
+        // Calculate the true list of kinds based on only and except filters
+        let only: ChangeKindSet = only.into_iter().collect();
+        let except: ChangeKindSet = except.into_iter().collect();
+        let allowed = if only.is_empty() {
+            ChangeKindSet::all() - except
+        } else {
+            only - except
+        };
+
+        let task = PollWatcher::new(
+            sftp,
+            path.to_path_buf(),
+            recursive,
+            allowed,
+            &options.include,
+            &options.exclude,
+            options.poll_interval_ms.map(Duration::from_millis),
+            Some(options.debounce_ms)
+                .filter(|ms| *ms > 0)
+                .map(Duration::from_millis),
+            options.max_debounce_wait(),
+            options.coalesce,
+            options.metadata,
+            Arc::clone(&self.change_seq),
+            ctx.reply,
+        )?
+        .spawn()
+        .await?;
+
+        ctx.local_data.watchers.write().await.insert(path, task);
+        Ok(())
+    }
+
+    async fn unwatch(&self, ctx: DistantCtx<Self::LocalData>, path: PathBuf) -> io::Result<()> {
+        debug!("[Conn {}] Unwatching {:?}", ctx.connection_id, path);
+
+        let path = utils::canonicalize(&self.session.sftp(), path.as_path())
+            .await
+            .unwrap_or(path);
+
+        if ctx.local_data.watchers.write().await.remove(&path) {
+            Ok(())
+        } else {
+            Err(io::Error::new(
+                io::ErrorKind::Other,
+                format!("{:?} is not being watched", path),
+            ))
+        }
+    }
+
     async fn exists(&self, ctx: DistantCtx<Self::LocalData>, path: PathBuf) -> io::Result<bool> {
         debug!("[Conn {}] Checking if {:?} exists", ctx.connection_id, path);
 
@@ -653,39 +751,79 @@ impl DistantApi for SshDistantApi {
                 .map_err(to_other_error)?
         };
 
-        let file_type = if metadata.is_dir() {
-            FileType::Dir
-        } else if metadata.is_file() {
-            FileType::File
-        } else {
-            FileType::Symlink
+        Ok(utils::to_metadata(metadata, canonicalized_path))
+    }
+
+    async fn search(
+        &self,
+        ctx: DistantCtx<Self::LocalData>,
+        query: SearchQuery,
+    ) -> io::Result<SearchId> {
+        debug!(
+            "[Conn {}] Performing search via {:?}",
+            ctx.connection_id, query
+        );
+
+        if self.is_windows().await? {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "Searching is unsupported on windows",
+            ));
+        }
+
+        let has_ripgrep = *self
+            .has_ripgrep
+            .get_or_try_init(utils::has_program(&self.session, "rg"))
+            .await?;
+        let has_grep_perl_regex = !has_ripgrep
+            && *self
+                .has_grep_perl_regex
+                .get_or_try_init(utils::grep_supports_perl_regex(&self.session))
+                .await?;
+
+        let searches = Arc::downgrade(&self.searches);
+        let cleanup = |id: SearchId| async move {
+            if let Some(searches) = Weak::upgrade(&searches) {
+                searches.write().await.remove(&id);
+            }
         };
 
-        Ok(Metadata {
-            canonicalized_path,
-            file_type,
-            len: metadata.size.unwrap_or(0),
-            // Check that owner, group, or other has write permission (if not, then readonly)
-            readonly: metadata
-                .permissions
-                .map(FilePermissions::is_readonly)
-                .unwrap_or(true),
-            accessed: metadata.accessed.map(u128::from),
-            modified: metadata.modified.map(u128::from),
-            created: None,
-            unix: metadata.permissions.as_ref().map(|p| UnixMetadata {
-                owner_read: p.owner_read,
-                owner_write: p.owner_write,
-                owner_exec: p.owner_exec,
-                group_read: p.group_read,
-                group_write: p.group_write,
-                group_exec: p.group_exec,
-                other_read: p.other_read,
-                other_write: p.other_write,
-                other_exec: p.other_exec,
-            }),
-            windows: None,
-        })
+        // NOTE: We hold onto the lock while spawning so the search cannot finish and be cleaned
+        //       up before we have added it
+        let mut searches = self.searches.write().await;
+        let (id, cancel_tx) = spawn_search(
+            &self.session,
+            query,
+            SearchPrograms {
+                ripgrep: has_ripgrep,
+                grep_perl_regex: has_grep_perl_regex,
+            },
+            ctx.reply,
+            cleanup,
+        )
+        .await?;
+        searches.insert(id, cancel_tx);
+
+        Ok(id)
+    }
+
+    async fn cancel_search(
+        &self,
+        ctx: DistantCtx<Self::LocalData>,
+        id: SearchId,
+    ) -> io::Result<()> {
+        debug!("[Conn {}] Cancelling search {}", ctx.connection_id, id);
+
+        match self.searches.write().await.remove(&id) {
+            Some(cancel_tx) => {
+                let _ = cancel_tx.send(());
+                Ok(())
+            }
+            None => Err(io::Error::new(
+                io::ErrorKind::Other,
+                format!("No search found with id {}", id),
+            )),
+        }
     }
 
     async fn proc_spawn(
@@ -880,6 +1018,8 @@ impl DistantApi for SshDistantApi {
             main_separator: if is_windows { '\\' } else { '/' },
             username,
             shell,
+            protocol_version: Some(PROTOCOL_VERSION),
+            watch_backend: Some(String::from("sftp polling")),
         })
     }
 }
@@ -0,0 +1,711 @@
+use crate::utils::to_other_error;
+use async_compat::CompatExt;
+use distant_core::{
+    data::{
+        DistantResponseData, FileType, SearchId, SearchQuery, SearchQueryContentsMatch,
+        SearchQueryMatch, SearchQueryMatchData, SearchQueryOptions, SearchQueryPathMatch,
+        SearchQuerySubmatch, SearchQueryTarget,
+    },
+    net::server::Reply,
+};
+use log::*;
+use regex::bytes::Regex;
+use serde_json::Value;
+use std::{
+    future::Future,
+    io::{self, Read},
+    path::{Component, Path, PathBuf},
+    time::Duration,
+};
+use tokio::sync::oneshot::{self, error::TryRecvError};
+use wezterm_ssh::{Child, ChildKiller, ExecResult, Session, SshChildProcess};
+
+const MAX_PIPE_CHUNK_SIZE: usize = 8192;
+const THREAD_PAUSE_MILLIS: u64 = 1;
+
+/// Program run on the remote machine to perform a search, which determines the format of the
+/// output that we parse into matches
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum SearchProgram {
+    /// `rg --json`, printing each match within contents as a line of JSON
+    RipgrepJson,
+
+    /// `rg --files --null`, printing each file path followed by a null byte
+    RipgrepFiles,
+
+    /// `find -print0`, printing each path followed by a null byte
+    Find,
+
+    /// `find -exec grep`, printing each matching line as `path\0line_number:offset:line`
+    Grep,
+}
+
+impl SearchProgram {
+    /// Byte that ends each record printed by the program
+    fn delimiter(self) -> u8 {
+        match self {
+            Self::RipgrepJson | Self::Grep => b'\n',
+            Self::RipgrepFiles | Self::Find => b'\0',
+        }
+    }
+}
+
+/// Programs available on the remote machine that determine how a search is performed
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct SearchPrograms {
+    /// Whether ripgrep is installed
+    pub ripgrep: bool,
+
+    /// Whether grep supports perl-compatible regular expressions via `-P`
+    pub grep_perl_regex: bool,
+}
+
+/// Spawns a search on the remote machine, using ripgrep if available and otherwise falling back
+/// to find and grep, returning the id of the search and a sender to cancel it
+///
+/// Matches are sent as [`DistantResponseData::SearchResults`] in pages of the query's pagination
+/// size, followed by [`DistantResponseData::SearchDone`] once the program has finished, the
+/// query's limit has been reached, or the search has been cancelled. `cleanup` is invoked with
+/// the id of the search once done.
+pub async fn spawn_search<F, R>(
+    session: &Session,
+    query: SearchQuery,
+    programs: SearchPrograms,
+    reply: Box<dyn Reply<Data = DistantResponseData>>,
+    cleanup: F,
+) -> io::Result<(SearchId, oneshot::Sender<()>)>
+where
+    F: FnOnce(SearchId) -> R + Send + 'static,
+    R: Future<Output = ()> + Send + 'static,
+{
+    let (program, cmd) = build_command(&query, programs)?;
+    let parser = SearchOutputParser::new(program, &query)?;
+
+    let ExecResult {
+        stdin: _stdin,
+        mut stdout,
+        stderr: _stderr,
+        child,
+    } = session
+        .exec(&cmd, None)
+        .compat()
+        .await
+        .map_err(to_other_error)?;
+    stdout.set_non_blocking(true).map_err(to_other_error)?;
+
+    let id = rand::random();
+    let (cancel_tx, cancel_rx) = oneshot::channel();
+    let reporter = SearchReporter::new(id, &query.options, reply);
+    let session = session.clone();
+
+    tokio::spawn(async move {
+        run_search(
+            id, session, child, stdout, program, parser, reporter, cancel_rx,
+        )
+        .await;
+        cleanup(id).await;
+    });
+
+    Ok((id, cancel_tx))
+}
+
+/// Reads the output of the search program, sending matches until the program has finished, the
+/// limit has been reached, or the search has been cancelled
+#[allow(clippy::too_many_arguments)]
+async fn run_search(
+    id: SearchId,
+    session: Session,
+    mut child: SshChildProcess,
+    mut stdout: impl Read,
+    program: SearchProgram,
+    parser: SearchOutputParser,
+    mut reporter: SearchReporter,
+    mut cancel_rx: oneshot::Receiver<()>,
+) {
+    let delimiter = program.delimiter();
+    let mut buf: [u8; MAX_PIPE_CHUNK_SIZE] = [0; MAX_PIPE_CHUNK_SIZE];
+    let mut pending = Vec::new();
+    let mut exited = false;
+    let mut stopped = false;
+
+    trace!("[Ssh | Query {id}] Reading output of {program:?}");
+    while !stopped {
+        // Stop if the search was cancelled or can no longer be cancelled as it was dropped
+        if !matches!(cancel_rx.try_recv(), Err(TryRecvError::Empty)) {
+            trace!("[Ssh | Query {id}] Cancelled");
+            stopped = true;
+            break;
+        }
+
+        let n = match stdout.read(&mut buf) {
+            Ok(n) if n > 0 => n,
+            Ok(_) => break,
+            Err(x) if x.kind() == io::ErrorKind::WouldBlock => {
+                // NOTE: When the ssh backend is libssh, the non-blocking reader never reports
+                //       Ok(0), so we stop once there is nothing left to read after the program
+                //       has exited
+                if exited {
+                    break;
+                }
+                exited = matches!(child.try_wait(), Ok(Some(_)));
+
+                tokio::time::sleep(Duration::from_millis(THREAD_PAUSE_MILLIS)).await;
+                continue;
+            }
+            Err(x) => {
+                error!("[Ssh | Query {id}] Output unexpectedly closed: {x}");
+                break;
+            }
+        };
+
+        pending.extend_from_slice(&buf[..n]);
+        while let Some(i) = pending.iter().position(|b| *b == delimiter) {
+            let record: Vec<u8> = pending.drain(..=i).collect();
+            if let Some(m) = parser.parse(&record[..i]) {
+                if !reporter.push(m).await {
+                    stopped = true;
+                    break;
+                }
+            }
+        }
+    }
+
+    // The last record may not be followed by a delimiter
+    if !stopped && !pending.is_empty() {
+        if let Some(m) = parser.parse(&pending) {
+            reporter.push(m).await;
+        }
+    }
+
+    if !matches!(child.try_wait(), Ok(Some(_))) {
+        debug!("[Ssh | Query {id}] Killing {program:?}");
+        if let Err(x) = child.kill() {
+            error!("[Ssh | Query {id}] Unable to kill {program:?}: {x}");
+        }
+
+        // NOTE: At the moment, child.kill does nothing for wezterm_ssh::SshChildProcess;
+        //       so, we need to manually run kill to make sure that the process is sent a
+        //       kill signal
+        if let Some(pid) = child.process_id() {
+            let _ = session
+                .exec(&format!("kill -9 {}", pid), None)
+                .compat()
+                .await;
+        }
+    }
+
+    reporter.finish().await;
+}
+
+/// Builds the shell command to run on the remote machine to perform `query`
+fn build_command(
+    query: &SearchQuery,
+    programs: SearchPrograms,
+) -> io::Result<(SearchProgram, String)> {
+    let has_ripgrep = programs.ripgrep;
+    let options = &query.options;
+    if options.search_archives {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "Searching archives is not supported over ssh",
+        ));
+    }
+
+    if !has_ripgrep && !options.ignore_files.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "Ignore files require ripgrep to be installed on the remote machine",
+        ));
+    }
+
+    // Ripgrep only lists files, so we can only use it to search paths when nothing else is wanted
+    let only_files = options.allowed_file_types.len() == 1
+        && options.allowed_file_types.contains(&FileType::File);
+
+    let mut args: Vec<String> = Vec::new();
+    let program = match query.target {
+        SearchQueryTarget::Contents if has_ripgrep => {
+            args.extend(["rg", "--no-config", "--json"].map(String::from));
+            args.extend(ripgrep_args(options));
+            args.push(String::from("--regexp"));
+            args.push(query.condition.to_regex_string());
+            args.push(String::from("--"));
+            args.extend(query.paths.iter().map(|p| p.to_string_lossy().to_string()));
+            SearchProgram::RipgrepJson
+        }
+        SearchQueryTarget::Path if has_ripgrep && only_files => {
+            args.extend(["rg", "--no-config", "--files", "--null"].map(String::from));
+            args.extend(ripgrep_args(options));
+            args.push(String::from("--"));
+            args.extend(query.paths.iter().map(|p| p.to_string_lossy().to_string()));
+            SearchProgram::RipgrepFiles
+        }
+        SearchQueryTarget::Contents => {
+            // Extended regular expressions lack much of the syntax of our regular expressions,
+            // so fall back to them only when the regular expression means the same thing
+            let regex = query.condition.to_regex_string();
+            let flags = if programs.grep_perl_regex {
+                "-nbHZP"
+            } else if is_extended_regex(&regex) {
+                "-nbHZE"
+            } else {
+                return Err(io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    format!(
+                        "Regex {regex:?} is not supported by grep on the remote machine, \
+                        install ripgrep or a grep that supports -P to search with it"
+                    ),
+                ));
+            };
+
+            args.extend(find_args(query, &[FileType::File]));
+            args.extend(["-exec", "grep", flags].map(String::from));
+            args.push(String::from(if options.binary { "-a" } else { "-I" }));
+            args.push(String::from("-e"));
+            args.push(regex);
+            args.extend(["--", "{}", "+"].map(String::from));
+            SearchProgram::Grep
+        }
+        SearchQueryTarget::Path => {
+            let file_types: Vec<FileType> = options.allowed_file_types.iter().copied().collect();
+            args.extend(find_args(query, &file_types));
+            args.push(String::from("-print0"));
+            SearchProgram::Find
+        }
+    };
+
+    // Errors are discarded so that the program cannot block on a full stderr that we never read
+    let inner = format!("{} 2>/dev/null", shell_words::join(args));
+    Ok((
+        program,
+        format!("/bin/sh -c {}", shell_words::quote(&inner)),
+    ))
+}
+
+/// Returns the arguments shared by every ripgrep invocation to apply `options`
+fn ripgrep_args(options: &SearchQueryOptions) -> Vec<String> {
+    let mut args = Vec::new();
+    if let Some(depth) = options.max_depth {
+        args.push(String::from("--max-depth"));
+        args.push(depth.to_string());
+    }
+    if options.follow_symbolic_links {
+        args.push(String::from("--follow"));
+    }
+    if options.hidden {
+        args.push(String::from("--hidden"));
+    }
+    if options.no_ignore {
+        args.push(String::from("--no-ignore"));
+    }
+    if options.no_ignore_vcs {
+        args.push(String::from("--no-ignore-vcs"));
+    }
+    for path in options.ignore_files.iter() {
+        args.push(String::from("--ignore-file"));
+        args.push(path.to_string_lossy().to_string());
+    }
+    if options.binary {
+        args.push(String::from("--binary"));
+    }
+    args
+}
+
+/// Returns the arguments to run find over the paths of `query`, limited to `file_types` unless
+/// empty
+///
+/// Hidden paths are filtered out when parsing the output rather than by find, as find cannot
+/// tell them apart from search paths that are themselves hidden.
+fn find_args(query: &SearchQuery, file_types: &[FileType]) -> Vec<String> {
+    let mut args = vec![String::from("find")];
+    if query.options.follow_symbolic_links {
+        args.push(String::from("-L"));
+    }
+    args.extend(query.paths.iter().map(|p| escape_find_path(p)));
+    if let Some(depth) = query.options.max_depth {
+        args.push(String::from("-maxdepth"));
+        args.push(depth.to_string());
+    }
+
+    if !file_types.is_empty() {
+        args.push(String::from("("));
+        for (i, file_type) in file_types.iter().enumerate() {
+            if i > 0 {
+                args.push(String::from("-o"));
+            }
+            args.push(String::from("-type"));
+            args.push(String::from(match file_type {
+                FileType::Dir => "d",
+                FileType::File => "f",
+                FileType::Symlink => "l",
+            }));
+        }
+        args.push(String::from(")"));
+    }
+
+    args
+}
+
+/// Prefixes `path` with `./` if it starts with `-`, as find has no way to mark the end of its
+/// options and would otherwise treat the path as part of its expression
+fn escape_find_path(path: &Path) -> String {
+    let path = path.to_string_lossy();
+    if path.starts_with('-') {
+        format!("./{path}")
+    } else {
+        path.to_string()
+    }
+}
+
+/// Returns true if `regex` has the same meaning as a POSIX extended regular expression, meaning
+/// that it only escapes punctuation, does not escape within brackets, and does not use groups
+/// with flags or lazy repetition
+fn is_extended_regex(regex: &str) -> bool {
+    let mut chars = regex.chars().peekable();
+    let mut in_brackets = false;
+    let mut prev = None;
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some(next) if !in_brackets && next.is_ascii_punctuation() => {
+                    prev = None;
+                    continue;
+                }
+                _ => return false,
+            },
+            '[' if !in_brackets => {
+                in_brackets = true;
+
+                // A closing bracket at the start of brackets is part of them
+                chars.next_if_eq(&'^');
+                chars.next_if_eq(&']');
+            }
+            ']' if in_brackets => in_brackets = false,
+            '(' if !in_brackets && chars.peek() == Some(&'?') => return false,
+            '?' if !in_brackets && matches!(prev, Some('*' | '+' | '?' | '}')) => return false,
+            _ => {}
+        }
+        prev = Some(c);
+    }
+
+    true
+}
+
+/// Converts records printed by a search program into matches of a query
+struct SearchOutputParser {
+    program: SearchProgram,
+    target: SearchQueryTarget,
+    matcher: Regex,
+    include: Option<Regex>,
+    exclude: Option<Regex>,
+    paths: Vec<PathBuf>,
+    hidden: bool,
+}
+
+impl SearchOutputParser {
+    fn new(program: SearchProgram, query: &SearchQuery) -> io::Result<Self> {
+        let build = |regex: String| {
+            Regex::new(&regex).map_err(|x| io::Error::new(io::ErrorKind::InvalidInput, x))
+        };
+
+        Ok(Self {
+            program,
+            target: query.target,
+            matcher: build(query.condition.to_regex_string())?,
+            include: query
+                .options
+                .include
+                .as_ref()
+                .map(|c| build(c.to_regex_string()))
+                .transpose()?,
+            exclude: query
+                .options
+                .exclude
+                .as_ref()
+                .map(|c| build(c.to_regex_string()))
+                .transpose()?,
+            paths: query.paths.clone(),
+            hidden: query.options.hidden,
+        })
+    }
+
+    /// Parses a single record, returning a match if the record represents one that is allowed by
+    /// the query
+    fn parse(&self, record: &[u8]) -> Option<SearchQueryMatch> {
+        let m = match self.program {
+            SearchProgram::RipgrepJson => self.parse_ripgrep_json(record),
+            SearchProgram::RipgrepFiles | SearchProgram::Find => self.parse_path(record),
+            SearchProgram::Grep => self.parse_grep(record),
+        }?;
+
+        let path = match &m {
+            SearchQueryMatch::Path(m) => m.path.as_path(),
+            SearchQueryMatch::Contents(m) => m.path.as_path(),
+        };
+
+        if self.is_filtered(path) {
+            None
+        } else {
+            Some(m)
+        }
+    }
+
+    /// Returns true if `path` should be excluded from the results
+    fn is_filtered(&self, path: &Path) -> bool {
+        let path_str = path.to_string_lossy();
+        if let Some(include) = self.include.as_ref() {
+            if !include.is_match(path_str.as_bytes()) {
+                return true;
+            }
+        }
+        if let Some(exclude) = self.exclude.as_ref() {
+            if exclude.is_match(path_str.as_bytes()) {
+                return true;
+            }
+        }
+
+        // Ripgrep skips hidden paths itself
+        let from_find = matches!(self.program, SearchProgram::Find | SearchProgram::Grep);
+        from_find && !self.hidden && self.is_hidden(path)
+    }
+
+    /// Returns true if any component of `path` beneath the search path containing it is hidden
+    fn is_hidden(&self, path: &Path) -> bool {
+        let relative = self
+            .paths
+            .iter()
+            .filter_map(|root| path.strip_prefix(root).ok())
+            .min_by_key(|relative| relative.components().count());
+
+        match relative {
+            Some(relative) => relative.components().any(|c| match c {
+                Component::Normal(name) => name.to_string_lossy().starts_with('.'),
+                _ => false,
+            }),
+            None => false,
+        }
+    }
+
+    /// Removes the `./` added by [`escape_find_path`] to a search path starting with `-` from
+    /// `path` printed by find
+    fn unescape_find_path(&self, path: PathBuf) -> PathBuf {
+        if self.program != SearchProgram::Find && self.program != SearchProgram::Grep {
+            return path;
+        }
+
+        let is_escaped = self.paths.iter().any(|root| {
+            root.to_string_lossy().starts_with('-') && path.starts_with(Path::new(".").join(root))
+        });
+        match path.strip_prefix(".") {
+            Ok(unescaped) if is_escaped => unescaped.to_path_buf(),
+            _ => path,
+        }
+    }
+
+    fn parse_path(&self, record: &[u8]) -> Option<SearchQueryMatch> {
+        if record.is_empty() {
+            return None;
+        }
+
+        let path =
+            self.unescape_find_path(PathBuf::from(String::from_utf8_lossy(record).to_string()));
+        match self.target {
+            SearchQueryTarget::Path => {
+                let submatches = self.submatches(path.to_string_lossy().as_bytes());
+                if submatches.is_empty() {
+                    None
+                } else {
+                    Some(SearchQueryMatch::Path(SearchQueryPathMatch {
+                        path,
+                        submatches,
+                    }))
+                }
+            }
+            SearchQueryTarget::Contents => None,
+        }
+    }
+
+    fn parse_grep(&self, record: &[u8]) -> Option<SearchQueryMatch> {
+        let i = record.iter().position(|b| *b == b'\0')?;
+        let path = self.unescape_find_path(PathBuf::from(
+            String::from_utf8_lossy(&record[..i]).to_string(),
+        ));
+
+        let mut parts = record[i + 1..].splitn(3, |b| *b == b':');
+        let line_number = std::str::from_utf8(parts.next()?).ok()?.parse().ok()?;
+        let absolute_offset = std::str::from_utf8(parts.next()?).ok()?.parse().ok()?;
+
+        // Grep strips the line terminator, which we include to match other searches
+        let mut lines = parts.next()?.to_vec();
+        lines.push(b'\n');
+
+        // Grep uses POSIX regular expressions, so we only report lines that also match the
+        // query's regular expression
+        let submatches = self.submatches(&lines);
+        if submatches.is_empty() {
+            return None;
+        }
+
+        Some(SearchQueryMatch::Contents(SearchQueryContentsMatch {
+            path,
+            archive_member: None,
+            lines: to_match_data(&lines),
+            line_number,
+            absolute_offset,
+            submatches,
+        }))
+    }
+
+    fn parse_ripgrep_json(&self, record: &[u8]) -> Option<SearchQueryMatch> {
+        let value: Value = serde_json::from_slice(record).ok()?;
+        if value.get("type")?.as_str()? != "match" {
+            return None;
+        }
+
+        let data = value.get("data")?;
+        let path =
+            PathBuf::from(String::from_utf8_lossy(&json_bytes(data.get("path")?)?).to_string());
+        let lines = json_bytes(data.get("lines")?)?;
+        let line_number = data.get("line_number")?.as_u64()?;
+        let absolute_offset = data.get("absolute_offset")?.as_u64()?;
+
+        let mut submatches = Vec::new();
+        for submatch in data.get("submatches")?.as_array()? {
+            submatches.push(SearchQuerySubmatch {
+                r#match: to_match_data(&json_bytes(submatch.get("match")?)?),
+                start: submatch.get("start")?.as_u64()?,
+                end: submatch.get("end")?.as_u64()?,
+            });
+        }
+
+        Some(SearchQueryMatch::Contents(SearchQueryContentsMatch {
+            path,
+            archive_member: None,
+            lines: to_match_data(&lines),
+            line_number,
+            absolute_offset,
+            submatches,
+        }))
+    }
+
+    /// Finds all matches of the query within `bytes`
+    fn submatches(&self, bytes: &[u8]) -> Vec<SearchQuerySubmatch> {
+        self.matcher
+            .find_iter(bytes)
+            .map(|m| SearchQuerySubmatch {
+                r#match: to_match_data(m.as_bytes()),
+                start: m.start() as u64,
+                end: m.end() as u64,
+            })
+            .collect()
+    }
+}
+
+/// Converts bytes into match data, using text if the bytes are valid UTF-8
+fn to_match_data(bytes: &[u8]) -> SearchQueryMatchData {
+    match std::str::from_utf8(bytes) {
+        Ok(s) => SearchQueryMatchData::Text(s.to_string()),
+        Err(_) => SearchQueryMatchData::Bytes(bytes.to_vec()),
+    }
+}
+
+/// Extracts the bytes of ripgrep's JSON representation of data, which is either
+/// `{"text": "..."}` or `{"bytes": "<base64>"}` when not valid UTF-8
+fn json_bytes(value: &Value) -> Option<Vec<u8>> {
+    if let Some(text) = value.get("text").and_then(Value::as_str) {
+        Some(text.as_bytes().to_vec())
+    } else {
+        base64::decode(value.get("bytes")?.as_str()?).ok()
+    }
+}
+
+/// Sends matches in pages, stopping once the limit has been reached
+struct SearchReporter {
+    id: SearchId,
+    limit: Option<u64>,
+    pagination: Option<u64>,
+    matches: Vec<SearchQueryMatch>,
+    total_matches_cnt: u64,
+    reply: Box<dyn Reply<Data = DistantResponseData>>,
+}
+
+impl SearchReporter {
+    fn new(
+        id: SearchId,
+        options: &SearchQueryOptions,
+        reply: Box<dyn Reply<Data = DistantResponseData>>,
+    ) -> Self {
+        Self {
+            id,
+            limit: options.limit,
+            pagination: options.pagination,
+            matches: Vec::new(),
+            total_matches_cnt: 0,
+            reply,
+        }
+    }
+
+    /// Queues a match, sending the queue once it reaches the pagination size, and returns false
+    /// if no more matches should be pushed as the limit has been reached or the reply is closed
+    async fn push(&mut self, m: SearchQueryMatch) -> bool {
+        let id = self.id;
+        self.matches.push(m);
+        self.total_matches_cnt += 1;
+
+        if let Some(len) = self.limit {
+            if self.total_matches_cnt >= len {
+                trace!("[Ssh | Query {id}] Reached limit of {len} matches");
+                return false;
+            }
+        }
+
+        if let Some(len) = self.pagination {
+            if self.matches.len() as u64 >= len {
+                trace!("[Ssh | Query {id}] Reached {len} paginated matches");
+                let matches = std::mem::take(&mut self.matches);
+                if let Err(x) = self
+                    .reply
+                    .send(DistantResponseData::SearchResults { id, matches })
+                    .await
+                {
+                    error!("[Ssh | Query {id}] Failed to send paginated matches: {x}");
+                    return false;
+                }
+            }
+        }
+
+        true
+    }
+
+    /// Sends any remaining matches followed by the conclusion of the search
+    async fn finish(self) {
+        let Self {
+            id, matches, reply, ..
+        } = self;
+
+        if !matches.is_empty() {
+            trace!(
+                "[Ssh | Query {id}] Sending {} remaining matches",
+                matches.len()
+            );
+            if let Err(x) = reply
+                .send(DistantResponseData::SearchResults { id, matches })
+                .await
+            {
+                error!("[Ssh | Query {id}] Failed to send final matches: {x}");
+            }
+        }
+
+        // NOTE: The programs we run do not report how many binary files they skipped
+        trace!("[Ssh | Query {id}] Reporting as done");
+        if let Err(x) = reply
+            .send(DistantResponseData::SearchDone {
+                id,
+                skipped_binary: None,
+            })
+            .await
+        {
+            error!("[Ssh | Query {id}] Failed to send done status: {x}");
+        }
+    }
+}
@@ -1,11 +1,12 @@
 use async_compat::CompatExt;
+use distant_core::data::{FileType, Metadata, UnixMetadata};
 use std::{
     fmt, io,
     path::{Path, PathBuf},
     time::Duration,
 };
 use typed_path::{windows::WindowsComponent, Components, WindowsPath, WindowsPathBuf};
-use wezterm_ssh::{ExecResult, Session, Sftp};
+use wezterm_ssh::{ExecResult, FilePermissions, Metadata as SftpMetadata, Session, Sftp};
 
 const SSH_EXEC_TIMEOUT: Option<Duration> = Some(Duration::from_secs(1));
 
@@ -162,6 +163,30 @@ pub async fn is_windows(session: &Session) -> io::Result<bool> {
         || contains_subslice(&output.stderr, b"Windows_NT"))
 }
 
+/// Determines if a program is available on the remote machine by looking it up in the path
+pub async fn has_program(session: &Session, program: &str) -> io::Result<bool> {
+    let cmd = format!("command -v {}", shell_words::quote(program));
+    let output = execute_output(
+        session,
+        &format!("/bin/sh -c {}", shell_words::quote(&cmd)),
+        SSH_EXEC_TIMEOUT,
+    )
+    .await?;
+    Ok(output.success)
+}
+
+/// Determines if grep on the remote machine supports perl-compatible regular expressions via `-P`
+pub async fn grep_supports_perl_regex(session: &Session) -> io::Result<bool> {
+    let cmd = "echo x | grep -qP '\\w'";
+    let output = execute_output(
+        session,
+        &format!("/bin/sh -c {}", shell_words::quote(cmd)),
+        SSH_EXEC_TIMEOUT,
+    )
+    .await?;
+    Ok(output.success)
+}
+
 /// Query remote system for name of current user
 pub async fn query_username(session: &Session, is_windows: bool) -> io::Result<String> {
     if is_windows {
@@ -253,3 +278,40 @@ pub async fn canonicalize(sftp: &Sftp, path: impl AsRef<Path>) -> io::Result<Pat
         .map(|p| p.into_std_path_buf())
         .map_err(to_other_error)
 }
+
+/// Converts metadata retrieved using SFTP into [`Metadata`]
+pub fn to_metadata(metadata: SftpMetadata, canonicalized_path: Option<PathBuf>) -> Metadata {
+    let file_type = if metadata.is_dir() {
+        FileType::Dir
+    } else if metadata.is_file() {
+        FileType::File
+    } else {
+        FileType::Symlink
+    };
+
+    Metadata {
+        canonicalized_path,
+        file_type,
+        len: metadata.size.unwrap_or(0),
+        // Check that owner, group, or other has write permission (if not, then readonly)
+        readonly: metadata
+            .permissions
+            .map(FilePermissions::is_readonly)
+            .unwrap_or(true),
+        accessed: metadata.accessed.map(u128::from),
+        modified: metadata.modified.map(u128::from),
+        created: None,
+        unix: metadata.permissions.as_ref().map(|p| UnixMetadata {
+            owner_read: p.owner_read,
+            owner_write: p.owner_write,
+            owner_exec: p.owner_exec,
+            group_read: p.group_read,
+            group_write: p.group_write,
+            group_exec: p.group_exec,
+            other_read: p.other_read,
+            other_write: p.other_write,
+            other_exec: p.other_exec,
+        }),
+        windows: None,
+    }
+}
@@ -0,0 +1,375 @@
+use crate::utils::{self, to_other_error};
+use async_compat::CompatExt;
+use distant_core::{
+    data::{Change, ChangeKind, ChangeKindSet, DistantResponseData, FileType, Metadata},
+    net::server::Reply,
+};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use log::*;
+use std::{
+    collections::{BTreeMap, HashMap},
+    io, mem,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+use tokio::task::JoinHandle;
+use wezterm_ssh::Sftp;
+
+/// Time to wait between polls of a watched path when the watch does not specify an interval
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Metadata of every path beneath a watched path, keyed by path
+type Snapshot = BTreeMap<PathBuf, Metadata>;
+
+/// Watches a path on the remote machine by periodically listing it over SFTP and comparing
+/// the metadata of each path with the previous listing
+pub struct PollWatcher {
+    sftp: Sftp,
+    path: PathBuf,
+    recursive: bool,
+    allowed: ChangeKindSet,
+    include: GlobSet,
+    exclude: GlobSet,
+    interval: Duration,
+    debounce: Option<Duration>,
+    max_debounce_wait: Duration,
+    coalesce: bool,
+    metadata: bool,
+    seq: Arc<AtomicU64>,
+    reply: Box<dyn Reply<Data = DistantResponseData>>,
+}
+
+impl PollWatcher {
+    /// Creates a watcher of `path` that reports changes of the `allowed` kinds to paths matching
+    /// the `include` globs and none of the `exclude` globs using `reply`, numbering the changes
+    /// using `seq`, failing if any of the globs is invalid
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        sftp: Sftp,
+        path: PathBuf,
+        recursive: bool,
+        allowed: ChangeKindSet,
+        include: &[String],
+        exclude: &[String],
+        interval: Option<Duration>,
+        debounce: Option<Duration>,
+        max_debounce_wait: Duration,
+        coalesce: bool,
+        metadata: bool,
+        seq: Arc<AtomicU64>,
+        reply: Box<dyn Reply<Data = DistantResponseData>>,
+    ) -> io::Result<Self> {
+        Ok(Self {
+            sftp,
+            path,
+            recursive,
+            allowed,
+            include: build_glob_set(include)?,
+            exclude: build_glob_set(exclude)?,
+            interval: interval.unwrap_or(DEFAULT_POLL_INTERVAL),
+            debounce,
+            max_debounce_wait,
+            coalesce,
+            metadata,
+            seq,
+            reply,
+        })
+    }
+
+    /// Takes the initial snapshot of the watched path, failing if it cannot be read, and then
+    /// spawns a task that polls the path for changes
+    pub async fn spawn(self) -> io::Result<JoinHandle<()>> {
+        let snapshot = snapshot(&self.sftp, &self.path, self.recursive).await?;
+        Ok(tokio::spawn(self.run(snapshot)))
+    }
+
+    async fn run(self, mut previous: Snapshot) {
+        // Changes held while debouncing, along with when the oldest and latest of them were found
+        let mut pending: Vec<Change> = Vec::new();
+        let mut first_change = Instant::now();
+        let mut last_change = Instant::now();
+
+        loop {
+            // While holding changes, poll again no later than when they need to be sent
+            let wait = match self.debounce {
+                Some(debounce) if !pending.is_empty() => self
+                    .interval
+                    .min(debounce.saturating_sub(last_change.elapsed()))
+                    .min(
+                        self.max_debounce_wait
+                            .saturating_sub(first_change.elapsed()),
+                    ),
+                _ => self.interval,
+            };
+            tokio::time::sleep(wait).await;
+
+            // If the watched path cannot be read, it was most likely removed, so we treat it as
+            // empty in order to report everything beneath it as removed
+            let current = match snapshot(&self.sftp, &self.path, self.recursive).await {
+                Ok(snapshot) => snapshot,
+                Err(x) => {
+                    trace!("Failed to poll {:?}: {x}", self.path);
+                    Snapshot::new()
+                }
+            };
+
+            let changes = self.diff(&previous, &current);
+            previous = current;
+
+            let res = match self.debounce {
+                Some(debounce) => {
+                    if !changes.is_empty() {
+                        if pending.is_empty() {
+                            first_change = Instant::now();
+                        }
+                        self.hold(&mut pending, changes);
+                        last_change = Instant::now();
+                    }
+
+                    // Only send once the debounce window has passed without any new changes, or
+                    // once changes that keep arriving have been held for the maximum wait
+                    if pending.is_empty()
+                        || (last_change.elapsed() < debounce
+                            && first_change.elapsed() < self.max_debounce_wait)
+                    {
+                        continue;
+                    }
+
+                    let changes = mem::take(&mut pending);
+                    self.reply
+                        .send(DistantResponseData::ChangedBatch { changes })
+                        .await
+                }
+                None => {
+                    let mut res = Ok(());
+                    for change in changes {
+                        res = self.reply.send(DistantResponseData::Changed(change)).await;
+                        if res.is_err() {
+                            break;
+                        }
+                    }
+                    res
+                }
+            };
+
+            // If we can no longer reply, the connection is gone and there is no need to watch
+            if let Err(x) = res {
+                error!(
+                    "Stopping watch of {:?} as failed to send changes: {x}",
+                    self.path
+                );
+                break;
+            }
+        }
+    }
+
+    /// Adds `changes` to those held while debouncing, merging changes to the same path into the
+    /// latest of them when coalescing
+    fn hold(&self, pending: &mut Vec<Change>, changes: Vec<Change>) {
+        if !self.coalesce {
+            pending.extend(changes);
+            return;
+        }
+
+        for change in changes {
+            match pending.iter_mut().find(|c| c.paths == change.paths) {
+                Some(existing) => {
+                    let kinds = mem::take(&mut existing.kinds) | change.kinds;
+                    *existing = Change { kinds, ..change };
+                }
+                None => pending.push(change),
+            }
+        }
+    }
+
+    /// Returns true if the given path, relative to the watched path, is included and not excluded
+    /// by the globs of this watcher. A glob matches if it matches the path or any of its parent
+    /// directories beneath the watched path, so globs like `target` exclude everything within
+    /// `target/`
+    fn matches_globs(&self, path: &Path) -> bool {
+        let relative = match path.strip_prefix(&self.path) {
+            Ok(relative) => relative,
+            Err(_) => return false,
+        };
+
+        let is_match = |set: &GlobSet| {
+            relative
+                .ancestors()
+                .filter(|p| !p.as_os_str().is_empty())
+                .any(|p| set.is_match(p))
+        };
+
+        (self.include.is_empty() || is_match(&self.include)) && !is_match(&self.exclude)
+    }
+
+    /// Returns the changes between two snapshots that are allowed by this watcher, ordered by
+    /// path
+    fn diff(&self, previous: &Snapshot, current: &Snapshot) -> Vec<Change> {
+        let mut kinds: BTreeMap<&Path, Vec<ChangeKind>> = BTreeMap::new();
+
+        for (path, metadata) in current {
+            let path_kinds = kinds.entry(path.as_path()).or_default();
+            match previous.get(path) {
+                None => path_kinds.push(ChangeKind::Create),
+                Some(old) => {
+                    // Directories are modified whenever their entries change, which we already
+                    // report as changes to the entries themselves
+                    if metadata.file_type != FileType::Dir
+                        && (old.file_type != metadata.file_type
+                            || old.len != metadata.len
+                            || old.modified != metadata.modified)
+                    {
+                        path_kinds.push(ChangeKind::Modify);
+                    }
+
+                    if old.readonly != metadata.readonly || old.unix != metadata.unix {
+                        path_kinds.push(ChangeKind::Permissions);
+                    }
+                }
+            }
+        }
+
+        for path in previous.keys() {
+            if !current.contains_key(path) {
+                kinds
+                    .entry(path.as_path())
+                    .or_default()
+                    .push(ChangeKind::Remove);
+            }
+        }
+
+        let mut changes = Vec::new();
+        for (path, path_kinds) in kinds {
+            // The watched path itself is always reported, as globs only apply beneath it
+            if path != self.path && !self.matches_globs(path) {
+                continue;
+            }
+
+            let path_kinds: Vec<ChangeKind> = path_kinds
+                .into_iter()
+                .filter(|kind| self.allowed.contains(kind))
+                .collect();
+
+            let metadata = if self.metadata {
+                current.get(path).cloned()
+            } else {
+                None
+            };
+
+            let make_change = |kind, kinds| Change {
+                seq: self.seq.fetch_add(1, Ordering::Relaxed) + 1,
+                timestamp: Change::now(),
+                kind,
+                paths: vec![path.to_path_buf()],
+                from: None,
+                to: None,
+                metadata: metadata.clone(),
+                kinds,
+            };
+
+            if self.coalesce {
+                if let Some(kind) = path_kinds.last().copied() {
+                    changes.push(make_change(kind, path_kinds.into_iter().collect()));
+                }
+            } else {
+                for kind in path_kinds {
+                    changes.push(make_change(kind, ChangeKindSet::empty()));
+                }
+            }
+        }
+
+        changes
+    }
+}
+
+/// Builds a set of globs, ignoring trailing separators so `target/` matches the directory itself
+fn build_glob_set(globs: &[String]) -> io::Result<GlobSet> {
+    let mut builder = GlobSetBuilder::new();
+    for glob in globs {
+        let glob = glob.trim_end_matches('/');
+        builder.add(Glob::new(glob).map_err(|x| io::Error::new(io::ErrorKind::InvalidInput, x))?);
+    }
+
+    builder
+        .build()
+        .map_err(|x| io::Error::new(io::ErrorKind::InvalidInput, x))
+}
+
+/// Reads the metadata of `root` and, if it is a directory, everything within it, descending into
+/// nested directories if `recursive` is true
+async fn snapshot(sftp: &Sftp, root: &Path, recursive: bool) -> io::Result<Snapshot> {
+    let metadata = sftp
+        .metadata(root.to_path_buf())
+        .compat()
+        .await
+        .map_err(to_other_error)?;
+    let is_dir = metadata.is_dir();
+
+    let mut snapshot = Snapshot::new();
+    snapshot.insert(root.to_path_buf(), utils::to_metadata(metadata, None));
+
+    let mut to_read = if is_dir {
+        vec![root.to_path_buf()]
+    } else {
+        Vec::new()
+    };
+
+    while let Some(dir) = to_read.pop() {
+        let entries = match sftp.read_dir(dir.to_path_buf()).compat().await {
+            Ok(entries) => entries,
+
+            // Only fail if we cannot read the watched path itself, skipping nested directories
+            // that are removed while we are reading them
+            Err(x) if dir == root => return Err(to_other_error(x)),
+            Err(_) => continue,
+        };
+
+        for (path, metadata) in entries {
+            let path = path.into_std_path_buf();
+            if recursive && metadata.is_dir() {
+                to_read.push(path.to_path_buf());
+            }
+
+            snapshot.insert(path, utils::to_metadata(metadata, None));
+        }
+    }
+
+    Ok(snapshot)
+}
+
+/// Collection of watchers for a single connection, keyed by the path being watched
+#[derive(Default)]
+pub struct Watchers(HashMap<PathBuf, Vec<JoinHandle<()>>>);
+
+impl Watchers {
+    /// Adds the task of a watcher for `path`
+    pub fn insert(&mut self, path: PathBuf, task: JoinHandle<()>) {
+        self.0.entry(path).or_default().push(task);
+    }
+
+    /// Stops all watchers for `path`, returning true if there were any
+    pub fn remove(&mut self, path: &Path) -> bool {
+        match self.0.remove(path) {
+            Some(tasks) => {
+                for task in tasks {
+                    task.abort();
+                }
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+impl Drop for Watchers {
+    /// Stops all watchers once the connection that owns them is gone
+    fn drop(&mut self) {
+        for task in self.0.values().flatten() {
+            task.abort();
+        }
+    }
+}
@@ -1,7 +1,11 @@
 use crate::sshd::*;
 use assert_fs::{prelude::*, TempDir};
 use distant_core::{
-    data::{ChangeKindSet, Environment, FileType, Metadata},
+    data::{
+        ChangeKind, ChangeKindSet, Environment, FileType, Metadata, SearchQuery,
+        SearchQueryCondition, SearchQueryContentsMatch, SearchQueryMatch, SearchQueryMatchData,
+        SearchQueryOptions, SearchQuerySubmatch, SearchQueryTarget, WatchOptions,
+    },
     DistantChannelExt, DistantClient,
 };
 use once_cell::sync::Lazy;
@@ -896,25 +900,157 @@ async fn rename_should_support_renaming_a_single_file(#[future] client: Ctx<Dist
 
 #[rstest]
 #[test(tokio::test)]
-async fn watch_should_fail_as_unsupported(#[future] client: Ctx<DistantClient>) {
-    // NOTE: Supporting multiple replies being sent back as part of creating, modifying, etc.
+async fn watch_should_report_changes_to_a_file(#[future] client: Ctx<DistantClient>) {
     let mut client = client.await;
     let temp = assert_fs::TempDir::new().unwrap();
 
     let file = temp.child("file");
     file.touch().unwrap();
 
-    let err = client
+    let mut watcher = client
         .watch(
             file.path().to_path_buf(),
             /* recursive */ false,
             /* only */ ChangeKindSet::default(),
             /* except */ ChangeKindSet::default(),
+            /* options */
+            WatchOptions {
+                poll_interval_ms: Some(50),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+
+    // Update the file and verify we get a notification
+    file.write_str("some text").unwrap();
+
+    let change = tokio::time::timeout(Duration::from_secs(5), watcher.next())
+        .await
+        .expect("Timed out waiting for change")
+        .expect("Watcher closed unexpectedly");
+    assert_eq!(change.kind, ChangeKind::Modify);
+    assert_eq!(change.paths, vec![file.path().canonicalize().unwrap()]);
+}
+
+#[rstest]
+#[test(tokio::test)]
+async fn watch_should_report_created_and_removed_paths_in_a_directory(
+    #[future] client: Ctx<DistantClient>,
+) {
+    let mut client = client.await;
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let removed = temp.child("removed");
+    removed.touch().unwrap();
+
+    let mut watcher = client
+        .watch(
+            temp.path().to_path_buf(),
+            /* recursive */ false,
+            /* only */ ChangeKind::Create | ChangeKind::Remove,
+            /* except */ ChangeKindSet::default(),
+            /* options */
+            WatchOptions {
+                poll_interval_ms: Some(50),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+
+    let created = temp.child("created");
+    created.touch().unwrap();
+    std::fs::remove_file(removed.path()).unwrap();
+
+    let mut changes = Vec::new();
+    while changes.len() < 2 {
+        let change = tokio::time::timeout(Duration::from_secs(5), watcher.next())
+            .await
+            .expect("Timed out waiting for change")
+            .expect("Watcher closed unexpectedly");
+        changes.push((change.kind, change.paths));
+    }
+    changes.sort();
+
+    let root = temp.path().canonicalize().unwrap();
+    assert_eq!(
+        changes,
+        vec![
+            (ChangeKind::Create, vec![root.join("created")]),
+            (ChangeKind::Remove, vec![root.join("removed")]),
+        ]
+    );
+}
+
+#[rstest]
+#[test(tokio::test)]
+async fn watch_should_skip_paths_excluded_by_globs(#[future] client: Ctx<DistantClient>) {
+    let mut client = client.await;
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("target").create_dir_all().unwrap();
+
+    let mut watcher = client
+        .watch(
+            temp.path().to_path_buf(),
+            /* recursive */ true,
+            /* only */ ChangeKind::Create,
+            /* except */ ChangeKindSet::default(),
+            /* options */
+            WatchOptions {
+                poll_interval_ms: Some(50),
+                exclude: vec![String::from("target/")],
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+
+    temp.child("target").child("excluded").touch().unwrap();
+    temp.child("included").touch().unwrap();
+
+    let change = tokio::time::timeout(Duration::from_secs(5), watcher.next())
+        .await
+        .expect("Timed out waiting for change")
+        .expect("Watcher closed unexpectedly");
+    assert_eq!(change.kind, ChangeKind::Create);
+    assert_eq!(
+        change.paths,
+        vec![temp.path().canonicalize().unwrap().join("included")]
+    );
+}
+
+#[rstest]
+#[test(tokio::test)]
+async fn watch_should_fail_if_using_invalid_globs(#[future] client: Ctx<DistantClient>) {
+    let mut client = client.await;
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let err = client
+        .watch(
+            temp.path().to_path_buf(),
+            /* recursive */ true,
+            /* only */ ChangeKindSet::default(),
+            /* except */ ChangeKindSet::default(),
+            /* options */
+            WatchOptions {
+                exclude: vec![String::from("[")],
+                ..Default::default()
+            },
         )
         .await
         .unwrap_err();
 
-    assert_eq!(err.kind(), io::ErrorKind::Unsupported, "{:?}", err);
+    assert_eq!(err.kind(), io::ErrorKind::InvalidInput, "{:?}", err);
+}
+
+#[rstest]
+#[test(tokio::test)]
+async fn unwatch_should_fail_if_path_not_watched(#[future] client: Ctx<DistantClient>) {
+    let mut client = client.await;
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let _ = client.unwatch(temp.path().to_path_buf()).await.unwrap_err();
 }
 
 #[rstest]
@@ -1204,6 +1340,104 @@ async fn metadata_should_resolve_file_type_of_symlink_if_flag_specified(
     );
 }
 
+#[rstest]
+#[test(tokio::test)]
+async fn search_should_send_back_matches_within_contents(#[future] client: Ctx<DistantClient>) {
+    let mut client = client.await;
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    temp.child("file1")
+        .write_str("some text\nother line\n")
+        .unwrap();
+    temp.child("file2").write_str("nothing here").unwrap();
+
+    let mut searcher = client
+        .search(SearchQuery {
+            target: SearchQueryTarget::Contents,
+            condition: SearchQueryCondition::contains("other"),
+            paths: vec![temp.path().to_path_buf()],
+            options: Default::default(),
+        })
+        .await
+        .unwrap();
+
+    let m = tokio::time::timeout(Duration::from_secs(5), searcher.next())
+        .await
+        .expect("Timed out waiting for match")
+        .expect("Searcher closed unexpectedly");
+    assert_eq!(
+        m,
+        SearchQueryMatch::Contents(SearchQueryContentsMatch {
+            path: temp.child("file1").path().to_path_buf(),
+            archive_member: None,
+            lines: SearchQueryMatchData::text("other line\n"),
+            line_number: 2,
+            absolute_offset: 10,
+            submatches: vec![SearchQuerySubmatch {
+                r#match: SearchQueryMatchData::text("other"),
+                start: 0,
+                end: 5,
+            }],
+        })
+    );
+
+    let next = tokio::time::timeout(Duration::from_secs(5), searcher.next())
+        .await
+        .expect("Timed out waiting for search to finish");
+    assert_eq!(next, None);
+}
+
+#[rstest]
+#[test(tokio::test)]
+async fn search_should_send_back_no_more_than_limit_of_path_matches(
+    #[future] client: Ctx<DistantClient>,
+) {
+    let mut client = client.await;
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    for i in 0..5 {
+        temp.child(format!("match{i}")).touch().unwrap();
+    }
+
+    let mut searcher = client
+        .search(SearchQuery {
+            target: SearchQueryTarget::Path,
+            condition: SearchQueryCondition::regex("match[0-9]$"),
+            paths: vec![temp.path().to_path_buf()],
+            options: SearchQueryOptions {
+                limit: Some(2),
+                pagination: Some(1),
+                ..Default::default()
+            },
+        })
+        .await
+        .unwrap();
+
+    let mut matches = Vec::new();
+    while let Some(m) = tokio::time::timeout(Duration::from_secs(5), searcher.next())
+        .await
+        .expect("Timed out waiting for search to finish")
+    {
+        matches.push(m);
+    }
+
+    assert_eq!(matches.len(), 2, "{:?}", matches);
+    for m in matches {
+        match m {
+            SearchQueryMatch::Path(m) => assert!(m.path.starts_with(temp.path()), "{:?}", m),
+            x => panic!("Unexpected match: {:?}", x),
+        }
+    }
+}
+
+#[rstest]
+#[test(tokio::test)]
+async fn cancel_search_should_fail_if_search_not_found(#[future] client: Ctx<DistantClient>) {
+    let mut client = client.await;
+
+    let _ = client.cancel_search(rand::random()).await.unwrap_err();
+}
+
 #[rstest]
 #[test(tokio::test)]
 async fn proc_spawn_should_not_fail_even_if_process_not_found(
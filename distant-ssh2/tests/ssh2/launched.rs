@@ -898,6 +898,7 @@ async fn watch_should_succeed(#[future] launched_client: Ctx<DistantClient>) {
             /* recursive */ false,
             /* only */ ChangeKindSet::default(),
             /* except */ ChangeKindSet::default(),
+            /* options */ Default::default(),
         )
         .await
         .unwrap();
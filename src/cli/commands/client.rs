@@ -17,13 +17,15 @@ use distant_core::{
     data::{ChangeKindSet, Environment},
     net::common::{ConnectionId, Destination, Host, Map, Request, Response},
     net::manager::ManagerClient,
-    DistantMsg, DistantRequestData, DistantResponseData, RemoteCommand, Searcher, Watcher,
+    DistantChannelExt, DistantMsg, DistantRequestData, DistantResponseData, RemoteCommand,
+    Searcher, TailEvent, Tailer, WatchEvent, Watcher,
 };
 use log::*;
 use serde_json::{json, Value};
 use std::{
     io,
     path::{Path, PathBuf},
+    str::FromStr,
     time::Duration,
 };
 use tokio::sync::mpsc;
@@ -90,7 +92,10 @@ pub enum ClientSubcommand {
         #[clap(short, long, default_value_t, value_enum)]
         format: Format,
 
-        destination: Box<Destination>,
+        /// Destination of the server, `exec://COMMAND` to spawn a command whose stdin and
+        /// stdout are connected to a server such as `exec://ssh host distant server listen --stdio`,
+        /// or `unix://PATH` to connect to a server listening on a Unix socket (unix-only)
+        destination: ConnectDestination,
     },
 
     /// Launches the server-portion of the binary on a remote machine
@@ -336,11 +341,53 @@ impl ClientSubcommand {
                             formatter.print(res).context("Failed to print match")?;
                         }
                     }
+                    DistantRequestData::FindFiles { query } => {
+                        debug!("Special request finding files for {:?}", query);
+                        let matches = channel
+                            .into_client()
+                            .into_channel()
+                            .find_files(query)
+                            .await
+                            .context("Failed to find files")?;
+
+                        let res = Response::new(
+                            "".to_string(),
+                            DistantMsg::Single(DistantResponseData::FindFilesDone {
+                                id: 0,
+                                matches,
+                            }),
+                        );
+
+                        formatter.print(res).context("Failed to print matches")?;
+                    }
+                    DistantRequestData::FileTail { path, options } => {
+                        debug!("Special request creating tailer for {:?}", path);
+                        let mut tailer = Tailer::tail(
+                            channel.into_client().into_channel(),
+                            path.as_path(),
+                            options,
+                        )
+                        .await
+                        .with_context(|| format!("Failed to tail {path:?}"))?;
+
+                        // Continue to receive and process data until the tail has concluded
+                        while let Some(event) = tailer.next().await {
+                            let id = tailer.id();
+                            let data = match event {
+                                TailEvent::Data(data) => DistantResponseData::TailData { id, data },
+                                TailEvent::Reset => DistantResponseData::TailReset { id },
+                            };
+                            let res = Response::new("".to_string(), DistantMsg::Single(data));
+
+                            formatter.print(res).context("Failed to print tail")?;
+                        }
+                    }
                     DistantRequestData::Watch {
                         path,
                         recursive,
                         only,
                         except,
+                        options,
                     } => {
                         debug!("Special request creating watcher for {:?}", path);
                         let mut watcher = Watcher::watch(
@@ -349,17 +396,24 @@ impl ClientSubcommand {
                             recursive,
                             only.into_iter().collect::<ChangeKindSet>(),
                             except.into_iter().collect::<ChangeKindSet>(),
+                            options,
                         )
                         .await
                         .with_context(|| format!("Failed to watch {path:?}"))?;
 
                         // Continue to receive and process changes
-                        while let Some(change) = watcher.next().await {
+                        while let Some(event) = watcher.next_event().await {
                             // TODO: Provide a cleaner way to print just a change
-                            let res = Response::new(
-                                "".to_string(),
-                                DistantMsg::Single(DistantResponseData::Changed(change)),
-                            );
+                            let data = match event {
+                                WatchEvent::Changed(change) => DistantResponseData::Changed(change),
+                                WatchEvent::Overflow { seq } => {
+                                    DistantResponseData::WatchOverflow {
+                                        path: path.clone(),
+                                        seq,
+                                    }
+                                }
+                            };
+                            let res = Response::new("".to_string(), DistantMsg::Single(data));
 
                             formatter.print(res).context("Failed to print change")?;
                         }
@@ -424,16 +478,17 @@ impl ClientSubcommand {
                 // arguments
                 let mut options = Map::from(config.connect);
                 options.extend(Map::from(connect_config).into_map());
+                let destination = destination.into_destination(&mut options);
 
                 // Trigger our manager to connect to the launched server
                 debug!("Connecting to server at {} with {}", destination, options);
                 let id = match format {
                     Format::Shell => client
-                        .connect(*destination, options, PromptAuthHandler::new())
+                        .connect(destination, options, PromptAuthHandler::new())
                         .await
                         .context("Failed to connect to server")?,
                     Format::Json => client
-                        .connect(*destination, options, JsonAuthHandler::default())
+                        .connect(destination, options, JsonAuthHandler::default())
                         .await
                         .context("Failed to connect to server")?,
                 };
@@ -931,3 +986,75 @@ async fn use_or_lookup_connection_id(
         }
     }
 }
+
+/// Destination given to `distant connect`, which is either a [`Destination`], in the form of
+/// `exec://COMMAND`, a command whose stdin and stdout are connected to a server, or, in the form
+/// of `unix://PATH`, the path to a Unix socket of a server
+#[derive(Clone, Debug)]
+pub enum ConnectDestination {
+    Destination(Box<Destination>),
+    Exec(String),
+    Unix(PathBuf),
+}
+
+impl ConnectDestination {
+    /// Converts into the destination handed to the manager, where a command becomes the
+    /// `command` option of an `exec://localhost` destination and a socket path becomes the
+    /// `unix_socket` option of a `unix://localhost` destination
+    fn into_destination(self, options: &mut Map) -> Destination {
+        let scheme = match self {
+            Self::Destination(destination) => return *destination,
+            Self::Exec(command) => {
+                options.insert("command".to_string(), command);
+                "exec"
+            }
+            Self::Unix(path) => {
+                options.insert(
+                    "unix_socket".to_string(),
+                    path.to_string_lossy().to_string(),
+                );
+                "unix"
+            }
+        };
+
+        Destination {
+            scheme: Some(scheme.to_string()),
+            username: None,
+            password: None,
+            host: Host::Name("localhost".to_string()),
+            port: None,
+        }
+    }
+}
+
+impl FromStr for ConnectDestination {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        const EXEC_PREFIX: &str = "exec://";
+        const UNIX_PREFIX: &str = "unix://";
+
+        let has_prefix = |prefix: &str| {
+            s.get(..prefix.len())
+                .map_or(false, |x| x.eq_ignore_ascii_case(prefix))
+        };
+
+        if has_prefix(EXEC_PREFIX) {
+            let command = s[EXEC_PREFIX.len()..].trim();
+            if command.is_empty() {
+                Err("Missing command to execute")
+            } else {
+                Ok(Self::Exec(command.to_string()))
+            }
+        } else if has_prefix(UNIX_PREFIX) {
+            let path = &s[UNIX_PREFIX.len()..];
+            if path.is_empty() {
+                Err("Missing path to unix socket")
+            } else {
+                Ok(Self::Unix(PathBuf::from(path)))
+            }
+        } else {
+            s.parse().map(Self::Destination)
+        }
+    }
+}
@@ -1,7 +1,7 @@
 use clap::ValueEnum;
 use distant_core::{
     data::{
-        ChangeKind, DistantMsg, DistantResponseData, Error, FileType, Metadata,
+        Change, ChangeKind, DistantMsg, DistantResponseData, Error, FileType, Metadata,
         SearchQueryContentsMatch, SearchQueryMatch, SearchQueryPathMatch, SystemInfo,
     },
     net::common::Response,
@@ -175,26 +175,30 @@ fn format_shell(state: &mut FormatterState, data: DistantResponseData) -> Output
 
             Output::Stdout(table)
         }
-        DistantResponseData::Changed(change) => Output::StdoutLine(
+        DistantResponseData::Changed(change) => {
+            Output::StdoutLine(format_change(change).into_bytes())
+        }
+        DistantResponseData::ChangedBatch { changes } => Output::StdoutLine(
+            changes
+                .into_iter()
+                .map(format_change)
+                .collect::<Vec<String>>()
+                .join("\n")
+                .into_bytes(),
+        ),
+        DistantResponseData::WatchOverflow { path, .. } => Output::StderrLine(
             format!(
-                "{}{}",
-                match change.kind {
-                    ChangeKind::Create => "Following paths were created:\n",
-                    ChangeKind::Remove => "Following paths were removed:\n",
-                    x if x.is_access_kind() => "Following paths were accessed:\n",
-                    x if x.is_modify_kind() => "Following paths were modified:\n",
-                    x if x.is_rename_kind() => "Following paths were renamed:\n",
-                    _ => "Following paths were affected:\n",
-                },
-                change
-                    .paths
-                    .into_iter()
-                    .map(|p| format!("* {}", p.to_string_lossy()))
-                    .collect::<Vec<String>>()
-                    .join("\n")
+                "Missed changes to {} that can no longer be replayed, resync needed",
+                path.to_string_lossy()
             )
             .into_bytes(),
         ),
+        DistantResponseData::TailStarted { .. } => Output::None,
+        DistantResponseData::TailData { data, .. } => Output::Stdout(data),
+        DistantResponseData::TailReset { .. } => {
+            Output::StderrLine(b"File was truncated or replaced, following from start".to_vec())
+        }
+        DistantResponseData::TailDone { .. } => Output::None,
         DistantResponseData::Exists { value: exists } => {
             if exists {
                 Output::StdoutLine(b"true".to_vec())
@@ -320,10 +324,17 @@ fn format_shell(state: &mut FormatterState, data: DistantResponseData) -> Output
 
                     SearchQueryMatch::Contents(SearchQueryContentsMatch {
                         path,
+                        archive_member,
                         lines,
                         line_number,
                         ..
                     }) => {
+                        // Matches within an archive member are grouped by the member's path
+                        // nested beneath the archive's path
+                        let path = match archive_member {
+                            Some(member) => path.join(member),
+                            None => path,
+                        };
                         let file_matches = files.entry(path).or_default();
 
                         file_matches.push(format!(
@@ -364,6 +375,20 @@ fn format_shell(state: &mut FormatterState, data: DistantResponseData) -> Output
                 Output::None
             }
         }
+        DistantResponseData::FindFilesResults { .. } => Output::None,
+        DistantResponseData::FindFilesDone { matches, .. } => {
+            let mut output = String::new();
+            for m in matches {
+                use std::fmt::Write;
+                writeln!(&mut output, "{}", m.path.to_string_lossy()).unwrap();
+            }
+
+            if !output.is_empty() {
+                Output::Stdout(output.into_bytes())
+            } else {
+                Output::None
+            }
+        }
         DistantResponseData::ProcSpawned { .. } => Output::None,
         DistantResponseData::ProcStdout { data, .. } => Output::Stdout(data),
         DistantResponseData::ProcStderr { data, .. } => Output::Stderr(data),
@@ -384,6 +409,8 @@ fn format_shell(state: &mut FormatterState, data: DistantResponseData) -> Output
             main_separator,
             username,
             shell,
+            protocol_version,
+            watch_backend,
         }) => Output::StdoutLine(
             format!(
                 concat!(
@@ -393,9 +420,21 @@ fn format_shell(state: &mut FormatterState, data: DistantResponseData) -> Output
                     "Cwd: {:?}\n",
                     "Path Sep: {:?}\n",
                     "Username: {:?}\n",
-                    "Shell: {:?}"
+                    "Shell: {:?}\n",
+                    "Protocol Version: {}\n",
+                    "Watch Backend: {}"
                 ),
-                family, os, arch, current_dir, main_separator, username, shell
+                family,
+                os,
+                arch,
+                current_dir,
+                main_separator,
+                username,
+                shell,
+                protocol_version
+                    .map(|x| x.to_string())
+                    .unwrap_or_else(|| String::from("unknown")),
+                watch_backend.as_deref().unwrap_or("unknown")
             )
             .into_bytes(),
         ),
@@ -419,3 +458,28 @@ fn format_shell(state: &mut FormatterState, data: DistantResponseData) -> Output
         }
     }
 }
+
+fn format_change(change: Change) -> String {
+    format!(
+        "{}{}",
+        match change.kind {
+            ChangeKind::Create => "Following paths were created:\n",
+            ChangeKind::Remove => "Following paths were removed:\n",
+            x if x.is_access_kind() => "Following paths were accessed:\n",
+            x if x.is_modify_kind() => "Following paths were modified:\n",
+            x if x.is_rename_kind() => "Following paths were renamed:\n",
+            _ => "Following paths were affected:\n",
+        },
+        match (change.from, change.to) {
+            (Some(from), Some(to)) => {
+                format!("* {} -> {}", from.to_string_lossy(), to.to_string_lossy())
+            }
+            _ => change
+                .paths
+                .into_iter()
+                .map(|p| format!("* {}", p.to_string_lossy()))
+                .collect::<Vec<String>>()
+                .join("\n"),
+        }
+    )
+}
@@ -38,6 +38,16 @@ pub enum ManagerSubcommand {
         #[clap(long, value_enum)]
         access: Option<AccessControl>,
 
+        /// Additional user ids allowed to connect when access is anyone, verified using the
+        /// credentials of the connecting process as reported by the kernel (unix-only)
+        #[clap(long, value_delimiter = ',')]
+        allow_uid: Option<Vec<u32>>,
+
+        /// Additional group ids allowed to connect when access is anyone, verified using the
+        /// credentials of the connecting process as reported by the kernel (unix-only)
+        #[clap(long, value_delimiter = ',')]
+        allow_gid: Option<Vec<u32>>,
+
         /// If specified, will fork the process to run as a standalone daemon
         #[clap(long)]
         daemon: bool,
@@ -263,11 +273,15 @@ impl ManagerSubcommand {
             }
             Self::Listen {
                 access,
+                allow_uid,
+                allow_gid,
                 network,
                 user,
                 ..
             } => {
                 let access = access.or(config.access).unwrap_or_default();
+                let allowed_uids = allow_uid.or(config.allow_uid).unwrap_or_default();
+                let allowed_gids = allow_gid.or(config.allow_gid).unwrap_or_default();
                 let network = network.merge(config.network);
 
                 info!(
@@ -284,6 +298,8 @@ impl ManagerSubcommand {
                 );
                 let manager_ref = Manager {
                     access,
+                    allowed_uids,
+                    allowed_gids,
                     config: NetManagerConfig {
                         user,
                         launch_handlers: {
@@ -304,10 +320,12 @@ impl ManagerSubcommand {
                             let mut handlers: HashMap<String, Box<dyn ConnectHandler>> =
                                 HashMap::new();
 
-                            handlers.insert(
-                                "distant".to_string(),
-                                Box::new(handlers::DistantConnectHandler),
-                            );
+                            for scheme in ["distant", "ws", "wss", "exec", "unix"] {
+                                handlers.insert(
+                                    scheme.to_string(),
+                                    Box::new(handlers::DistantConnectHandler),
+                                );
+                            }
 
                             #[cfg(any(feature = "libssh", feature = "ssh2"))]
                             handlers
@@ -383,6 +401,8 @@ impl ManagerSubcommand {
                     scheme: String,
                     host: String,
                     port: String,
+                    key_exchange: String,
+                    version: String,
                     options: String,
                 }
 
@@ -397,6 +417,11 @@ impl ManagerSubcommand {
                             .port
                             .map(|x| x.to_string())
                             .unwrap_or_default(),
+                        key_exchange: info
+                            .key_exchange_type
+                            .map(|x| x.to_string())
+                            .unwrap_or_default(),
+                        version: info.peer_version.map(|x| x.to_string()).unwrap_or_default(),
                         options: info.options.to_string()
                     }])
                 );
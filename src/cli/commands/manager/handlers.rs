@@ -1,12 +1,19 @@
 use crate::config::ClientLaunchConfig;
 use async_trait::async_trait;
-use distant_core::net::client::{Client, ClientConfig, ReconnectStrategy, UntypedClient};
+#[cfg(unix)]
+use distant_core::net::client::UnixSocketConnector;
+use distant_core::net::client::{
+    ClientBuilder, ClientConfig, Connector, ReconnectStrategy, TcpConnector, UntypedClient,
+    WebSocketConnector,
+};
 use distant_core::net::common::authentication::msg::*;
 use distant_core::net::common::authentication::{
-    AuthHandler, Authenticator, DynAuthHandler, ProxyAuthHandler, SingleAuthHandler,
-    StaticKeyAuthMethodHandler,
+    AuthHandler, AuthHandlerMap, AuthMethodHandler, Authenticator, DynAuthHandler,
+    Ed25519SigningKey, ProxyAuthHandler,
+};
+use distant_core::net::common::{
+    Destination, Host, Map, SecretKey32, StdioTransport, TlsClientConfig, TlsVerification,
 };
-use distant_core::net::common::{Destination, Map, SecretKey32};
 use distant_core::net::manager::{ConnectHandler, LaunchHandler};
 use log::*;
 use std::{
@@ -192,56 +199,35 @@ impl LaunchHandler for SshLaunchHandler {
     }
 }
 
-/// Supports connecting to a remote distant TCP server as defined by `distant://...`
+/// Supports connecting to a remote distant server over TCP as defined by `distant://...`, over
+/// WebSocket as defined by `ws://...` and `wss://...`, over the stdin and stdout of a local
+/// command given by the `command` option as defined by `exec://...`, or over a local Unix socket
+/// given by the `unix_socket` option as defined by `unix://...`
 pub struct DistantConnectHandler;
 
 impl DistantConnectHandler {
     async fn try_connect(
-        ips: Vec<IpAddr>,
-        port: u16,
-        mut auth_handler: impl AuthHandler,
+        destination: &Destination,
+        options: &Map,
+        auth_handler: impl AuthHandler + Send,
     ) -> io::Result<UntypedClient> {
-        // Try each IP address with the same port to see if one works
-        let mut err = None;
-        for ip in ips {
-            let addr = SocketAddr::new(ip, port);
-            debug!("Attempting to connect to distant server @ {}", addr);
-
-            match Client::tcp(addr)
-                .auth_handler(DynAuthHandler::from(&mut auth_handler))
-                .config(ClientConfig {
-                    reconnect_strategy: ReconnectStrategy::ExponentialBackoff {
-                        base: Duration::from_secs(1),
-                        factor: 2.0,
-                        max_duration: Some(Duration::from_secs(10)),
-                        max_retries: None,
-                        timeout: None,
-                    },
-                    ..Default::default()
-                })
-                .connect_timeout(Duration::from_secs(180))
-                .connect_untyped()
-                .await
-            {
-                Ok(client) => return Ok(client),
-                Err(x) => err = Some(x),
-            }
+        if destination.scheme_eq("ws") || destination.scheme_eq("wss") {
+            let connector = Self::websocket_connector(destination, options).await?;
+            Self::connect_with(connector, auth_handler).await
+        } else if destination.scheme_eq("exec") {
+            let transport = Self::spawn_command(options)?;
+            Self::connect_with(transport, auth_handler).await
+        } else if destination.scheme_eq("unix") {
+            Self::try_connect_unix_socket(options, auth_handler).await
+        } else {
+            Self::try_connect_tcp(destination, auth_handler).await
         }
-
-        // If all failed, return the last error we got
-        Err(err.expect("Err set above"))
     }
-}
 
-#[async_trait]
-impl ConnectHandler for DistantConnectHandler {
-    async fn connect(
-        &self,
+    async fn try_connect_tcp(
         destination: &Destination,
-        options: &Map,
-        authenticator: &mut dyn Authenticator,
+        mut auth_handler: impl AuthHandler + Send,
     ) -> io::Result<UntypedClient> {
-        debug!("Handling connect of {destination} with options '{options}'");
         let host = destination.host.to_string();
         let port = destination.port.ok_or_else(|| missing("port"))?;
 
@@ -266,6 +252,154 @@ impl ConnectHandler for DistantConnectHandler {
             ));
         }
 
+        // Try each IP address with the same port to see if one works
+        let mut err = None;
+        for ip in candidate_ips {
+            let addr = SocketAddr::new(ip, port);
+            debug!("Attempting to connect to distant server @ {}", addr);
+
+            match Self::connect_with(
+                TcpConnector::from(addr),
+                DynAuthHandler::from(&mut auth_handler),
+            )
+            .await
+            {
+                Ok(client) => return Ok(client),
+                Err(x) => err = Some(x),
+            }
+        }
+
+        // If all failed, return the last error we got
+        Err(err.expect("Err set above"))
+    }
+
+    #[cfg(unix)]
+    async fn try_connect_unix_socket(
+        options: &Map,
+        auth_handler: impl AuthHandler + Send,
+    ) -> io::Result<UntypedClient> {
+        let path = options
+            .get("unix_socket")
+            .ok_or_else(|| missing("unix_socket"))?;
+        debug!("Attempting to connect to distant server @ unix socket {path:?}");
+        Self::connect_with(UnixSocketConnector::new(path), auth_handler).await
+    }
+
+    #[cfg(windows)]
+    async fn try_connect_unix_socket(
+        _options: &Map,
+        _auth_handler: impl AuthHandler + Send,
+    ) -> io::Result<UntypedClient> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "Unix sockets are only supported on unix",
+        ))
+    }
+
+    /// Builds a connector to the WebSocket url of the destination, using the `path` option as the
+    /// path of the url and, for `wss://`, verifying the server by the `tls_fingerprint` or
+    /// `tls_ca` options before falling back to the CA certificates trusted by the system
+    async fn websocket_connector(
+        destination: &Destination,
+        options: &Map,
+    ) -> io::Result<WebSocketConnector> {
+        let secure = destination.scheme_eq("wss");
+        let port = destination
+            .port_or_default()
+            .ok_or_else(|| missing("port"))?;
+        let host = match &destination.host {
+            Host::Ipv6(ip) => format!("[{ip}]"),
+            host => host.to_string(),
+        };
+        let path = options.get("path").map(String::as_str).unwrap_or("/");
+        let url = format!(
+            "{}://{host}:{port}{}{path}",
+            if secure { "wss" } else { "ws" },
+            if path.starts_with('/') { "" } else { "/" },
+        );
+        debug!("Attempting to connect to distant server @ {url}");
+
+        let connector = WebSocketConnector::new(url);
+        if !secure {
+            return Ok(connector);
+        }
+
+        let verification = if let Some(fingerprint) = options.get("tls_fingerprint") {
+            TlsVerification::fingerprint(
+                fingerprint
+                    .parse()
+                    .map_err(|_| invalid("tls_fingerprint"))?,
+            )
+        } else if let Some(path) = options.get("tls_ca") {
+            let pem = tokio::fs::read(path).await.map_err(|x| {
+                io::Error::new(x.kind(), format!("Failed to load tls_ca {path:?}: {x}"))
+            })?;
+            TlsVerification::ca_from_pem(&pem)?
+        } else {
+            TlsVerification::Native
+        };
+
+        Ok(connector.tls(TlsClientConfig::new(
+            destination.host.to_string(),
+            verification,
+        )))
+    }
+
+    /// Spawns the command within the `command` option, such as
+    /// `ssh host distant server listen --stdio`, to talk to the server over its stdin and stdout
+    fn spawn_command(options: &Map) -> io::Result<StdioTransport> {
+        let command = options.get("command").ok_or_else(|| missing("command"))?;
+        let mut args = shell_words::split(command).map_err(|_| invalid("command"))?;
+        if args.is_empty() {
+            return Err(missing("command"));
+        }
+
+        let program = args.remove(0);
+        debug!("Spawning {command:?} to connect to distant server over its stdin and stdout");
+        StdioTransport::spawn(program, args)
+    }
+
+    async fn connect_with(
+        connector: impl Connector + Send,
+        auth_handler: impl AuthHandler + Send,
+    ) -> io::Result<UntypedClient> {
+        ClientBuilder::new()
+            .connector(connector)
+            .auth_handler(auth_handler)
+            .config(ClientConfig {
+                reconnect_strategy: ReconnectStrategy::ExponentialBackoff {
+                    base: Duration::from_secs(1),
+                    factor: 2.0,
+                    max_duration: Some(Duration::from_secs(10)),
+                    max_retries: None,
+                    timeout: None,
+                },
+                ..Default::default()
+            })
+            .connect_timeout(Duration::from_secs(180))
+            .connect_untyped()
+            .await
+    }
+}
+
+#[async_trait]
+impl ConnectHandler for DistantConnectHandler {
+    async fn connect(
+        &self,
+        destination: &Destination,
+        options: &Map,
+        authenticator: &mut dyn Authenticator,
+    ) -> io::Result<UntypedClient> {
+        debug!("Handling connect of {destination} with options '{options}'");
+
+        // Over a unix socket, the server may identify us by the credentials of our process, which
+        // is attempted before prompting for anything
+        let known = if destination.scheme_eq("unix") {
+            AuthHandlerMap::new().with_peer_credentials()
+        } else {
+            AuthHandlerMap::new()
+        };
+
         // For legacy reasons, we need to support a static key being provided
         // via part of the destination OR an option, and attempt to use it
         // during authentication if it is provided
@@ -276,17 +410,118 @@ impl ConnectHandler for DistantConnectHandler {
         {
             let key = key.parse::<SecretKey32>().map_err(|_| invalid("key"))?;
             Self::try_connect(
-                candidate_ips,
-                port,
-                SingleAuthHandler::new(StaticKeyAuthMethodHandler::simple(key)),
+                destination,
+                options,
+                FallbackAuthHandler::new(known.with_static_key(key), authenticator),
+            )
+            .await
+        } else if let Some(path) = options.get("identity_file") {
+            // Sign challenges using a private key whose public key is authorized by the server
+            let key = Ed25519SigningKey::from_file(path).map_err(|x| {
+                io::Error::new(
+                    x.kind(),
+                    format!("Failed to load identity file {path:?}: {x}"),
+                )
+            })?;
+            Self::try_connect(
+                destination,
+                options,
+                FallbackAuthHandler::new(known.with_public_key(key), authenticator),
+            )
+            .await
+        } else if destination.scheme_eq("unix") {
+            Self::try_connect(
+                destination,
+                options,
+                FallbackAuthHandler::new(known, authenticator),
             )
             .await
         } else {
-            Self::try_connect(candidate_ips, port, ProxyAuthHandler::new(authenticator)).await
+            Self::try_connect(destination, options, ProxyAuthHandler::new(authenticator)).await
         }
     }
 }
 
+/// Implementation of [`AuthHandler`] that uses the `known` handlers for the methods they support,
+/// relaying all other methods (such as a second factor like TOTP) to an [`Authenticator`]
+struct FallbackAuthHandler<'a> {
+    known: AuthHandlerMap,
+    proxy: ProxyAuthHandler<'a>,
+    use_known: bool,
+}
+
+impl<'a> FallbackAuthHandler<'a> {
+    pub fn new(known: AuthHandlerMap, authenticator: &'a mut dyn Authenticator) -> Self {
+        Self {
+            known,
+            proxy: ProxyAuthHandler::new(authenticator),
+            use_known: false,
+        }
+    }
+
+    fn active(&mut self) -> &mut dyn AuthMethodHandler {
+        if self.use_known {
+            &mut self.known
+        } else {
+            &mut self.proxy
+        }
+    }
+}
+
+#[async_trait]
+impl<'a> AuthHandler for FallbackAuthHandler<'a> {
+    async fn on_initialization(
+        &mut self,
+        initialization: Initialization,
+    ) -> io::Result<InitializationResponse> {
+        // Prefer methods with known handlers so that they are attempted before prompting
+        let (mut methods, others): (Vec<_>, Vec<_>) = initialization
+            .methods
+            .into_iter()
+            .partition(|method| self.known.get_mut_method_handler(method).is_some());
+        methods.extend(others);
+        Ok(InitializationResponse { methods })
+    }
+
+    async fn on_start_method(&mut self, start_method: StartMethod) -> io::Result<()> {
+        self.use_known = self
+            .known
+            .get_mut_method_handler(&start_method.method)
+            .is_some();
+        if self.use_known {
+            self.known.on_start_method(start_method).await
+        } else {
+            self.proxy.on_start_method(start_method).await
+        }
+    }
+
+    async fn on_finished(&mut self) -> io::Result<()> {
+        self.proxy.on_finished().await
+    }
+}
+
+#[async_trait]
+impl<'a> AuthMethodHandler for FallbackAuthHandler<'a> {
+    async fn on_challenge(&mut self, challenge: Challenge) -> io::Result<ChallengeResponse> {
+        self.active().on_challenge(challenge).await
+    }
+
+    async fn on_verification(
+        &mut self,
+        verification: Verification,
+    ) -> io::Result<VerificationResponse> {
+        self.active().on_verification(verification).await
+    }
+
+    async fn on_info(&mut self, info: Info) -> io::Result<()> {
+        self.active().on_info(info).await
+    }
+
+    async fn on_error(&mut self, error: Error) -> io::Result<()> {
+        self.active().on_error(error).await
+    }
+}
+
 /// Supports connecting to a remote SSH server as defined by `ssh://...`
 #[cfg(any(feature = "libssh", feature = "ssh2"))]
 pub struct SshConnectHandler;
@@ -1,15 +1,30 @@
+#[cfg(unix)]
+use crate::config::AccessControl;
 use crate::{
     config::{BindAddress, ServerConfig, ServerListenConfig},
     CliError, CliResult,
 };
 use anyhow::Context;
 use clap::Subcommand;
-use distant_core::net::common::authentication::Verifier;
-use distant_core::net::common::{Host, SecretKey32};
-use distant_core::net::server::{Server, ServerConfig as NetServerConfig, ServerRef};
-use distant_core::{DistantApiServerHandler, DistantSingleKeyCredentials};
+#[cfg(unix)]
+use distant_core::net::common::authentication::PeerCredentialsAuthenticationMethod;
+use distant_core::net::common::authentication::{
+    AuthenticationMethod, AuthorizedKeys, NoneAuthenticationMethod, PasswordAuthenticationMethod,
+    PasswordCredentials, PublicKeyAuthenticationMethod, StaticKeyAuthenticationMethod,
+    TotpAuthenticationMethod, TotpSecret, VerificationPolicy, Verifier,
+};
+#[cfg(unix)]
+use distant_core::net::common::UnixSocketListener;
+use distant_core::net::common::{
+    EncryptionType, Host, Listener, MappedListener, MultiListener, OneshotListener, PortRange,
+    SecretKey32, StdioTransport, TcpListener, Transport, WebSocketListener,
+};
+use distant_core::net::server::{Server, ServerConfig as NetServerConfig};
+use distant_core::{DistantApiServerHandler, DistantSingleKeyCredentials, WatcherConfig};
 use log::*;
 use std::io::{self, Read, Write};
+use std::path::PathBuf;
+use std::time::Duration;
 
 #[derive(Debug, Subcommand)]
 pub enum ServerSubcommand {
@@ -28,18 +43,85 @@ pub enum ServerSubcommand {
         #[clap(long)]
         key_from_stdin: bool,
 
+        /// If specified, serves a single connection over stdin and stdout instead of binding a
+        /// port, such as when run through `ssh` or `kubectl exec` by a client connecting to an
+        /// `exec://` destination
+        ///
+        /// Nothing is printed as stdout carries the connection, and clients are not asked to
+        /// authenticate unless another method such as `--authorized-keys` is specified, as only
+        /// whatever spawned the server can reach it.
+        #[clap(
+            long,
+            conflicts_with_all = [
+                "daemon",
+                "key_from_stdin",
+                "host",
+                "port",
+                "websocket",
+                "unix_socket",
+            ]
+        )]
+        stdio: bool,
+
         /// If specified, will send output to the specified named pipe (internal usage)
         #[cfg(windows)]
         #[clap(long, help = None, long_help = None)]
         output_to_local_pipe: Option<std::ffi::OsString>,
     },
+
+    /// Manage the users that can authenticate with a password
+    #[clap(subcommand)]
+    User(ServerUserSubcommand),
+
+    /// Generate a secret for time-based one-time passwords (TOTP), printing a URI to add it to an
+    /// authenticator app
+    TotpSecret {
+        /// Path to write the secret, which is used with `distant server listen --totp-secret-file`
+        #[clap(long, value_name = "PATH")]
+        file: PathBuf,
+
+        /// Name of the account shown in the authenticator app
+        #[clap(long, default_value = "distant")]
+        account: String,
+
+        /// Name of the issuer shown in the authenticator app
+        #[clap(long, default_value = "distant")]
+        issuer: String,
+
+        /// If specified, will overwrite the secret if the file already exists
+        #[clap(long)]
+        force: bool,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ServerUserSubcommand {
+    /// Add a user or change the password of an existing user, prompting for the password
+    Add {
+        /// Path to the password file, which is created if it does not exist
+        #[clap(long, value_name = "PATH")]
+        password_file: PathBuf,
+
+        /// Name of the user
+        username: String,
+    },
+
+    /// Remove a user so that they can no longer authenticate
+    Remove {
+        /// Path to the password file
+        #[clap(long, value_name = "PATH")]
+        password_file: PathBuf,
+
+        /// Name of the user
+        username: String,
+    },
 }
 
 impl ServerSubcommand {
     pub fn run(self, config: ServerConfig) -> CliResult {
         match &self {
             Self::Listen { daemon, .. } if *daemon => Self::run_daemon(self, config),
-            Self::Listen { .. } => {
+            _ => {
                 let rt = tokio::runtime::Runtime::new().context("Failed to start up runtime")?;
                 rt.block_on(Self::async_run(self, config, false))
             }
@@ -118,11 +200,30 @@ impl ServerSubcommand {
         }
     }
 
+    /// Binds a unix socket at `path` with permissions based on `access`, creating the directory
+    /// containing the socket if it does not exist
+    #[cfg(unix)]
+    async fn bind_unix_socket(
+        path: &std::path::Path,
+        access: AccessControl,
+    ) -> anyhow::Result<UnixSocketListener> {
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent)
+                .await
+                .with_context(|| format!("Failed to create socket directory {parent:?}"))?;
+        }
+
+        UnixSocketListener::bind_with_permissions(path, access.into_mode())
+            .await
+            .with_context(|| format!("Failed to start server at socket {path:?}"))
+    }
+
     async fn async_run(self, config: ServerConfig, _is_forked: bool) -> CliResult {
         match self {
             Self::Listen {
                 config: listen_config,
                 key_from_stdin,
+                stdio,
                 #[cfg(windows)]
                 output_to_local_pipe,
                 ..
@@ -136,9 +237,28 @@ impl ServerSubcommand {
                     }};
                 }
 
-                let host = get!(host).unwrap_or(BindAddress::Any);
-                trace!("Starting server using unresolved host '{host}'");
-                let addr = host.resolve(get!(@flag use_ipv6)).await?;
+                // Arguments are already checked by clap, but the same settings can come from the
+                // config file, where they would have no effect when serving over stdio
+                if stdio
+                    && (config.listen.host.is_some()
+                        || config.listen.port.is_some()
+                        || config.listen.websocket
+                        || config.listen.unix_socket.is_some())
+                {
+                    return Err(CliError::Error(anyhow::anyhow!(
+                        "--stdio cannot be combined with host, port, websocket, or unix_socket \
+                        from the config file"
+                    )));
+                }
+
+                let hosts = get!(host)
+                    .filter(|hosts| !hosts.is_empty())
+                    .unwrap_or_else(|| vec![BindAddress::Any]);
+                let mut addrs = Vec::new();
+                for host in hosts {
+                    trace!("Starting server using unresolved host '{host}'");
+                    addrs.push(host.resolve(get!(@flag use_ipv6)).await?);
+                }
 
                 // If specified, change the current working directory of this program
                 if let Some(path) = get!(current_dir) {
@@ -161,43 +281,209 @@ impl ServerSubcommand {
 
                 debug!(
                     "Starting local API server, binding to {} {}",
-                    addr,
+                    addrs
+                        .iter()
+                        .map(ToString::to_string)
+                        .collect::<Vec<_>>()
+                        .join(", "),
                     match get!(port) {
                         Some(range) => format!("with port in range {}", range),
                         None => "using an ephemeral port".to_string(),
                     }
                 );
-                let handler = DistantApiServerHandler::local()
+                let poll_interval = match get!(watch_poll_interval) {
+                    // Validated here as converting invalid seconds into a duration panics
+                    Some(secs) if !secs.is_finite() || secs < 0.0 || secs > u64::MAX as f32 => {
+                        return Err(CliError::Error(anyhow::anyhow!(
+                            "Invalid watch poll interval: {secs}"
+                        )));
+                    }
+                    secs => secs.map(Duration::from_secs_f32),
+                };
+                let watcher_config = WatcherConfig {
+                    poll: get!(@flag watch_polling),
+                    poll_interval,
+                    compare_contents: get!(@flag watch_compare_contents),
+                };
+                debug!("Watching filesystem using {:?}", watcher_config);
+                let handler = DistantApiServerHandler::local_with_watcher_config(watcher_config)
                     .context("Failed to create local distant api")?;
-                let server = Server::tcp()
-                    .config(NetServerConfig {
-                        shutdown: get!(shutdown).unwrap_or_default(),
-                        ..Default::default()
-                    })
-                    .handler(handler)
-                    .verifier(Verifier::static_key(key.clone()))
-                    .start(addr, get!(port).unwrap_or_else(|| 0.into()))
-                    .await
-                    .with_context(|| {
+
+                // If given authorized keys or a password file, only those methods are accepted
+                // instead of the static key
+                //
+                // NOTE: Each file is loaded now to fail early instead of when a client connects
+                let mut methods: Vec<Box<dyn AuthenticationMethod>> = Vec::new();
+                if let Some(path) = get!(authorized_keys) {
+                    let keys = AuthorizedKeys::from_file(&path)
+                        .with_context(|| format!("Failed to load authorized keys from {path:?}"))?;
+                    debug!("Authenticating with {} keys from {:?}", keys.len(), path);
+                    methods.push(Box::new(PublicKeyAuthenticationMethod::from_file(path)));
+                }
+                if let Some(path) = get!(password_file) {
+                    let credentials = PasswordCredentials::from_file(&path)
+                        .with_context(|| format!("Failed to load password file {path:?}"))?;
+                    debug!(
+                        "Authenticating with {} users from {:?}",
+                        credentials.len(),
+                        path
+                    );
+                    methods.push(Box::new(PasswordAuthenticationMethod::from_file(path)));
+                }
+                if methods.is_empty() && stdio {
+                    methods.push(Box::new(NoneAuthenticationMethod::new()));
+                } else if methods.is_empty() {
+                    methods.push(Box::new(StaticKeyAuthenticationMethod::new(key.clone())));
+                }
+
+                // Clients on the unix socket are identified by the kernel, where the permissions of
+                // the socket already restrict it to its owner (or its group when shared with the
+                // group) unless open to anyone, in which case only its owner is let in without
+                // the methods above
+                #[cfg(unix)]
+                let unix_socket_listener = match get!(unix_socket) {
+                    Some(path) => {
+                        let access = get!(access).unwrap_or_default();
+                        let listener = Self::bind_unix_socket(&path, access).await?;
+                        methods.push(Box::new(match access {
+                            AccessControl::Anyone => {
+                                let owner = listener.owner_credentials().with_context(|| {
+                                    format!("Failed to read owner of socket {path:?}")
+                                })?;
+                                PeerCredentialsAuthenticationMethod::default().with_uid(owner.uid)
+                            }
+                            _ => PeerCredentialsAuthenticationMethod::any(),
+                        }));
+                        Some(listener)
+                    }
+                    _ => None,
+                };
+
+                #[cfg(windows)]
+                if get!(unix_socket).is_some() {
+                    return Err(CliError::Error(anyhow::anyhow!(
+                        "Unix sockets are only supported on unix"
+                    )));
+                }
+
+                let has_totp = match get!(totp_secret_file) {
+                    Some(path) => {
+                        let secret = TotpSecret::from_file(&path)
+                            .with_context(|| format!("Failed to load totp secret from {path:?}"))?;
+                        methods.push(Box::new(TotpAuthenticationMethod::new(secret)));
+                        true
+                    }
+                    None => false,
+                };
+
+                // Every listed method must succeed if required, otherwise any one will do
+                let policy = match get!(require_methods) {
+                    Some(ids) => {
+                        if let Some(id) = ids
+                            .iter()
+                            .find(|id| !methods.iter().any(|m| m.id() == id.as_str()))
+                        {
+                            return Err(CliError::Error(anyhow::anyhow!(
+                                "Required method {id} is not available"
+                            )));
+                        }
+                        VerificationPolicy::All(ids)
+                    }
+                    None if has_totp => {
+                        return Err(CliError::Error(anyhow::anyhow!(
+                            "TOTP must be combined with another method using --require-methods"
+                        )));
+                    }
+                    None => VerificationPolicy::Any,
+                };
+                debug!("Verifying connections using {:?}", policy);
+                let verifier = Verifier::new(methods).with_policy(policy);
+
+                // Connections are always encrypted, so at least one algorithm has to be offered
+                let encryption_types = match get!(encryption_types) {
+                    Some(types) if types.is_empty() => {
+                        return Err(CliError::Error(anyhow::anyhow!(
+                            "At least one encryption type must be provided"
+                        )));
+                    }
+                    Some(types) => types,
+                    None => EncryptionType::known_variants().to_vec(),
+                };
+
+                let net_config = NetServerConfig {
+                    shutdown: get!(shutdown).unwrap_or_default(),
+                    encryption_types,
+                    ..Default::default()
+                };
+                if stdio {
+                    let server = Server::new()
+                        .config(net_config)
+                        .handler(handler)
+                        .verifier(verifier)
+                        .start(OneshotListener::from_value(StdioTransport::stdio()))
+                        .context("Failed to start server over stdio")?;
+                    info!("Server listening over stdio");
+
+                    // Server stops once its only connection ends
+                    server
+                        .as_ref()
+                        .polling_wait()
+                        .await
+                        .context("Failed to wait on server")?;
+                    info!("Server is shutting down");
+                    return Ok(());
+                }
+
+                // Every address is bound to the port of the first so that the credentials work
+                // with any of them, all feeding connections into the same server
+                let websocket = get!(@flag websocket);
+                let mut listeners: Vec<Box<dyn Listener<Output = Box<dyn Transport>>>> = Vec::new();
+                let mut port: Option<u16> = None;
+                for addr in addrs.iter().copied() {
+                    let range = port
+                        .map(PortRange::from)
+                        .or(get!(port))
+                        .unwrap_or_else(|| 0.into());
+                    let listener = TcpListener::bind(addr, range).await.with_context(|| {
                         format!(
                             "Failed to start server @ {} with {}",
                             addr,
-                            get!(port)
-                                .map(|p| format!("port in range {p}"))
-                                .unwrap_or_else(|| String::from("ephemeral port"))
+                            match (port, get!(port)) {
+                                (Some(port), _) => format!("port {port}"),
+                                (None, Some(range)) => format!("port in range {range}"),
+                                (None, None) => String::from("ephemeral port"),
+                            }
                         )
                     })?;
+                    let port = *port.get_or_insert(listener.port());
+                    info!("Server listening at {addr}:{port}");
+
+                    listeners.push(if websocket {
+                        boxed_listener(WebSocketListener::new(listener))
+                    } else {
+                        boxed_listener(listener)
+                    });
+                }
+
+                #[cfg(unix)]
+                if let Some(listener) = unix_socket_listener {
+                    info!("Server listening at unix socket {:?}", listener.path());
+                    listeners.push(boxed_listener(listener));
+                }
+
+                let server = Server::new()
+                    .config(net_config)
+                    .handler(handler)
+                    .verifier(verifier)
+                    .start(MultiListener::new(listeners))
+                    .context("Failed to start server")?;
 
                 let credentials = DistantSingleKeyCredentials {
-                    host: Host::from(addr),
-                    port: server.port(),
+                    host: Host::from(addrs[0]),
+                    port: port.expect("Bound to at least one address"),
                     key,
                     username: None,
                 };
-                info!(
-                    "Server listening at {}:{}",
-                    credentials.host, credentials.port
-                );
 
                 // Print information about port, key, etc.
                 // NOTE: Following mosh approach of printing to make sure there's no garbage floating around
@@ -239,11 +525,90 @@ impl ServerSubcommand {
                 }
 
                 // Let our server run to completion
-                server.wait().await.context("Failed to wait on server")?;
+                server
+                    .as_ref()
+                    .polling_wait()
+                    .await
+                    .context("Failed to wait on server")?;
                 info!("Server is shutting down");
             }
+            Self::User(ServerUserSubcommand::Add {
+                password_file,
+                username,
+            }) => {
+                let mut credentials = if password_file.exists() {
+                    PasswordCredentials::from_file(&password_file).with_context(|| {
+                        format!("Failed to load password file {password_file:?}")
+                    })?
+                } else {
+                    PasswordCredentials::new()
+                };
+
+                let password = rpassword::prompt_password(format!("Password for {username}: "))
+                    .context("Failed to read password")?;
+                let confirmation = rpassword::prompt_password("Confirm password: ")
+                    .context("Failed to read password")?;
+                if password != confirmation {
+                    return Err(CliError::Error(anyhow::anyhow!("Passwords do not match")));
+                }
+
+                debug!("Adding {username} to {:?}", password_file);
+                credentials
+                    .insert(username, &password)
+                    .context("Failed to add user")?;
+                credentials
+                    .save(&password_file)
+                    .with_context(|| format!("Failed to save password file {password_file:?}"))?;
+            }
+            Self::TotpSecret {
+                file,
+                account,
+                issuer,
+                force,
+            } => {
+                if file.exists() && !force {
+                    return Err(CliError::Error(anyhow::anyhow!(
+                        "{file:?} already exists, use --force to overwrite it"
+                    )));
+                }
+
+                let secret = TotpSecret::generate();
+                secret
+                    .save(&file)
+                    .with_context(|| format!("Failed to save totp secret to {file:?}"))?;
+                println!("{}", secret.to_uri(&account, &issuer));
+            }
+            Self::User(ServerUserSubcommand::Remove {
+                password_file,
+                username,
+            }) => {
+                let mut credentials = PasswordCredentials::from_file(&password_file)
+                    .with_context(|| format!("Failed to load password file {password_file:?}"))?;
+
+                debug!("Removing {username} from {:?}", password_file);
+                if !credentials.remove(&username) {
+                    return Err(CliError::Error(anyhow::anyhow!(
+                        "No user named {username} in {password_file:?}"
+                    )));
+                }
+                credentials
+                    .save(&password_file)
+                    .with_context(|| format!("Failed to save password file {password_file:?}"))?;
+            }
         }
 
         Ok(())
     }
 }
+
+/// Boxes `listener` and the transports it accepts so that it can be combined with listeners of
+/// other kinds into a [`MultiListener`]
+fn boxed_listener<L>(listener: L) -> Box<dyn Listener<Output = Box<dyn Transport>>>
+where
+    L: Listener + 'static,
+    L::Output: Transport + 'static,
+{
+    Box::new(MappedListener::new(listener, |transport| {
+        Box::new(transport) as Box<dyn Transport>
+    }))
+}
@@ -3,6 +3,8 @@ use crate::{
     paths::{global as global_paths, user as user_paths},
 };
 use anyhow::Context;
+#[cfg(unix)]
+use distant_core::net::common::authentication::PeerCredentialsAuthenticationMethod;
 use distant_core::net::common::authentication::Verifier;
 use distant_core::net::manager::{Config as ManagerConfig, ManagerServer};
 use distant_core::net::server::ServerRef;
@@ -10,6 +12,8 @@ use log::*;
 
 pub struct Manager {
     pub access: AccessControl,
+    pub allowed_uids: Vec<u32>,
+    pub allowed_gids: Vec<u32>,
     pub config: ManagerConfig,
     pub network: NetworkConfig,
 }
@@ -37,12 +41,36 @@ impl Manager {
                     .with_context(|| format!("Failed to create socket directory {parent:?}"))?;
             }
 
-            let boxed_ref = ManagerServer::new(self.config)
-                .verifier(Verifier::none())
-                .start(
-                    UnixSocketListener::bind_with_permissions(socket_path, self.access.into_mode())
-                        .await?,
+            let listener =
+                UnixSocketListener::bind_with_permissions(socket_path, self.access.into_mode())
+                    .await?;
+
+            // The permissions of the socket restrict who can connect, including supplementary
+            // members of its group and root, so only rely on the kernel to identify connecting
+            // processes when the socket is open to anyone and limited to the owner of the socket
+            // alongside explicitly allowed users and groups
+            let has_allowed = !self.allowed_uids.is_empty() || !self.allowed_gids.is_empty();
+            let verifier = if self.access == AccessControl::Anyone && has_allowed {
+                let owner = listener
+                    .owner_credentials()
+                    .with_context(|| format!("Failed to read owner of socket {socket_path:?}"))?;
+                Verifier::peer_credentials(
+                    PeerCredentialsAuthenticationMethod::new(self.allowed_uids, self.allowed_gids)
+                        .with_uid(owner.uid),
                 )
+            } else {
+                if has_allowed {
+                    warn!(
+                        "Allowed users and groups only apply when access is anyone, as the \
+                         permissions of the socket otherwise keep them from connecting"
+                    );
+                }
+                Verifier::none()
+            };
+
+            let boxed_ref = ManagerServer::new(self.config)
+                .verifier(verifier)
+                .start(listener)
                 .with_context(|| format!("Failed to start manager at socket {socket_path:?}"))?;
 
             info!("Manager listening using unix socket @ {:?}", socket_path);
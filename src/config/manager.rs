@@ -11,6 +11,14 @@ pub struct ManagerConfig {
     #[clap(long, value_enum)]
     pub access: Option<AccessControl>,
 
+    /// Additional user ids allowed to connect to the unix socket (unix-only)
+    #[clap(long, value_delimiter = ',')]
+    pub allow_uid: Option<Vec<u32>>,
+
+    /// Additional group ids allowed to connect to the unix socket (unix-only)
+    #[clap(long, value_delimiter = ',')]
+    pub allow_gid: Option<Vec<u32>>,
+
     #[clap(flatten)]
     #[serde(flatten)]
     pub common: CommonConfig,
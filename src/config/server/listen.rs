@@ -1,8 +1,9 @@
+use crate::config::AccessControl;
 use anyhow::Context;
-use clap::Args;
-use distant_core::net::common::{Host, HostParseError, Map, PortRange};
+use clap::{Args, ValueEnum};
+use distant_core::net::common::{EncryptionType, Host, HostParseError, Map, PortRange};
 use distant_core::net::server::Shutdown;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 use std::{
     env, fmt,
     net::{IpAddr, Ipv4Addr, Ipv6Addr},
@@ -25,8 +26,14 @@ pub struct ServerListenConfig {
     /// another tool that makes the SSH connection appear to come from localhost.
     ///
     /// 3. `IP`: the server will attempt to bind to the specified IP address.
-    #[clap(long, value_name = "ssh|any|IP")]
-    pub host: Option<BindAddress>,
+    ///
+    /// Several addresses can be given, separated by commas, to listen on all of them at once
+    /// such as `127.0.0.1,::1`, where every address uses the same port and the first is the one
+    /// printed in the credentials. Note that on some platforms such as Linux, binding to `::`
+    /// also accepts IPv4 connections and conflicts with binding to `0.0.0.0` on the same port.
+    #[clap(long, value_name = "ssh|any|IP,...", value_delimiter = ',')]
+    #[serde(default, deserialize_with = "deserialize_bind_addresses")]
+    pub host: Option<Vec<BindAddress>>,
 
     /// Set the port(s) that the server will attempt to bind to
     ///
@@ -54,6 +61,80 @@ pub struct ServerListenConfig {
     /// Changes the current working directory (cwd) to the specified directory
     #[clap(long)]
     pub current_dir: Option<PathBuf>,
+
+    /// If specified, will detect filesystem changes by polling instead of using the native
+    /// watcher of the platform, which is needed for network filesystems such as NFS or SMB
+    #[clap(long)]
+    pub watch_polling: bool,
+
+    /// Interval in seconds between polls when detecting filesystem changes by polling
+    #[clap(long, value_name = "SECONDS")]
+    pub watch_poll_interval: Option<f32>,
+
+    /// If specified, polling will compare the contents of files to detect changes
+    #[clap(long)]
+    pub watch_compare_contents: bool,
+
+    /// If specified, clients must authenticate by signing a challenge with the private key of
+    /// one of the Ed25519 public keys listed in this file instead of using the printed key
+    ///
+    /// Each line contains a public key as a hex string optionally followed by a comment. The file
+    /// is read for every connection, so keys can be added or revoked without a restart.
+    #[clap(long, value_name = "PATH")]
+    pub authorized_keys: Option<PathBuf>,
+
+    /// If specified, clients can authenticate with a username and password checked against the
+    /// Argon2 hashes in this file instead of using the printed key
+    ///
+    /// Users are managed with `distant server user add` and `distant server user remove`. The file
+    /// is read for every connection, so users can be added or removed without a restart.
+    #[clap(long, value_name = "PATH")]
+    pub password_file: Option<PathBuf>,
+
+    /// If specified, clients can provide a time-based one-time password (TOTP) from an
+    /// authenticator app whose secret is in this file
+    ///
+    /// The secret is created with `distant server totp-secret`. As a second factor, this must be
+    /// combined with another method using `--require-methods`, such as `static_key,totp`.
+    #[clap(long, value_name = "PATH")]
+    pub totp_secret_file: Option<PathBuf>,
+
+    /// If specified, clients must succeed with every one of these authentication methods in the
+    /// order given instead of any one of them
+    ///
+    /// Available methods are `static_key`, `public_key`, `password`, and `totp`, where
+    /// `static_key` is only available when neither `--authorized-keys` nor `--password-file`
+    /// is specified.
+    #[clap(long, value_name = "METHOD,...", value_delimiter = ',')]
+    pub require_methods: Option<Vec<String>>,
+
+    /// If specified, restricts the encryption that clients can choose to these algorithms,
+    /// otherwise all are offered
+    ///
+    /// Available algorithms are `xchacha20poly1305` and `aes256gcm`.
+    #[clap(long, value_name = "ALGORITHM,...", value_delimiter = ',')]
+    pub encryption_types: Option<Vec<EncryptionType>>,
+
+    /// If specified, clients connect using WebSocket instead of plain TCP, which lets the server
+    /// sit behind an HTTP reverse proxy or ingress
+    ///
+    /// Clients connect to a `ws://` destination, or `wss://` when the proxy terminates TLS, using
+    /// the key from the printed credentials.
+    #[clap(long)]
+    pub websocket: bool,
+
+    /// If specified, the server also listens on a Unix socket at this path (unix-only), letting
+    /// local processes such as containers with the socket mounted connect without a port
+    ///
+    /// Clients connect to a `unix://PATH` destination. Those running as the owner of the socket
+    /// (and its group with `--access group`) are identified by the operating system and do not
+    /// need the printed key, while any other client must still authenticate.
+    #[clap(long, value_name = "PATH")]
+    pub unix_socket: Option<PathBuf>,
+
+    /// Type of access to apply to the Unix socket, defaulting to the owner only
+    #[clap(long, value_enum)]
+    pub access: Option<AccessControl>,
 }
 
 impl From<Map> for ServerListenConfig {
@@ -61,7 +142,7 @@ impl From<Map> for ServerListenConfig {
         Self {
             host: map
                 .remove("host")
-                .and_then(|x| x.parse::<BindAddress>().ok()),
+                .and_then(|x| x.split(',').map(str::parse).collect::<Result<_, _>>().ok()),
             port: map.remove("port").and_then(|x| x.parse::<PortRange>().ok()),
             use_ipv6: map
                 .remove("use_ipv6")
@@ -73,6 +154,42 @@ impl From<Map> for ServerListenConfig {
             current_dir: map
                 .remove("current_dir")
                 .and_then(|x| x.parse::<PathBuf>().ok()),
+            watch_polling: map
+                .remove("watch_polling")
+                .and_then(|x| x.parse::<bool>().ok())
+                .unwrap_or_default(),
+            watch_poll_interval: map
+                .remove("watch_poll_interval")
+                .and_then(|x| x.parse::<f32>().ok()),
+            watch_compare_contents: map
+                .remove("watch_compare_contents")
+                .and_then(|x| x.parse::<bool>().ok())
+                .unwrap_or_default(),
+            authorized_keys: map
+                .remove("authorized_keys")
+                .and_then(|x| x.parse::<PathBuf>().ok()),
+            password_file: map
+                .remove("password_file")
+                .and_then(|x| x.parse::<PathBuf>().ok()),
+            totp_secret_file: map
+                .remove("totp_secret_file")
+                .and_then(|x| x.parse::<PathBuf>().ok()),
+            require_methods: map
+                .remove("require_methods")
+                .map(|x| x.split(',').map(ToString::to_string).collect()),
+            encryption_types: map
+                .remove("encryption_types")
+                .and_then(|x| x.split(',').map(str::parse).collect::<Result<_, _>>().ok()),
+            websocket: map
+                .remove("websocket")
+                .and_then(|x| x.parse::<bool>().ok())
+                .unwrap_or_default(),
+            unix_socket: map
+                .remove("unix_socket")
+                .and_then(|x| x.parse::<PathBuf>().ok()),
+            access: map
+                .remove("access")
+                .and_then(|x| AccessControl::from_str(&x, true).ok()),
         }
     }
 }
@@ -82,7 +199,13 @@ impl From<ServerListenConfig> for Map {
         let mut this = Self::new();
 
         if let Some(x) = config.host {
-            this.insert("host".to_string(), x.to_string());
+            this.insert(
+                "host".to_string(),
+                x.iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(","),
+            );
         }
 
         if let Some(x) = config.port {
@@ -99,10 +222,87 @@ impl From<ServerListenConfig> for Map {
             this.insert("current_dir".to_string(), x.to_string_lossy().to_string());
         }
 
+        this.insert(
+            "watch_polling".to_string(),
+            config.watch_polling.to_string(),
+        );
+
+        if let Some(x) = config.watch_poll_interval {
+            this.insert("watch_poll_interval".to_string(), x.to_string());
+        }
+
+        this.insert(
+            "watch_compare_contents".to_string(),
+            config.watch_compare_contents.to_string(),
+        );
+
+        if let Some(x) = config.authorized_keys {
+            this.insert(
+                "authorized_keys".to_string(),
+                x.to_string_lossy().to_string(),
+            );
+        }
+
+        if let Some(x) = config.password_file {
+            this.insert("password_file".to_string(), x.to_string_lossy().to_string());
+        }
+
+        if let Some(x) = config.totp_secret_file {
+            this.insert(
+                "totp_secret_file".to_string(),
+                x.to_string_lossy().to_string(),
+            );
+        }
+
+        if let Some(x) = config.require_methods {
+            this.insert("require_methods".to_string(), x.join(","));
+        }
+
+        if let Some(x) = config.encryption_types {
+            this.insert(
+                "encryption_types".to_string(),
+                x.iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(","),
+            );
+        }
+
+        this.insert("websocket".to_string(), config.websocket.to_string());
+
+        if let Some(x) = config.unix_socket {
+            this.insert("unix_socket".to_string(), x.to_string_lossy().to_string());
+        }
+
+        if let Some(x) = config.access.and_then(|x| x.to_possible_value()) {
+            this.insert("access".to_string(), x.get_name().to_string());
+        }
+
         this
     }
 }
 
+/// Deserializes either a list of addresses or a single address, which is how the host was
+/// configured before several addresses could be given
+fn deserialize_bind_addresses<'de, D>(deserializer: D) -> Result<Option<Vec<BindAddress>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(BindAddress),
+        Many(Vec<BindAddress>),
+    }
+
+    Ok(
+        Option::<OneOrMany>::deserialize(deserializer)?.map(|x| match x {
+            OneOrMany::One(x) => vec![x],
+            OneOrMany::Many(x) => x,
+        }),
+    )
+}
+
 /// Represents options for binding a server to an IP address
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum BindAddress {
@@ -218,6 +418,24 @@ mod tests {
         );
     }
 
+    #[test]
+    fn host_should_deserialize_from_a_single_address_or_a_list() {
+        #[derive(Deserialize)]
+        struct Config {
+            #[serde(default, deserialize_with = "deserialize_bind_addresses")]
+            host: Option<Vec<BindAddress>>,
+        }
+
+        let config: Config = toml_edit::de::from_str(r#"host = "Any""#).unwrap();
+        assert_eq!(config.host, Some(vec![BindAddress::Any]));
+
+        let config: Config = toml_edit::de::from_str(r#"host = ["Ssh", "Any"]"#).unwrap();
+        assert_eq!(config.host, Some(vec![BindAddress::Ssh, BindAddress::Any]));
+
+        let config: Config = toml_edit::de::from_str("").unwrap();
+        assert_eq!(config.host, None);
+    }
+
     #[test]
     fn parse_should_correctly_parse_host_or_special_cases() {
         assert_eq!("any".parse::<BindAddress>().unwrap(), BindAddress::Any);
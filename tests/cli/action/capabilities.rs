@@ -4,55 +4,67 @@ use indoc::indoc;
 use rstest::*;
 
 const EXPECTED_TABLE: &str = indoc! {"
-+------------------+------------------------------------------------------------------+
-| kind             | description                                                      |
-+------------------+------------------------------------------------------------------+
-| cancel_search    | Supports canceling an active search against the filesystem       |
-+------------------+------------------------------------------------------------------+
-| capabilities     | Supports retrieving capabilities                                 |
-+------------------+------------------------------------------------------------------+
-| copy             | Supports copying files, directories, and symlinks                |
-+------------------+------------------------------------------------------------------+
-| dir_create       | Supports creating directory                                      |
-+------------------+------------------------------------------------------------------+
-| dir_read         | Supports reading directory                                       |
-+------------------+------------------------------------------------------------------+
-| exists           | Supports checking if a path exists                               |
-+------------------+------------------------------------------------------------------+
-| file_append      | Supports appending to binary file                                |
-+------------------+------------------------------------------------------------------+
-| file_append_text | Supports appending to text file                                  |
-+------------------+------------------------------------------------------------------+
-| file_read        | Supports reading binary file                                     |
-+------------------+------------------------------------------------------------------+
-| file_read_text   | Supports reading text file                                       |
-+------------------+------------------------------------------------------------------+
-| file_write       | Supports writing binary file                                     |
-+------------------+------------------------------------------------------------------+
-| file_write_text  | Supports writing text file                                       |
-+------------------+------------------------------------------------------------------+
-| metadata         | Supports retrieving metadata about a file, directory, or symlink |
-+------------------+------------------------------------------------------------------+
-| proc_kill        | Supports killing a spawned process                               |
-+------------------+------------------------------------------------------------------+
-| proc_resize_pty  | Supports resizing the pty of a spawned process                   |
-+------------------+------------------------------------------------------------------+
-| proc_spawn       | Supports spawning a process                                      |
-+------------------+------------------------------------------------------------------+
-| proc_stdin       | Supports sending stdin to a spawned process                      |
-+------------------+------------------------------------------------------------------+
-| remove           | Supports removing files, directories, and symlinks               |
-+------------------+------------------------------------------------------------------+
-| rename           | Supports renaming files, directories, and symlinks               |
-+------------------+------------------------------------------------------------------+
-| search           | Supports searching filesystem using queries                      |
-+------------------+------------------------------------------------------------------+
-| system_info      | Supports retrieving system information                           |
-+------------------+------------------------------------------------------------------+
-| unwatch          | Supports unwatching filesystem for changes                       |
-+------------------+------------------------------------------------------------------+
-| watch            | Supports watching filesystem for changes                         |
-+------------------+------------------------------------------------------------------+
++------------------+------------------------------------------------------------------------------+
+| kind             | description                                                                  |
++------------------+------------------------------------------------------------------------------+
+| cancel_search    | Supports canceling an active search against the filesystem                   |
++------------------+------------------------------------------------------------------------------+
+| cancel_tail      | Supports canceling an active tail of a file                                  |
++------------------+------------------------------------------------------------------------------+
+| capabilities     | Supports retrieving capabilities                                             |
++------------------+------------------------------------------------------------------------------+
+| copy             | Supports copying files, directories, and symlinks                            |
++------------------+------------------------------------------------------------------------------+
+| dir_create       | Supports creating directory                                                  |
++------------------+------------------------------------------------------------------------------+
+| dir_read         | Supports reading directory                                                   |
++------------------+------------------------------------------------------------------------------+
+| exists           | Supports checking if a path exists                                           |
++------------------+------------------------------------------------------------------------------+
+| file_append      | Supports appending to binary file                                            |
++------------------+------------------------------------------------------------------------------+
+| file_append_text | Supports appending to text file                                              |
++------------------+------------------------------------------------------------------------------+
+| file_read        | Supports reading binary file                                                 |
++------------------+------------------------------------------------------------------------------+
+| file_read_text   | Supports reading text file                                                   |
++------------------+------------------------------------------------------------------------------+
+| file_tail        | Supports following a file for appended data                                  |
++------------------+------------------------------------------------------------------------------+
+| file_write       | Supports writing binary file                                                 |
++------------------+------------------------------------------------------------------------------+
+| file_write_text  | Supports writing text file                                                   |
++------------------+------------------------------------------------------------------------------+
+| find_files       | Supports finding paths using fuzzy matching                                  |
++------------------+------------------------------------------------------------------------------+
+| metadata         | Supports retrieving metadata about a file, directory, or symlink             |
++------------------+------------------------------------------------------------------------------+
+| proc_kill        | Supports killing a spawned process                                           |
++------------------+------------------------------------------------------------------------------+
+| proc_resize_pty  | Supports resizing the pty of a spawned process                               |
++------------------+------------------------------------------------------------------------------+
+| proc_spawn       | Supports spawning a process                                                  |
++------------------+------------------------------------------------------------------------------+
+| proc_stdin       | Supports sending stdin to a spawned process                                  |
++------------------+------------------------------------------------------------------------------+
+| remove           | Supports removing files, directories, and symlinks                           |
++------------------+------------------------------------------------------------------------------+
+| rename           | Supports renaming files, directories, and symlinks                           |
++------------------+------------------------------------------------------------------------------+
+| search           | Supports searching filesystem using queries                                  |
++------------------+------------------------------------------------------------------------------+
+| search_index     | Supports indexing a directory to speed up content searches                   |
++------------------+------------------------------------------------------------------------------+
+| search_unindex   | Supports removing the index of a directory used to speed up content searches |
++------------------+------------------------------------------------------------------------------+
+| system_info      | Supports retrieving system information                                       |
++------------------+------------------------------------------------------------------------------+
+| unwatch          | Supports unwatching filesystem for changes                                   |
++------------------+------------------------------------------------------------------------------+
+| watch            | Supports watching filesystem for changes                                     |
++------------------+------------------------------------------------------------------------------+
+| watch_resume     | Supports resuming a watch by replaying missed changes                        |
++------------------+------------------------------------------------------------------------------+
 "};
 
 #[rstest]
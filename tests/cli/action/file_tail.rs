@@ -0,0 +1,54 @@
+use crate::cli::fixtures::*;
+use assert_cmd::Command;
+use assert_fs::prelude::*;
+use predicates::prelude::*;
+use rstest::*;
+
+const FILE_CONTENTS: &str = "line 1\nline 2\nline 3\n";
+
+#[rstest]
+#[test_log::test]
+fn should_print_out_last_lines_of_file(mut action_cmd: CtxCommand<Command>) {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let file = temp.child("test-file");
+    file.write_str(FILE_CONTENTS).unwrap();
+
+    // distant action file-tail -n 2 {path}
+    action_cmd
+        .args(["file-tail", "-n", "2", file.to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout("line 2\nline 3\n")
+        .stderr("");
+}
+
+#[rstest]
+#[test_log::test]
+fn should_print_out_file_from_offset(mut action_cmd: CtxCommand<Command>) {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let file = temp.child("test-file");
+    file.write_str(FILE_CONTENTS).unwrap();
+
+    // distant action file-tail --offset 7 {path}
+    action_cmd
+        .args(["file-tail", "--offset", "7", file.to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout("line 2\nline 3\n")
+        .stderr("");
+}
+
+#[rstest]
+#[test_log::test]
+fn yield_an_error_when_fails(mut action_cmd: CtxCommand<Command>) {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let file = temp.child("missing-file");
+
+    // distant action file-tail {path}
+    action_cmd
+        .args(["file-tail", file.to_str().unwrap()])
+        .assert()
+        .failure()
+        .stdout("")
+        .stderr(predicates::str::is_empty().not());
+}
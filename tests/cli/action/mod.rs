@@ -7,6 +7,7 @@ mod file_append;
 mod file_append_text;
 mod file_read;
 mod file_read_text;
+mod file_tail;
 mod file_write;
 mod file_write_text;
 mod metadata;
@@ -1,5 +1,6 @@
 use crate::cli::fixtures::*;
 use assert_cmd::Command;
+use distant_core::net::common::PROTOCOL_VERSION;
 use rstest::*;
 use std::env;
 
@@ -7,11 +8,21 @@ use std::env;
 #[test_log::test]
 fn should_output_system_info(mut action_cmd: CtxCommand<Command>) {
     // distant action system-info
-    action_cmd
-        .arg("system-info")
-        .assert()
-        .success()
-        .stdout(format!(
+    let assert = action_cmd.arg("system-info").assert().success().stderr("");
+
+    // Backend used to watch the filesystem depends on the platform, so it is checked on its own
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout).to_string();
+    let (stdout, watch_backend) = stdout
+        .rsplit_once("Watch Backend: ")
+        .expect("Missing watch backend");
+    assert!(
+        !watch_backend.trim().is_empty() && watch_backend.trim() != "unknown",
+        "Unexpected watch backend: {watch_backend:?}"
+    );
+
+    assert_eq!(
+        stdout,
+        format!(
             concat!(
                 "Family: {:?}\n",
                 "Operating System: {:?}\n",
@@ -20,6 +31,7 @@ fn should_output_system_info(mut action_cmd: CtxCommand<Command>) {
                 "Path Sep: {:?}\n",
                 "Username: {:?}\n",
                 "Shell: {:?}\n",
+                "Protocol Version: {}\n",
             ),
             env::consts::FAMILY.to_string(),
             env::consts::OS.to_string(),
@@ -31,7 +43,8 @@ fn should_output_system_info(mut action_cmd: CtxCommand<Command>) {
                 std::env::var("ComSpec").unwrap_or_else(|_| String::from("cmd.exe"))
             } else {
                 std::env::var("SHELL").unwrap_or_else(|_| String::from("/bin/sh"))
-            }
-        ))
-        .stderr("");
+            },
+            PROTOCOL_VERSION,
+        )
+    );
 }
@@ -81,6 +81,7 @@ async fn should_support_json_search_filesystem_using_query(mut json_repl: CtxCom
         json!({
             "type": "search_done",
             "id": search_id,
+            "skipped_binary": 0,
         }),
         "JSON: {res}"
     );
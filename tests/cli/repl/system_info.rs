@@ -1,4 +1,5 @@
 use crate::cli::fixtures::*;
+use distant_core::net::common::PROTOCOL_VERSION;
 use rstest::*;
 use serde_json::json;
 use std::env;
@@ -15,7 +16,16 @@ async fn should_support_json_system_info(mut json_repl: CtxCommand<Repl>) {
         "payload": { "type": "system_info" },
     });
 
-    let res = json_repl.write_and_read_json(req).await.unwrap().unwrap();
+    let mut res = json_repl.write_and_read_json(req).await.unwrap().unwrap();
+
+    // Backend used to watch the filesystem depends on the platform, so it is checked on its own
+    let watch_backend = res["payload"]
+        .as_object_mut()
+        .and_then(|payload| payload.remove("watch_backend"));
+    assert!(
+        matches!(&watch_backend, Some(serde_json::Value::String(x)) if !x.is_empty()),
+        "Unexpected watch backend: {watch_backend:?}"
+    );
 
     assert_eq!(res["origin_id"], id, "JSON: {res}");
     assert_eq!(
@@ -32,7 +42,8 @@ async fn should_support_json_system_info(mut json_repl: CtxCommand<Repl>) {
                 std::env::var("ComSpec").unwrap_or_else(|_| String::from("cmd.exe"))
             } else {
                 std::env::var("SHELL").unwrap_or_else(|_| String::from("/bin/sh"))
-            }
+            },
+            "protocol_version": PROTOCOL_VERSION,
         }),
         "JSON: {res}"
     );